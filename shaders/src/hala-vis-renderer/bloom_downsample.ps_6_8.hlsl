@@ -0,0 +1,105 @@
+#define HALA_NO_GLOBAL_PUSH_CONSTANT
+
+#ifdef HALA_HLSL
+
+  #include "scene.hlsl"
+  #include "tonemapping.hlsl"
+
+#else
+
+  #include "scene.glsl"
+  #include "tonemapping.glsl"
+
+#endif
+
+TEXTURE2D(3, 0, in_source);
+SAMPLER(3, 1, in_linear_sampler);
+
+BEGIN_PUSH_CONSTANTS(BloomDownsamplePushConstants)
+  float2 source_texel_size;
+  uint use_karis_average;  // Only the first mip level, which is filtered from the scene color.
+END_PUSH_CONSTANTS(BloomDownsamplePushConstants, g_push_constants)
+
+#ifdef HALA_HLSL
+
+  struct ToFragment {
+    float4 position: SV_Position;
+    [[vk::location(0)]] float2 uv: TEXCOORD0;
+  };
+
+  struct FragmentOutput {
+    [[vk::location(0)]] float4 color: SV_Target0;
+  };
+
+  FragmentOutput main(ToFragment input) {
+    FragmentOutput output = (FragmentOutput)0;
+    #define IN_UV input.uv
+    #define OUT_COLOR output.color
+
+#else
+
+  layout(location = 0) in float2 in_uv;
+
+  layout(location = 0) out float4 out_color;
+
+  void main() {
+    #define IN_UV in_uv
+    #define OUT_COLOR out_color
+
+#endif
+
+  //////////////////////////////////////////////////////////////////////////
+  // Begin Function Code.
+
+  // The 13 taps downsampling filter of the Call of Duty: Advanced Warfare bloom.
+  // a . b . c
+  // . j . k .
+  // d . e . f
+  // . l . m .
+  // g . h . i
+  const float2 t = g_push_constants.source_texel_size;
+  const float2 uv = get_render_target_uv(IN_UV);
+  const float3 a = SAMPLE_TEXTURE_LEVEL(in_source, in_linear_sampler, uv + t * float2(-2.0, -2.0), 0.0).rgb;
+  const float3 b = SAMPLE_TEXTURE_LEVEL(in_source, in_linear_sampler, uv + t * float2(0.0, -2.0), 0.0).rgb;
+  const float3 c = SAMPLE_TEXTURE_LEVEL(in_source, in_linear_sampler, uv + t * float2(2.0, -2.0), 0.0).rgb;
+  const float3 d = SAMPLE_TEXTURE_LEVEL(in_source, in_linear_sampler, uv + t * float2(-2.0, 0.0), 0.0).rgb;
+  const float3 e = SAMPLE_TEXTURE_LEVEL(in_source, in_linear_sampler, uv, 0.0).rgb;
+  const float3 f = SAMPLE_TEXTURE_LEVEL(in_source, in_linear_sampler, uv + t * float2(2.0, 0.0), 0.0).rgb;
+  const float3 g = SAMPLE_TEXTURE_LEVEL(in_source, in_linear_sampler, uv + t * float2(-2.0, 2.0), 0.0).rgb;
+  const float3 h = SAMPLE_TEXTURE_LEVEL(in_source, in_linear_sampler, uv + t * float2(0.0, 2.0), 0.0).rgb;
+  const float3 i = SAMPLE_TEXTURE_LEVEL(in_source, in_linear_sampler, uv + t * float2(2.0, 2.0), 0.0).rgb;
+  const float3 j = SAMPLE_TEXTURE_LEVEL(in_source, in_linear_sampler, uv + t * float2(-1.0, -1.0), 0.0).rgb;
+  const float3 k = SAMPLE_TEXTURE_LEVEL(in_source, in_linear_sampler, uv + t * float2(1.0, -1.0), 0.0).rgb;
+  const float3 l = SAMPLE_TEXTURE_LEVEL(in_source, in_linear_sampler, uv + t * float2(-1.0, 1.0), 0.0).rgb;
+  const float3 m = SAMPLE_TEXTURE_LEVEL(in_source, in_linear_sampler, uv + t * float2(1.0, 1.0), 0.0).rgb;
+
+  // The center box is weighted 0.5, the 4 overlapped corner boxes are weighted 0.125 each.
+  float3 boxes[5];
+  boxes[0] = (j + k + l + m) * 0.25;
+  boxes[1] = (a + b + d + e) * 0.25;
+  boxes[2] = (b + c + e + f) * 0.25;
+  boxes[3] = (d + e + g + h) * 0.25;
+  boxes[4] = (e + f + h + i) * 0.25;
+
+  float3 color = float3(0.0, 0.0, 0.0);
+  float total_weight = 0.0;
+  ANNOTATION_UNROLL
+  for (int box_index = 0; box_index < 5; ++box_index) {
+    float weight = box_index == 0 ? 0.5 : 0.125;
+    // The Karis average weights the boxes by the inverse luminance, so a single bright pixel does not flicker as a big blob.
+    if (g_push_constants.use_karis_average != 0) {
+      weight /= 1.0 + get_luminance(boxes[box_index]);
+    }
+    color += boxes[box_index] * weight;
+    total_weight += weight;
+  }
+
+  OUT_COLOR = float4(max(color / total_weight, float3(0.0, 0.0, 0.0)), 1.0);
+
+  // End Function Code.
+  //////////////////////////////////////////////////////////////////////////
+
+#ifdef HALA_HLSL
+  return output;
+#endif
+}
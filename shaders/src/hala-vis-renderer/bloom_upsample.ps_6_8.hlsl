@@ -0,0 +1,76 @@
+#define HALA_NO_GLOBAL_PUSH_CONSTANT
+
+#ifdef HALA_HLSL
+
+  #include "scene.hlsl"
+
+#else
+
+  #include "scene.glsl"
+
+#endif
+
+TEXTURE2D(3, 0, in_source);
+SAMPLER(3, 1, in_linear_sampler);
+
+BEGIN_PUSH_CONSTANTS(BloomUpsamplePushConstants)
+  float2 source_texel_size;
+  float radius;  // In texels of the source mip level.
+END_PUSH_CONSTANTS(BloomUpsamplePushConstants, g_push_constants)
+
+#ifdef HALA_HLSL
+
+  struct ToFragment {
+    float4 position: SV_Position;
+    [[vk::location(0)]] float2 uv: TEXCOORD0;
+  };
+
+  struct FragmentOutput {
+    [[vk::location(0)]] float4 color: SV_Target0;
+  };
+
+  FragmentOutput main(ToFragment input) {
+    FragmentOutput output = (FragmentOutput)0;
+    #define IN_UV input.uv
+    #define OUT_COLOR output.color
+
+#else
+
+  layout(location = 0) in float2 in_uv;
+
+  layout(location = 0) out float4 out_color;
+
+  void main() {
+    #define IN_UV in_uv
+    #define OUT_COLOR out_color
+
+#endif
+
+  //////////////////////////////////////////////////////////////////////////
+  // Begin Function Code.
+
+  // The 3x3 tent filter, the result is added onto the target by the blending.
+  // 1 2 1
+  // 2 4 2 / 16
+  // 1 2 1
+  const float2 t = g_push_constants.source_texel_size * g_push_constants.radius;
+  const float2 uv = get_render_target_uv(IN_UV);
+  float3 color = SAMPLE_TEXTURE_LEVEL(in_source, in_linear_sampler, uv, 0.0).rgb * 4.0;
+  color += SAMPLE_TEXTURE_LEVEL(in_source, in_linear_sampler, uv + t * float2(0.0, -1.0), 0.0).rgb * 2.0;
+  color += SAMPLE_TEXTURE_LEVEL(in_source, in_linear_sampler, uv + t * float2(-1.0, 0.0), 0.0).rgb * 2.0;
+  color += SAMPLE_TEXTURE_LEVEL(in_source, in_linear_sampler, uv + t * float2(1.0, 0.0), 0.0).rgb * 2.0;
+  color += SAMPLE_TEXTURE_LEVEL(in_source, in_linear_sampler, uv + t * float2(0.0, 1.0), 0.0).rgb * 2.0;
+  color += SAMPLE_TEXTURE_LEVEL(in_source, in_linear_sampler, uv + t * float2(-1.0, -1.0), 0.0).rgb;
+  color += SAMPLE_TEXTURE_LEVEL(in_source, in_linear_sampler, uv + t * float2(1.0, -1.0), 0.0).rgb;
+  color += SAMPLE_TEXTURE_LEVEL(in_source, in_linear_sampler, uv + t * float2(-1.0, 1.0), 0.0).rgb;
+  color += SAMPLE_TEXTURE_LEVEL(in_source, in_linear_sampler, uv + t * float2(1.0, 1.0), 0.0).rgb;
+
+  OUT_COLOR = float4(color / 16.0, 1.0);
+
+  // End Function Code.
+  //////////////////////////////////////////////////////////////////////////
+
+#ifdef HALA_HLSL
+  return output;
+#endif
+}
@@ -12,6 +12,10 @@
 
 TEXTURE2D(3, 0, in_depth);
 
+BEGIN_PUSH_CONSTANTS(DepthReductionPushConstants)
+  uint2 source_size;  // The rendered size of the source, the texels out of it are left by the larger render scales.
+END_PUSH_CONSTANTS(DepthReductionPushConstants, g_push_constants)
+
 #ifdef HALA_HLSL
 
   struct ToFragment {
@@ -42,10 +46,11 @@ TEXTURE2D(3, 0, in_depth);
 
   uint2 pos = uint2(IN_POSITION.xy);
   uint2 xy = pos * 2;
-  float depth = LOAD_SAMPLE(in_depth, xy, 0).r;
-  depth = min(depth, LOAD_SAMPLE(in_depth, xy + uint2(1, 0), 0).r);
-  depth = min(depth, LOAD_SAMPLE(in_depth, xy + uint2(0, 1), 0).r);
-  depth = min(depth, LOAD_SAMPLE(in_depth, xy + uint2(1, 1), 0).r);
+  const uint2 max_xy = g_push_constants.source_size - 1;
+  float depth = LOAD_SAMPLE(in_depth, min(xy, max_xy), 0).r;
+  depth = min(depth, LOAD_SAMPLE(in_depth, min(xy + uint2(1, 0), max_xy), 0).r);
+  depth = min(depth, LOAD_SAMPLE(in_depth, min(xy + uint2(0, 1), max_xy), 0).r);
+  depth = min(depth, LOAD_SAMPLE(in_depth, min(xy + uint2(1, 1), max_xy), 0).r);
   OUT_DEPTH = depth;
 
   // End Function Code.
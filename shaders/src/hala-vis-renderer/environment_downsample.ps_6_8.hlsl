@@ -0,0 +1,54 @@
+#ifdef HALA_HLSL
+
+  #include "defines.hlsl"
+
+#else
+
+  #include "defines.glsl"
+
+#endif
+
+TEXTURE2D(3, 0, in_source);
+SAMPLER(3, 1, in_linear_sampler);
+
+#ifdef HALA_HLSL
+
+  struct ToFragment {
+    float4 position: SV_Position;
+    [[vk::location(0)]] float2 uv: TEXCOORD0;
+  };
+
+  struct FragmentOutput {
+    [[vk::location(0)]] float4 color: SV_Target0;
+  };
+
+  FragmentOutput main(ToFragment input) {
+    FragmentOutput output = (FragmentOutput)0;
+    #define IN_UV input.uv
+    #define OUT_COLOR output.color
+
+#else
+
+  layout(location = 0) in float2 in_uv;
+
+  layout(location = 0) out float4 out_color;
+
+  void main() {
+    #define IN_UV in_uv
+    #define OUT_COLOR out_color
+
+#endif
+
+  //////////////////////////////////////////////////////////////////////////
+  // Begin Function Code.
+
+  // The bilinear tap at the corner of the 2x2 source texels is their box filtered average.
+  OUT_COLOR = float4(SAMPLE_TEXTURE_LEVEL(in_source, in_linear_sampler, IN_UV, 0.0).rgb, 1.0);
+
+  // End Function Code.
+  //////////////////////////////////////////////////////////////////////////
+
+#ifdef HALA_HLSL
+  return output;
+#endif
+}
\ No newline at end of file
@@ -0,0 +1,82 @@
+// The number of the cosine weighted samples of each texel.
+#define IRRADIANCE_SAMPLE_COUNT 1024
+
+#ifdef HALA_HLSL
+
+  #include "defines.hlsl"
+  #include "octahedral.hlsl"
+  #include "brdf.hlsl"
+  #include "environment.hlsl"
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(0, 3)]]
+  Texture2D<float4> in_environment;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(0, 3)]]
+  SamplerState in_environment_sampler;
+
+#else
+
+  #include "defines.glsl"
+  #include "octahedral.glsl"
+  #include "brdf.glsl"
+  #include "environment.glsl"
+
+  layout(set = 3, binding = 0) uniform sampler2D in_environment;
+
+#endif
+
+BEGIN_PUSH_CONSTANTS(EnvironmentIrradiancePushConstants)
+  uint size;             // The width and height of the irradiance map.
+  uint equirect_width;   // The width of the first mip level of the equirectangular map.
+  uint equirect_height;  // The height of the first mip level of the equirectangular map.
+END_PUSH_CONSTANTS(EnvironmentIrradiancePushConstants, g_push_constants)
+
+#ifdef HALA_HLSL
+
+  struct ToFragment {
+    float4 position: SV_Position;
+  };
+
+  struct FragmentOutput {
+    [[vk::location(0)]] float4 color: SV_Target0;
+  };
+
+  FragmentOutput main(ToFragment input) {
+    FragmentOutput output = (FragmentOutput)0;
+    #define IN_POSITION input.position
+    #define OUT_COLOR output.color
+
+#else
+
+  layout(location = 0) out float4 out_color;
+
+  void main() {
+    #define IN_POSITION gl_FragCoord
+    #define OUT_COLOR out_color
+
+#endif
+
+  //////////////////////////////////////////////////////////////////////////
+  // Begin Function Code.
+
+  // The cosine weighted average radiance of the hemisphere around the normal of the texel.
+  const float3 n = decode_octahedral_normal(IN_POSITION.xy / float(g_push_constants.size));
+  const float2 equirect_size = float2(g_push_constants.equirect_width, g_push_constants.equirect_height);
+  float3 radiance = float3(0.0, 0.0, 0.0);
+  for (uint i = 0; i < IRRADIANCE_SAMPLE_COUNT; ++i) {
+    const float3 local_dir = sample_cosine_hemisphere(hammersley(i, IRRADIANCE_SAMPLE_COUNT));
+    const float3 l = to_world_direction(local_dir, n);
+    // Each sample reads the mip level covering its share of the hemisphere, the pdf is n_dot_l / PI.
+    const float level = get_equirect_sample_level(local_dir.z / PI, IRRADIANCE_SAMPLE_COUNT, l, equirect_size);
+    radiance += SAMPLE_COMBINED_TEXTURE_LEVEL(in_environment, get_equirect_uv(l), level).rgb;
+  }
+  OUT_COLOR = float4(radiance / float(IRRADIANCE_SAMPLE_COUNT), 1.0);
+
+  // End Function Code.
+  //////////////////////////////////////////////////////////////////////////
+
+#ifdef HALA_HLSL
+  return output;
+#endif
+}
\ No newline at end of file
@@ -0,0 +1,8 @@
+#version 460 core
+
+#extension GL_EXT_nonuniform_qualifier : require
+#extension GL_EXT_buffer_reference2 : require
+#extension GL_EXT_debug_printf : enable
+#extension GL_ARB_gpu_shader_int64 : enable
+
+#include "environment_prefilter.ps_6_8.hlsl"
\ No newline at end of file
@@ -0,0 +1,96 @@
+// The number of the GGX samples of each texel.
+#define PREFILTER_SAMPLE_COUNT 512
+
+#ifdef HALA_HLSL
+
+  #include "defines.hlsl"
+  #include "octahedral.hlsl"
+  #include "brdf.hlsl"
+  #include "environment.hlsl"
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(0, 3)]]
+  Texture2D<float4> in_environment;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(0, 3)]]
+  SamplerState in_environment_sampler;
+
+#else
+
+  #include "defines.glsl"
+  #include "octahedral.glsl"
+  #include "brdf.glsl"
+  #include "environment.glsl"
+
+  layout(set = 3, binding = 0) uniform sampler2D in_environment;
+
+#endif
+
+BEGIN_PUSH_CONSTANTS(EnvironmentPrefilterPushConstants)
+  uint size;        // The width and height of the mip level.
+  float roughness;  // The perceptual roughness of the mip level.
+  uint equirect_width;   // The width of the first mip level of the equirectangular map.
+  uint equirect_height;  // The height of the first mip level of the equirectangular map.
+END_PUSH_CONSTANTS(EnvironmentPrefilterPushConstants, g_push_constants)
+
+#ifdef HALA_HLSL
+
+  struct ToFragment {
+    float4 position: SV_Position;
+  };
+
+  struct FragmentOutput {
+    [[vk::location(0)]] float4 color: SV_Target0;
+  };
+
+  FragmentOutput main(ToFragment input) {
+    FragmentOutput output = (FragmentOutput)0;
+    #define IN_POSITION input.position
+    #define OUT_COLOR output.color
+
+#else
+
+  layout(location = 0) out float4 out_color;
+
+  void main() {
+    #define IN_POSITION gl_FragCoord
+    #define OUT_COLOR out_color
+
+#endif
+
+  //////////////////////////////////////////////////////////////////////////
+  // Begin Function Code.
+
+  // The texel of the octahedral map, assume the view and the normal directions are the reflection direction.
+  const float3 n = decode_octahedral_normal(IN_POSITION.xy / float(g_push_constants.size));
+  if (g_push_constants.roughness <= 0.0) {
+    OUT_COLOR = float4(SAMPLE_COMBINED_TEXTURE_LEVEL(in_environment, get_equirect_uv(n), 0.0).rgb, 1.0);
+  } else {
+    const float alpha = g_push_constants.roughness * g_push_constants.roughness;
+    const float2 equirect_size = float2(g_push_constants.equirect_width, g_push_constants.equirect_height);
+    float3 radiance = float3(0.0, 0.0, 0.0);
+    float total_weight = 0.0;
+    for (uint i = 0; i < PREFILTER_SAMPLE_COUNT; ++i) {
+      const float3 h = to_world_direction(importance_sample_ggx(hammersley(i, PREFILTER_SAMPLE_COUNT), alpha), n);
+      const float n_dot_h = dot(n, h);
+      const float3 l = 2.0 * n_dot_h * h - n;
+      const float n_dot_l = dot(n, l);
+      if (n_dot_l > 0.0) {
+        // The view is the normal, so the pdf of the light direction D * n_dot_h / (4 * v_dot_h) is D / 4.
+        // The sparse samples of the wide lobe read the lower mip levels instead of aliasing the bright texels.
+        const float pdf = distribution_ggx(n_dot_h, alpha) * 0.25;
+        const float level = get_equirect_sample_level(pdf, PREFILTER_SAMPLE_COUNT, l, equirect_size);
+        radiance += SAMPLE_COMBINED_TEXTURE_LEVEL(in_environment, get_equirect_uv(l), level).rgb * n_dot_l;
+        total_weight += n_dot_l;
+      }
+    }
+    OUT_COLOR = float4(radiance / max(total_weight, 1e-4), 1.0);
+  }
+
+  // End Function Code.
+  //////////////////////////////////////////////////////////////////////////
+
+#ifdef HALA_HLSL
+  return output;
+#endif
+}
\ No newline at end of file
@@ -0,0 +1,83 @@
+#define HALA_NO_GLOBAL_PUSH_CONSTANT
+
+#ifdef HALA_HLSL
+
+  #include "scene.hlsl"
+  #include "tonemapping.hlsl"
+
+  [[vk::binding(0, 3)]]
+  RWByteAddressBuffer inout_histogram;
+
+  [[vk::binding(1, 3)]]
+  RWByteAddressBuffer inout_exposure;
+
+  groupshared uint gs_weighted_counts[LUMINANCE_HISTOGRAM_BINS];
+
+#else
+
+  #include "scene.glsl"
+  #include "tonemapping.glsl"
+
+  layout(set = 3, binding = 0) buffer LuminanceHistogramBuffer {
+    uint inout_histogram[];
+  };
+
+  layout(set = 3, binding = 1) buffer ExposureBuffer {
+    uint inout_exposure[];
+  };
+
+  shared uint gs_weighted_counts[LUMINANCE_HISTOGRAM_BINS];
+
+#endif
+
+BEGIN_PUSH_CONSTANTS(ExposurePushConstants)
+  uint num_of_pixels;
+  float min_log_luminance;
+  float log_luminance_range;
+  float adaptation;  // The blend weight of the target luminance, 1 jumps to it.
+END_PUSH_CONSTANTS(ExposurePushConstants, g_push_constants)
+
+#ifdef HALA_HLSL
+
+  [numthreads(LUMINANCE_HISTOGRAM_BINS, 1, 1)]
+  void main(uint3 group_thread_id : SV_GroupThreadID) {
+
+#else
+
+  layout(local_size_x = LUMINANCE_HISTOGRAM_BINS, local_size_y = 1, local_size_z = 1) in;
+  void main() {
+    #define group_thread_id gl_LocalInvocationID
+
+#endif
+
+  // Each thread reads a bin and clears it for the next frame.
+  const uint bin = group_thread_id.x;
+  const uint count = LOAD_BUFFER(inout_histogram, bin * 4);
+  STORE_RWBUFFER(inout_histogram, bin * 4, 0u);
+  gs_weighted_counts[bin] = count * bin;
+
+  GroupMemoryBarrierWithGroupSync();
+
+  for (uint stride = LUMINANCE_HISTOGRAM_BINS / 2; stride > 0; stride >>= 1) {
+    if (bin < stride) {
+      gs_weighted_counts[bin] += gs_weighted_counts[bin + stride];
+    }
+    GroupMemoryBarrierWithGroupSync();
+  }
+
+  if (bin == 0) {
+    // The black pixels in the bin 0 are excluded from the average.
+    const float num_of_lit_pixels = max(float(g_push_constants.num_of_pixels - count), 1.0);
+    const float average_bin = max(float(gs_weighted_counts[0]) / num_of_lit_pixels - 1.0, 0.0);
+    const float log_luminance = average_bin / float(LUMINANCE_HISTOGRAM_BINS - 2) * g_push_constants.log_luminance_range + g_push_constants.min_log_luminance;
+    const float target_luminance = exp2(log_luminance);
+
+    // Adapt to the target luminance over the frames like the eye, the last luminance is undefined when jumping to it.
+    float luminance = target_luminance;
+    if (g_push_constants.adaptation < 1.0) {
+      const float last_luminance = asfloat(LOAD_BUFFER(inout_exposure, 0));
+      luminance = lerp(last_luminance, target_luminance, g_push_constants.adaptation);
+    }
+    STORE_RWBUFFER(inout_exposure, 0, asuint(luminance));
+  }
+}
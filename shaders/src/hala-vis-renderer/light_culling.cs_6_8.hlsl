@@ -0,0 +1,142 @@
+#define HALA_NO_GLOBAL_PUSH_CONSTANT
+
+#define LIGHT_CULLING_THREAD_WIDTH 8
+
+#ifdef HALA_HLSL
+
+  #include "scene.hlsl"
+  #include "lights.hlsl"
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(0, 3)]]
+  Texture2D<float> in_hiz_image;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(0, 3)]]
+  SamplerState in_hiz_sampler;
+
+  [[vk::binding(1, 3)]]
+  RWByteAddressBuffer out_light_clusters;
+
+  groupshared uint gs_tile_depth;
+
+#else
+
+  #include "scene.glsl"
+  #include "lights.glsl"
+
+  layout(set = 3, binding = 0) uniform sampler2D in_hiz_image;
+
+  layout(set = 3, binding = 1) buffer LightClustersBuffer {
+    uint out_light_clusters[];
+  };
+
+  shared uint gs_tile_depth;
+
+#endif
+
+BEGIN_PUSH_CONSTANTS(LightCullingPushConstants)
+  uint2 screen_size;
+  float2 proj_scale;  // The X and Y scales of the projection matrix.
+  uint num_of_tiles_x;
+  uint num_of_tiles;
+  uint num_of_lights;
+  uint hiz_level;
+  float z_near;
+END_PUSH_CONSTANTS(LightCullingPushConstants, g_push_constants)
+
+// Whether the sphere intersects the axis aligned box.
+bool is_sphere_box_overlapped(const float3 center, const float radius, const float3 box_min, const float3 box_max) {
+  const float3 closest = clamp(center, box_min, box_max);
+  const float3 d = closest - center;
+  return dot(d, d) <= radius * radius;
+}
+
+#ifdef HALA_HLSL
+
+  [numthreads(LIGHT_CULLING_THREAD_WIDTH, LIGHT_CULLING_THREAD_WIDTH, 1)]
+  void main(
+    uint3 group_id : SV_GroupID,
+    uint3 group_thread_id : SV_GroupThreadID,
+    uint3 dispatch_thread_id : SV_DispatchThreadID)
+  {
+
+#else
+
+  layout(local_size_x = LIGHT_CULLING_THREAD_WIDTH, local_size_y = LIGHT_CULLING_THREAD_WIDTH, local_size_z = 1) in;
+  void main() {
+    #define group_id gl_WorkGroupID
+    #define group_thread_id gl_LocalInvocationID
+    #define dispatch_thread_id gl_GlobalInvocationID
+
+#endif
+
+  const uint thread_index = group_thread_id.y * LIGHT_CULLING_THREAD_WIDTH + group_thread_id.x;
+  if (thread_index == 0) {
+    gs_tile_depth = asuint(1.0);
+  }
+
+  GroupMemoryBarrierWithGroupSync();
+
+  // Find the farthest depth of the tile in the Hi-Z buffer, it keeps the minimum of the reversed-Z depth.
+  // The first Hi-Z level is in the half resolution.
+  const uint hiz_texel_size = 2u << g_push_constants.hiz_level;
+  const uint num_of_texels = max(LIGHT_TILE_SIZE / hiz_texel_size, 1u);
+  const uint2 hiz_size = max(g_push_constants.screen_size / hiz_texel_size, uint2(1, 1));
+  const uint2 hiz_base = group_id.xy * num_of_texels;
+  for (uint y = group_thread_id.y; y < num_of_texels; y += LIGHT_CULLING_THREAD_WIDTH) {
+    for (uint x = group_thread_id.x; x < num_of_texels; x += LIGHT_CULLING_THREAD_WIDTH) {
+      const uint2 hiz_pos = min(hiz_base + uint2(x, y), hiz_size - uint2(1, 1));
+      const float depth = LOAD_SAMPLE(in_hiz_image, hiz_pos, g_push_constants.hiz_level).x;
+      uint orig;
+      // The positive floats keep their order as uints.
+      INTERLOCKED_MIN(gs_tile_depth, asuint(max(depth, 0.0)), orig);
+    }
+  }
+
+  GroupMemoryBarrierWithGroupSync();
+
+  const float z_near = g_push_constants.z_near;
+  const float tile_far = z_near / max(asfloat(gs_tile_depth), LIGHT_CLUSTER_MIN_DEPTH);
+  const uint tile_index = group_id.y * g_push_constants.num_of_tiles_x + group_id.x;
+  if (thread_index == 0) {
+    STORE_RWBUFFER(out_light_clusters, get_light_tile_far_address(g_push_constants.num_of_tiles, tile_index), asuint(tile_far));
+  }
+
+  // One thread for each slice.
+  if (thread_index >= LIGHT_NUM_OF_SLICES) {
+    return;
+  }
+
+  // The view space bounding box of the cluster, the camera looks at -Z.
+  const float2 tile_min = float2(group_id.xy * LIGHT_TILE_SIZE) / float2(g_push_constants.screen_size);
+  const float2 tile_max = min(float2((group_id.xy + uint2(1, 1)) * LIGHT_TILE_SIZE) / float2(g_push_constants.screen_size), float2(1.0, 1.0));
+  const float2 ndc_min = float2(tile_min.x, 1.0 - tile_max.y) * 2.0 - 1.0;
+  const float2 ndc_max = float2(tile_max.x, 1.0 - tile_min.y) * 2.0 - 1.0;
+  const float slice_near = get_light_slice_distance(z_near, tile_far, float(thread_index));
+  // The last slice reaches the infinity in case the Hi-Z buffer misses some pixels of the tile.
+  const float slice_far = thread_index == LIGHT_NUM_OF_SLICES - 1 ? 1e30 : get_light_slice_distance(z_near, tile_far, float(thread_index + 1));
+  const float2 scale_min = min(ndc_min / g_push_constants.proj_scale, ndc_max / g_push_constants.proj_scale);
+  const float2 scale_max = max(ndc_min / g_push_constants.proj_scale, ndc_max / g_push_constants.proj_scale);
+  const float3 box_min = float3(
+    min(scale_min * slice_near, scale_min * slice_far),
+    -slice_far
+  );
+  const float3 box_max = float3(
+    max(scale_max * slice_near, scale_max * slice_far),
+    -slice_near
+  );
+
+  uint light_mask = 0;
+  for (uint i = 0; i < g_push_constants.num_of_lights; ++i) {
+    const Light light = g_lights.data[i];
+    if (light.type == LIGHT_TYPE_DIRECTIONAL) {
+      light_mask |= 0x1u << i;
+    } else if (light.type == LIGHT_TYPE_POINT || light.type == LIGHT_TYPE_SPOT) {
+      const float3 center = mul(g_global_uniform.v_mtx, float4(light.position, 1.0)).xyz;
+      if (is_sphere_box_overlapped(center, get_light_range(light, get_scene_light_range(i)), box_min, box_max)) {
+        light_mask |= 0x1u << i;
+      }
+    }
+  }
+  STORE_RWBUFFER(out_light_clusters, get_light_cluster_address(tile_index, thread_index), light_mask);
+}
@@ -0,0 +1,13 @@
+#define LIGHTING_DEBUG_NONE 0
+#define LIGHTING_DEBUG_LIGHT_COUNT 1
+#define LIGHTING_DEBUG_SHADOW_CASCADES 2
+#define LIGHTING_DEBUG_AMBIENT_OCCLUSION 3
+
+BEGIN_PUSH_CONSTANTS(LightingPushConstants)
+  uint num_of_tiles_x;
+  uint num_of_tiles;
+  float z_near;
+  uint debug_mode;
+  float environment_intensity;
+  uint use_ambient_occlusion;  // Whether the screen space ambient occlusion is computed in this frame.
+END_PUSH_CONSTANTS(LightingPushConstants, g_push_constants)
@@ -3,6 +3,14 @@
 #ifdef HALA_HLSL
 
   #include "scene.hlsl"
+  #include "octahedral.hlsl"
+  #include "brdf.hlsl"
+  #include "lights.hlsl"
+  #include "shadow.hlsl"
+  #include "direct_lighting.hlsl"
+  #include "environment.hlsl"
+  #include "color_mapping.hlsl"
+  #include "lighting.hlsl"
 
   [[vk::input_attachment_index(0)]]
   [[vk::binding(0, 3)]]
@@ -10,12 +18,55 @@
 
   [[vk::input_attachment_index(1)]]
   [[vk::binding(1, 3)]]
-  SubpassInput<float3> in_normal_image;
+  SubpassInput<float2> in_normal_image;
 
   [[vk::input_attachment_index(2)]]
   [[vk::binding(2, 3)]]
   SubpassInput<float> in_depth_image;
 
+  [[vk::input_attachment_index(3)]]
+  [[vk::binding(3, 3)]]
+  SubpassInput<float3> in_material_image;
+
+  [[vk::input_attachment_index(4)]]
+  [[vk::binding(4, 3)]]
+  SubpassInput<float3> in_emissive_image;
+
+  [[vk::binding(5, 3)]]
+  ByteAddressBuffer in_light_clusters;
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(6, 3)]]
+  Texture2D<float> in_shadow_map;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(6, 3)]]
+  SamplerState in_shadow_map_sampler;
+
+  BEGIN_UNIFORM_BUFFER(3, 7, ShadowUniform)
+    ShadowData data;
+  END_UNIFORM_BUFFER(3, 7, ShadowUniform, g_shadow)
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(8, 3)]]
+  Texture2D<float4> in_specular_environment;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(8, 3)]]
+  SamplerState in_specular_environment_sampler;
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(9, 3)]]
+  Texture2D<float4> in_irradiance_environment;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(9, 3)]]
+  SamplerState in_irradiance_environment_sampler;
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(10, 3)]]
+  Texture2D<float> in_ambient_occlusion;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(10, 3)]]
+  SamplerState in_ambient_occlusion_sampler;
+
   struct ToFragment {
     float4 position: SV_Position;
     [[vk::location(0)]] float2 uv: TEXCOORD0;
@@ -23,28 +74,57 @@
 
   struct FragmentOutput {
     [[vk::location(0)]] float4 color: SV_Target0;
+    float depth: SV_Depth;
   };
 
   FragmentOutput main(ToFragment input) {
     FragmentOutput output = (FragmentOutput)0;
+    #define IN_POSITION input.position
     #define IN_UV input.uv
     #define OUT_COLOR output.color
+    #define OUT_DEPTH output.depth
 
 #else
 
   #include "scene.glsl"
+  #include "octahedral.glsl"
+  #include "brdf.glsl"
+  #include "lights.glsl"
+  #include "shadow.glsl"
+  #include "direct_lighting.glsl"
+  #include "environment.glsl"
+  #include "color_mapping.glsl"
+  #include "hala-vis-renderer\lighting.hlsl"
 
   layout(input_attachment_index = 0, binding = 0, set = 3) uniform subpassInput in_albedo_image;
   layout(input_attachment_index = 1, binding = 1, set = 3) uniform subpassInput in_normal_image;
   layout(input_attachment_index = 2, binding = 2, set = 3) uniform subpassInput in_depth_image;
+  layout(input_attachment_index = 3, binding = 3, set = 3) uniform subpassInput in_material_image;
+  layout(input_attachment_index = 4, binding = 4, set = 3) uniform subpassInput in_emissive_image;
+
+  layout(set = 3, binding = 5) readonly buffer LightClustersBuffer {
+    uint in_light_clusters[];
+  };
+
+  layout(set = 3, binding = 6) uniform sampler2D in_shadow_map;
+
+  BEGIN_UNIFORM_BUFFER(3, 7, ShadowUniform)
+    ShadowData data;
+  END_UNIFORM_BUFFER(3, 7, ShadowUniform, g_shadow)
+
+  layout(set = 3, binding = 8) uniform sampler2D in_specular_environment;
+  layout(set = 3, binding = 9) uniform sampler2D in_irradiance_environment;
+  layout(set = 3, binding = 10) uniform sampler2D in_ambient_occlusion;
 
   layout(location = 0) in float2 in_uv;
 
   layout(location = 0) out float4 out_color;
 
   void main() {
+    #define IN_POSITION gl_FragCoord
     #define IN_UV in_uv
     #define OUT_COLOR out_color
+    #define OUT_DEPTH gl_FragDepth
 
 #endif
 
@@ -52,27 +132,94 @@
   // Begin Function Code.
 
   const float3 albedo = LOAD_SUBPASS_INPUT(in_albedo_image).rgb;
-  const float3 normal = LOAD_SUBPASS_INPUT(in_normal_image).xyz * 2.0 - 1.0;
+  const float3 normal = decode_octahedral_normal(LOAD_SUBPASS_INPUT(in_normal_image).xy);
   const float depth = LOAD_SUBPASS_INPUT(in_depth_image).x;
+  const float3 material = LOAD_SUBPASS_INPUT(in_material_image).rgb;
+  const float metallic = material.r;
+  const float roughness = material.g;
+  // The baked occlusion of the material.
+  const float occlusion = material.b;
+  const float3 emissive = LOAD_SUBPASS_INPUT(in_emissive_image).rgb;
 
   if (depth <= 0.0) {
     discard;
   }
+  // Copy the scene depth to the target, the forward transparency is depth tested against it.
+  OUT_DEPTH = depth;
 
   const float2 pos_ndc = float2(IN_UV.x, 1.0 - IN_UV.y);
   const float4 clip_pos = float4(pos_ndc * 2.0 - 1.0, depth, 1.0);
   const float4 world_w = mul(g_global_uniform.i_vp_mtx, clip_pos);
   const float3 pos = world_w.xyz * rcp(world_w.w);
+  const float3 view_dir = normalize(g_global_uniform.camera_position.xyz - pos);
+
+  // Find the cluster of the pixel, the view distance of the reversed-Z infinite projection is z_near / depth.
+  const uint2 tile_pos = uint2(IN_POSITION.xy) / LIGHT_TILE_SIZE;
+  const uint tile_index = tile_pos.y * g_push_constants.num_of_tiles_x + tile_pos.x;
+  const float tile_far = asfloat(LOAD_BUFFER(in_light_clusters, get_light_tile_far_address(g_push_constants.num_of_tiles, tile_index)));
+  const float z_near = g_push_constants.z_near;
+  const float view_distance = z_near / depth;
+  const uint slice = get_light_slice(z_near, tile_far, view_distance);
+  const uint light_mask = LOAD_BUFFER(in_light_clusters, get_light_cluster_address(tile_index, slice));
+
+  if (g_push_constants.debug_mode == LIGHTING_DEBUG_LIGHT_COUNT) {
+    const float light_count = float(countbits(light_mask));
+    OUT_COLOR = float4(light_count > 0.0 ? color_map_turbo(light_count / float(MAX_LIGHTS)) : float3(0.0, 0.0, 0.0), 1.0);
+#ifdef HALA_HLSL
+    return output;
+#else
+    return;
+#endif
+  }
 
-  const Light light = g_lights.data[0];
-  const float3 light_2_surface = light.position - pos;
-  const float light_distance_sq = dot(light_2_surface, light_2_surface);
-  const float attenuation = rcp(light_distance_sq);
-  const float3 light_dir = light_2_surface * rsqrt(light_distance_sq);
+  // The ambient occlusion may be in the half resolution, it is upsampled by the bilinear filter.
+  float ambient_occlusion = 1.0;
+  if (g_push_constants.use_ambient_occlusion != 0) {
+    ambient_occlusion = SAMPLE_COMBINED_TEXTURE_LEVEL(in_ambient_occlusion, get_render_target_uv(IN_UV), 0.0).r;
+  }
+  if (g_push_constants.debug_mode == LIGHTING_DEBUG_AMBIENT_OCCLUSION) {
+    OUT_COLOR = float4(ambient_occlusion, ambient_occlusion, ambient_occlusion, 1.0);
+#ifdef HALA_HLSL
+    return output;
+#else
+    return;
+#endif
+  }
 
-  const float intensity = max(dot(normal, light_dir) * attenuation, 0.0);
+  const uint shadow_cascade = get_shadow_cascade(g_shadow.data, view_distance);
+  const float3 direct = evaluate_direct_lighting(
+    in_shadow_map,
+    g_shadow.data,
+    shadow_cascade,
+    light_mask,
+    pos,
+    normal,
+    view_dir,
+    albedo,
+    metallic,
+    roughness
+  );
+
+  // The environment intensity is 0 without the environment map.
+  float3 ambient;
+  if (g_push_constants.environment_intensity > 0.0) {
+    ambient = evaluate_environment_lighting(
+      COMBINED_TEXTURE_ARG(in_specular_environment),
+      COMBINED_TEXTURE_ARG(in_irradiance_environment),
+      normal,
+      view_dir,
+      albedo,
+      metallic,
+      roughness
+    ) * g_push_constants.environment_intensity * occlusion * ambient_occlusion;
+  } else {
+    ambient = AMBIENT_INTENSITY * albedo * (1.0 - metallic) * occlusion * ambient_occlusion;
+  }
 
-  OUT_COLOR = float4(light.intensity * intensity * albedo, 1.0);
+  OUT_COLOR = float4(direct + ambient + emissive, 1.0);
+  if (g_push_constants.debug_mode == LIGHTING_DEBUG_SHADOW_CASCADES) {
+    OUT_COLOR.rgb *= get_shadow_cascade_color(shadow_cascade);
+  }
 
   // End Function Code.
   //////////////////////////////////////////////////////////////////////////
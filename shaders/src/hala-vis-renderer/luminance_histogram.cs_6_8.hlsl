@@ -0,0 +1,81 @@
+#define HALA_NO_GLOBAL_PUSH_CONSTANT
+
+#define LUMINANCE_HISTOGRAM_THREAD_WIDTH 16
+
+#ifdef HALA_HLSL
+
+  #include "scene.hlsl"
+  #include "tonemapping.hlsl"
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(0, 3)]]
+  Texture2D<float4> in_scene_color;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(0, 3)]]
+  SamplerState in_scene_color_sampler;
+
+  [[vk::binding(1, 3)]]
+  RWByteAddressBuffer out_histogram;
+
+  groupshared uint gs_histogram[LUMINANCE_HISTOGRAM_BINS];
+
+#else
+
+  #include "scene.glsl"
+  #include "tonemapping.glsl"
+
+  layout(set = 3, binding = 0) uniform sampler2D in_scene_color;
+
+  layout(set = 3, binding = 1) buffer LuminanceHistogramBuffer {
+    uint out_histogram[];
+  };
+
+  shared uint gs_histogram[LUMINANCE_HISTOGRAM_BINS];
+
+#endif
+
+BEGIN_PUSH_CONSTANTS(LuminanceHistogramPushConstants)
+  uint2 screen_size;
+  float min_log_luminance;
+  float inv_log_luminance_range;
+END_PUSH_CONSTANTS(LuminanceHistogramPushConstants, g_push_constants)
+
+#ifdef HALA_HLSL
+
+  [numthreads(LUMINANCE_HISTOGRAM_THREAD_WIDTH, LUMINANCE_HISTOGRAM_THREAD_WIDTH, 1)]
+  void main(
+    uint3 group_thread_id : SV_GroupThreadID,
+    uint3 dispatch_thread_id : SV_DispatchThreadID)
+  {
+
+#else
+
+  layout(local_size_x = LUMINANCE_HISTOGRAM_THREAD_WIDTH, local_size_y = LUMINANCE_HISTOGRAM_THREAD_WIDTH, local_size_z = 1) in;
+  void main() {
+    #define group_thread_id gl_LocalInvocationID
+    #define dispatch_thread_id gl_GlobalInvocationID
+
+#endif
+
+  // Each thread of the group owns a bin.
+  const uint thread_index = group_thread_id.y * LUMINANCE_HISTOGRAM_THREAD_WIDTH + group_thread_id.x;
+  gs_histogram[thread_index] = 0;
+
+  GroupMemoryBarrierWithGroupSync();
+
+  if (dispatch_thread_id.x < g_push_constants.screen_size.x && dispatch_thread_id.y < g_push_constants.screen_size.y) {
+    const float3 color = LOAD_SAMPLE(in_scene_color, dispatch_thread_id.xy, 0).rgb;
+    const uint bin = get_luminance_histogram_bin(get_luminance(color), g_push_constants.min_log_luminance, g_push_constants.inv_log_luminance_range);
+    uint orig;
+    INTERLOCKED_ADD(gs_histogram[bin], 1u, orig);
+  }
+
+  GroupMemoryBarrierWithGroupSync();
+
+  // Merge the group histogram into the global one, which is cleared by the exposure pass.
+  const uint count = gs_histogram[thread_index];
+  if (count > 0) {
+    uint orig;
+    INTERLOCKED_ADD_RWBUFFER(out_histogram, thread_index * 4, count, orig);
+  }
+}
@@ -7,22 +7,15 @@
 
   [[vk::combinedImageSampler]]
   [[vk::binding(0, 3)]]
-  Texture2D<uint> in_vis_image;
+  Texture2D<float> in_material_depth_image;
   [[vk::combinedImageSampler]]
   [[vk::binding(0, 3)]]
-  SamplerState in_vis_sampler;
+  SamplerState in_material_depth_sampler;
 
-  [[vk::combinedImageSampler]]
-  [[vk::binding(1, 3)]]
-  Texture2D<float> in_depth_image;
-  [[vk::combinedImageSampler]]
   [[vk::binding(1, 3)]]
-  SamplerState in_depth_sampler;
-
-  [[vk::binding(2, 3)]]
   RWByteAddressBuffer out_indirect_draw_arguments;
 
-  [[vk::binding(3, 3)]]
+  [[vk::binding(2, 3)]]
   RWByteAddressBuffer out_tile_index;
 
   groupshared uint gs_material_flag[CLASSIFY_NUM_OF_MATERIALS_PER_GROUP];
@@ -34,14 +27,13 @@
   #include "scene.glsl"
   #include "hala-vis-renderer\visibility.glsl"
 
-  layout(set = 3, binding = 0) uniform usampler2D in_vis_image;
-  layout(set = 3, binding = 1) uniform sampler2D in_depth_image;
+  layout(set = 3, binding = 0) uniform sampler2D in_material_depth_image;
 
-  layout(set = 3, binding = 2) buffer IndirectDrawArgumentsBuffer {
+  layout(set = 3, binding = 1) buffer IndirectDrawArgumentsBuffer {
     uint out_indirect_draw_arguments[];
   };
 
-  layout(set = 3, binding = 3) buffer TileIndexBuffer {
+  layout(set = 3, binding = 2) buffer TileIndexBuffer {
     uint out_tile_index[];
   };
 
@@ -60,19 +52,14 @@ END_PUSH_CONSTANTS(MaterialClassifyPushConstants, g_push_constants)
 
 void classify_pixel(in uint2 pos) {
   if (all(lessThan(pos, g_push_constants.screen_size))) {
-    const float depth = LOAD_SAMPLE(in_depth_image, pos, 0).x;
+    // The material depth is the material index divided by the depth range, and 1.0 for the background.
+    const float material_depth = LOAD_SAMPLE(in_material_depth_image, pos, 0).x;
 
     ANNOTATION_BRANCH
-    if (depth > 0.0) {
-      const uint id = LOAD_SAMPLE(in_vis_image, pos, 0).x;
-      uint meshlet_index, triangle_id;
-      unpack_meshlet_triangle_index(id, meshlet_index, triangle_id);
-
-      const Meshlet meshlet = g_global_meshlets[meshlet_index];
-      const DrawData draw_data = g_draw_data[meshlet.draw_index];
-      const uint material_index = draw_data.material_index;
-      const uint index = draw_data.material_index / 32;
-      const uint bit = draw_data.material_index % 32;
+    if (material_depth < 1.0) {
+      const uint material_index = (uint)round(material_depth * (float)CLASSIFY_DEPTH_RANGE);
+      const uint index = material_index / 32;
+      const uint bit = material_index % 32;
       uint orig;
       INTERLOCKED_OR(gs_material_flag[index], 0x1u << bit, orig);
     }
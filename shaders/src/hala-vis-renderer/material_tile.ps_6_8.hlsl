@@ -5,6 +5,7 @@
   #include "scene.hlsl"
   #include "visibility.hlsl"
   #include "material_tile.hlsl"
+  #include "octahedral.hlsl"
 
   [[vk::input_attachment_index(0)]]
   [[vk::binding(1, 3)]]
@@ -13,6 +14,9 @@
   struct FragmentOutput {
     [[vk::location(0)]] float4 albedo: SV_Target0;
     [[vk::location(1)]] float4 normal: SV_Target1;
+    [[vk::location(2)]] float4 material: SV_Target2;
+    [[vk::location(3)]] float4 emissive: SV_Target3;
+    [[vk::location(4)]] float2 motion: SV_Target4;
   };
 
   FragmentOutput main(ToFragment input) {
@@ -20,25 +24,36 @@
     FragmentOutput output = (FragmentOutput)0;
     #define OUT_ALBEDO output.albedo
     #define OUT_NORMAL output.normal
+    #define OUT_MATERIAL output.material
+    #define OUT_EMISSIVE output.emissive
+    #define OUT_MOTION output.motion
 
 #else
 
   #include "scene.glsl"
   #include "hala-vis-renderer\visibility.glsl"
   #include "hala-vis-renderer\material_tile.hlsl"
+  #include "octahedral.glsl"
 
   layout(input_attachment_index = 0, binding = 1, set = 3) uniform usubpassInput in_vis_image;
 
   layout(location = 0) out float4 out_albedo;
   layout(location = 1) out float4 out_normal;
+  layout(location = 2) out float4 out_material;
+  layout(location = 3) out float4 out_emissive;
+  layout(location = 4) out float2 out_motion;
 
   void main() {
     #define IN_POSITION gl_FragCoord
     #define OUT_ALBEDO out_albedo
     #define OUT_NORMAL out_normal
+    #define OUT_MATERIAL out_material
+    #define OUT_EMISSIVE out_emissive
+    #define OUT_MOTION out_motion
 
     #define g_global_meshlets (g_global_meshlets.data)
     #define g_draw_data (g_draw_data.data)
+    #define g_draw_material_flags (g_draw_material_flags.data)
 
 #endif
 
@@ -72,7 +87,74 @@
     OUT_ALBEDO = float4(mtrl.base_color, 1.0);
   }
 
-  OUT_NORMAL = float4(vertex_attributes.normal * 0.5 + 0.5, 1.0);
+  // The metallic-roughness map stores the roughness in G and the metallic in B as glTF.
+  float metallic = mtrl.metallic;
+  float roughness = mtrl.roughness;
+  if (mtrl.metallic_roughness_map_index != INVALID_INDEX) {
+    const float4 metallic_roughness = SAMPLE_TEXTURE_GRAD(
+      g_textures[mtrl.metallic_roughness_map_index],
+      g_samplers[mtrl.metallic_roughness_map_index],
+      vertex_attributes.texcoord,
+      vertex_attributes.texcoord_ddx,
+      vertex_attributes.texcoord_ddy
+    );
+    metallic *= metallic_roughness.b;
+    roughness *= metallic_roughness.g;
+  }
+  // The occlusion map stores the baked occlusion in R as glTF, it only darkens the ambient term.
+  const DrawMaterialFlags draw_material_flags = g_draw_material_flags[meshlet.draw_index];
+  float occlusion = 1.0;
+  if (draw_material_flags.occlusion_map_index != INVALID_INDEX) {
+    const float map_occlusion = SAMPLE_TEXTURE_GRAD(
+      g_textures[draw_material_flags.occlusion_map_index],
+      g_samplers[draw_material_flags.occlusion_map_index],
+      vertex_attributes.texcoord,
+      vertex_attributes.texcoord_ddx,
+      vertex_attributes.texcoord_ddy
+    ).r;
+    occlusion = 1.0 + draw_material_flags.occlusion_strength * (map_occlusion - 1.0);
+  }
+  OUT_MATERIAL = float4(saturate(metallic), saturate(roughness), occlusion, 1.0);
+
+  float3 emissive = mtrl.emission;
+  if (mtrl.emission_map_index != INVALID_INDEX) {
+    emissive *= SAMPLE_TEXTURE_GRAD(
+      g_textures[mtrl.emission_map_index],
+      g_samplers[mtrl.emission_map_index],
+      vertex_attributes.texcoord,
+      vertex_attributes.texcoord_ddx,
+      vertex_attributes.texcoord_ddy
+    ).rgb;
+  }
+  OUT_EMISSIVE = float4(emissive, 1.0);
+
+  float3 normal = normalize(vertex_attributes.normal);
+  // The back faces of the double-sided meshlets are shaded with the flipped normal.
+  if ((draw_material_flags.flags & DRAW_FLAG_DOUBLE_SIDED) != 0 && dot(normal, g_global_uniform.camera_position.xyz - vertex_attributes.position) < 0.0) {
+    normal = -normal;
+  }
+  if (mtrl.normal_map_index != INVALID_INDEX) {
+    const float3 tangent_normal = SAMPLE_TEXTURE_GRAD(
+      g_textures[mtrl.normal_map_index],
+      g_samplers[mtrl.normal_map_index],
+      vertex_attributes.texcoord,
+      vertex_attributes.texcoord_ddx,
+      vertex_attributes.texcoord_ddy
+    ).rgb * 2.0 - 1.0;
+    // Orthogonalize the interpolated tangent, the degenerated frame keeps the vertex normal.
+    const float3 tangent = vertex_attributes.tangent - normal * dot(normal, vertex_attributes.tangent);
+    if (dot(tangent, tangent) > 1e-8) {
+      const float3 t = normalize(tangent);
+      const float3 b = cross(normal, t) * vertex_attributes.bitangent_sign;
+      normal = normalize(t * tangent_normal.x + b * tangent_normal.y + normal * tangent_normal.z);
+    }
+  }
+
+  // The normal is octahedral encoded to keep the precision in the compact G-Buffer.
+  OUT_NORMAL = float4(encode_octahedral_normal(normal), 0.0, 1.0);
+
+  // The motion vectors reproject the temporal anti-aliasing history.
+  OUT_MOTION = vertex_attributes.motion;
 
   // End Function Code.
   //////////////////////////////////////////////////////////////////////////
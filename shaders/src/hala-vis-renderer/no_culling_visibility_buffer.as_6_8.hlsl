@@ -1,9 +1,23 @@
 #define USE_MESH_SHADER
+#define HALA_NO_GLOBAL_PUSH_CONSTANT
 
 #ifdef HALA_GLSL
 
   #include "scene.glsl"
 
+#else
+
+  #include "scene.hlsl"
+
+#endif
+
+BEGIN_PUSH_CONSTANTS(PushConstants)
+  uint meshlet_count;
+  uint material_variant;
+END_PUSH_CONSTANTS(PushConstants, g_push_constants)
+
+#ifdef HALA_GLSL
+
   layout(local_size_x = TASK_SHADER_GROUP_SIZE, local_size_y = 1, local_size_z = 1) in;
 
   taskPayloadSharedEXT MeshShaderPayLoad ms_payload;
@@ -15,8 +29,6 @@
 
 #else
 
-#include "scene.hlsl"
-
   groupshared MeshShaderPayLoad ms_payload;
 
   [numthreads(TASK_SHADER_GROUP_SIZE, 1, 1)]
@@ -37,18 +49,23 @@
 
 #ifdef HALA_GLSL
   const Meshlet meshlet = g_global_meshlets.data[meshlet_index];
-  const DrawData draw_data = g_draw_data.data[meshlet.draw_index];
-  #define per_object_data (g_per_object_uniforms[draw_data.object_index])
+  const DrawMaterialFlags draw_material_flags = g_draw_material_flags.data[meshlet.draw_index];
 #else
   const Meshlet meshlet = g_global_meshlets[meshlet_index];
-  const DrawData draw_data = g_draw_data[meshlet.draw_index];
-  const ObjectUniform per_object_data = g_per_object_uniforms[draw_data.object_index];
+  const DrawMaterialFlags draw_material_flags = g_draw_material_flags[meshlet.draw_index];
 #endif
 
-  ms_payload.meshlet_indices[group_thread_id.x] = meshlet_index;
+  // Only the meshlets of the material variant are drawn by this pipeline.
+  const bool is_visible = (draw_material_flags.flags & MATERIAL_VARIANT_MASK) == g_push_constants.material_variant;
+  if (is_visible) {
+    const uint index = WavePrefixCountBits(is_visible);
+    ms_payload.meshlet_indices[index] = meshlet_index;
+  }
 
   // One meshlet to one mesh group.
-  DISPATCH_MESH(TASK_SHADER_GROUP_SIZE, 1, 1, ms_payload);
+  const uint visible_count = WaveActiveCountBits(is_visible);
+  add_drawn_meshlets(visible_count);
+  DISPATCH_MESH(visible_count, 1, 1, ms_payload);
   // End Function Code.
   //////////////////////////////////////////////////////////////////////////
 }
@@ -21,6 +21,7 @@ BEGIN_PUSH_CONSTANTS(PushConstants)
   uint meshlet_count;
   uint hiz_levels;
   uint2 hiz_size;
+  uint material_variant;
 END_PUSH_CONSTANTS(PushConstants, g_push_constants)
 
 #ifdef HALA_GLSL
@@ -57,25 +58,32 @@ END_PUSH_CONSTANTS(PushConstants, g_push_constants)
 #ifdef HALA_GLSL
   const Meshlet meshlet = g_global_meshlets.data[meshlet_index];
   const DrawData draw_data = g_draw_data.data[meshlet.draw_index];
+  const DrawMaterialFlags draw_material_flags = g_draw_material_flags.data[meshlet.draw_index];
   #define per_object_data (g_per_object_uniforms[draw_data.object_index])
 #else
   const Meshlet meshlet = g_global_meshlets[meshlet_index];
   const DrawData draw_data = g_draw_data[meshlet.draw_index];
+  const DrawMaterialFlags draw_material_flags = g_draw_material_flags[meshlet.draw_index];
   const ObjectUniform per_object_data = g_per_object_uniforms[draw_data.object_index];
 #endif
-  const float3 camera_position = g_cameras.data[0].position;
+  // Only the meshlets of the material variant are drawn by this pipeline.
+  const bool is_in_variant = (draw_material_flags.flags & MATERIAL_VARIANT_MASK) == g_push_constants.material_variant;
+  const float3 camera_position = g_global_uniform.camera_position.xyz;
 
   // printf("[TASK SHADER] Draw Index: %d\n", meshlet.draw_index);
   // printf("[TASK SHADER] Material Index: %d\n", draw_data.material_index);
 
-  bool is_visible = true;
+  bool is_visible = is_in_variant;
   bool is_occluded_by_hiz = false;
 
-  const float3 cone_apex = mul(per_object_data.m_mtx, float4(meshlet.cone_apex, 1.0)).xyz;
-  const float3 cone_axis = normalize(mul(float4(meshlet.cone_axis, 0.0), per_object_data.i_m_mtx).xyz);
-  if (dot(normalize(cone_apex - camera_position), cone_axis) >= meshlet.cone_cutoff) {
-    is_visible = false;
-    // printf("[TASK SHADER] Draw Index %d Meshlet %d is culled by cone test.\n", meshlet.draw_index, meshlet_index);
+  // The back faces of the double-sided meshlets are visible, so they skip the cone test.
+  if (is_visible && (draw_material_flags.flags & DRAW_FLAG_DOUBLE_SIDED) == 0) {
+    const float3 cone_apex = mul(per_object_data.m_mtx, float4(meshlet.cone_apex, 1.0)).xyz;
+    const float3 cone_axis = normalize(mul(float4(meshlet.cone_axis, 0.0), per_object_data.i_m_mtx).xyz);
+    if (dot(normalize(cone_apex - camera_position), cone_axis) >= meshlet.cone_cutoff) {
+      is_visible = false;
+      // printf("[TASK SHADER] Draw Index %d Meshlet %d is culled by cone test.\n", meshlet.draw_index, meshlet_index);
+    }
   }
 
   if (is_visible) {
@@ -110,6 +118,7 @@ END_PUSH_CONSTANTS(PushConstants, g_push_constants)
 
   // One meshlet to one mesh group.
   const uint visible_count = WaveActiveCountBits(is_visible);
+  add_drawn_meshlets(visible_count);
   DISPATCH_MESH(visible_count, 1, 1, ms_payload);
   // End Function Code.
   //////////////////////////////////////////////////////////////////////////
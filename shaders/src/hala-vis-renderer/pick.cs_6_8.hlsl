@@ -0,0 +1,87 @@
+#define HALA_NO_GLOBAL_PUSH_CONSTANT
+
+// NOTICE: Keep it same as PICK_NONE in picking.rs.
+#define PICK_NONE 0xFFFFFFFFu
+
+#ifdef HALA_HLSL
+
+  #include "scene.hlsl"
+  #include "visibility.hlsl"
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(0, 3)]]
+  Texture2D<uint> in_visibility;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(0, 3)]]
+  SamplerState in_visibility_sampler;
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(1, 3)]]
+  Texture2D<float> in_depth;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(1, 3)]]
+  SamplerState in_depth_sampler;
+
+  [[vk::binding(2, 3)]]
+  RWByteAddressBuffer out_pick;
+
+#else
+
+  #include "scene.glsl"
+  #include "hala-vis-renderer/visibility.glsl"
+
+  layout(set = 3, binding = 0) uniform usampler2D in_visibility;
+  layout(set = 3, binding = 1) uniform sampler2D in_depth;
+
+  layout(set = 3, binding = 2) buffer PickBuffer {
+    uint out_pick[];
+  };
+
+#endif
+
+BEGIN_PUSH_CONSTANTS(PickPushConstants)
+  uint2 texel;
+END_PUSH_CONSTANTS(PickPushConstants, g_push_constants)
+
+#ifdef HALA_HLSL
+
+  [numthreads(1, 1, 1)]
+  void main() {
+
+#else
+
+  layout(local_size_x = 1, local_size_y = 1, local_size_z = 1) in;
+  void main() {
+
+#endif
+
+  //////////////////////////////////////////////////////////////////////////
+  // Begin Function Code.
+
+  // The visibility buffer is cleared to 0, which is also a valid ID, so the background is found by the depth.
+  const float depth = LOAD_SAMPLE(in_depth, g_push_constants.texel, 0).r;
+  if (depth <= 0.0) {
+    STORE_RWBUFFER(out_pick, 0, PICK_NONE);
+    STORE_RWBUFFER(out_pick, 4, 0u);
+    STORE_RWBUFFER(out_pick, 8, 0u);
+    STORE_RWBUFFER(out_pick, 12, asuint(depth));
+    return;
+  }
+
+  const uint id = LOAD_SAMPLE(in_visibility, g_push_constants.texel, 0).r;
+  uint meshlet_index, triangle_index;
+  unpack_meshlet_triangle_index(id, meshlet_index, triangle_index);
+#ifdef HALA_GLSL
+  const Meshlet meshlet = g_global_meshlets.data[meshlet_index];
+#else
+  const Meshlet meshlet = g_global_meshlets[meshlet_index];
+#endif
+
+  STORE_RWBUFFER(out_pick, 0, meshlet.draw_index);
+  STORE_RWBUFFER(out_pick, 4, meshlet_index);
+  STORE_RWBUFFER(out_pick, 8, triangle_index);
+  STORE_RWBUFFER(out_pick, 12, asuint(depth));
+
+  // End Function Code.
+  //////////////////////////////////////////////////////////////////////////
+}
@@ -44,7 +44,7 @@
   const DrawData draw_data = g_draw_data[meshlet.draw_index];
   const ObjectUniform per_object_data = g_per_object_uniforms[draw_data.object_index];
 #endif
-  const float3 camera_position = g_cameras.data[0].position;
+  const float3 camera_position = g_global_uniform.camera_position.xyz;
 
   bool is_visible = true;
 
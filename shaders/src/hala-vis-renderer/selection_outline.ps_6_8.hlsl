@@ -0,0 +1,118 @@
+#define HALA_NO_GLOBAL_PUSH_CONSTANT
+
+// The color of the outline, and the tint over the visible pixels of the selected draw.
+#define SELECTION_OUTLINE_COLOR float4(1.0, 0.6, 0.1, 1.0)
+#define SELECTION_TINT_ALPHA 0.15
+
+#ifdef HALA_HLSL
+
+  #include "scene.hlsl"
+  #include "visibility.hlsl"
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(0, 3)]]
+  Texture2D<uint> in_visibility;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(0, 3)]]
+  SamplerState in_visibility_sampler;
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(1, 3)]]
+  Texture2D<float> in_depth;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(1, 3)]]
+  SamplerState in_depth_sampler;
+
+#else
+
+  #include "scene.glsl"
+  #include "hala-vis-renderer/visibility.glsl"
+
+  layout(set = 3, binding = 0) uniform usampler2D in_visibility;
+  layout(set = 3, binding = 1) uniform sampler2D in_depth;
+
+#endif
+
+BEGIN_PUSH_CONSTANTS(SelectionOutlinePushConstants)
+  uint2 screen_size;      // The render size of the visibility buffer.
+  float2 outline_offset;  // The outline width in UV of the final target.
+  uint selected_draw_index;
+END_PUSH_CONSTANTS(SelectionOutlinePushConstants, g_push_constants)
+
+// Whether the texel of the visibility buffer belongs to the selected draw.
+bool is_selected(float2 uv) {
+  const int2 texel = clamp(int2(uv * float2(g_push_constants.screen_size)), int2(0, 0), int2(g_push_constants.screen_size) - 1);
+
+  // The visibility buffer is cleared to 0, which is also a valid ID, so the background is found by the depth.
+  const float depth = LOAD_SAMPLE(in_depth, texel, 0).r;
+  if (depth <= 0.0) {
+    return false;
+  }
+
+  const uint id = LOAD_SAMPLE(in_visibility, texel, 0).r;
+  uint meshlet_index, triangle_index;
+  unpack_meshlet_triangle_index(id, meshlet_index, triangle_index);
+#ifdef HALA_GLSL
+  const Meshlet meshlet = g_global_meshlets.data[meshlet_index];
+#else
+  const Meshlet meshlet = g_global_meshlets[meshlet_index];
+#endif
+  return meshlet.draw_index == g_push_constants.selected_draw_index;
+}
+
+#ifdef HALA_HLSL
+
+  struct ToFragment {
+    float4 position: SV_Position;
+    [[vk::location(0)]] float2 uv: TEXCOORD0;
+  };
+
+  struct FragmentOutput {
+    [[vk::location(0)]] float4 color: SV_Target0;
+  };
+
+  FragmentOutput main(ToFragment input) {
+    FragmentOutput output = (FragmentOutput)0;
+    #define IN_UV input.uv
+    #define OUT_COLOR output.color
+
+#else
+
+  layout(location = 0) in float2 in_uv;
+
+  layout(location = 0) out float4 out_color;
+
+  void main() {
+    #define IN_UV in_uv
+    #define OUT_COLOR out_color
+
+#endif
+
+  //////////////////////////////////////////////////////////////////////////
+  // Begin Function Code.
+
+  // The pixels of the selected draw are tinted, the other pixels next to them are the outline.
+  float4 color = float4(SELECTION_OUTLINE_COLOR.rgb, 0.0);
+  if (is_selected(IN_UV)) {
+    color.a = SELECTION_TINT_ALPHA;
+  } else {
+    const float2 offset = g_push_constants.outline_offset;
+    ANNOTATION_UNROLL
+    for (int y = -1; y <= 1; ++y) {
+      ANNOTATION_UNROLL
+      for (int x = -1; x <= 1; ++x) {
+        if ((x != 0 || y != 0) && is_selected(IN_UV + float2(x, y) * offset)) {
+          color.a = SELECTION_OUTLINE_COLOR.a;
+        }
+      }
+    }
+  }
+  OUT_COLOR = color;
+
+  // End Function Code.
+  //////////////////////////////////////////////////////////////////////////
+
+#ifdef HALA_HLSL
+  return output;
+#endif
+}
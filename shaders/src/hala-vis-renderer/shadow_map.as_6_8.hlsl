@@ -0,0 +1,75 @@
+#define USE_MESH_SHADER
+#define HALA_NO_GLOBAL_PUSH_CONSTANT
+
+#ifdef HALA_GLSL
+
+  #include "scene.glsl"
+  #include "shadow.glsl"
+  #include "hala-vis-renderer\shadow_map.hlsl"
+
+  layout(local_size_x = TASK_SHADER_GROUP_SIZE, local_size_y = 1, local_size_z = 1) in;
+
+  taskPayloadSharedEXT MeshShaderPayLoad ms_payload;
+
+  void main() {
+    uvec3 group_id = gl_WorkGroupID;
+    uvec3 group_thread_id = gl_LocalInvocationID;
+    uvec3 dispatch_thread_id = gl_GlobalInvocationID;
+
+#else
+
+  #include "scene.hlsl"
+  #include "shadow.hlsl"
+  #include "shadow_map.hlsl"
+
+  groupshared MeshShaderPayLoad ms_payload;
+
+  [numthreads(TASK_SHADER_GROUP_SIZE, 1, 1)]
+  void main(
+    uint3 group_id : SV_GroupID,
+    uint3 group_thread_id : SV_GroupThreadID,
+    uint3 dispatch_thread_id : SV_DispatchThreadID
+  ) {
+
+#endif
+
+  //////////////////////////////////////////////////////////////////////////
+  // Begin Function Code.
+  const uint meshlet_index = dispatch_thread_id.x;
+  if (meshlet_index >= g_push_constants.meshlet_count) {
+    return;
+  }
+
+#ifdef HALA_GLSL
+  const Meshlet meshlet = g_global_meshlets.data[meshlet_index];
+  const DrawData draw_data = g_draw_data.data[meshlet.draw_index];
+  const DrawMaterialFlags draw_material_flags = g_draw_material_flags.data[meshlet.draw_index];
+  #define per_object_data (g_per_object_uniforms[draw_data.object_index])
+#else
+  const Meshlet meshlet = g_global_meshlets[meshlet_index];
+  const DrawData draw_data = g_draw_data[meshlet.draw_index];
+  const DrawMaterialFlags draw_material_flags = g_draw_material_flags[meshlet.draw_index];
+  const ObjectUniform per_object_data = g_per_object_uniforms[draw_data.object_index];
+#endif
+
+  // Only the frustum test of the cascade. The back faces still cast the shadow,
+  // and the Hi-Z buffer of the camera says nothing about the visibility from the light.
+  const float world_scale = max(length(per_object_data.m_mtx[0]), max(length(per_object_data.m_mtx[1]), length(per_object_data.m_mtx[2])));
+  const float3 bound_sphere_center_ws = mul(per_object_data.m_mtx, float4(meshlet.bound_sphere.xyz, 1.0)).xyz;
+  const float bound_sphere_radius = meshlet.bound_sphere.w * world_scale;
+  // The transparent meshlets do not cast the shadow.
+  const bool is_visible = (draw_material_flags.flags & DRAW_FLAG_TRANSPARENT) == 0
+    && !is_sphere_cascade_culled(g_shadow.data, g_push_constants.cascade_index, bound_sphere_center_ws, bound_sphere_radius);
+
+  if (is_visible) {
+    const uint index = WavePrefixCountBits(is_visible);
+    ms_payload.meshlet_indices[index] = meshlet_index;
+  }
+
+  // One meshlet to one mesh group.
+  // The drawn meshlets of the render statistics only count the camera view.
+  const uint visible_count = WaveActiveCountBits(is_visible);
+  DISPATCH_MESH(visible_count, 1, 1, ms_payload);
+  // End Function Code.
+  //////////////////////////////////////////////////////////////////////////
+}
@@ -0,0 +1,3 @@
+#version 460 core
+
+#include "shadow_map.ps_6_8.hlsl"
\ No newline at end of file
@@ -0,0 +1,8 @@
+BEGIN_UNIFORM_BUFFER(3, 0, ShadowUniform)
+  ShadowData data;
+END_UNIFORM_BUFFER(3, 0, ShadowUniform, g_shadow)
+
+BEGIN_PUSH_CONSTANTS(ShadowMapPushConstants)
+  uint meshlet_count;
+  uint cascade_index;
+END_PUSH_CONSTANTS(ShadowMapPushConstants, g_push_constants)
@@ -0,0 +1,3 @@
+// The shadow map only keeps the depth, nothing is written by the fragment shader.
+void main() {
+}
\ No newline at end of file
@@ -0,0 +1,78 @@
+#define HALA_NO_GLOBAL_PUSH_CONSTANT
+
+#ifdef HALA_HLSL
+
+  #include "scene.hlsl"
+  #include "octahedral.hlsl"
+  #include "brdf.hlsl"
+  #include "environment.hlsl"
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(0, 3)]]
+  Texture2D<float4> in_environment;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(0, 3)]]
+  SamplerState in_environment_sampler;
+
+#else
+
+  #include "scene.glsl"
+  #include "octahedral.glsl"
+  #include "brdf.glsl"
+  #include "environment.glsl"
+
+  layout(set = 3, binding = 0) uniform sampler2D in_environment;
+
+#endif
+
+BEGIN_PUSH_CONSTANTS(SkyboxPushConstants)
+  float environment_intensity;
+END_PUSH_CONSTANTS(SkyboxPushConstants, g_push_constants)
+
+#ifdef HALA_HLSL
+
+  struct ToFragment {
+    float4 position: SV_Position;
+    [[vk::location(0)]] float2 uv: TEXCOORD0;
+  };
+
+  struct FragmentOutput {
+    [[vk::location(0)]] float4 color: SV_Target0;
+  };
+
+  FragmentOutput main(ToFragment input) {
+    FragmentOutput output = (FragmentOutput)0;
+    #define IN_UV input.uv
+    #define OUT_COLOR output.color
+
+#else
+
+  layout(location = 0) in float2 in_uv;
+
+  layout(location = 0) out float4 out_color;
+
+  void main() {
+    #define IN_UV in_uv
+    #define OUT_COLOR out_color
+
+#endif
+
+  //////////////////////////////////////////////////////////////////////////
+  // Begin Function Code.
+
+  // Only the pixels without the scene depth are drawn, the view direction is through the pixel on the near plane.
+  const float2 pos_ndc = float2(IN_UV.x, 1.0 - IN_UV.y);
+  const float4 clip_pos = float4(pos_ndc * 2.0 - 1.0, 1.0, 1.0);
+  const float4 world_w = mul(g_global_uniform.i_vp_mtx, clip_pos);
+  const float3 view_dir = normalize(world_w.xyz * rcp(world_w.w) - g_global_uniform.camera_position.xyz);
+
+  const float3 radiance = SAMPLE_COMBINED_TEXTURE_LEVEL(in_environment, get_equirect_uv(view_dir), 0.0).rgb;
+  OUT_COLOR = float4(radiance * g_push_constants.environment_intensity, 1.0);
+
+  // End Function Code.
+  //////////////////////////////////////////////////////////////////////////
+
+#ifdef HALA_HLSL
+  return output;
+#endif
+}
\ No newline at end of file
@@ -0,0 +1,167 @@
+#define HALA_NO_GLOBAL_PUSH_CONSTANT
+
+// NOTICE: Keep it same as SSAO_THREAD_WIDTH in ssao.rs.
+#define SSAO_THREAD_WIDTH 8
+// The slices through the view direction, each one searches the horizons on its both sides.
+#define SSAO_NUM_OF_SLICES 2
+#define SSAO_NUM_OF_STEPS 4
+// The screen radius is limited, so the far samples do not thrash the texture cache.
+#define SSAO_MAX_RADIUS_PIXELS 128.0
+
+#ifndef PI
+#define PI 3.14159265358979
+#endif
+
+#ifdef HALA_HLSL
+
+  #include "scene.hlsl"
+  #include "octahedral.hlsl"
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(0, 3)]]
+  Texture2D<float> in_depth;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(0, 3)]]
+  SamplerState in_depth_sampler;
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(1, 3)]]
+  Texture2D<float4> in_normal;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(1, 3)]]
+  SamplerState in_normal_sampler;
+
+  [[vk::binding(2, 3)]]
+  [[vk::image_format("r32f")]]
+  RWTexture2D<float> out_ambient_occlusion;
+
+#else
+
+  #include "scene.glsl"
+  #include "octahedral.glsl"
+
+  layout(set = 3, binding = 0) uniform sampler2D in_depth;
+  layout(set = 3, binding = 1) uniform sampler2D in_normal;
+  layout(set = 3, binding = 2, r32f) uniform writeonly image2D out_ambient_occlusion;
+
+#endif
+
+BEGIN_PUSH_CONSTANTS(SSAOPushConstants)
+  uint2 screen_size;
+  uint resolution_scale;  // 2 for the half resolution.
+  float radius;  // The world radius of the hemisphere.
+  float intensity;
+  float projection_scale;  // The pixels of an unit at the view distance 1.
+  float z_near;
+END_PUSH_CONSTANTS(SSAOPushConstants, g_push_constants)
+
+// Reconstruct the world position from the screen UV and the depth.
+float3 get_world_position(const float2 uv, const float depth) {
+  const float4 clip_pos = float4(uv * float2(2, -2) + float2(-1, 1), depth, 1.0);
+  const float4 world_w = mul(g_global_uniform.i_vp_mtx, clip_pos);
+  return world_w.xyz / world_w.w;
+}
+
+#ifdef HALA_HLSL
+
+  [numthreads(SSAO_THREAD_WIDTH, SSAO_THREAD_WIDTH, 1)]
+  void main(
+    uint3 dispatch_thread_id : SV_DispatchThreadID)
+  {
+
+#else
+
+  layout(local_size_x = SSAO_THREAD_WIDTH, local_size_y = SSAO_THREAD_WIDTH, local_size_z = 1) in;
+  void main() {
+    #define dispatch_thread_id gl_GlobalInvocationID
+
+#endif
+
+  const uint2 ao_size = (g_push_constants.screen_size + g_push_constants.resolution_scale - 1) / g_push_constants.resolution_scale;
+  if (dispatch_thread_id.x >= ao_size.x || dispatch_thread_id.y >= ao_size.y) {
+    return;
+  }
+
+  const int2 max_texel = int2(g_push_constants.screen_size) - 1;
+  const int2 texel = min(int2(dispatch_thread_id.xy * g_push_constants.resolution_scale), max_texel);
+  const float depth = LOAD_SAMPLE(in_depth, texel, 0).r;
+  if (depth <= 0.0) {
+    STORE_IMAGE(out_ambient_occlusion, dispatch_thread_id.xy, 1.0);
+    return;
+  }
+
+  const float2 screen_size = float2(g_push_constants.screen_size);
+  const float3 pos = get_world_position((float2(texel) + 0.5) / screen_size, depth);
+  const float3 normal = decode_octahedral_normal(LOAD_SAMPLE(in_normal, texel, 0).xy);
+
+  // The view distance of the reversed-Z infinite projection is z_near / depth.
+  const float radius_pixels = min(g_push_constants.radius * g_push_constants.projection_scale * depth / g_push_constants.z_near, SSAO_MAX_RADIUS_PIXELS);
+  if (radius_pixels < 1.0) {
+    STORE_IMAGE(out_ambient_occlusion, dispatch_thread_id.xy, 1.0);
+    return;
+  }
+  const float step_pixels = radius_pixels / float(SSAO_NUM_OF_STEPS);
+  const float radius_sq = g_push_constants.radius * g_push_constants.radius;
+
+  // Rotate the slices and offset the steps per pixel by the interleaved gradient noise, the blur removes the pattern.
+  const float noise = frac(52.9829189 * frac(dot(float2(texel), float2(0.06711056, 0.00583715))));
+  const float3 view_dir = normalize(g_global_uniform.camera_position.xyz - pos);
+
+  // The ground truth ambient occlusion: each slice tracks the maximum horizon angle on its both sides,
+  // then integrates the cosine weighted visibility between them with the normal projected onto the slice.
+  // The projected normal length weights the slices, so their average is the visibility of the hemisphere.
+  float visibility = 0.0;
+  for (uint slice_index = 0; slice_index < SSAO_NUM_OF_SLICES; ++slice_index) {
+    const float angle = (float(slice_index) + noise) * (PI / float(SSAO_NUM_OF_SLICES));
+    const float2 direction = float2(cos(angle), sin(angle));
+
+    // The slice plane holds the view direction and the world direction of the screen direction.
+    const float3 slice_dir = get_world_position((float2(texel) + 0.5 + direction) / screen_size, depth) - pos;
+    const float3 ortho_dir = normalize(slice_dir - view_dir * dot(slice_dir, view_dir));
+    const float3 axis = cross(ortho_dir, view_dir);
+    const float3 projected_normal = normal - axis * dot(normal, axis);
+    const float projected_normal_length = length(projected_normal);
+    if (projected_normal_length < 1e-4) {
+      continue;
+    }
+    const float cos_n = saturate(dot(projected_normal, view_dir) / projected_normal_length);
+    const float n = (dot(projected_normal, ortho_dir) < 0.0 ? -1.0 : 1.0) * acos(cos_n);
+
+    // The horizons start at the tangent plane, the samples fade to it beyond the radius.
+    const float low_horizon_cos_pos = cos(n + 0.5 * PI);
+    const float low_horizon_cos_neg = cos(n - 0.5 * PI);
+    float horizon_cos_pos = low_horizon_cos_pos;
+    float horizon_cos_neg = low_horizon_cos_neg;
+    for (uint step_index = 0; step_index < SSAO_NUM_OF_STEPS; ++step_index) {
+      const float2 offset = direction * (step_pixels * (float(step_index) + noise) + 1.0);
+      for (int side = 0; side < 2; ++side) {
+        const int2 sample_texel = clamp(int2(float2(texel) + 0.5 + (side == 0 ? offset : -offset)), int2(0, 0), max_texel);
+        const float sample_depth = LOAD_SAMPLE(in_depth, sample_texel, 0).r;
+        if (sample_depth <= 0.0) {
+          continue;
+        }
+
+        const float3 v = get_world_position((float2(sample_texel) + 0.5) / screen_size, sample_depth) - pos;
+        const float dist_sq = dot(v, v);
+        const float sample_cos = dot(v, view_dir) * rsqrt(dist_sq + 1e-6);
+        const float weight = saturate(1.0 - dist_sq / radius_sq);
+        if (side == 0) {
+          horizon_cos_pos = max(horizon_cos_pos, lerp(low_horizon_cos_pos, sample_cos, weight));
+        } else {
+          horizon_cos_neg = max(horizon_cos_neg, lerp(low_horizon_cos_neg, sample_cos, weight));
+        }
+      }
+    }
+
+    // The horizon angles from the view direction, clamped to the hemisphere around the normal.
+    const float h_pos = n + min(acos(horizon_cos_pos) - n, 0.5 * PI);
+    const float h_neg = n + max(-acos(horizon_cos_neg) - n, -0.5 * PI);
+    const float sin_n = sin(n);
+    const float arc_pos = -cos(2.0 * h_pos - n) + cos_n + 2.0 * h_pos * sin_n;
+    const float arc_neg = -cos(2.0 * h_neg - n) + cos_n + 2.0 * h_neg * sin_n;
+    visibility += projected_normal_length * 0.25 * (arc_pos + arc_neg);
+  }
+
+  const float ao = pow(saturate(visibility / float(SSAO_NUM_OF_SLICES)), g_push_constants.intensity);
+  STORE_IMAGE(out_ambient_occlusion, dispatch_thread_id.xy, ao);
+}
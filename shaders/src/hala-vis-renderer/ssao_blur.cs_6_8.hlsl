@@ -0,0 +1,95 @@
+#define HALA_NO_GLOBAL_PUSH_CONSTANT
+
+// NOTICE: Keep it same as SSAO_THREAD_WIDTH in ssao.rs.
+#define SSAO_THREAD_WIDTH 8
+#define SSAO_BLUR_RADIUS 2
+// The larger the sharper the edges, the weight falls off by the relative difference of the view distances.
+#define SSAO_BLUR_DEPTH_SHARPNESS 32.0
+
+#ifdef HALA_HLSL
+
+  #include "scene.hlsl"
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(0, 3)]]
+  Texture2D<float> in_ambient_occlusion;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(0, 3)]]
+  SamplerState in_ambient_occlusion_sampler;
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(1, 3)]]
+  Texture2D<float> in_depth;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(1, 3)]]
+  SamplerState in_depth_sampler;
+
+  [[vk::binding(2, 3)]]
+  [[vk::image_format("r32f")]]
+  RWTexture2D<float> out_ambient_occlusion;
+
+#else
+
+  #include "scene.glsl"
+
+  layout(set = 3, binding = 0) uniform sampler2D in_ambient_occlusion;
+  layout(set = 3, binding = 1) uniform sampler2D in_depth;
+  layout(set = 3, binding = 2, r32f) uniform writeonly image2D out_ambient_occlusion;
+
+#endif
+
+BEGIN_PUSH_CONSTANTS(SSAOBlurPushConstants)
+  uint2 screen_size;
+  uint resolution_scale;  // 2 for the half resolution.
+END_PUSH_CONSTANTS(SSAOBlurPushConstants, g_push_constants)
+
+#ifdef HALA_HLSL
+
+  [numthreads(SSAO_THREAD_WIDTH, SSAO_THREAD_WIDTH, 1)]
+  void main(
+    uint3 dispatch_thread_id : SV_DispatchThreadID)
+  {
+
+#else
+
+  layout(local_size_x = SSAO_THREAD_WIDTH, local_size_y = SSAO_THREAD_WIDTH, local_size_z = 1) in;
+  void main() {
+    #define dispatch_thread_id gl_GlobalInvocationID
+
+#endif
+
+  const uint2 ao_size = (g_push_constants.screen_size + g_push_constants.resolution_scale - 1) / g_push_constants.resolution_scale;
+  if (dispatch_thread_id.x >= ao_size.x || dispatch_thread_id.y >= ao_size.y) {
+    return;
+  }
+
+  const int2 max_texel = int2(g_push_constants.screen_size) - 1;
+  const int2 max_ao_texel = int2(ao_size) - 1;
+  const int2 ao_texel = int2(dispatch_thread_id.xy);
+  const float center_depth = LOAD_SAMPLE(in_depth, min(ao_texel * int(g_push_constants.resolution_scale), max_texel), 0).r;
+  if (center_depth <= 0.0) {
+    STORE_IMAGE(out_ambient_occlusion, dispatch_thread_id.xy, 1.0);
+    return;
+  }
+
+  // The gaussian weights are scaled by the depth similarity, so the occlusion does not bleed across the edges.
+  float sum = 0.0;
+  float weight_sum = 0.0;
+  for (int y = -SSAO_BLUR_RADIUS; y <= SSAO_BLUR_RADIUS; ++y) {
+    for (int x = -SSAO_BLUR_RADIUS; x <= SSAO_BLUR_RADIUS; ++x) {
+      const int2 sample_texel = clamp(ao_texel + int2(x, y), int2(0, 0), max_ao_texel);
+      const float sample_depth = LOAD_SAMPLE(in_depth, min(sample_texel * int(g_push_constants.resolution_scale), max_texel), 0).r;
+      if (sample_depth <= 0.0) {
+        continue;
+      }
+
+      // The ratio of the depths is the inverse ratio of the view distances.
+      const float depth_weight = exp(-abs(center_depth / sample_depth - 1.0) * SSAO_BLUR_DEPTH_SHARPNESS);
+      const float weight = exp(-float(x * x + y * y) * 0.2222) * depth_weight;
+      sum += LOAD_SAMPLE(in_ambient_occlusion, sample_texel, 0).r * weight;
+      weight_sum += weight;
+    }
+  }
+
+  STORE_IMAGE(out_ambient_occlusion, dispatch_thread_id.xy, sum / max(weight_sum, 1e-5));
+}
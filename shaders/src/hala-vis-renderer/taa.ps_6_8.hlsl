@@ -0,0 +1,140 @@
+#define HALA_NO_GLOBAL_PUSH_CONSTANT
+
+#ifdef HALA_HLSL
+
+  #include "scene.hlsl"
+  #include "tonemapping.hlsl"
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(0, 3)]]
+  Texture2D<float4> in_scene_color;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(0, 3)]]
+  SamplerState in_scene_color_sampler;
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(1, 3)]]
+  Texture2D<float4> in_history;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(1, 3)]]
+  SamplerState in_history_sampler;
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(2, 3)]]
+  Texture2D<float2> in_motion;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(2, 3)]]
+  SamplerState in_motion_sampler;
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(3, 3)]]
+  Texture2D<float> in_depth;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(3, 3)]]
+  SamplerState in_depth_sampler;
+
+#else
+
+  #include "scene.glsl"
+  #include "tonemapping.glsl"
+
+  layout(set = 3, binding = 0) uniform sampler2D in_scene_color;
+  layout(set = 3, binding = 1) uniform sampler2D in_history;
+  layout(set = 3, binding = 2) uniform sampler2D in_motion;
+  layout(set = 3, binding = 3) uniform sampler2D in_depth;
+
+#endif
+
+BEGIN_PUSH_CONSTANTS(TAAPushConstants)
+  uint2 screen_size;
+  float history_weight;  // 0 drops the history, e.g. the first frame.
+END_PUSH_CONSTANTS(TAAPushConstants, g_push_constants)
+
+#ifdef HALA_HLSL
+
+  struct ToFragment {
+    float4 position: SV_Position;
+    [[vk::location(0)]] float2 uv: TEXCOORD0;
+  };
+
+  struct FragmentOutput {
+    [[vk::location(0)]] float4 color: SV_Target0;
+  };
+
+  FragmentOutput main(ToFragment input) {
+    FragmentOutput output = (FragmentOutput)0;
+    #define IN_POSITION input.position
+    #define IN_UV input.uv
+    #define OUT_COLOR output.color
+
+#else
+
+  layout(location = 0) in float2 in_uv;
+
+  layout(location = 0) out float4 out_color;
+
+  void main() {
+    #define IN_POSITION gl_FragCoord
+    #define IN_UV in_uv
+    #define OUT_COLOR out_color
+
+#endif
+
+  //////////////////////////////////////////////////////////////////////////
+  // Begin Function Code.
+
+  const int2 texel = int2(IN_POSITION.xy);
+  const int2 max_texel = int2(g_push_constants.screen_size) - 1;
+  const float3 current = LOAD_SAMPLE(in_scene_color, texel, 0).rgb;
+
+  // The history is clamped to the color range of the 3x3 neighborhood to reject the disoccluded and changed pixels.
+  float3 color_min = current;
+  float3 color_max = current;
+  ANNOTATION_UNROLL
+  for (int y = -1; y <= 1; ++y) {
+    ANNOTATION_UNROLL
+    for (int x = -1; x <= 1; ++x) {
+      const float3 neighbor = LOAD_SAMPLE(in_scene_color, clamp(texel + int2(x, y), int2(0, 0), max_texel), 0).rgb;
+      color_min = min(color_min, neighbor);
+      color_max = max(color_max, neighbor);
+    }
+  }
+
+  float2 motion;
+  const float depth = LOAD_SAMPLE(in_depth, texel, 0).r;
+  if (depth > 0.0) {
+    motion = LOAD_SAMPLE(in_motion, texel, 0).rg;
+  } else {
+    // The background has no motion vector, it is at the infinity of the reversed-Z, so only the camera rotation moves it.
+    const float2 clip_pos = IN_UV * float2(2, -2) + float2(-1, 1);
+    const float4 world_pos = mul(g_global_uniform.i_vp_mtx, float4(clip_pos, 0.0, 1.0));
+    const float4 prev_clip_pos = mul(g_global_uniform.prev_vp_mtx, world_pos);
+    const float2 prev_screen_pos = prev_clip_pos.xy / prev_clip_pos.w * float2(0.5, -0.5) + 0.5;
+    motion = prev_screen_pos - (IN_UV - g_global_uniform.jitter.zw);
+  }
+
+  // The history off the screen is dropped, and it is not sampled at all without the valid content.
+  const float2 history_uv = IN_UV + motion;
+  float history_weight = g_push_constants.history_weight;
+  if (min(history_uv.x, history_uv.y) < 0.0 || max(history_uv.x, history_uv.y) > 1.0) {
+    history_weight = 0.0;
+  }
+  float3 history = current;
+  if (history_weight > 0.0) {
+    // The history is rendered at the render size of the last frame, which may differ from this one by the dynamic resolution.
+    history = clamp(SAMPLE_COMBINED_TEXTURE_LEVEL(in_history, get_prev_render_target_uv(history_uv), 0.0).rgb, color_min, color_max);
+  }
+
+  // Weight the colors by the inverse luminance, so the HDR highlights do not flicker.
+  const float current_weight = (1.0 - history_weight) / (1.0 + get_luminance(current));
+  const float weighted_history = history_weight / (1.0 + get_luminance(history));
+  const float3 color = (current * current_weight + history * weighted_history) / max(current_weight + weighted_history, 1e-5);
+  OUT_COLOR = float4(color, 1.0);
+
+  // End Function Code.
+  //////////////////////////////////////////////////////////////////////////
+
+#ifdef HALA_HLSL
+  return output;
+#endif
+}
\ No newline at end of file
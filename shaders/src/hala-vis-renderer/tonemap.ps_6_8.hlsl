@@ -0,0 +1,122 @@
+#define HALA_NO_GLOBAL_PUSH_CONSTANT
+
+#ifdef HALA_HLSL
+
+  #include "scene.hlsl"
+  #include "tonemapping.hlsl"
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(0, 3)]]
+  Texture2D<float4> in_scene_color;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(0, 3)]]
+  SamplerState in_scene_color_sampler;
+
+  [[vk::binding(1, 3)]]
+  ByteAddressBuffer in_exposure;
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(2, 3)]]
+  Texture2D<float4> in_bloom;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(2, 3)]]
+  SamplerState in_bloom_sampler;
+
+#else
+
+  #include "scene.glsl"
+  #include "tonemapping.glsl"
+
+  layout(set = 3, binding = 0) uniform sampler2D in_scene_color;
+
+  layout(set = 3, binding = 1) readonly buffer ExposureBuffer {
+    uint in_exposure[];
+  };
+
+  layout(set = 3, binding = 2) uniform sampler2D in_bloom;
+
+#endif
+
+BEGIN_PUSH_CONSTANTS(TonemapPushConstants)
+  uint tonemap_operator;
+  float exposure;  // The manual exposure, or the exposure compensation of the automatic exposure.
+  uint use_auto_exposure;
+  float bloom_intensity;
+  uint bloom_mip_levels;  // 0 without the bloom.
+  float vignette_intensity;
+  float chromatic_aberration;  // The UV offset of the red and blue channels at the screen corners.
+END_PUSH_CONSTANTS(TonemapPushConstants, g_push_constants)
+
+#ifdef HALA_HLSL
+
+  struct ToFragment {
+    float4 position: SV_Position;
+    [[vk::location(0)]] float2 uv: TEXCOORD0;
+  };
+
+  struct FragmentOutput {
+    [[vk::location(0)]] float4 color: SV_Target0;
+  };
+
+  FragmentOutput main(ToFragment input) {
+    FragmentOutput output = (FragmentOutput)0;
+    #define IN_UV input.uv
+    #define OUT_COLOR output.color
+
+#else
+
+  layout(location = 0) in float2 in_uv;
+
+  layout(location = 0) out float4 out_color;
+
+  void main() {
+    #define IN_UV in_uv
+    #define OUT_COLOR out_color
+
+#endif
+
+  //////////////////////////////////////////////////////////////////////////
+  // Begin Function Code.
+
+  // The scene color is rendered into the top left of its target at the render size, the linear sampler upscales it to the window size.
+  float3 color;
+  const float2 center_offset = IN_UV - 0.5;
+  if (g_push_constants.chromatic_aberration > 0.0) {
+    // The red and blue channels are scaled away from the screen center in the opposite directions.
+    const float2 offset = center_offset * g_push_constants.chromatic_aberration * 2.0;
+    color.r = SAMPLE_COMBINED_TEXTURE_LEVEL(in_scene_color, get_render_target_uv(IN_UV + offset), 0.0).r;
+    color.g = SAMPLE_COMBINED_TEXTURE_LEVEL(in_scene_color, get_render_target_uv(IN_UV), 0.0).g;
+    color.b = SAMPLE_COMBINED_TEXTURE_LEVEL(in_scene_color, get_render_target_uv(IN_UV - offset), 0.0).b;
+  } else {
+    color = SAMPLE_COMBINED_TEXTURE_LEVEL(in_scene_color, get_render_target_uv(IN_UV), 0.0).rgb;
+  }
+
+  // The bloom accumulates all mip levels, so it is averaged before blending with the scene color.
+  if (g_push_constants.bloom_mip_levels > 0) {
+    const float3 bloom = SAMPLE_COMBINED_TEXTURE_LEVEL(in_bloom, get_render_target_uv(IN_UV), 0.0).rgb / float(g_push_constants.bloom_mip_levels);
+    color = lerp(color, bloom, g_push_constants.bloom_intensity);
+  }
+
+  // The natural vignetting falls off by cos^4 of the angle to the optical axis, 1 / (1 + r^2)^2 with r = 1 at the screen corners.
+  if (g_push_constants.vignette_intensity > 0.0) {
+    const float r2 = dot(center_offset, center_offset) * 2.0;
+    const float falloff = 1.0 / ((1.0 + r2) * (1.0 + r2));
+    color *= lerp(1.0, falloff, g_push_constants.vignette_intensity);
+  }
+
+  float exposure = g_push_constants.exposure;
+  if (g_push_constants.use_auto_exposure != 0) {
+    const float average_luminance = asfloat(LOAD_BUFFER(in_exposure, 0));
+    exposure *= EXPOSURE_MIDDLE_GRAY / max(average_luminance, 1e-4);
+  }
+
+  // The final target is sRGB, so the output stays linear.
+  OUT_COLOR = float4(apply_tonemap(color * exposure, g_push_constants.tonemap_operator), 1.0);
+
+  // End Function Code.
+  //////////////////////////////////////////////////////////////////////////
+
+#ifdef HALA_HLSL
+  return output;
+#endif
+}
\ No newline at end of file
@@ -0,0 +1,14 @@
+BEGIN_PUSH_CONSTANTS(TransparentPushConstants)
+  uint meshlet_count;     // The number of the meshlets to dispatch.
+  uint meshlet_offset;    // The first meshlet to dispatch in the global meshlet buffer.
+  uint num_of_tiles_x;
+  uint num_of_tiles;
+  float z_near;
+  uint draw_index;        // The only draw to render, TRANSPARENT_ALL_DRAWS for all transparent draws.
+  uint material_variant;  // DRAW_FLAG_DOUBLE_SIDED or 0.
+  float environment_intensity;
+END_PUSH_CONSTANTS(TransparentPushConstants, g_push_constants)
+#define USE_MESH_SHADER
+#define HALA_NO_GLOBAL_PUSH_CONSTANT
+
+#define TRANSPARENT_ALL_DRAWS 0xFFFFFFFF
@@ -0,0 +1,117 @@
+#define USE_MESH_SHADER
+#define HALA_NO_GLOBAL_PUSH_CONSTANT
+
+#define MAX_VERTEX_COUNT 64
+#define MAX_TRIANGLE_COUNT 124
+
+#ifdef HALA_GLSL
+
+#include "scene.glsl"
+#include "hala-vis-renderer/visibility.glsl"
+#include "hala-vis-renderer/transparent.hlsl"
+
+layout(triangles) out;
+layout(local_size_x = MESH_SHADER_GROUP_SIZE, local_size_y = 1, local_size_z = 1) in;
+layout(max_vertices = MAX_VERTEX_COUNT, max_primitives = MAX_TRIANGLE_COUNT) out;
+
+taskPayloadSharedEXT MeshShaderPayLoad ms_payload;
+
+layout(location = 0) out float3 out_position[];
+layout(location = 1) out float3 out_normal[];
+layout(location = 2) out float2 out_texcoord[];
+layout(location = 3) flat out uint out_draw_index[];
+
+void main() {
+  #define triangles gl_PrimitiveTriangleIndicesEXT
+
+  const uvec3 group_id = gl_WorkGroupID;
+  const uvec3 group_thread_id = gl_LocalInvocationID;
+
+  #define OUT_POSITION(index) gl_MeshVerticesEXT[index].gl_Position
+  #define OUT_POSITION_WS(index) out_position[index]
+  #define OUT_NORMAL(index) out_normal[index]
+  #define OUT_TEXCOORD(index) out_texcoord[index]
+  #define OUT_DRAW_INDEX(index) out_draw_index[index]
+
+  #define g_global_meshlets (g_global_meshlets.data)
+  #define g_draw_data (g_draw_data.data)
+#else
+
+#include "scene.hlsl"
+#include "visibility.hlsl"
+#include "transparent.hlsl"
+
+struct ToFragment {
+  float4 position: SV_Position;
+  [[vk::location(0)]] float3 position_ws: POSITION0;
+  [[vk::location(1)]] float3 normal: NORMAL0;
+  [[vk::location(2)]] float2 texcoord: TEXCOORD0;
+  [[vk::location(3)]] nointerpolation uint draw_index: DRAW_INDEX0;
+};
+
+[outputtopology("triangle")]
+[numthreads(MESH_SHADER_GROUP_SIZE, 1, 1)]
+void main(
+  out indices uint3 triangles[MAX_TRIANGLE_COUNT],
+  out vertices ToFragment vertices[MAX_VERTEX_COUNT],
+  in payload MeshShaderPayLoad ms_payload,
+  uint3 group_id : SV_GroupID,
+  uint3 group_thread_id : SV_GroupThreadID
+) {
+  #define OUT_POSITION(index) vertices[index].position
+  #define OUT_POSITION_WS(index) vertices[index].position_ws
+  #define OUT_NORMAL(index) vertices[index].normal
+  #define OUT_TEXCOORD(index) vertices[index].texcoord
+  #define OUT_DRAW_INDEX(index) vertices[index].draw_index
+
+#endif
+
+  //////////////////////////////////////////////////////////////////////////
+  // Begin Function Code.
+  const uint meshlet_index = ms_payload.meshlet_indices[group_id.x];
+
+  const Meshlet meshlet = g_global_meshlets[meshlet_index];
+  const DrawData draw_data = g_draw_data[meshlet.draw_index];
+#ifdef HALA_GLSL
+  #define per_object_data (g_per_object_uniforms[draw_data.object_index])
+  #define vertex_buffer (g_vertices[meshlet.draw_index].data)
+  #define vertex_index_buffer (g_unique_vertices[meshlet.draw_index].data)
+#else
+  const ObjectUniform per_object_data = g_per_object_uniforms[draw_data.object_index];
+  StructuredBuffer<Vertex> vertex_buffer = g_vertices[meshlet.draw_index];
+  StructuredBuffer<uint> vertex_index_buffer = g_unique_vertices[meshlet.draw_index];
+#endif
+
+  SetMeshOutputCounts(meshlet.num_of_vertices, meshlet.num_of_primitives);
+
+  // Per thread write one vertex.
+  // The forward shading has no visibility buffer to fetch from, so the attributes are interpolated by the rasterizer.
+  const uint vertex_id = group_thread_id.x;
+  if (vertex_id < min(meshlet.num_of_vertices, MAX_VERTEX_COUNT)) {
+    const uint vertex_index = vertex_index_buffer[meshlet.offset_of_vertices + vertex_id];
+    const Vertex vertex = vertex_buffer[vertex_index];
+    const float4 position = float4(vertex.position_x, vertex.position_y, vertex.position_z, 1.0);
+    const float4 normal = float4(vertex.normal_x, vertex.normal_y, vertex.normal_z, 0.0);
+
+    OUT_POSITION(vertex_id) = mul(per_object_data.mvp_mtx, position);
+    OUT_POSITION_WS(vertex_id) = mul(per_object_data.m_mtx, position).xyz;
+    OUT_NORMAL(vertex_id) = normalize(mul(normal, per_object_data.i_m_mtx).xyz);
+    OUT_TEXCOORD(vertex_id) = float2(vertex.tex_coord_x, vertex.tex_coord_y);
+    OUT_DRAW_INDEX(vertex_id) = meshlet.draw_index;
+  }
+
+  // Per thread write two triangles.
+  uint triangle_id = group_thread_id.x * 2;
+  uint triangle_index = meshlet.offset_of_primitives + triangle_id;
+  if (triangle_id < min(meshlet.num_of_primitives, MAX_TRIANGLE_COUNT)) {
+    triangles[triangle_id] = load_primitive_index(triangle_index, meshlet.draw_index);
+
+    triangle_id++;
+    triangle_index = meshlet.offset_of_primitives + triangle_id;
+    if (triangle_id < min(meshlet.num_of_primitives, MAX_TRIANGLE_COUNT)) {
+      triangles[triangle_id] = load_primitive_index(triangle_index, meshlet.draw_index);
+    }
+  }
+  // End Function Code.
+  //////////////////////////////////////////////////////////////////////////
+}
\ No newline at end of file
@@ -0,0 +1,77 @@
+#define HALA_NO_GLOBAL_PUSH_CONSTANT
+
+#ifdef HALA_HLSL
+
+  #include "scene.hlsl"
+  #include "octahedral.hlsl"
+  #include "brdf.hlsl"
+  #include "lights.hlsl"
+  #include "shadow.hlsl"
+  #include "direct_lighting.hlsl"
+  #include "environment.hlsl"
+  #include "transparent.hlsl"
+  #include "transparent_shading.hlsl"
+
+  struct ToFragment {
+    float4 position: SV_Position;
+    [[vk::location(0)]] float3 position_ws: POSITION0;
+    [[vk::location(1)]] float3 normal: NORMAL0;
+    [[vk::location(2)]] float2 texcoord: TEXCOORD0;
+    [[vk::location(3)]] nointerpolation uint draw_index: DRAW_INDEX0;
+  };
+
+  struct FragmentOutput {
+    [[vk::location(0)]] float4 color: SV_Target0;
+  };
+
+  FragmentOutput main(ToFragment input) {
+    FragmentOutput output = (FragmentOutput)0;
+    #define IN_FRAG_COORD input.position
+    #define IN_POSITION input.position_ws
+    #define IN_NORMAL input.normal
+    #define IN_TEXCOORD input.texcoord
+    #define IN_DRAW_INDEX input.draw_index
+    #define OUT_COLOR output.color
+
+#else
+
+  #include "scene.glsl"
+  #include "octahedral.glsl"
+  #include "brdf.glsl"
+  #include "lights.glsl"
+  #include "shadow.glsl"
+  #include "direct_lighting.glsl"
+  #include "environment.glsl"
+  #include "hala-vis-renderer\transparent.hlsl"
+  #include "hala-vis-renderer\transparent_shading.hlsl"
+
+  layout(location = 0) in float3 in_position;
+  layout(location = 1) in float3 in_normal;
+  layout(location = 2) in float2 in_texcoord;
+  layout(location = 3) flat in uint in_draw_index;
+
+  layout(location = 0) out float4 out_color;
+
+  void main() {
+    #define IN_FRAG_COORD gl_FragCoord
+    #define IN_POSITION in_position
+    #define IN_NORMAL in_normal
+    #define IN_TEXCOORD in_texcoord
+    #define IN_DRAW_INDEX in_draw_index
+    #define OUT_COLOR out_color
+
+#endif
+
+  //////////////////////////////////////////////////////////////////////////
+  // Begin Function Code.
+
+  // Blended over the lighting result by the alpha, the draws are sorted back to front.
+  OUT_COLOR = shade_transparent(IN_FRAG_COORD, IN_POSITION, IN_NORMAL, IN_TEXCOORD, IN_DRAW_INDEX);
+
+  // End Function Code.
+  //////////////////////////////////////////////////////////////////////////
+
+#ifdef HALA_HLSL
+  return output;
+#endif
+}
\ No newline at end of file
@@ -0,0 +1,71 @@
+#ifdef HALA_HLSL
+
+  #include "defines.hlsl"
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(0, 3)]]
+  Texture2D<float4> in_accumulation_image;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(0, 3)]]
+  SamplerState in_accumulation_sampler;
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(1, 3)]]
+  Texture2D<float> in_weight_image;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(1, 3)]]
+  SamplerState in_weight_sampler;
+
+  struct ToFragment {
+    float4 position: SV_Position;
+    [[vk::location(0)]] float2 uv: TEXCOORD0;
+  };
+
+  struct FragmentOutput {
+    [[vk::location(0)]] float4 color: SV_Target0;
+  };
+
+  FragmentOutput main(ToFragment input) {
+    FragmentOutput output = (FragmentOutput)0;
+    #define IN_POSITION input.position
+    #define OUT_COLOR output.color
+
+#else
+
+  #include "defines.glsl"
+
+  layout(set = 3, binding = 0) uniform sampler2D in_accumulation_image;
+  layout(set = 3, binding = 1) uniform sampler2D in_weight_image;
+
+  layout(location = 0) in float2 in_uv;
+
+  layout(location = 0) out float4 out_color;
+
+  void main() {
+    #define IN_POSITION gl_FragCoord
+    #define OUT_COLOR out_color
+
+#endif
+
+  //////////////////////////////////////////////////////////////////////////
+  // Begin Function Code.
+
+  const int2 texel = int2(IN_POSITION.xy);
+  const float4 accumulation = LOAD_SAMPLE(in_accumulation_image, texel, 0);
+  // The product of (1 - alpha) of all transparent fragments, 1 for no transparent fragment.
+  const float revealage = accumulation.a;
+  if (revealage >= 1.0) {
+    discard;
+  }
+
+  // The weighted average color, blended over the lighting result by the coverage.
+  const float weight = LOAD_SAMPLE(in_weight_image, texel, 0).r;
+  OUT_COLOR = float4(accumulation.rgb / max(weight, 1e-5), 1.0 - revealage);
+
+  // End Function Code.
+  //////////////////////////////////////////////////////////////////////////
+
+#ifdef HALA_HLSL
+  return output;
+#endif
+}
\ No newline at end of file
@@ -0,0 +1,86 @@
+#define HALA_NO_GLOBAL_PUSH_CONSTANT
+
+#ifdef HALA_HLSL
+
+  #include "scene.hlsl"
+  #include "octahedral.hlsl"
+  #include "brdf.hlsl"
+  #include "lights.hlsl"
+  #include "shadow.hlsl"
+  #include "direct_lighting.hlsl"
+  #include "environment.hlsl"
+  #include "transparent.hlsl"
+  #include "transparent_shading.hlsl"
+
+  struct ToFragment {
+    float4 position: SV_Position;
+    [[vk::location(0)]] float3 position_ws: POSITION0;
+    [[vk::location(1)]] float3 normal: NORMAL0;
+    [[vk::location(2)]] float2 texcoord: TEXCOORD0;
+    [[vk::location(3)]] nointerpolation uint draw_index: DRAW_INDEX0;
+  };
+
+  struct FragmentOutput {
+    [[vk::location(0)]] float4 accumulation: SV_Target0;
+    [[vk::location(1)]] float weight: SV_Target1;
+  };
+
+  FragmentOutput main(ToFragment input) {
+    FragmentOutput output = (FragmentOutput)0;
+    #define IN_FRAG_COORD input.position
+    #define IN_POSITION input.position_ws
+    #define IN_NORMAL input.normal
+    #define IN_TEXCOORD input.texcoord
+    #define IN_DRAW_INDEX input.draw_index
+    #define OUT_ACCUMULATION output.accumulation
+    #define OUT_WEIGHT output.weight
+
+#else
+
+  #include "scene.glsl"
+  #include "octahedral.glsl"
+  #include "brdf.glsl"
+  #include "lights.glsl"
+  #include "shadow.glsl"
+  #include "direct_lighting.glsl"
+  #include "environment.glsl"
+  #include "hala-vis-renderer\transparent.hlsl"
+  #include "hala-vis-renderer\transparent_shading.hlsl"
+
+  layout(location = 0) in float3 in_position;
+  layout(location = 1) in float3 in_normal;
+  layout(location = 2) in float2 in_texcoord;
+  layout(location = 3) flat in uint in_draw_index;
+
+  layout(location = 0) out float4 out_accumulation;
+  layout(location = 1) out float out_weight;
+
+  void main() {
+    #define IN_FRAG_COORD gl_FragCoord
+    #define IN_POSITION in_position
+    #define IN_NORMAL in_normal
+    #define IN_TEXCOORD in_texcoord
+    #define IN_DRAW_INDEX in_draw_index
+    #define OUT_ACCUMULATION out_accumulation
+    #define OUT_WEIGHT out_weight
+
+#endif
+
+  //////////////////////////////////////////////////////////////////////////
+  // Begin Function Code.
+
+  const float4 color = shade_transparent(IN_FRAG_COORD, IN_POSITION, IN_NORMAL, IN_TEXCOORD, IN_DRAW_INDEX);
+
+  // The accumulation target sums the weighted premultiplied colors, its alpha keeps the product of (1 - alpha) by the blending.
+  // The weight target sums the weighted alphas, the composite pass divides the color by it.
+  const float weight = get_oit_weight(g_push_constants.z_near / max(IN_FRAG_COORD.z, LIGHT_CLUSTER_MIN_DEPTH), color.a);
+  OUT_ACCUMULATION = float4(color.rgb * color.a * weight, color.a);
+  OUT_WEIGHT = color.a * weight;
+
+  // End Function Code.
+  //////////////////////////////////////////////////////////////////////////
+
+#ifdef HALA_HLSL
+  return output;
+#endif
+}
\ No newline at end of file
@@ -0,0 +1,169 @@
+// The weights of the weighted blended OIT, depending on the view distance.
+// http://jcgt.org/published/0002/02/09/
+#define OIT_WEIGHT_DISTANCE 200.0
+#define OIT_MIN_WEIGHT 1e-2
+#define OIT_MAX_WEIGHT 3e3
+
+#ifdef HALA_HLSL
+
+  [[vk::binding(0, 3)]]
+  ByteAddressBuffer in_light_clusters;
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(1, 3)]]
+  Texture2D<float> in_shadow_map;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(1, 3)]]
+  SamplerState in_shadow_map_sampler;
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(3, 3)]]
+  Texture2D<float4> in_specular_environment;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(3, 3)]]
+  SamplerState in_specular_environment_sampler;
+
+  [[vk::combinedImageSampler]]
+  [[vk::binding(4, 3)]]
+  Texture2D<float4> in_irradiance_environment;
+  [[vk::combinedImageSampler]]
+  [[vk::binding(4, 3)]]
+  SamplerState in_irradiance_environment_sampler;
+
+#else
+
+  layout(set = 3, binding = 0) readonly buffer LightClustersBuffer {
+    uint in_light_clusters[];
+  };
+
+  layout(set = 3, binding = 1) uniform sampler2D in_shadow_map;
+
+  layout(set = 3, binding = 3) uniform sampler2D in_specular_environment;
+  layout(set = 3, binding = 4) uniform sampler2D in_irradiance_environment;
+
+#endif
+
+BEGIN_UNIFORM_BUFFER(3, 2, ShadowUniform)
+  ShadowData data;
+END_UNIFORM_BUFFER(3, 2, ShadowUniform, g_shadow)
+
+// Shade the transparent surface with the lights in its cluster.
+// return: The lit color and the alpha, the color is not premultiplied.
+float4 shade_transparent(
+  const float4 frag_coord,
+  const float3 pos,
+  const float3 vertex_normal,
+  const float2 texcoord,
+  const uint draw_index
+) {
+#ifdef HALA_GLSL
+  const DrawData draw_data = g_draw_data.data[draw_index];
+  const DrawMaterialFlags draw_material_flags = g_draw_material_flags.data[draw_index];
+#else
+  const DrawData draw_data = g_draw_data[draw_index];
+  const DrawMaterialFlags draw_material_flags = g_draw_material_flags[draw_index];
+#endif
+  const Material mtrl = g_materials[draw_data.material_index].data;
+
+  // The alpha is the opacity multiplied by the alpha of the base color map as glTF.
+  float3 albedo = mtrl.base_color;
+  float alpha = mtrl.opacity;
+  if (mtrl.base_color_map_index != INVALID_INDEX) {
+    const float4 base_color = SAMPLE_TEXTURE(
+      g_textures[mtrl.base_color_map_index],
+      g_samplers[mtrl.base_color_map_index],
+      texcoord
+    );
+    albedo = base_color.rgb;
+    alpha *= base_color.a;
+  }
+
+  // The metallic-roughness map stores the roughness in G and the metallic in B as glTF.
+  float metallic = mtrl.metallic;
+  float roughness = mtrl.roughness;
+  if (mtrl.metallic_roughness_map_index != INVALID_INDEX) {
+    const float4 metallic_roughness = SAMPLE_TEXTURE(
+      g_textures[mtrl.metallic_roughness_map_index],
+      g_samplers[mtrl.metallic_roughness_map_index],
+      texcoord
+    );
+    metallic *= metallic_roughness.b;
+    roughness *= metallic_roughness.g;
+  }
+  metallic = saturate(metallic);
+  roughness = saturate(roughness);
+
+  float3 emissive = mtrl.emission;
+  if (mtrl.emission_map_index != INVALID_INDEX) {
+    emissive *= SAMPLE_TEXTURE(
+      g_textures[mtrl.emission_map_index],
+      g_samplers[mtrl.emission_map_index],
+      texcoord
+    ).rgb;
+  }
+
+  // The occlusion map stores the baked occlusion in R as glTF, it only darkens the ambient term.
+  float occlusion = 1.0;
+  if (draw_material_flags.occlusion_map_index != INVALID_INDEX) {
+    const float map_occlusion = SAMPLE_TEXTURE(
+      g_textures[draw_material_flags.occlusion_map_index],
+      g_samplers[draw_material_flags.occlusion_map_index],
+      texcoord
+    ).r;
+    occlusion = 1.0 + draw_material_flags.occlusion_strength * (map_occlusion - 1.0);
+  }
+
+  const float3 view_dir = normalize(g_global_uniform.camera_position.xyz - pos);
+  float3 normal = normalize(vertex_normal);
+  // The back faces of the double-sided meshlets are shaded with the flipped normal.
+  if ((draw_material_flags.flags & DRAW_FLAG_DOUBLE_SIDED) != 0 && dot(normal, view_dir) < 0.0) {
+    normal = -normal;
+  }
+
+  // Find the cluster of the fragment as the lighting pass.
+  // The clusters end at the farthest opaque depth of the tile, the transparent surface in front of it is always inside.
+  const uint2 tile_pos = uint2(frag_coord.xy) / LIGHT_TILE_SIZE;
+  const uint tile_index = tile_pos.y * g_push_constants.num_of_tiles_x + tile_pos.x;
+  const float tile_far = asfloat(LOAD_BUFFER(in_light_clusters, get_light_tile_far_address(g_push_constants.num_of_tiles, tile_index)));
+  const float z_near = g_push_constants.z_near;
+  const float view_distance = z_near / max(frag_coord.z, LIGHT_CLUSTER_MIN_DEPTH);
+  const uint slice = get_light_slice(z_near, tile_far, view_distance);
+  const uint light_mask = LOAD_BUFFER(in_light_clusters, get_light_cluster_address(tile_index, slice));
+
+  const uint shadow_cascade = get_shadow_cascade(g_shadow.data, view_distance);
+  const float3 direct = evaluate_direct_lighting(
+    in_shadow_map,
+    g_shadow.data,
+    shadow_cascade,
+    light_mask,
+    pos,
+    normal,
+    view_dir,
+    albedo,
+    metallic,
+    roughness
+  );
+  float3 ambient;
+  if (g_push_constants.environment_intensity > 0.0) {
+    ambient = evaluate_environment_lighting(
+      COMBINED_TEXTURE_ARG(in_specular_environment),
+      COMBINED_TEXTURE_ARG(in_irradiance_environment),
+      normal,
+      view_dir,
+      albedo,
+      metallic,
+      roughness
+    ) * g_push_constants.environment_intensity;
+  } else {
+    ambient = AMBIENT_INTENSITY * albedo * (1.0 - metallic);
+  }
+  ambient *= occlusion;
+
+  return float4(direct + ambient + emissive, saturate(alpha));
+}
+
+// The weight of the fragment in the weighted blended OIT, the closer fragments weigh more.
+float get_oit_weight(const float view_distance, const float alpha) {
+  const float d = view_distance / OIT_WEIGHT_DISTANCE;
+  return alpha * clamp(0.03 / (1e-5 + d * d * d * d), OIT_MIN_WEIGHT, OIT_MAX_WEIGHT);
+}
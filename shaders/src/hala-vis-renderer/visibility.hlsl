@@ -112,11 +112,25 @@ struct VertexAttributes {
   float3 position_ddy;
   float3 normal;
   float3 tangent;
+  float bitangent_sign; // The handedness of the tangent frame, -1 for the mirrored texture coordinates.
   float2 texcoord;
   float2 texcoord_ddx;
   float2 texcoord_ddy;
+  float2 motion; // The UV offset from this frame to the last frame, without the jitter.
 };
 
+// Get the handedness of the tangent frame of the vertex, the tangent W of glTF.
+float get_tangent_sign(in uint draw_index, in uint vertex_index) {
+#ifdef HALA_HLSL
+  const uint offset = g_tangent_signs[draw_index];
+  const uint bits = g_tangent_signs[offset + vertex_index / 32];
+#else
+  const uint offset = g_tangent_signs.data[draw_index];
+  const uint bits = g_tangent_signs.data[offset + vertex_index / 32];
+#endif
+  return ((bits >> (vertex_index % 32)) & 1) != 0 ? -1.0 : 1.0;
+}
+
 VertexAttributes get_vertex_attributes(in float2 screen_size, in float2 pixel_pos, in DrawData draw_data, in uint3 tri, in Meshlet meshlet) {
 #ifdef HALA_HLSL
   const ObjectUniform per_object_data = g_per_object_uniforms[draw_data.object_index];
@@ -144,9 +158,10 @@ VertexAttributes get_vertex_attributes(in float2 screen_size, in float2 pixel_po
   const float3 n0 = normalize(mul(float4(vertex0.normal_x, vertex0.normal_y, vertex0.normal_z, 0.0), per_object_data.i_m_mtx).xyz);
   const float3 n1 = normalize(mul(float4(vertex1.normal_x, vertex1.normal_y, vertex1.normal_z, 0.0), per_object_data.i_m_mtx).xyz);
   const float3 n2 = normalize(mul(float4(vertex2.normal_x, vertex2.normal_y, vertex2.normal_z, 0.0), per_object_data.i_m_mtx).xyz);
-  const float3 t0 = normalize(mul(float4(vertex0.tangent_x, vertex0.tangent_y, vertex0.tangent_z, 0.0), per_object_data.i_m_mtx).xyz);
-  const float3 t1 = normalize(mul(float4(vertex1.tangent_x, vertex1.tangent_y, vertex1.tangent_z, 0.0), per_object_data.i_m_mtx).xyz);
-  const float3 t2 = normalize(mul(float4(vertex2.tangent_x, vertex2.tangent_y, vertex2.tangent_z, 0.0), per_object_data.i_m_mtx).xyz);
+  // The tangent lies on the surface, so it is transformed by the model matrix rather than the normal matrix.
+  const float3 t0 = normalize(mul(per_object_data.m_mtx, float4(vertex0.tangent_x, vertex0.tangent_y, vertex0.tangent_z, 0.0)).xyz);
+  const float3 t1 = normalize(mul(per_object_data.m_mtx, float4(vertex1.tangent_x, vertex1.tangent_y, vertex1.tangent_z, 0.0)).xyz);
+  const float3 t2 = normalize(mul(per_object_data.m_mtx, float4(vertex2.tangent_x, vertex2.tangent_y, vertex2.tangent_z, 0.0)).xyz);
 
   const float4 pt0 = mul(per_object_data.mvp_mtx, vp0);
   const float4 pt1 = mul(per_object_data.mvp_mtx, vp1);
@@ -189,6 +204,30 @@ VertexAttributes get_vertex_attributes(in float2 screen_size, in float2 pixel_po
     dx3, dy3
   );
 
+  // The clip position of the last frame is linear in the object position, so it is interpolated as the other attributes.
+  float4 prev_clip_pos;
+  float4 dx4, dy4;
+  calc_deriv_float4(
+    C,
+    mul(per_object_data.prev_mvp_mtx, vp0),
+    mul(per_object_data.prev_mvp_mtx, vp1),
+    mul(per_object_data.prev_mvp_mtx, vp2),
+    prev_clip_pos,
+    dx4, dy4
+  );
+  const float2 prev_screen_pos = prev_clip_pos.xy / prev_clip_pos.w * float2(0.5, -0.5) + 0.5;
+  vertex_attributes.motion = prev_screen_pos - (screen_pos - g_global_uniform.jitter.zw);
+
+  // The handedness of the tangent frame is interpolated from the vertices, the vertices of a triangle usually agree.
+  const float3 signs = float3(
+    get_tangent_sign(meshlet.draw_index, vertex_index0),
+    get_tangent_sign(meshlet.draw_index, vertex_index1),
+    get_tangent_sign(meshlet.draw_index, vertex_index2)
+  );
+  float tangent_sign, dx1, dy1;
+  calc_deriv_float(C, signs.x, signs.y, signs.z, tangent_sign, dx1, dy1);
+  vertex_attributes.bitangent_sign = tangent_sign < 0.0 ? -1.0 : 1.0;
+
   return vertex_attributes;
 }
 
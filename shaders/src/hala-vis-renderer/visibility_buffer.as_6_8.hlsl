@@ -25,6 +25,7 @@ BEGIN_PUSH_CONSTANTS(PushConstants)
   uint meshlet_count;
   uint hiz_levels;
   uint2 hiz_size;
+  uint material_variant;
 END_PUSH_CONSTANTS(PushConstants, g_push_constants)
 
 #ifdef HALA_GLSL
@@ -61,13 +62,17 @@ END_PUSH_CONSTANTS(PushConstants, g_push_constants)
 #ifdef HALA_GLSL
   const Meshlet meshlet = g_global_meshlets.data[meshlet_index];
   const DrawData draw_data = g_draw_data.data[meshlet.draw_index];
+  const DrawMaterialFlags draw_material_flags = g_draw_material_flags.data[meshlet.draw_index];
   #define per_object_data (g_per_object_uniforms[draw_data.object_index])
 #else
   const Meshlet meshlet = g_global_meshlets[meshlet_index];
   const DrawData draw_data = g_draw_data[meshlet.draw_index];
+  const DrawMaterialFlags draw_material_flags = g_draw_material_flags[meshlet.draw_index];
   const ObjectUniform per_object_data = g_per_object_uniforms[draw_data.object_index];
 #endif
-  const float3 camera_position = g_cameras.data[0].position;
+  // Only the meshlets of the material variant are drawn by this pipeline.
+  const bool is_in_variant = (draw_material_flags.flags & MATERIAL_VARIANT_MASK) == g_push_constants.material_variant;
+  const float3 camera_position = g_global_uniform.camera_position.xyz;
 
   // printf("[TASK SHADER] Draw Index: %d\n", meshlet.draw_index);
   // printf("[TASK SHADER] Material Index: %d\n", draw_data.material_index);
@@ -75,7 +80,7 @@ END_PUSH_CONSTANTS(PushConstants, g_push_constants)
   bool is_visible = false;
 
   const uint culling_flag = LOAD_BUFFER(in_culling_flags, meshlet_index * 4);
-  if (culling_flag == 0) {
+  if (is_in_variant && culling_flag == 0) {
     const float world_scale = max(length(per_object_data.m_mtx[0]), max(length(per_object_data.m_mtx[1]), length(per_object_data.m_mtx[2])));
     const float3 bound_sphere_center_vs = mul(per_object_data.mv_mtx, float4(meshlet.bound_sphere.xyz, 1.0)).xyz;
     const float bound_sphere_radius = meshlet.bound_sphere.w * world_scale;
@@ -100,6 +105,7 @@ END_PUSH_CONSTANTS(PushConstants, g_push_constants)
 
   // One meshlet to one mesh group.
   const uint visible_count = WaveActiveCountBits(is_visible);
+  add_drawn_meshlets(visible_count);
   DISPATCH_MESH(visible_count, 1, 1, ms_payload);
   // End Function Code.
   //////////////////////////////////////////////////////////////////////////
@@ -16,6 +16,9 @@ layout(max_vertices = MAX_VERTEX_COUNT, max_primitives = MAX_TRIANGLE_COUNT) out
 
 taskPayloadSharedEXT MeshShaderPayLoad ms_payload;
 
+// The texture coordinates for the alpha test of the masked meshlets.
+layout(location = 0) out float2 out_texcoord[];
+
 void main() {
   #define triangles gl_PrimitiveTriangleIndicesEXT
 
@@ -23,6 +26,7 @@ void main() {
   const uvec3 group_thread_id = gl_LocalInvocationID;
 
   #define OUT_POSITION(index) gl_MeshVerticesEXT[index].gl_Position
+  #define OUT_TEXCOORD(index) out_texcoord[index]
 
   #define OUT_PRIMITIVE_ID(index) gl_MeshPrimitivesEXT[index].gl_PrimitiveID
 
@@ -35,6 +39,8 @@ void main() {
 
 struct ToFragment {
   float4 position: SV_Position;
+  // The texture coordinates for the alpha test of the masked meshlets.
+  [[vk::location(0)]] float2 texcoord: TEXCOORD0;
 };
 
 struct ToFragmentPrimitive {
@@ -52,6 +58,7 @@ void main(
   uint3 group_thread_id : SV_GroupThreadID
 ) {
   #define OUT_POSITION(index) vertices[index].position
+  #define OUT_TEXCOORD(index) vertices[index].texcoord
 
   #define OUT_PRIMITIVE_ID(index) primitives[index].primitive_id
 
@@ -84,6 +91,7 @@ void main(
     const float4 h_position = mul(per_object_data.mvp_mtx, float4(position, 1.0));
 
     OUT_POSITION(vertex_id) = h_position;
+    OUT_TEXCOORD(vertex_id) = float2(vertex.tex_coord_x, vertex.tex_coord_y);
   }
 
   // Per thread write two triangles.
@@ -0,0 +1,64 @@
+#define USE_MESH_SHADER
+
+#ifdef HALA_GLSL
+
+#include "scene.glsl"
+#include "hala-vis-renderer/visibility.glsl"
+
+layout(location = 0) in float2 in_texcoord;
+
+layout(location = 0) out uint out_color;
+
+void main() {
+  #define IN_PRIMITIVE_ID gl_PrimitiveID
+  #define IN_TEXCOORD in_texcoord
+  #define OUT_COLOR out_color
+
+  #define g_global_meshlets (g_global_meshlets.data)
+  #define g_draw_data (g_draw_data.data)
+  #define g_draw_material_flags (g_draw_material_flags.data)
+
+#else
+
+#include "scene.hlsl"
+#include "visibility.hlsl"
+
+struct FragmentOutput {
+  [[vk::location(0)]] uint color: SV_Target0;
+};
+
+FragmentOutput main(uint primitive_id: SV_PrimitiveID, [[vk::location(0)]] float2 texcoord: TEXCOORD0) {
+  #define IN_PRIMITIVE_ID primitive_id
+  #define IN_TEXCOORD texcoord
+  #define OUT_COLOR output.color
+
+  FragmentOutput output = (FragmentOutput)0;
+
+#endif
+
+  uint meshlet_index, triangle_id;
+  unpack_meshlet_triangle_index(uint(IN_PRIMITIVE_ID), meshlet_index, triangle_id);
+  const Meshlet meshlet = g_global_meshlets[meshlet_index];
+  const DrawData draw_data = g_draw_data[meshlet.draw_index];
+  const DrawMaterialFlags draw_material_flags = g_draw_material_flags[meshlet.draw_index];
+  const Material mtrl = g_materials[draw_data.material_index].data;
+
+  // The alpha is the opacity multiplied by the alpha of the base color map as glTF.
+  float alpha = mtrl.opacity;
+  if (mtrl.base_color_map_index != INVALID_INDEX) {
+    alpha *= SAMPLE_TEXTURE(
+      g_textures[mtrl.base_color_map_index],
+      g_samplers[mtrl.base_color_map_index],
+      IN_TEXCOORD
+    ).a;
+  }
+  if (alpha < draw_material_flags.alpha_cutoff) {
+    discard;
+  }
+
+  OUT_COLOR = IN_PRIMITIVE_ID;
+
+#ifdef HALA_HLSL
+  return output;
+#endif
+}
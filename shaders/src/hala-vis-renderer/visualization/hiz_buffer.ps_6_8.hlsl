@@ -53,7 +53,7 @@
   //////////////////////////////////////////////////////////////////////////
   // Begin Function Code.
 
-  OUT_COLOR = float4(SAMPLE_TEXTURE(in_depth_image, in_depth_sampler, IN_UV).rrr * g_push_constants.scale, 1.0);
+  OUT_COLOR = float4(SAMPLE_TEXTURE(in_depth_image, in_depth_sampler, get_render_target_uv(IN_UV)).rrr * g_push_constants.scale, 1.0);
 
   // End Function Code.
   //////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,18 @@
+#version 460 core
+
+#extension GL_EXT_nonuniform_qualifier : require
+#extension GL_EXT_buffer_reference2 : require
+#extension GL_EXT_debug_printf : enable
+#extension GL_ARB_gpu_shader_int64 : enable
+
+#include "defines.glsl"
+#include "octahedral.glsl"
+
+layout(input_attachment_index = 0, binding = 0, set = 3) uniform subpassInput in_input_image;
+
+layout(location = 0) out vec4 out_color;
+
+void main() {
+  const float3 normal = decode_octahedral_normal(subpassLoad(in_input_image).xy);
+  out_color = float4(normal * 0.5 + 0.5, 1.0);
+}
@@ -0,0 +1,19 @@
+#include "defines.hlsl"
+#include "octahedral.hlsl"
+
+[[vk::input_attachment_index(0)]]
+[[vk::binding(0, 3)]]
+SubpassInput<float2> in_input_image;
+
+struct FragmentOutput {
+  [[vk::location(0)]] float4 color: SV_Target0;
+};
+
+FragmentOutput main() {
+  FragmentOutput output = (FragmentOutput)0;
+
+  const float3 normal = decode_octahedral_normal(in_input_image.SubpassLoad());
+  output.color = float4(normal * 0.5 + 0.5, 1.0);
+
+  return output;
+}
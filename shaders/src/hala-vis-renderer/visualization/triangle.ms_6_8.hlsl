@@ -108,7 +108,7 @@ void main(
   StructuredBuffer<Vertex> vertex_buffer = g_vertices[meshlet.draw_index];
   StructuredBuffer<uint> vertex_index_buffer = g_unique_vertices[meshlet.draw_index];
 #endif
-  const float3 camera_position = g_cameras.data[0].position;
+  const float3 camera_position = g_global_uniform.camera_position.xyz;
 
   SetMeshOutputCounts(meshlet.num_of_vertices, meshlet.num_of_primitives);
 
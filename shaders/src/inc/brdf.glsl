@@ -0,0 +1,2 @@
+#include "defines.glsl"
+#include "brdf.hlsl"
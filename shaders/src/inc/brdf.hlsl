@@ -0,0 +1,80 @@
+#ifndef _BRDF_HLSL_
+#define _BRDF_HLSL_
+
+#ifndef PI
+#define PI 3.14159265358979
+#endif
+
+// The minimum perceptual roughness to keep the specular highlight of the point lights.
+#define MIN_ROUGHNESS 0.045
+
+//////////////////////////////////////////////////////////////////////////
+// Cook-Torrance GGX microfacet BRDF.
+float3 fresnel_schlick(const float3 f0, const float v_dot_h) {
+  const float f = pow(1.0 - v_dot_h, 5.0);
+  return f0 + (float3(1.0, 1.0, 1.0) - f0) * f;
+}
+
+// The GGX(Trowbridge-Reitz) normal distribution, alpha is the squared perceptual roughness.
+float distribution_ggx(const float n_dot_h, const float alpha) {
+  const float alpha_sq = alpha * alpha;
+  const float d = n_dot_h * n_dot_h * (alpha_sq - 1.0) + 1.0;
+  return alpha_sq / (PI * d * d);
+}
+
+// The height correlated Smith visibility term, already divided by 4 * n_dot_l * n_dot_v.
+float visibility_smith_ggx_correlated(const float n_dot_v, const float n_dot_l, const float alpha) {
+  const float alpha_sq = alpha * alpha;
+  const float ggx_v = n_dot_l * sqrt(n_dot_v * n_dot_v * (1.0 - alpha_sq) + alpha_sq);
+  const float ggx_l = n_dot_v * sqrt(n_dot_l * n_dot_l * (1.0 - alpha_sq) + alpha_sq);
+  return 0.5 / max(ggx_v + ggx_l, 1e-5);
+}
+
+// Evaluate the metallic-roughness BRDF multiplied by the cosine term.
+// n, v and l are the normalized normal, view and light directions pointing away from the surface.
+float3 evaluate_brdf(
+  const float3 base_color,
+  const float metallic,
+  const float roughness,
+  const float3 n,
+  const float3 v,
+  const float3 l
+) {
+  const float n_dot_l = saturate(dot(n, l));
+  if (n_dot_l <= 0.0) {
+    return float3(0.0, 0.0, 0.0);
+  }
+  const float3 h = normalize(v + l);
+  const float n_dot_v = max(dot(n, v), 1e-4);
+  const float n_dot_h = saturate(dot(n, h));
+  const float v_dot_h = saturate(dot(v, h));
+
+  const float perceptual_roughness = clamp(roughness, MIN_ROUGHNESS, 1.0);
+  const float alpha = perceptual_roughness * perceptual_roughness;
+
+  // The dielectric reflects 4% at the normal incidence, the metal tints the reflection by the base color.
+  const float3 f0 = lerp(float3(0.04, 0.04, 0.04), base_color, metallic);
+  const float3 f = fresnel_schlick(f0, v_dot_h);
+  const float d = distribution_ggx(n_dot_h, alpha);
+  const float vis = visibility_smith_ggx_correlated(n_dot_v, n_dot_l, alpha);
+  const float3 specular = f * (d * vis);
+
+  // The metal has no diffuse, the energy reflected by the specular does not enter the surface.
+  const float3 diffuse = (float3(1.0, 1.0, 1.0) - f) * (1.0 - metallic) * base_color * (1.0 / PI);
+
+  return (diffuse + specular) * n_dot_l;
+}
+
+// The scale and bias of f0 of the split sum, integrated over the hemisphere for the image based lighting.
+// The analytic fit of the pre-integrated BRDF, instead of the lookup table.
+// https://www.unrealengine.com/en-US/blog/physically-based-shading-on-mobile
+float3 get_environment_brdf(const float3 f0, const float perceptual_roughness, const float n_dot_v) {
+  const float4 c0 = float4(-1.0, -0.0275, -0.572, 0.022);
+  const float4 c1 = float4(1.0, 0.0425, 1.04, -0.04);
+  const float4 r = perceptual_roughness * c0 + c1;
+  const float a004 = min(r.x * r.x, exp2(-9.28 * n_dot_v)) * r.x + r.y;
+  const float2 ab = float2(-1.04, 1.04) * a004 + r.zw;
+  return f0 * ab.x + ab.y;
+}
+
+#endif // _BRDF_HLSL_
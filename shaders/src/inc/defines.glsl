@@ -20,6 +20,7 @@
 #define SetMeshOutputCounts SetMeshOutputsEXT
 #define WavePrefixCountBits(x) subgroupBallotExclusiveBitCount(subgroupBallot(x))
 #define WaveActiveCountBits(x) subgroupBallotBitCount(subgroupBallot(x))
+#define WaveIsFirstLane() subgroupElect()
 #define GroupMemoryBarrierWithGroupSync() groupMemoryBarrier(); \
   barrier()
 #define GroupMemoryBarrier() groupMemoryBarrier()
@@ -48,9 +49,12 @@
 #define asfloat(a) uintBitsToFloat(a)
 #define firstbitlow(a) findLSB(a)
 #define firstbithigh(a) findMSB(a)
+#define countbits(a) bitCount(a)
 #define rcp(a) (1.0 / a)
 #define rsqrt(a) inversesqrt(a)
 #define frac(a) fract(a)
+#define atan2(y, x) atan(y, x)
+#define reversebits(a) bitfieldReverse(a)
 
 #define DISPATCH_MESH(x, y, z, payload) EmitMeshTasksEXT(x, y, z)
 
@@ -89,15 +93,23 @@
 
 #define LOAD_SAMPLE(sampler, uv, level) (texelFetch(sampler, ivec2(uv), int(level)))
 
+#define COMBINED_TEXTURE2D_PARAM(type, tex) in sampler2D tex
+#define COMBINED_TEXTURE_ARG(tex) tex
+#define SAMPLE_COMBINED_TEXTURE_LEVEL(tex, uv, level) (textureLod(tex, uv, level))
+
 #define LOAD_SUBPASS_INPUT(input) (subpassLoad(input))
 
 #define INTERLOCKED_OR(ptr, value, out) (out = atomicOr(ptr, value))
+#define INTERLOCKED_MIN(ptr, value, out) (out = atomicMin(ptr, value))
+#define INTERLOCKED_ADD(ptr, value, out) (out = atomicAdd(ptr, value))
 
 #define INTERLOCKED_ADD_RWBUFFER(ptr, addr, value, out) (out = atomicAdd(ptr[(addr) / 4], value))
 
 #define LOAD_BUFFER(ptr, addr) (ptr[(addr) / 4])
 #define STORE_RWBUFFER(ptr, addr, value) (ptr[(addr) / 4] = value)
 
+#define STORE_IMAGE(image, texel, value) (imageStore(image, ivec2(texel), float4(value)))
+
 #define BEGIN_CONST(type, name) const type name = type(
 #define END_CONST() );
 
@@ -57,15 +57,24 @@
 
 #define LOAD_SAMPLE(tex, uv, level) (tex.Load(uint3(uv, level)))
 
+// The texture and its sampler of a combined image sampler, the sampler is named with the suffix "_sampler".
+#define COMBINED_TEXTURE2D_PARAM(type, tex) in Texture2D<type> tex, in SamplerState tex##_sampler
+#define COMBINED_TEXTURE_ARG(tex) tex, tex##_sampler
+#define SAMPLE_COMBINED_TEXTURE_LEVEL(tex, uv, level) (tex.SampleLevel(tex##_sampler, uv, level))
+
 #define LOAD_SUBPASS_INPUT(input) (input.SubpassLoad())
 
 #define INTERLOCKED_OR(ptr, value, out) (InterlockedOr(ptr, value, out))
+#define INTERLOCKED_MIN(ptr, value, out) (InterlockedMin(ptr, value, out))
+#define INTERLOCKED_ADD(ptr, value, out) (InterlockedAdd(ptr, value, out))
 
 #define INTERLOCKED_ADD_RWBUFFER(buffer, addr, value, out) (buffer.InterlockedAdd(addr, value, out))
 
 #define LOAD_BUFFER(buffer, addr) (buffer.Load(addr))
 #define STORE_RWBUFFER(buffer, addr, value) (buffer.Store(addr, value))
 
+#define STORE_IMAGE(image, texel, value) (image[texel] = (value))
+
 #define BEGIN_CONST(type, name) static const type name = {
 #define END_CONST() };
 
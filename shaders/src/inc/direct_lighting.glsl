@@ -0,0 +1,2 @@
+#include "defines.glsl"
+#include "direct_lighting.hlsl"
\ No newline at end of file
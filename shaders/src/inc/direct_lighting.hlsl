@@ -0,0 +1,41 @@
+#ifndef _DIRECT_LIGHTING_HLSL_
+#define _DIRECT_LIGHTING_HLSL_
+
+// The constant ambient without the environment map.
+#define AMBIENT_INTENSITY 0.03
+
+// The direct lighting of the lights in the cluster, shared by the deferred and the forward shading.
+// Only the directional light of the shadow uniform is shadowed, until the view distance of the last cascade.
+float3 evaluate_direct_lighting(
+#ifdef HALA_GLSL
+  in sampler2D shadow_map,
+#else
+  in Texture2D<float> shadow_map,
+#endif
+  const ShadowData shadow,
+  const uint shadow_cascade,
+  uint light_mask,
+  const float3 pos,
+  const float3 normal,
+  const float3 view_dir,
+  const float3 albedo,
+  const float metallic,
+  const float roughness
+) {
+  float3 direct = float3(0.0, 0.0, 0.0);
+  while (light_mask != 0) {
+    const uint i = firstbitlow(light_mask);
+    light_mask &= ~(0x1u << i);
+
+    float3 light_dir, radiance;
+    if (get_light_radiance(g_lights.data[i], get_scene_light_range(i), pos, light_dir, radiance)) {
+      if (int(i) == shadow.light_index && shadow_cascade < shadow.num_of_cascades) {
+        radiance *= get_shadow_visibility(shadow_map, shadow, shadow_cascade, pos, normal);
+      }
+      direct += evaluate_brdf(albedo, metallic, roughness, normal, view_dir, light_dir) * radiance;
+    }
+  }
+  return direct;
+}
+
+#endif // _DIRECT_LIGHTING_HLSL_
@@ -0,0 +1,85 @@
+#ifndef _ENVIRONMENT_HLSL_
+#define _ENVIRONMENT_HLSL_
+
+#ifndef PI
+#define PI 3.14159265358979
+#endif
+
+// The image based lighting of the equirectangular environment map.
+// The specular and irradiance maps are octahedral mapped, the specular roughness is linear in the mip levels.
+// NOTICE: Keep it same as SPECULAR_MIP_LEVELS in environment.rs.
+#define ENVIRONMENT_SPECULAR_MIP_LEVELS 6
+
+// Get the UV of the direction in the equirectangular map, +Y is up and the top row is the zenith.
+float2 get_equirect_uv(const float3 dir) {
+  const float u = atan2(dir.z, dir.x) * (0.5 / PI) + 0.5;
+  const float v = acos(clamp(dir.y, -1.0, 1.0)) * (1.0 / PI);
+  return float2(u, v);
+}
+
+// The i-th of the n points of the Hammersley sequence in [0, 1)^2.
+float2 hammersley(const uint i, const uint n) {
+  return float2(float(i) / float(n), float(reversebits(i)) * 2.3283064365386963e-10);
+}
+
+// Build an orthonormal basis around the normal and transform the local direction to it.
+float3 to_world_direction(const float3 local_dir, const float3 n) {
+  const float3 up = abs(n.y) < 0.999 ? float3(0.0, 1.0, 0.0) : float3(1.0, 0.0, 0.0);
+  const float3 t = normalize(cross(up, n));
+  const float3 b = cross(n, t);
+  return t * local_dir.x + b * local_dir.y + n * local_dir.z;
+}
+
+// Get the mip level of the equirectangular map whose texels cover the solid angle of a sample, the filtered importance sampling.
+// pdf is the probability density of the sample direction over the solid angle, size is the size of the first mip level.
+float get_equirect_sample_level(const float pdf, const uint sample_count, const float3 dir, const float2 size) {
+  // The solid angle of the texel shrinks by sin(theta) towards the poles.
+  const float sin_theta = max(sqrt(saturate(1.0 - dir.y * dir.y)), 1e-4);
+  const float texel_solid_angle = 2.0 * PI * PI * sin_theta / (size.x * size.y);
+  const float sample_solid_angle = 1.0 / (float(sample_count) * pdf + 1e-4);
+  // One level above the matched solid angle, the overlapped filters hide the gaps between the samples.
+  return max(0.5 * log2(sample_solid_angle / texel_solid_angle) + 1.0, 0.0);
+}
+
+// Sample the half vector by the GGX distribution around +Z, alpha is the squared perceptual roughness.
+float3 importance_sample_ggx(const float2 xi, const float alpha) {
+  const float phi = 2.0 * PI * xi.x;
+  const float cos_theta = sqrt((1.0 - xi.y) / (1.0 + (alpha * alpha - 1.0) * xi.y));
+  const float sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+  return float3(sin_theta * cos(phi), sin_theta * sin(phi), cos_theta);
+}
+
+// Sample the direction by the cosine weighted distribution around +Z.
+float3 sample_cosine_hemisphere(const float2 xi) {
+  const float phi = 2.0 * PI * xi.x;
+  const float sin_theta = sqrt(xi.y);
+  return float3(sin_theta * cos(phi), sin_theta * sin(phi), sqrt(1.0 - xi.y));
+}
+
+// The ambient lighting of the environment by the split sum approximation.
+// The irradiance map holds the cosine weighted average radiance, so the Lambert diffuse is albedo * irradiance.
+float3 evaluate_environment_lighting(
+  COMBINED_TEXTURE2D_PARAM(float4, specular_map),
+  COMBINED_TEXTURE2D_PARAM(float4, irradiance_map),
+  const float3 normal,
+  const float3 view_dir,
+  const float3 albedo,
+  const float metallic,
+  const float roughness
+) {
+  const float n_dot_v = max(dot(normal, view_dir), 1e-4);
+  const float perceptual_roughness = clamp(roughness, MIN_ROUGHNESS, 1.0);
+  const float3 f0 = lerp(float3(0.04, 0.04, 0.04), albedo, metallic);
+  const float3 environment_brdf = get_environment_brdf(f0, perceptual_roughness, n_dot_v);
+
+  const float3 reflect_dir = reflect(-view_dir, normal);
+  const float level = perceptual_roughness * float(ENVIRONMENT_SPECULAR_MIP_LEVELS - 1);
+  const float3 specular = SAMPLE_COMBINED_TEXTURE_LEVEL(specular_map, encode_octahedral_normal(reflect_dir), level).rgb * environment_brdf;
+
+  const float3 irradiance = SAMPLE_COMBINED_TEXTURE_LEVEL(irradiance_map, encode_octahedral_normal(normal), 0.0).rgb;
+  const float3 diffuse = (float3(1.0, 1.0, 1.0) - environment_brdf) * (1.0 - metallic) * albedo * irradiance;
+
+  return diffuse + specular;
+}
+
+#endif // _ENVIRONMENT_HLSL_
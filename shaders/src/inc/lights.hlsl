@@ -0,0 +1,99 @@
+#ifndef _LIGHTS_HLSL_
+#define _LIGHTS_HLSL_
+
+#define LIGHT_TYPE_POINT 0
+#define LIGHT_TYPE_DIRECTIONAL 1
+#define LIGHT_TYPE_SPOT 2
+
+// The range of the light without the range of KHR_lights_punctual is where the intensity falls below this cutoff.
+#define LIGHT_CUTOFF_INTENSITY 0.01
+
+// The lights are binned into the clusters of the screen tiles and the depth slices.
+// Each cluster keeps a bit mask of the lights, the light buffer holds at most MAX_LIGHTS(<= 32) lights.
+#define LIGHT_TILE_SIZE 32
+#define LIGHT_NUM_OF_SLICES 16
+// The reversed-Z depth of the tile without any geometry is clamped to it to keep the slices finite.
+#define LIGHT_CLUSTER_MIN_DEPTH 1e-7
+
+//////////////////////////////////////////////////////////////////////////
+// Punctual lights of KHR_lights_punctual.
+
+// Get the influence range of the point light or the spot light.
+// gltf_range: The range of KHR_lights_punctual, 0 if the light has no range.
+float get_light_range(const Light light, const float gltf_range) {
+  if (gltf_range > 0.0) {
+    return gltf_range;
+  }
+  const float max_intensity = max(light.intensity.r, max(light.intensity.g, light.intensity.b));
+  return sqrt(max(max_intensity, 0.0) / LIGHT_CUTOFF_INTENSITY);
+}
+
+// The inverse square falloff windowed to reach zero at the range, as recommended by KHR_lights_punctual.
+float get_range_attenuation(const float distance_sq, const float range) {
+  const float ratio = distance_sq / (range * range);
+  const float window = saturate(1.0 - ratio * ratio);
+  return window * window / max(distance_sq, 1e-4);
+}
+
+// The smooth falloff between the outer cone and the inner cone.
+float get_spot_attenuation(const float3 light_dir, const Light light) {
+  const float cos_angle = dot(normalize(light.u), -light_dir);
+  const float cos_inner = light.v.x;
+  const float cos_outer = light.v.y;
+  const float t = saturate((cos_angle - cos_outer) / max(cos_inner - cos_outer, 1e-4));
+  return t * t;
+}
+
+// Get the direction from the surface to the light and the radiance arriving at the surface.
+// gltf_range: The range of KHR_lights_punctual, 0 if the light has no range.
+// return: Whether the light reaches the surface.
+bool get_light_radiance(const Light light, const float gltf_range, const float3 pos, out float3 light_dir, out float3 radiance) {
+  if (light.type == LIGHT_TYPE_DIRECTIONAL) {
+    light_dir = -normalize(light.u);
+    radiance = light.intensity;
+    return true;
+  }
+
+  const float3 light_2_surface = light.position - pos;
+  const float light_distance_sq = dot(light_2_surface, light_2_surface);
+  light_dir = light_2_surface * rsqrt(max(light_distance_sq, 1e-8));
+  radiance = float3(0.0, 0.0, 0.0);
+
+  const float range = get_light_range(light, gltf_range);
+  if (light_distance_sq >= range * range) {
+    return false;
+  }
+  float attenuation = get_range_attenuation(light_distance_sq, range);
+  if (light.type == LIGHT_TYPE_SPOT) {
+    attenuation *= get_spot_attenuation(light_dir, light);
+  } else if (light.type != LIGHT_TYPE_POINT) {
+    // The area lights are not supported by the rasterizer.
+    return false;
+  }
+  radiance = light.intensity * attenuation;
+  return attenuation > 0.0;
+}
+
+//////////////////////////////////////////////////////////////////////////
+// Light clusters.
+// The cluster buffer holds the light masks of all clusters, ordered by tile then slice,
+// followed by the farthest view distance of each tile.
+uint get_light_cluster_address(const uint tile_index, const uint slice) {
+  return (tile_index * LIGHT_NUM_OF_SLICES + slice) * 4;
+}
+
+uint get_light_tile_far_address(const uint num_of_tiles, const uint tile_index) {
+  return (num_of_tiles * LIGHT_NUM_OF_SLICES + tile_index) * 4;
+}
+
+// The slices split the view distance between the near plane and the farthest geometry of the tile exponentially.
+float get_light_slice_distance(const float z_near, const float tile_far, const float slice) {
+  return z_near * pow(tile_far / z_near, slice / float(LIGHT_NUM_OF_SLICES));
+}
+
+uint get_light_slice(const float z_near, const float tile_far, const float view_distance) {
+  const float t = log(max(view_distance, z_near) / z_near) / log(max(tile_far, z_near * 1.001) / z_near);
+  return uint(clamp(t * float(LIGHT_NUM_OF_SLICES), 0.0, float(LIGHT_NUM_OF_SLICES - 1));
+}
+
+#endif // _LIGHTS_HLSL_
@@ -0,0 +1,2 @@
+#include "defines.glsl"
+#include "octahedral.hlsl"
\ No newline at end of file
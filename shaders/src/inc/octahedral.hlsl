@@ -0,0 +1,26 @@
+#ifndef _OCTAHEDRAL_HLSL_
+#define _OCTAHEDRAL_HLSL_
+
+//////////////////////////////////////////////////////////////////////////
+// Octahedral normal encoding.
+float2 sign_not_zero(float2 v) {
+  return float2(v.x >= 0.0 ? 1.0 : -1.0, v.y >= 0.0 ? 1.0 : -1.0);
+}
+
+// Encode the unit normal to [0, 1]^2.
+float2 encode_octahedral_normal(float3 n) {
+  n /= abs(n.x) + abs(n.y) + abs(n.z);
+  const float2 e = n.z >= 0.0 ? n.xy : (1.0 - abs(n.yx)) * sign_not_zero(n.xy);
+  return e * 0.5 + 0.5;
+}
+
+// Decode the unit normal from [0, 1]^2.
+float3 decode_octahedral_normal(float2 e) {
+  e = e * 2.0 - 1.0;
+  float3 n = float3(e.x, e.y, 1.0 - abs(e.x) - abs(e.y));
+  const float t = saturate(-n.z);
+  n.xy -= sign_not_zero(n.xy) * t;
+  return normalize(n);
+}
+
+#endif // _OCTAHEDRAL_HLSL_
@@ -33,6 +33,14 @@ BEGIN_UNIFORM_BUFFER(0, 0, GlobalUniform)
   float4x4 i_vp_mtx;    // The inverse view-projection matrix.
 
   float4 frustum_planes[6]; // The view frustum planes.
+
+  float4 camera_position; // The camera position in world space.
+
+  float4 jitter;        // The sub-pixel jitter of the projection matrix, xy in NDC and zw in UV.
+  float4x4 prev_vp_mtx; // The view-projection matrix of the last frame, without the jitter.
+
+  float4 render_size;     // The render size of this frame in xy and of the last frame in zw.
+  float4 inv_target_size; // The reciprocal of the size of the render targets in xy, they are allocated at the maximum render scale.
 END_UNIFORM_BUFFER(0, 0, GlobalUniform, g_global_uniform)
 
 BEGIN_UNIFORM_BUFFER(0, 1, CameraData)
@@ -49,6 +57,30 @@ END_BUFFER(0, 3, DrawData, g_draw_data)
 BEGIN_BUFFER(0, 4, Meshlet)
 END_BUFFER(0, 4, Meshlet, g_global_meshlets)
 
+// The render statistics accumulated over all frames.
+// [0]: The number of drawn opaque meshlets, counted by the task shaders of the visibility buffer.
+#ifdef HALA_HLSL
+[[vk::binding(5, 0)]]
+RWByteAddressBuffer g_render_statistics;
+#else
+layout(set = 0, binding = 5) buffer RenderStatistics {
+  uint g_render_statistics[];
+};
+#endif
+
+BEGIN_BUFFER(0, 6, DrawMaterialFlags)
+END_BUFFER(0, 6, DrawMaterialFlags, g_draw_material_flags)
+
+// The handedness of the tangent frames, the tangent W of glTF.
+// [0, number of draws): The offset in words of the bits of each draw.
+// Then one bit per vertex, set for -1.
+BEGIN_BUFFER(0, 7, uint)
+END_BUFFER(0, 7, uint, g_tangent_signs)
+
+// The range of each light of KHR_lights_punctual, 0 for the light without the range.
+BEGIN_BUFFER(0, 8, float)
+END_BUFFER(0, 8, float, g_light_ranges)
+
 BEGIN_UNIFORM_BUFFER_BINDLESS(1, 0, MaterialBuffer)
   Material data;
 END_UNIFORM_BUFFER_BINDLESS(1, 0, MaterialBuffer, g_materials)
@@ -60,6 +92,7 @@ BEGIN_UNIFORM_BUFFER_BINDLESS(1, 1, ObjectUniform)
   float4x4 t_mv_mtx;  // The transposed model-view matrix
   float4x4 it_mv_mtx; // The inverse transposed model-view matrix
   float4x4 mvp_mtx;   // The model-view-projection matrix
+  float4x4 prev_mvp_mtx; // The model-view-projection matrix of the last frame, without the jitter
 END_UNIFORM_BUFFER_BINDLESS(1, 1, ObjectUniform, g_per_object_uniforms)
 
 struct Vertex {
@@ -105,8 +138,25 @@ SAMPLER_BINDLESS(2, 1, g_samplers)
 struct MeshShaderPayLoad {
   uint meshlet_indices[TASK_SHADER_GROUP_SIZE];
 };
+
+// Accumulate the number of drawn opaque meshlets, only one atomic operation per wave.
+void add_drawn_meshlets(const uint count) {
+  if (WaveIsFirstLane() && count > 0) {
+    uint prev_count;
+    INTERLOCKED_ADD_RWBUFFER(g_render_statistics, 0, count, prev_count);
+  }
+}
 #endif
 
+// Get the glTF range of the light, 0 if the light has no range.
+float get_scene_light_range(const uint light_index) {
+#ifdef HALA_HLSL
+  return g_light_ranges[light_index];
+#else
+  return g_light_ranges.data[light_index];
+#endif
+}
+
 bool is_sphere_frustum_culled(const float3 center, const float radius) {
   for (uint i = 0; i < 6; ++i) {
     const float distance = dot(g_global_uniform.frustum_planes[i], float4(center, 1.0));
@@ -145,4 +195,17 @@ bool is_box_frustum_culled(const float3 aabb_min_ws, const float3 aabb_max_ws) {
   return false;
 }
 
+// Get the UV in the render targets from the UV in the render viewport.
+// Only the top left of the render targets is rendered, the UV is clamped to the rendered texels so the filtering never reads the stale ones.
+float2 get_render_target_uv(const float2 uv) {
+  const float2 render_size = g_global_uniform.render_size.xy;
+  return clamp(uv * render_size, float2(0.5, 0.5), render_size - 0.5) * g_global_uniform.inv_target_size.xy;
+}
+
+// Get the UV in the render targets rendered by the last frame, e.g. the TAA history, from the UV in the render viewport.
+float2 get_prev_render_target_uv(const float2 uv) {
+  const float2 render_size = g_global_uniform.render_size.zw;
+  return clamp(uv * render_size, float2(0.5, 0.5), render_size - 0.5) * g_global_uniform.inv_target_size.xy;
+}
+
 #endif // _SCENE_HLSL_
\ No newline at end of file
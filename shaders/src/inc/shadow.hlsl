@@ -0,0 +1,100 @@
+#ifndef _SHADOW_HLSL_
+#define _SHADOW_HLSL_
+
+// The cascaded shadow map of the directional light.
+// The cascades are placed side by side in the shadow map, each one is resolution x resolution texels.
+#define MAX_SHADOW_CASCADES 4
+// The radius of the PCF kernel in texels.
+#define SHADOW_PCF_RADIUS 1
+// The receivers are offset along the normal by this number of texels.
+#define SHADOW_NORMAL_BIAS 1.5
+
+struct ShadowData {
+  float4x4 vp_mtx[MAX_SHADOW_CASCADES];           // The view-projection matrices of the cascades.
+  float4 frustum_planes[MAX_SHADOW_CASCADES * 6]; // The frustum planes of the cascades.
+  float4 split_distances;                         // The farthest view distance of each cascade.
+  float4 texel_sizes;                             // The world size of a shadow map texel of each cascade.
+  float4 depth_biases;                            // The depth of a texel size of each cascade.
+  uint num_of_cascades;
+  int light_index;                                // The directional light casting the shadow, -1 for no shadow.
+  uint resolution;                                // The width and height of each cascade.
+};
+
+// Whether the sphere is outside the frustum of the cascade.
+bool is_sphere_cascade_culled(const ShadowData shadow, const uint cascade, const float3 center, const float radius) {
+  for (uint i = 0; i < 6; ++i) {
+    const float distance = dot(shadow.frustum_planes[cascade * 6 + i], float4(center, 1.0));
+    if (distance <= -radius) {
+      return true;
+    }
+  }
+  return false;
+}
+
+// Get the cascade covering the view distance, num_of_cascades if it is beyond the last cascade.
+uint get_shadow_cascade(const ShadowData shadow, const float view_distance) {
+  uint cascade = 0;
+  while (cascade < shadow.num_of_cascades && view_distance > shadow.split_distances[cascade]) {
+    cascade++;
+  }
+  return cascade;
+}
+
+// Get the debug color of the cascade.
+float3 get_shadow_cascade_color(const uint cascade) {
+  if (cascade == 0) {
+    return float3(1.0, 0.25, 0.25);
+  } else if (cascade == 1) {
+    return float3(0.25, 1.0, 0.25);
+  } else if (cascade == 2) {
+    return float3(0.25, 0.25, 1.0);
+  } else if (cascade == 3) {
+    return float3(1.0, 1.0, 0.25);
+  }
+  // Beyond the last cascade.
+  return float3(1.0, 1.0, 1.0);
+}
+
+// Get the fraction of the light passing the shadow casters, filtered by the bilinear weighted PCF.
+float get_shadow_visibility(
+#ifdef HALA_GLSL
+  in sampler2D shadow_map,
+#else
+  in Texture2D<float> shadow_map,
+#endif
+  const ShadowData shadow,
+  const uint cascade,
+  const float3 pos,
+  const float3 normal
+) {
+  const float3 offset_pos = pos + normal * shadow.texel_sizes[cascade] * SHADOW_NORMAL_BIAS;
+  const float4 shadow_pos = mul(shadow.vp_mtx[cascade], float4(offset_pos, 1.0));
+  const float3 ndc = shadow_pos.xyz / shadow_pos.w;
+  if (abs(ndc.x) > 1.0 || abs(ndc.y) > 1.0 || ndc.z <= 0.0 || ndc.z > 1.0) {
+    return 1.0;
+  }
+
+  const float2 texel_pos = (ndc.xy * 0.5 + 0.5) * float(shadow.resolution);
+  const float receiver_depth = ndc.z + shadow.depth_biases[cascade];
+  // Keep the kernel in the texels of the cascade.
+  const int2 min_texel = int2(int(cascade * shadow.resolution), 0);
+  const int2 max_texel = min_texel + int2(int(shadow.resolution) - 1, int(shadow.resolution) - 1);
+  const float2 base = floor(texel_pos - 0.5);
+  const float2 f = texel_pos - 0.5 - base;
+
+  float visibility = 0.0;
+  for (int y = -SHADOW_PCF_RADIUS; y <= SHADOW_PCF_RADIUS + 1; ++y) {
+    const float wy = y == -SHADOW_PCF_RADIUS ? 1.0 - f.y : (y == SHADOW_PCF_RADIUS + 1 ? f.y : 1.0);
+    for (int x = -SHADOW_PCF_RADIUS; x <= SHADOW_PCF_RADIUS + 1; ++x) {
+      const float wx = x == -SHADOW_PCF_RADIUS ? 1.0 - f.x : (x == SHADOW_PCF_RADIUS + 1 ? f.x : 1.0);
+      const int2 texel = clamp(min_texel + int2(base) + int2(x, y), min_texel, max_texel);
+      const float occluder_depth = LOAD_SAMPLE(shadow_map, texel, 0).r;
+      // The reversed-Z, the receiver is lit unless the occluder is closer to the light.
+      visibility += (receiver_depth >= occluder_depth ? 1.0 : 0.0) * wx * wy;
+    }
+  }
+  const float kernel_size = float(2 * SHADOW_PCF_RADIUS + 1);
+  return visibility / (kernel_size * kernel_size);
+}
+
+#endif // _SHADOW_HLSL_
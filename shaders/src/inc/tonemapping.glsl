@@ -0,0 +1,2 @@
+#include "defines.glsl"
+#include "tonemapping.hlsl"
\ No newline at end of file
@@ -0,0 +1,106 @@
+#ifndef _TONEMAPPING_HLSL_
+#define _TONEMAPPING_HLSL_
+
+// The number of the bins of the log luminance histogram.
+// NOTICE: Keep it same as LUMINANCE_HISTOGRAM_BINS in tonemap.rs.
+#define LUMINANCE_HISTOGRAM_BINS 256
+
+// The tonemapping operators, the none operator only clamps the color for the debug views.
+// NOTICE: Keep them same as TonemapOperator in tonemap.rs.
+#define TONEMAP_OPERATOR_NONE 0
+#define TONEMAP_OPERATOR_ACES 1
+#define TONEMAP_OPERATOR_AGX 2
+#define TONEMAP_OPERATOR_REINHARD 3
+
+// The automatic exposure maps the average luminance to the middle gray.
+#define EXPOSURE_MIDDLE_GRAY 0.18
+
+// The Rec. 709 relative luminance of the linear color.
+float get_luminance(const float3 color) {
+  return dot(color, float3(0.2126, 0.7152, 0.0722));
+}
+
+// Get the histogram bin of the luminance.
+// The bin 0 holds the black pixels, which are excluded from the average, the others cover the log luminance range.
+uint get_luminance_histogram_bin(const float luminance, const float min_log_luminance, const float inv_log_luminance_range) {
+  if (luminance < 1e-5) {
+    return 0;
+  }
+  const float t = saturate((log2(luminance) - min_log_luminance) * inv_log_luminance_range);
+  return min(uint(t * float(LUMINANCE_HISTOGRAM_BINS - 2) + 1.0), uint(LUMINANCE_HISTOGRAM_BINS - 1));
+}
+
+//////////////////////////////////////////////////////////////////////////
+// The ACES filmic curve fitted by Stephen Hill, including the sRGB to ACEScg and back conversions.
+// https://github.com/TheRealMJP/BakingLab/blob/master/BakingLab/ACES.hlsl
+float3 rrt_and_odt_fit(const float3 v) {
+  const float3 a = v * (v + 0.0245786) - 0.000090537;
+  const float3 b = v * (0.983729 * v + 0.4329510) + 0.238081;
+  return a / b;
+}
+
+float3 tonemap_aces(const float3 color) {
+  // The matrices are written by rows, so they work the same in HLSL and GLSL.
+  const float3 aces = float3(
+    dot(float3(0.59719, 0.35458, 0.04823), color),
+    dot(float3(0.07600, 0.90834, 0.01566), color),
+    dot(float3(0.02840, 0.13383, 0.83777), color)
+  );
+  const float3 fitted = rrt_and_odt_fit(aces);
+  return saturate(float3(
+    dot(float3(1.60475, -0.53108, -0.07367), fitted),
+    dot(float3(-0.10208, 1.10813, -0.00605), fitted),
+    dot(float3(-0.00327, -0.07276, 1.07602), fitted)
+  ));
+}
+
+//////////////////////////////////////////////////////////////////////////
+// The AgX base look with the polynomial fit of its sigmoid by Benjamin Wrensch.
+// https://iolite-engine.com/blog_posts/minimal_agx_implementation
+float3 agx_default_contrast_approx(const float3 x) {
+  const float3 x2 = x * x;
+  const float3 x4 = x2 * x2;
+  return 15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x - 0.00232;
+}
+
+float3 tonemap_agx(const float3 color) {
+  const float min_ev = -12.47393;
+  const float max_ev = 4.026069;
+
+  const float3 inset = float3(
+    dot(float3(0.842479062253094, 0.0784335999999992, 0.0792237451477643), color),
+    dot(float3(0.0423282422610123, 0.878468636469772, 0.0791661274605434), color),
+    dot(float3(0.0423756549057051, 0.0784336, 0.879142973793104), color)
+  );
+  const float3 log_color = (clamp(log2(max(inset, float3(1e-10, 1e-10, 1e-10))), min_ev, max_ev) - min_ev) / (max_ev - min_ev);
+  const float3 curve = agx_default_contrast_approx(log_color);
+
+  const float3 outset = float3(
+    dot(float3(1.19687900512017, -0.0980208811401368, -0.0990297440797205), curve),
+    dot(float3(-0.0528968517574562, 1.15190312990417, -0.0989611768448433), curve),
+    dot(float3(-0.0529716355144438, -0.0980434501171241, 1.15107367264116), curve)
+  );
+  // The curve is in the display encoding, the sRGB target encodes it again.
+  return pow(saturate(outset), float3(2.2, 2.2, 2.2));
+}
+
+//////////////////////////////////////////////////////////////////////////
+// The Reinhard operator on the luminance, which keeps the hue of the bright colors.
+float3 tonemap_reinhard(const float3 color) {
+  return saturate(color / (1.0 + get_luminance(color)));
+}
+
+// Map the exposed HDR color to the displayable range by the operator.
+float3 apply_tonemap(const float3 color, const uint tonemap_operator) {
+  const float3 c = max(color, float3(0.0, 0.0, 0.0));
+  if (tonemap_operator == TONEMAP_OPERATOR_ACES) {
+    return tonemap_aces(c);
+  } else if (tonemap_operator == TONEMAP_OPERATOR_AGX) {
+    return tonemap_agx(c);
+  } else if (tonemap_operator == TONEMAP_OPERATOR_REINHARD) {
+    return tonemap_reinhard(c);
+  }
+  return saturate(c);
+}
+
+#endif // _TONEMAPPING_HLSL_
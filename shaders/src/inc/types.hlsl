@@ -93,6 +93,20 @@ struct DrawData {
   uint material_index;
 };
 
+// The material flags of a draw, the lower bits are the material variant drawn by its own pipeline.
+#define DRAW_FLAG_ALPHA_MASK    1
+#define DRAW_FLAG_DOUBLE_SIDED  2
+#define DRAW_FLAG_TRANSPARENT   4
+// The transparent bit is in the mask, so the transparent draws match none of the opaque variants.
+#define MATERIAL_VARIANT_MASK   (DRAW_FLAG_ALPHA_MASK | DRAW_FLAG_DOUBLE_SIDED | DRAW_FLAG_TRANSPARENT)
+
+struct DrawMaterialFlags {
+  uint flags;
+  float alpha_cutoff;
+  uint occlusion_map_index;  // INVALID_INDEX without the occlusion map.
+  float occlusion_strength;
+};
+
 struct Meshlet {
   float4 bound_sphere;  // center, radius
   float3 cone_apex;
@@ -0,0 +1,222 @@
+use std::path::Path;
+use std::fmt::Write;
+
+use anyhow::{
+  Result,
+  Context,
+};
+
+use serde::{
+  Deserialize,
+  Serialize,
+};
+
+use crate::renderer::{
+  DebugSettings,
+  GpuPass,
+  GpuTimingSummary,
+};
+
+/// The camera keyframe of the benchmark path.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct CameraKeyframe {
+  pub position: [f32; 3],
+  pub target: [f32; 3],
+}
+
+/// The benchmark configure.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BenchmarkConfig {
+  // The number of measured frames of each culling strategy.
+  #[serde(default = "BenchmarkConfig::default_num_of_frames")]
+  pub num_of_frames: u32,
+  // The number of frames rendered before measuring, at the first keyframe.
+  #[serde(default = "BenchmarkConfig::default_num_of_warmup_frames")]
+  pub num_of_warmup_frames: u32,
+  // Read back the culling flags every N frames, when replaying the path after the timed frames.
+  #[serde(default = "BenchmarkConfig::default_culling_sample_interval")]
+  pub culling_sample_interval: u32,
+  // The file path of the summary report.
+  #[serde(default = "BenchmarkConfig::default_report_file")]
+  pub report_file: String,
+  // The camera keyframes, the camera moves along a Catmull-Rom spline through them.
+  pub keyframes: Vec<CameraKeyframe>,
+}
+
+/// The implementation of the benchmark configure.
+impl BenchmarkConfig {
+
+  fn default_num_of_frames() -> u32 {
+    600
+  }
+  fn default_num_of_warmup_frames() -> u32 {
+    30
+  }
+  fn default_culling_sample_interval() -> u32 {
+    30
+  }
+  fn default_report_file() -> String {
+    "./out/benchmark_report.md".to_string()
+  }
+
+  /// Load the benchmark configure.
+  /// param config_path: The configure file path.
+  /// return: The benchmark configure.
+  pub fn load<P: AsRef<Path>>(config_path: P) -> Result<Self> {
+    let path = config_path.as_ref();
+    let config_str = std::fs::read_to_string(path)
+      .with_context(|| format!("Failed to read the benchmark file: {:?}", path))?;
+    let config: Self = toml::from_str(&config_str)
+      .with_context(|| format!("Failed to parse the benchmark file: {:?}", path))?;
+
+    if config.keyframes.len() < 2 {
+      return Err(anyhow::anyhow!("The benchmark camera path needs at least 2 keyframes."));
+    }
+    if config.num_of_frames == 0 {
+      return Err(anyhow::anyhow!("The number of benchmark frames is 0."));
+    }
+
+    Ok(config)
+  }
+
+  /// Create the benchmark configure of the recorded camera path with the default settings.
+  /// param keyframes: The recorded camera keyframes.
+  /// return: The benchmark configure.
+  pub fn from_keyframes(keyframes: Vec<CameraKeyframe>) -> Self {
+    Self {
+      num_of_frames: Self::default_num_of_frames(),
+      num_of_warmup_frames: Self::default_num_of_warmup_frames(),
+      culling_sample_interval: Self::default_culling_sample_interval(),
+      report_file: Self::default_report_file(),
+      keyframes,
+    }
+  }
+
+  /// Save the benchmark configure, which is loaded back by --benchmark.
+  /// param config_path: The configure file path.
+  /// return: The result.
+  pub fn save<P: AsRef<Path>>(&self, config_path: P) -> Result<()> {
+    let path = config_path.as_ref();
+    let config_str = toml::to_string_pretty(self)
+      .with_context(|| "Failed to serialize the benchmark configure.")?;
+    std::fs::write(path, config_str)
+      .with_context(|| format!("Failed to write the benchmark file: {:?}", path))?;
+    log::info!("Saved the benchmark camera path of {} keyframes to {:?}.", self.keyframes.len(), path);
+
+    Ok(())
+  }
+
+  /// Sample the camera path.
+  /// param t: The normalized time in [0, 1].
+  /// return: The camera position and target.
+  pub fn sample_camera(&self, t: f32) -> (glam::Vec3, glam::Vec3) {
+    let num_of_segments = self.keyframes.len() - 1;
+    let x = t.clamp(0.0, 1.0) * num_of_segments as f32;
+    let segment = (x.floor() as usize).min(num_of_segments - 1);
+    let local_t = x - segment as f32;
+
+    // Clamp the neighbors at both ends of the path.
+    let keyframe = |index: isize| self.keyframes[index.clamp(0, num_of_segments as isize) as usize];
+    let k0 = keyframe(segment as isize - 1);
+    let k1 = keyframe(segment as isize);
+    let k2 = keyframe(segment as isize + 1);
+    let k3 = keyframe(segment as isize + 2);
+
+    let position = catmull_rom(
+      k0.position.into(), k1.position.into(), k2.position.into(), k3.position.into(), local_t);
+    let target = catmull_rom(
+      k0.target.into(), k1.target.into(), k2.target.into(), k3.target.into(), local_t);
+    (position, target)
+  }
+
+}
+
+/// The culling strategies to compare.
+/// return: The name and the debug settings of each strategy.
+pub(crate) fn culling_strategies() -> [(&'static str, DebugSettings); 3] {
+  [
+    ("No Culling", DebugSettings { disable_culling: true, ..Default::default() }),
+    ("One Pass Culling", DebugSettings { one_pass_culling: true, ..Default::default() }),
+    ("Two Pass Culling", DebugSettings::default()),
+  ]
+}
+
+/// The benchmark result of one culling strategy.
+pub(crate) struct BenchmarkResult {
+  pub name: &'static str,
+  pub gpu_timings: GpuTimingSummary,
+  // The average number of drawn opaque meshlets per frame.
+  pub avg_drawn_meshlets: f64,
+  pub num_of_meshlets: u32,
+  // The average culling rate of the first culling pass, only for the two pass culling.
+  pub avg_culling_rate: Option<f64>,
+}
+
+/// Write the summary report of the benchmark results.
+/// param config: The benchmark configure.
+/// param results: The benchmark results.
+/// return: The result.
+pub(crate) fn write_benchmark_report(config: &BenchmarkConfig, results: &[BenchmarkResult]) -> Result<()> {
+  let mut report = String::new();
+  writeln!(report, "# Benchmark Report")?;
+  writeln!(report)?;
+  writeln!(report, "{} measured frames per strategy, {} warm-up frames, {} keyframes.", config.num_of_frames, config.num_of_warmup_frames, config.keyframes.len())?;
+  writeln!(report)?;
+  writeln!(report, "| Strategy | Avg GPU (ms) | Min (ms) | P95 (ms) | Max (ms) | Drawn Meshlets | Culling Rate |")?;
+  writeln!(report, "|---|---|---|---|---|---|---|")?;
+  for result in results.iter() {
+    let culling_rate = result.avg_culling_rate
+      .map_or("n/a".to_string(), |rate| format!("{:.2}%", rate * 100.0));
+    writeln!(
+      report,
+      "| {} | {:.3} | {:.3} | {:.3} | {:.3} | {:.1} / {} | {} |",
+      result.name,
+      result.gpu_timings.get_frame_average(),
+      result.gpu_timings.get_frame_percentile(0.0),
+      result.gpu_timings.get_frame_percentile(0.95),
+      result.gpu_timings.get_frame_percentile(1.0),
+      result.avg_drawn_meshlets,
+      result.num_of_meshlets,
+      culling_rate,
+    )?;
+  }
+  writeln!(report)?;
+
+  writeln!(report, "## Average Pass Timings (ms)")?;
+  writeln!(report)?;
+  let header = results.iter().map(|result| result.name).collect::<Vec<_>>().join(" | ");
+  writeln!(report, "| Pass | {} |", header)?;
+  writeln!(report, "|---|{}", "---|".repeat(results.len()))?;
+  for pass in GpuPass::ALL {
+    let timings = results.iter()
+      .map(|result| format!("{:.3}", result.gpu_timings.get_pass_average(pass)))
+      .collect::<Vec<_>>()
+      .join(" | ");
+    writeln!(report, "| {} | {} |", pass.name(), timings)?;
+  }
+
+  log::info!("Benchmark Report:\n{}", report);
+  std::fs::write(&config.report_file, report)
+    .with_context(|| format!("Failed to write the benchmark report: {}", config.report_file))?;
+  log::info!("Saved the benchmark report \"{}\".", config.report_file);
+
+  Ok(())
+}
+
+/// The uniform Catmull-Rom interpolation.
+/// param p0: The point before the segment.
+/// param p1: The start point of the segment.
+/// param p2: The end point of the segment.
+/// param p3: The point after the segment.
+/// param t: The local time in [0, 1].
+/// return: The interpolated point.
+fn catmull_rom(p0: glam::Vec3, p1: glam::Vec3, p2: glam::Vec3, p3: glam::Vec3, t: f32) -> glam::Vec3 {
+  let t2 = t * t;
+  let t3 = t2 * t;
+  0.5 * (
+    2.0 * p1
+    + (p2 - p0) * t
+    + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+    + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3
+  )
+}
@@ -0,0 +1,335 @@
+use winit::{
+  event::{
+    ElementState,
+    MouseButton,
+    MouseScrollDelta,
+    WindowEvent,
+  },
+  keyboard::{
+    KeyCode,
+    PhysicalKey,
+  },
+};
+
+use super::{
+  CameraMode,
+  CameraProjection,
+  CameraSettings,
+};
+
+/// The camera controller.
+/// It supports the free-fly(WASD + right mouse button) and the orbit(left mouse button + wheel) modes.
+#[derive(Debug, Clone)]
+pub struct CameraController {
+  pub settings: CameraSettings,
+
+  position: glam::Vec3,
+  yaw: f32,
+  pitch: f32,
+  target: glam::Vec3,
+  distance: f32,
+
+  move_forward: bool,
+  move_backward: bool,
+  move_left: bool,
+  move_right: bool,
+  move_up: bool,
+  move_down: bool,
+  is_boosting: bool,
+  is_rotating: bool,
+  is_panning: bool,
+
+  last_cursor_position: Option<glam::Vec2>,
+  cursor_delta: glam::Vec2,
+  wheel_delta: f32,
+}
+
+/// The default implementation of the camera controller.
+impl Default for CameraController {
+
+  fn default() -> Self {
+    Self {
+      settings: CameraSettings::default(),
+
+      position: glam::Vec3::new(0.0, 0.0, 5.0),
+      yaw: 0.0,
+      pitch: 0.0,
+      target: glam::Vec3::ZERO,
+      distance: 5.0,
+
+      move_forward: false,
+      move_backward: false,
+      move_left: false,
+      move_right: false,
+      move_up: false,
+      move_down: false,
+      is_boosting: false,
+      is_rotating: false,
+      is_panning: false,
+
+      last_cursor_position: None,
+      cursor_delta: glam::Vec2::ZERO,
+      wheel_delta: 0.0,
+    }
+  }
+
+}
+
+/// The implementation of the camera controller.
+impl CameraController {
+
+  /// The maximum pitch angle to avoid the gimbal lock.
+  const MAX_PITCH: f32 = std::f32::consts::FRAC_PI_2 - 0.01;
+  /// The speed multiplier when the shift key is pressed.
+  const BOOST_FACTOR: f32 = 4.0;
+
+  /// Reset the camera controller from the view and projection matrices.
+  /// The projection matrix must be a reversed-Z infinite perspective matrix or an orthographic matrix.
+  /// The orthographic matrix keeps its extents and depth range, the wheel scales the extents instead of moving the camera.
+  /// param v_mtx: The view matrix.
+  /// param p_mtx: The projection matrix.
+  pub fn reset(&mut self, v_mtx: &glam::Mat4, p_mtx: &glam::Mat4) {
+    let world_mtx = v_mtx.inverse();
+    let forward = -world_mtx.z_axis.truncate().normalize();
+
+    self.position = world_mtx.w_axis.truncate();
+    self.pitch = forward.y.clamp(-1.0, 1.0).asin().clamp(-Self::MAX_PITCH, Self::MAX_PITCH);
+    self.yaw = (-forward.x).atan2(-forward.z);
+    self.target = self.position + forward * self.distance;
+
+    // The orthographic matrix keeps w, x_axis.x = 1 / xmag and y_axis.y = 1 / ymag.
+    if p_mtx.w_axis.w != 0.0 {
+      // The view depth d maps to the NDC depth w_axis.z - d * z_axis.z, solve d at the NDC depth 0 and 1.
+      // Both the standard and the reversed-Z matrices are accepted.
+      let depth_0 = p_mtx.w_axis.z / p_mtx.z_axis.z;
+      let depth_1 = (p_mtx.w_axis.z - 1.0) / p_mtx.z_axis.z;
+      self.settings.z_near = depth_0.min(depth_1).max(0.0);
+      self.settings.projection = CameraProjection::Orthographic {
+        xmag: 1.0 / p_mtx.x_axis.x.abs(),
+        ymag: 1.0 / p_mtx.y_axis.y.abs(),
+        z_far: depth_0.max(depth_1),
+      };
+      return;
+    }
+
+    // For the reversed-Z infinite perspective matrix, y_axis.y = 1 / tan(yfov / 2) and w_axis.z = z_near.
+    self.settings.projection = CameraProjection::Perspective;
+    if p_mtx.y_axis.y > 0.0 {
+      self.settings.yfov = (2.0 * (1.0 / p_mtx.y_axis.y).atan()).to_degrees();
+    }
+    if p_mtx.w_axis.z > 0.0 {
+      self.settings.z_near = p_mtx.w_axis.z;
+    }
+  }
+
+  /// Place the camera at the position and look at the target.
+  /// param position: The camera position in world space.
+  /// param target: The target position in world space.
+  pub fn look_at(&mut self, position: glam::Vec3, target: glam::Vec3) {
+    let offset = target - position;
+    let distance = offset.length();
+    if distance <= f32::EPSILON {
+      return;
+    }
+    let forward = offset / distance;
+
+    self.position = position;
+    self.pitch = forward.y.clamp(-1.0, 1.0).asin().clamp(-Self::MAX_PITCH, Self::MAX_PITCH);
+    self.yaw = (-forward.x).atan2(-forward.z);
+    self.target = target;
+    self.distance = distance;
+  }
+
+  /// Set the camera mode.
+  /// The current camera pose is kept when switching the modes.
+  /// param mode: The camera mode.
+  pub fn set_mode(&mut self, mode: CameraMode) {
+    if self.settings.mode == mode {
+      return;
+    }
+    if mode == CameraMode::Orbit {
+      self.target = self.position + self.forward() * self.distance;
+    }
+    self.settings.mode = mode;
+  }
+
+  /// Get the camera position.
+  /// return: The camera position in world space.
+  pub fn position(&self) -> glam::Vec3 {
+    self.position
+  }
+
+  /// Get the point the camera looks at.
+  /// return: The orbit target, or the point in front of the free-fly camera at the orbit distance.
+  pub fn target(&self) -> glam::Vec3 {
+    match self.settings.mode {
+      CameraMode::Orbit => self.target,
+      CameraMode::FreeFly => self.position + self.forward() * self.distance,
+    }
+  }
+
+  /// Get the camera forward direction.
+  /// return: The forward direction in world space.
+  pub fn forward(&self) -> glam::Vec3 {
+    glam::Vec3::new(
+      -self.yaw.sin() * self.pitch.cos(),
+      self.pitch.sin(),
+      -self.yaw.cos() * self.pitch.cos(),
+    )
+  }
+
+  /// Get the view matrix.
+  /// return: The view matrix.
+  pub fn view_matrix(&self) -> glam::Mat4 {
+    glam::Mat4::look_to_rh(self.position, self.forward(), glam::Vec3::Y)
+  }
+
+  /// Get the projection matrix.
+  /// param aspect: The aspect ratio of the viewport, the orthographic projection keeps its own extents.
+  /// return: The reversed-Z infinite perspective or the reversed-Z orthographic projection matrix.
+  pub fn proj_matrix(&self, aspect: f32) -> glam::Mat4 {
+    match self.settings.projection {
+      CameraProjection::Perspective => glam::Mat4::perspective_infinite_reverse_rh(
+        self.settings.yfov.to_radians(),
+        aspect,
+        self.settings.z_near,
+      ),
+      CameraProjection::Orthographic { xmag, ymag, z_far } => glam::Mat4::orthographic_rh(
+        -xmag,
+        xmag,
+        -ymag,
+        ymag,
+        z_far,
+        self.settings.z_near,
+      ),
+    }
+  }
+
+  /// Handle the window event.
+  /// param event: The window event.
+  /// param is_ui_capturing_mouse: Whether the UI is capturing the mouse.
+  /// param is_ui_capturing_keyboard: Whether the UI is capturing the keyboard.
+  pub fn handle_window_event(&mut self, event: &WindowEvent, is_ui_capturing_mouse: bool, is_ui_capturing_keyboard: bool) {
+    match event {
+      WindowEvent::KeyboardInput { event, .. } => {
+        let is_pressed = event.state == ElementState::Pressed && !is_ui_capturing_keyboard;
+        if let PhysicalKey::Code(code) = event.physical_key {
+          match code {
+            KeyCode::KeyW => self.move_forward = is_pressed,
+            KeyCode::KeyS => self.move_backward = is_pressed,
+            KeyCode::KeyA => self.move_left = is_pressed,
+            KeyCode::KeyD => self.move_right = is_pressed,
+            KeyCode::KeyE => self.move_up = is_pressed,
+            KeyCode::KeyQ => self.move_down = is_pressed,
+            KeyCode::ShiftLeft | KeyCode::ShiftRight => self.is_boosting = is_pressed,
+            _ => (),
+          }
+        }
+      },
+      WindowEvent::MouseInput { state, button, .. } => {
+        let is_pressed = *state == ElementState::Pressed;
+        // Only start the dragging when the UI does not capture the mouse, but always stop it.
+        if is_pressed && is_ui_capturing_mouse {
+          return;
+        }
+        match (self.settings.mode, button) {
+          (CameraMode::FreeFly, MouseButton::Right) => self.is_rotating = is_pressed,
+          (CameraMode::Orbit, MouseButton::Left) => self.is_rotating = is_pressed,
+          (CameraMode::Orbit, MouseButton::Middle) => self.is_panning = is_pressed,
+          _ => (),
+        }
+      },
+      WindowEvent::CursorMoved { position, .. } => {
+        let position = glam::Vec2::new(position.x as f32, position.y as f32);
+        if let Some(last_position) = self.last_cursor_position {
+          if self.is_rotating || self.is_panning {
+            self.cursor_delta += position - last_position;
+          }
+        }
+        self.last_cursor_position = Some(position);
+      },
+      WindowEvent::CursorLeft { .. } => {
+        self.last_cursor_position = None;
+      },
+      WindowEvent::MouseWheel { delta, .. } => {
+        if is_ui_capturing_mouse {
+          return;
+        }
+        self.wheel_delta += match delta {
+          MouseScrollDelta::LineDelta(_, y) => *y,
+          MouseScrollDelta::PixelDelta(position) => position.y as f32 / 120.0,
+        };
+      },
+      WindowEvent::Focused(false) => {
+        self.release_all();
+      },
+      _ => (),
+    }
+  }
+
+  /// Update the camera by the accumulated input.
+  /// param delta_time: The delta time in seconds.
+  pub fn update(&mut self, delta_time: f64) {
+    let delta_time = delta_time as f32;
+    let cursor_delta = std::mem::take(&mut self.cursor_delta);
+    let wheel_delta = std::mem::take(&mut self.wheel_delta);
+    let speed = self.settings.move_speed * if self.is_boosting { Self::BOOST_FACTOR } else { 1.0 };
+
+    if self.is_rotating {
+      self.yaw -= cursor_delta.x * self.settings.rotate_speed;
+      self.pitch = (self.pitch - cursor_delta.y * self.settings.rotate_speed).clamp(-Self::MAX_PITCH, Self::MAX_PITCH);
+    }
+
+    let forward = self.forward();
+    let right = forward.cross(glam::Vec3::Y).normalize();
+    let up = right.cross(forward);
+
+    // Moving the orthographic camera along the forward direction does not zoom, the wheel scales the extents.
+    let wheel_delta = if let CameraProjection::Orthographic { xmag, ymag, z_far } = self.settings.projection {
+      let scale = (1.0 - wheel_delta * 0.1).max(0.1);
+      self.settings.projection = CameraProjection::Orthographic { xmag: xmag * scale, ymag: ymag * scale, z_far };
+      0.0
+    } else {
+      wheel_delta
+    };
+
+    match self.settings.mode {
+      CameraMode::FreeFly => {
+        let mut direction = glam::Vec3::ZERO;
+        if self.move_forward { direction += forward; }
+        if self.move_backward { direction -= forward; }
+        if self.move_right { direction += right; }
+        if self.move_left { direction -= right; }
+        if self.move_up { direction += glam::Vec3::Y; }
+        if self.move_down { direction -= glam::Vec3::Y; }
+        self.position += direction.normalize_or_zero() * speed * delta_time;
+        // Use the wheel to dolly along the forward direction.
+        self.position += forward * wheel_delta * speed * 0.1;
+      },
+      CameraMode::Orbit => {
+        if self.is_panning {
+          let pan_scale = self.distance * self.settings.rotate_speed;
+          self.target += (-right * cursor_delta.x + up * cursor_delta.y) * pan_scale;
+        }
+        self.distance = (self.distance * (1.0 - wheel_delta * 0.1)).max(self.settings.z_near);
+        self.position = self.target - forward * self.distance;
+      },
+    }
+  }
+
+  /// Release all pressed keys and buttons.
+  fn release_all(&mut self) {
+    self.move_forward = false;
+    self.move_backward = false;
+    self.move_left = false;
+    self.move_right = false;
+    self.move_up = false;
+    self.move_down = false;
+    self.is_boosting = false;
+    self.is_rotating = false;
+    self.is_panning = false;
+    self.cursor_delta = glam::Vec2::ZERO;
+    self.wheel_delta = 0.0;
+  }
+
+}
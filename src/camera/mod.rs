@@ -0,0 +1,59 @@
+mod controller;
+mod names;
+
+pub use controller::*;
+pub use names::load_camera_names;
+
+/// The camera control mode.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CameraMode {
+  #[default]
+  FreeFly,
+  Orbit,
+}
+
+/// The camera projection.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum CameraProjection {
+  // The reversed-Z infinite perspective projection by the vertical field of view.
+  #[default]
+  Perspective,
+  // The reversed-Z orthographic projection of the orthographic glTF camera.
+  // The half width and the half height of the view volume, and the far plane distance.
+  Orthographic {
+    xmag: f32,
+    ymag: f32,
+    z_far: f32,
+  },
+}
+
+/// The camera settings.
+#[derive(Debug, Clone, Copy)]
+pub struct CameraSettings {
+  pub mode: CameraMode,
+  pub projection: CameraProjection,
+  // The move speed in units per second.
+  pub move_speed: f32,
+  // The rotate speed in radians per pixel.
+  pub rotate_speed: f32,
+  // The vertical field of view in degrees.
+  pub yfov: f32,
+  // The near plane distance.
+  pub z_near: f32,
+}
+
+/// The default implementation of the camera settings.
+impl Default for CameraSettings {
+
+  fn default() -> Self {
+    Self {
+      mode: CameraMode::FreeFly,
+      projection: CameraProjection::Perspective,
+      move_speed: 2.0,
+      rotate_speed: 0.003,
+      yfov: 45.0,
+      z_near: 0.1,
+    }
+  }
+
+}
@@ -0,0 +1,25 @@
+use anyhow::{
+  Result,
+  Context,
+};
+
+/// Load the names of the scene cameras from the glTF file.
+/// NOTICE: The scene camera keeps no name, so it is read from the glTF document.
+/// The cameras are uploaded per camera node, the names follow the order of the camera nodes in the document.
+/// param scene_file: The glTF file of the scene.
+/// return: The name of each camera node, the node name or the camera name, empty if both are unnamed.
+pub fn load_camera_names<P: AsRef<std::path::Path>>(scene_file: P) -> Result<Vec<String>> {
+  let path = scene_file.as_ref();
+  let document = gltf::Gltf::open(path)
+    .with_context(|| format!("Failed to open the glTF file: {:?}", path))?;
+
+  Ok(document.nodes()
+    .filter_map(|node| node.camera().map(|camera| (node.name(), camera.name())))
+    .map(|(node_name, camera_name)| {
+      node_name.filter(|name| !name.is_empty())
+        .or(camera_name)
+        .unwrap_or_default()
+        .to_string()
+    })
+    .collect())
+}
@@ -0,0 +1,59 @@
+use anyhow::Result;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct HeadlessConfig {
+  // The format of the final image, "RGBA8_SRGB", "RGBA8_UNORM", "BGRA8_SRGB" or "BGRA8_UNORM".
+  #[serde(default = "HeadlessConfig::default_output_format")]
+  pub output_format: String,
+  // The number of the frames recorded ahead of the GPU.
+  #[serde(default = "HeadlessConfig::default_num_of_frames_in_flight")]
+  pub num_of_frames_in_flight: u32,
+}
+
+impl HeadlessConfig {
+
+  fn default_output_format() -> String {
+    "RGBA8_SRGB".to_string()
+  }
+  fn default_num_of_frames_in_flight() -> u32 {
+    2
+  }
+
+  /// Get the format of the final image.
+  /// return: The format, None if the name is unknown.
+  pub fn get_output_format(&self) -> Option<hala_gfx::HalaFormat> {
+    match self.output_format.as_str() {
+      "RGBA8_SRGB" => Some(hala_gfx::HalaFormat::R8G8B8A8_SRGB),
+      "RGBA8_UNORM" => Some(hala_gfx::HalaFormat::R8G8B8A8_UNORM),
+      "BGRA8_SRGB" => Some(hala_gfx::HalaFormat::B8G8R8A8_SRGB),
+      "BGRA8_UNORM" => Some(hala_gfx::HalaFormat::B8G8R8A8_UNORM),
+      _ => None,
+    }
+  }
+
+}
+
+impl Default for HeadlessConfig {
+
+  fn default() -> Self {
+    Self {
+      output_format: Self::default_output_format(),
+      num_of_frames_in_flight: Self::default_num_of_frames_in_flight(),
+    }
+  }
+
+}
+
+/// Validate the headless configure.
+/// param: config: the configure.
+/// return: the result of the validation.
+pub fn validate_headless_config(config: &HeadlessConfig) -> Result<()> {
+  if config.get_output_format().is_none() {
+    return Err(anyhow::anyhow!("The headless output format \"{}\" is not one of RGBA8_SRGB, RGBA8_UNORM, BGRA8_SRGB and BGRA8_UNORM.", config.output_format));
+  }
+  if config.num_of_frames_in_flight == 0 || config.num_of_frames_in_flight > 3 {
+    return Err(anyhow::anyhow!("The number of the headless frames in flight must be in [1, 3]."));
+  }
+  Ok(())
+}
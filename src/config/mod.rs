@@ -2,15 +2,23 @@ use anyhow::{Result, Context};
 use serde::Deserialize;
 
 mod window;
+mod renderer;
 mod gpu_programs;
+mod headless;
 
 pub use window::*;
+pub use renderer::*;
 pub use gpu_programs::*;
+pub use headless::*;
 
 /// The application configure.
 #[derive(Debug, Deserialize, Default, Clone)]
 pub struct AppConfig {
   pub window: WindowConfig,
+  #[serde(default)]
+  pub renderer: RendererConfig,
+  #[serde(default)]
+  pub headless: HeadlessConfig,
   pub scene_file: String,
   pub programs_file: String,
 }
@@ -20,6 +28,8 @@ pub struct AppConfig {
 /// return: the result of the validation.
 pub fn validate_app_config(config: &AppConfig) -> Result<()> {
   validate_window_config(&config.window)?;
+  validate_renderer_config(&config.renderer)?;
+  validate_headless_config(&config.headless)?;
   if !std::path::Path::new(&config.scene_file).exists() {
     return Err(anyhow::anyhow!("The scene file \"{}\" is not found.", config.scene_file));
   }
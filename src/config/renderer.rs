@@ -0,0 +1,422 @@
+use anyhow::Result;
+use serde::Deserialize;
+
+use crate::renderer::TonemapOperator;
+
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct RendererConfig {
+  // Use the RGBA8 albedo and material, A2R10G10B10 normal and RGBA16F emissive G-Buffer instead of the RGBA32F ones.
+  #[serde(default)]
+  pub use_small_gbuffer: bool,
+  // Run the material classification on the async compute queue instead of the graphics queue.
+  #[serde(default)]
+  pub use_async_compute: bool,
+  // Blend the transparent draws by the weighted blended OIT instead of sorting them from back to front.
+  #[serde(default)]
+  pub use_weighted_blended_oit: bool,
+  #[serde(default)]
+  pub dynamic_resolution: DynamicResolutionConfig,
+  #[serde(default)]
+  pub shadow: ShadowConfig,
+  #[serde(default)]
+  pub environment: EnvironmentConfig,
+  #[serde(default)]
+  pub tonemap: TonemapConfig,
+  #[serde(default)]
+  pub taa: TaaConfig,
+  #[serde(default)]
+  pub ssao: SsaoConfig,
+  #[serde(default)]
+  pub post_process: PostProcessConfig,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct DynamicResolutionConfig {
+  #[serde(default)]
+  pub enabled: bool,
+  // The GPU time budget of a frame in milliseconds.
+  #[serde(default = "DynamicResolutionConfig::default_gpu_budget")]
+  pub gpu_budget: f32,
+  // The range of the render scale relative to the window size.
+  #[serde(default = "DynamicResolutionConfig::default_min_scale")]
+  pub min_scale: f32,
+  #[serde(default = "DynamicResolutionConfig::default_max_scale")]
+  pub max_scale: f32,
+}
+
+impl DynamicResolutionConfig {
+
+  fn default_gpu_budget() -> f32 {
+    8.0
+  }
+  fn default_min_scale() -> f32 {
+    0.5
+  }
+  fn default_max_scale() -> f32 {
+    1.0
+  }
+
+}
+
+impl Default for DynamicResolutionConfig {
+
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      gpu_budget: Self::default_gpu_budget(),
+      min_scale: Self::default_min_scale(),
+      max_scale: Self::default_max_scale(),
+    }
+  }
+
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ShadowConfig {
+  // The number of the cascades of the directional light shadow, at most 4.
+  #[serde(default = "ShadowConfig::default_num_of_cascades")]
+  pub num_of_cascades: u32,
+  // The width and height of each cascade in texels.
+  #[serde(default = "ShadowConfig::default_resolution")]
+  pub resolution: u32,
+  // The blend between the uniform(0.0) and the logarithmic(1.0) split of the cascades.
+  #[serde(default = "ShadowConfig::default_split_lambda")]
+  pub split_lambda: f32,
+  // The view distance covered by the last cascade.
+  #[serde(default = "ShadowConfig::default_max_distance")]
+  pub max_distance: f32,
+}
+
+impl ShadowConfig {
+
+  fn default_num_of_cascades() -> u32 {
+    4
+  }
+  fn default_resolution() -> u32 {
+    2048
+  }
+  fn default_split_lambda() -> f32 {
+    0.75
+  }
+  fn default_max_distance() -> f32 {
+    100.0
+  }
+
+}
+
+impl Default for ShadowConfig {
+
+  fn default() -> Self {
+    Self {
+      num_of_cascades: Self::default_num_of_cascades(),
+      resolution: Self::default_resolution(),
+      split_lambda: Self::default_split_lambda(),
+      max_distance: Self::default_max_distance(),
+    }
+  }
+
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct EnvironmentConfig {
+  // The equirectangular HDR image of the image based lighting and the skybox, the background is the clear color without it.
+  #[serde(default)]
+  pub file: Option<String>,
+  // The scale of the environment lighting and the skybox.
+  #[serde(default = "EnvironmentConfig::default_intensity")]
+  pub intensity: f32,
+}
+
+impl EnvironmentConfig {
+
+  fn default_intensity() -> f32 {
+    1.0
+  }
+
+}
+
+impl Default for EnvironmentConfig {
+
+  fn default() -> Self {
+    Self {
+      file: None,
+      intensity: Self::default_intensity(),
+    }
+  }
+
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct TonemapConfig {
+  // The tonemapping operator, "ACES", "AgX" or "Reinhard".
+  #[serde(default = "TonemapConfig::default_operator")]
+  pub operator: String,
+  // Expose the average luminance of the scene to the middle gray, otherwise use the manual exposure.
+  #[serde(default)]
+  pub auto_exposure: bool,
+  // The manual exposure in EV.
+  #[serde(default)]
+  pub exposure: f32,
+  // The EV added to the automatic exposure.
+  #[serde(default)]
+  pub exposure_compensation: f32,
+  // The log2 luminance range of the automatic exposure.
+  #[serde(default = "TonemapConfig::default_min_log_luminance")]
+  pub min_log_luminance: f32,
+  #[serde(default = "TonemapConfig::default_max_log_luminance")]
+  pub max_log_luminance: f32,
+  // The speed of the eye adaptation per second.
+  #[serde(default = "TonemapConfig::default_adaptation_speed")]
+  pub adaptation_speed: f32,
+}
+
+impl TonemapConfig {
+
+  fn default_operator() -> String {
+    "ACES".to_string()
+  }
+  fn default_min_log_luminance() -> f32 {
+    -10.0
+  }
+  fn default_max_log_luminance() -> f32 {
+    10.0
+  }
+  fn default_adaptation_speed() -> f32 {
+    1.5
+  }
+
+}
+
+impl Default for TonemapConfig {
+
+  fn default() -> Self {
+    Self {
+      operator: Self::default_operator(),
+      auto_exposure: false,
+      exposure: 0.0,
+      exposure_compensation: 0.0,
+      min_log_luminance: Self::default_min_log_luminance(),
+      max_log_luminance: Self::default_max_log_luminance(),
+      adaptation_speed: Self::default_adaptation_speed(),
+    }
+  }
+
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct TaaConfig {
+  // Resolve the jittered frames with the reprojected history.
+  #[serde(default = "TaaConfig::default_enabled")]
+  pub enabled: bool,
+  // The blend weight of the history, larger is smoother but follows the changes slower.
+  #[serde(default = "TaaConfig::default_history_weight")]
+  pub history_weight: f32,
+}
+
+impl TaaConfig {
+
+  fn default_enabled() -> bool {
+    true
+  }
+  fn default_history_weight() -> f32 {
+    0.9
+  }
+
+}
+
+impl Default for TaaConfig {
+
+  fn default() -> Self {
+    Self {
+      enabled: Self::default_enabled(),
+      history_weight: Self::default_history_weight(),
+    }
+  }
+
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SsaoConfig {
+  // Darken the ambient lighting by the screen space ambient occlusion.
+  #[serde(default = "SsaoConfig::default_enabled")]
+  pub enabled: bool,
+  // Compute the ambient occlusion in the half resolution.
+  #[serde(default = "SsaoConfig::default_half_resolution")]
+  pub half_resolution: bool,
+  // Blur the ambient occlusion by the depth aware bilateral filter.
+  #[serde(default = "SsaoConfig::default_blur")]
+  pub blur: bool,
+  // The world radius of the occluders.
+  #[serde(default = "SsaoConfig::default_radius")]
+  pub radius: f32,
+  // The scale of the occlusion.
+  #[serde(default = "SsaoConfig::default_intensity")]
+  pub intensity: f32,
+}
+
+impl SsaoConfig {
+
+  fn default_enabled() -> bool {
+    true
+  }
+  fn default_half_resolution() -> bool {
+    true
+  }
+  fn default_blur() -> bool {
+    true
+  }
+  fn default_radius() -> f32 {
+    0.5
+  }
+  fn default_intensity() -> f32 {
+    1.0
+  }
+
+}
+
+impl Default for SsaoConfig {
+
+  fn default() -> Self {
+    Self {
+      enabled: Self::default_enabled(),
+      half_resolution: Self::default_half_resolution(),
+      blur: Self::default_blur(),
+      radius: Self::default_radius(),
+      intensity: Self::default_intensity(),
+    }
+  }
+
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PostProcessConfig {
+  // Blur the bright areas through a mip chain and blend them over the scene color.
+  #[serde(default = "PostProcessConfig::default_bloom")]
+  pub bloom: bool,
+  // The blend weight of the bloom.
+  #[serde(default = "PostProcessConfig::default_bloom_intensity")]
+  pub bloom_intensity: f32,
+  // The radius of the upsampling filter in texels of each mip level.
+  #[serde(default = "PostProcessConfig::default_bloom_radius")]
+  pub bloom_radius: f32,
+  // Darken the screen corners.
+  #[serde(default = "PostProcessConfig::default_vignette")]
+  pub vignette: bool,
+  // 1 follows the natural cos^4 falloff.
+  #[serde(default = "PostProcessConfig::default_vignette_intensity")]
+  pub vignette_intensity: f32,
+  // Offset the red and blue channels towards the screen corners.
+  #[serde(default)]
+  pub chromatic_aberration: bool,
+  // The offset at the screen corners in percent of the screen size.
+  #[serde(default = "PostProcessConfig::default_chromatic_aberration_intensity")]
+  pub chromatic_aberration_intensity: f32,
+}
+
+impl PostProcessConfig {
+
+  fn default_bloom() -> bool {
+    true
+  }
+  fn default_bloom_intensity() -> f32 {
+    0.04
+  }
+  fn default_bloom_radius() -> f32 {
+    1.0
+  }
+  fn default_vignette() -> bool {
+    true
+  }
+  fn default_vignette_intensity() -> f32 {
+    0.5
+  }
+  fn default_chromatic_aberration_intensity() -> f32 {
+    0.5
+  }
+
+}
+
+impl Default for PostProcessConfig {
+
+  fn default() -> Self {
+    Self {
+      bloom: Self::default_bloom(),
+      bloom_intensity: Self::default_bloom_intensity(),
+      bloom_radius: Self::default_bloom_radius(),
+      vignette: Self::default_vignette(),
+      vignette_intensity: Self::default_vignette_intensity(),
+      chromatic_aberration: false,
+      chromatic_aberration_intensity: Self::default_chromatic_aberration_intensity(),
+    }
+  }
+
+}
+
+/// Validate the renderer configure.
+/// param: config: the configure.
+/// return: the result of the validation.
+pub fn validate_renderer_config(config: &RendererConfig) -> Result<()> {
+  let dynamic_resolution = &config.dynamic_resolution;
+  if dynamic_resolution.gpu_budget <= 0.0 {
+    return Err(anyhow::anyhow!("The GPU budget of the dynamic resolution is not positive."));
+  }
+  if dynamic_resolution.min_scale <= 0.0 || dynamic_resolution.min_scale > dynamic_resolution.max_scale || dynamic_resolution.max_scale > 1.0 {
+    return Err(anyhow::anyhow!("The scale range of the dynamic resolution must be in (0, 1]."));
+  }
+  let shadow = &config.shadow;
+  if shadow.num_of_cascades == 0 || shadow.num_of_cascades > 4 {
+    return Err(anyhow::anyhow!("The number of the shadow cascades must be in [1, 4]."));
+  }
+  if shadow.resolution == 0 || shadow.resolution > 4096 {
+    return Err(anyhow::anyhow!("The shadow resolution must be in [1, 4096]."));
+  }
+  if !(0.0..=1.0).contains(&shadow.split_lambda) {
+    return Err(anyhow::anyhow!("The shadow split lambda must be in [0, 1]."));
+  }
+  if shadow.max_distance <= 0.0 {
+    return Err(anyhow::anyhow!("The max distance of the shadow is not positive."));
+  }
+  let environment = &config.environment;
+  if let Some(file) = environment.file.as_ref() {
+    if !std::path::Path::new(file).exists() {
+      return Err(anyhow::anyhow!("The environment file \"{}\" is not found.", file));
+    }
+  }
+  if environment.intensity < 0.0 {
+    return Err(anyhow::anyhow!("The environment intensity is negative."));
+  }
+  let tonemap = &config.tonemap;
+  if TonemapOperator::from_name(&tonemap.operator).is_none() {
+    return Err(anyhow::anyhow!("The tonemap operator \"{}\" is not one of ACES, AgX and Reinhard.", tonemap.operator));
+  }
+  if tonemap.min_log_luminance >= tonemap.max_log_luminance {
+    return Err(anyhow::anyhow!("The min log luminance of the automatic exposure is not less than the max one."));
+  }
+  if tonemap.adaptation_speed <= 0.0 {
+    return Err(anyhow::anyhow!("The adaptation speed of the automatic exposure is not positive."));
+  }
+  if !(0.0..1.0).contains(&config.taa.history_weight) {
+    return Err(anyhow::anyhow!("The history weight of the TAA must be in [0, 1)."));
+  }
+  let ssao = &config.ssao;
+  if ssao.radius <= 0.0 {
+    return Err(anyhow::anyhow!("The radius of the SSAO is not positive."));
+  }
+  if ssao.intensity < 0.0 {
+    return Err(anyhow::anyhow!("The intensity of the SSAO is negative."));
+  }
+  let post_process = &config.post_process;
+  if !(0.0..=1.0).contains(&post_process.bloom_intensity) {
+    return Err(anyhow::anyhow!("The bloom intensity must be in [0, 1]."));
+  }
+  if post_process.bloom_radius <= 0.0 {
+    return Err(anyhow::anyhow!("The bloom radius is not positive."));
+  }
+  if post_process.vignette_intensity < 0.0 {
+    return Err(anyhow::anyhow!("The vignette intensity is negative."));
+  }
+  if post_process.chromatic_aberration_intensity < 0.0 {
+    return Err(anyhow::anyhow!("The chromatic aberration intensity is negative."));
+  }
+  Ok(())
+}
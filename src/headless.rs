@@ -0,0 +1,272 @@
+use anyhow::{
+  Result,
+  Context,
+};
+
+use hala_imgui::HalaApplicationContextTrait;
+
+use hala_renderer::renderer::HalaRendererTrait;
+
+use crate::MyApplicationContext;
+use crate::benchmark::{
+  BenchmarkConfig,
+  BenchmarkResult,
+  culling_strategies,
+  write_benchmark_report,
+};
+use crate::regression::{
+  RegressionOutcome,
+  RegressionSettings,
+  build_regression_cases,
+  check_regression_case,
+};
+
+/// The settings of the headless mode.
+#[derive(Debug, Clone)]
+pub(crate) struct HeadlessSettings {
+  // The number of frames to render before writing the outputs.
+  pub num_of_frames: u32,
+  // Whether write the albedo, normal, visibility and Hi-Z targets too.
+  pub dump_targets: bool,
+  // Run the golden-image regression instead of writing a single frame.
+  pub regression: Option<RegressionSettings>,
+  // Run the benchmark instead of writing a single frame.
+  pub benchmark: Option<BenchmarkConfig>,
+}
+
+/// The headless application.
+/// The renderer is created without a window, and every frame renders to the offscreen final target of its frame in flight.
+struct HeadlessApplication {
+  context: MyApplicationContext,
+  settings: HeadlessSettings,
+}
+
+/// The implementation of the headless application.
+impl HeadlessApplication {
+
+  /// The fixed delta time to make the outputs reproducible.
+  const DELTA_TIME: f64 = 1.0 / 60.0;
+
+  /// Render the frames and write the outputs to ./out.
+  /// return: The result.
+  fn run_frames(&mut self) -> Result<()> {
+    let size = self.context.get_window_size();
+    self.context.before_run_headless()?;
+
+    let result = if let Some(regression) = self.settings.regression.clone() {
+      self.run_regression(&regression, size.width, size.height)
+    } else if let Some(benchmark) = self.settings.benchmark.clone() {
+      self.run_benchmark(&benchmark, size.width, size.height)
+    } else {
+      self.render_frames(size.width, size.height).and_then(|_| {
+        let renderer = self.context.renderer.as_ref()
+          .with_context(|| "Failed to find the renderer.")?;
+        renderer.save_final_image("./out/final.png")?;
+        if self.settings.dump_targets {
+          renderer.save_offscreen_targets("./out", "frame")?;
+        }
+        log::info!("Rendered {} frames in headless mode.", self.settings.num_of_frames);
+        Ok(())
+      })
+    };
+
+    // Always release the renderer, even if the rendering failed.
+    self.context.after_run();
+
+    result
+  }
+
+  /// Render the frames and wait until the GPU finished them.
+  /// param width: The width of the frames.
+  /// param height: The height of the frames.
+  /// return: The result.
+  fn render_frames(&mut self, width: u32, height: u32) -> Result<()> {
+    for _ in 0..self.settings.num_of_frames {
+      self.context.update(Self::DELTA_TIME, width, height)?;
+      self.context.render()?;
+    }
+
+    let renderer = self.context.renderer.as_ref()
+      .with_context(|| "Failed to find the renderer.")?;
+    renderer.wait_idle()?;
+
+    Ok(())
+  }
+
+  /// Render every regression case and compare it against the reference image.
+  /// The outputs are written to ./out/regression.
+  /// param regression: The regression settings.
+  /// param width: The width of the frames.
+  /// param height: The height of the frames.
+  /// return: The result, error if any case failed.
+  fn run_regression(&mut self, regression: &RegressionSettings, width: u32, height: u32) -> Result<()> {
+    let out_dir = std::path::Path::new("./out/regression");
+    std::fs::create_dir_all(out_dir)
+      .with_context(|| format!("Failed to create the output directory: {:?}", out_dir))?;
+
+    let cases = build_regression_cases();
+    let mut failed_cases = Vec::new();
+    let mut missing_cases = Vec::new();
+    for case in cases.iter() {
+      // The descriptor sets are updated by the debug settings, so the GPU must be idle.
+      let renderer = self.context.renderer.as_mut()
+        .with_context(|| "Failed to find the renderer.")?;
+      renderer.wait_idle()?;
+      renderer.update_debug_settings(case.debug_settings)?;
+      // Every case starts from the same temporal state, so the output does not depend on the case order.
+      renderer.reset_temporal_state();
+      self.context.settings.debug_settings = case.debug_settings;
+
+      // Render several frames to let the Hi-Z buffer of the last frame settle down.
+      self.render_frames(width, height)?;
+
+      let renderer = self.context.renderer.as_ref()
+        .with_context(|| "Failed to find the renderer.")?;
+      let image = renderer.download_final_image()?;
+      match check_regression_case(regression, case, &image, out_dir)? {
+        RegressionOutcome::Passed => (),
+        RegressionOutcome::Failed => failed_cases.push(case.name.as_str()),
+        RegressionOutcome::Missing => missing_cases.push(case.name.as_str()),
+      }
+    }
+
+    if !failed_cases.is_empty() || !missing_cases.is_empty() {
+      return Err(anyhow::anyhow!(
+        "{} of {} regression cases failed: [{}], {} have no reference image: [{}]",
+        failed_cases.len(),
+        cases.len(),
+        failed_cases.join(", "),
+        missing_cases.len(),
+        missing_cases.join(", ")
+      ));
+    }
+    if regression.update_golden {
+      log::info!("Updated the reference images of all {} regression cases in {:?}.", cases.len(), regression.golden_dir);
+    } else {
+      log::info!("All {} regression cases passed.", cases.len());
+    }
+
+    Ok(())
+  }
+
+  /// Render the camera path with every culling strategy and write the summary report.
+  /// Only the GPU timings and the drawn meshlets are measured in the timed frames, the culling rates are sampled by replaying the path afterwards.
+  /// param benchmark: The benchmark configure.
+  /// param width: The width of the frames.
+  /// param height: The height of the frames.
+  /// return: The result.
+  fn run_benchmark(&mut self, benchmark: &BenchmarkConfig, width: u32, height: u32) -> Result<()> {
+    let mut results = Vec::new();
+    for (name, debug_settings) in culling_strategies() {
+      log::info!("Benchmarking \"{}\"...", name);
+      let renderer = self.context.renderer.as_mut()
+        .with_context(|| "Failed to find the renderer.")?;
+      renderer.wait_idle()?;
+      renderer.update_debug_settings(debug_settings)?;
+      self.context.settings.debug_settings = debug_settings;
+
+      // Warm up at the first keyframe.
+      let (position, target) = benchmark.sample_camera(0.0);
+      self.context.camera_controller.look_at(position, target);
+      for _ in 0..benchmark.num_of_warmup_frames {
+        self.context.update(Self::DELTA_TIME, width, height)?;
+        self.context.render()?;
+      }
+
+      let renderer = self.context.renderer.as_mut()
+        .with_context(|| "Failed to find the renderer.")?;
+      renderer.wait_idle()?;
+      renderer.get_gpu_profiler_mut().resolve_all()?;
+      renderer.get_gpu_profiler_mut().start_accumulation();
+      let start_drawn_meshlets = renderer.get_drawn_meshlets_counter()?;
+
+      for frame_index in 0..benchmark.num_of_frames {
+        self.render_benchmark_frame(benchmark, frame_index, width, height)?;
+      }
+
+      let renderer = self.context.renderer.as_mut()
+        .with_context(|| "Failed to find the renderer.")?;
+      renderer.wait_idle()?;
+      renderer.get_gpu_profiler_mut().resolve_all()?;
+      let gpu_timings = renderer.get_gpu_profiler_mut().take_accumulation();
+      let num_of_meshlets = renderer.get_num_of_meshlets();
+      // Every strategy counts the drawn opaque meshlets by the same counter.
+      let end_drawn_meshlets = renderer.get_drawn_meshlets_counter()?;
+      let avg_drawn_meshlets = end_drawn_meshlets.wrapping_sub(start_drawn_meshlets) as f64 / benchmark.num_of_frames as f64;
+
+      // The culling flags are only written by the first pass of the two pass culling.
+      let is_two_pass = !debug_settings.disable_culling && !debug_settings.one_pass_culling;
+      let avg_culling_rate = if is_two_pass {
+        self.sample_culling_rate(benchmark, width, height)?
+      } else {
+        None
+      };
+
+      results.push(BenchmarkResult {
+        name,
+        gpu_timings,
+        avg_drawn_meshlets,
+        num_of_meshlets,
+        avg_culling_rate,
+      });
+    }
+
+    write_benchmark_report(benchmark, &results)
+  }
+
+  /// Render the frame of the camera path.
+  /// param benchmark: The benchmark configure.
+  /// param frame_index: The index of the frame on the path.
+  /// param width: The width of the frames.
+  /// param height: The height of the frames.
+  /// return: The result.
+  fn render_benchmark_frame(&mut self, benchmark: &BenchmarkConfig, frame_index: u32, width: u32, height: u32) -> Result<()> {
+    let t = frame_index as f32 / (benchmark.num_of_frames - 1).max(1) as f32;
+    let (position, target) = benchmark.sample_camera(t);
+    self.context.camera_controller.look_at(position, target);
+    self.context.update(Self::DELTA_TIME, width, height)?;
+    self.context.render()?;
+
+    Ok(())
+  }
+
+  /// Replay the camera path and read back the culling flags every sample interval.
+  /// Reading the flags waits the GPU idle, so it runs after the timed frames to keep the strategies comparable.
+  /// param benchmark: The benchmark configure.
+  /// param width: The width of the frames.
+  /// param height: The height of the frames.
+  /// return: The average culling rate, None if no frame is sampled.
+  fn sample_culling_rate(&mut self, benchmark: &BenchmarkConfig, width: u32, height: u32) -> Result<Option<f64>> {
+    let mut culling_rates = Vec::new();
+    for frame_index in 0..benchmark.num_of_frames {
+      self.render_benchmark_frame(benchmark, frame_index, width, height)?;
+
+      if (frame_index + 1) % benchmark.culling_sample_interval.max(1) == 0 {
+        let renderer = self.context.renderer.as_ref()
+          .with_context(|| "Failed to find the renderer.")?;
+        renderer.wait_idle()?;
+        let (culled_count, meshlet_count) = renderer.get_culling_results()?;
+        culling_rates.push(culled_count as f64 / meshlet_count.max(1) as f64);
+      }
+    }
+
+    if culling_rates.is_empty() {
+      Ok(None)
+    } else {
+      Ok(Some(culling_rates.iter().sum::<f64>() / culling_rates.len() as f64))
+    }
+  }
+}
+
+/// Run the application context in the headless mode.
+/// param context: The application context.
+/// param settings: The headless settings.
+/// return: The result.
+pub(crate) fn run(context: MyApplicationContext, settings: HeadlessSettings) -> Result<()> {
+  let mut app = HeadlessApplication {
+    context,
+    settings,
+  };
+
+  app.run_frames()
+}
@@ -18,17 +18,58 @@ use hala_renderer::{
 };
 
 mod config;
+mod camera;
 mod renderer;
+mod headless;
+mod regression;
+mod benchmark;
+
+use camera::{
+  CameraMode,
+  CameraProjection,
+  CameraController,
+  load_camera_names,
+};
 
 use renderer::{
   DebugSettings,
+  DynamicResolutionSettings,
+  GpuPass,
+  HeadlessOutputSettings,
+  PostProcessSettings,
+  ShadowSettings,
+  SsaoSettings,
+  TaaSettings,
+  TonemapOperator,
+  TonemapSettings,
   VisRenderer,
+  load_light_ranges,
+};
+
+use headless::HeadlessSettings;
+use regression::RegressionSettings;
+use benchmark::{
+  BenchmarkConfig,
+  CameraKeyframe,
 };
 
+/// The max distance in pixels the cursor moves between pressing and releasing the left button to pick.
+const MAX_PICK_CLICK_DISTANCE: f32 = 4.0;
+
 /// The settings of the application.
 #[derive(Debug, Default, Clone)]
 pub(crate) struct MySettings {
   pub debug_settings: DebugSettings,
+  pub use_small_gbuffer: bool,
+  pub use_async_compute: bool,
+  pub use_weighted_blended_oit: bool,
+  pub dynamic_resolution: DynamicResolutionSettings,
+  pub shadow: ShadowSettings,
+  pub environment_intensity: f32,
+  pub tonemap: TonemapSettings,
+  pub taa: TaaSettings,
+  pub ssao: SsaoSettings,
+  pub post_process: PostProcessSettings,
 }
 
 /// The application.
@@ -36,6 +77,20 @@ struct MyApplicationContext {
   log_file: String,
   config: config::AppConfig,
   settings: MySettings,
+  camera_index: usize,
+  // The names of the scene cameras in the glTF file, empty for the unnamed ones.
+  camera_names: Vec<String>,
+  gpu_timings_file: Option<String>,
+  // The camera keyframes recorded from the UI, and the benchmark file they are saved to.
+  benchmark_keyframes: Vec<CameraKeyframe>,
+  benchmark_path_file: Option<String>,
+  camera_controller: CameraController,
+  is_ui_capturing_mouse: bool,
+  is_ui_capturing_keyboard: bool,
+  // The cursor position in the window, and where the left button is pressed to pick.
+  cursor_position: Option<glam::Vec2>,
+  pick_press_position: Option<glam::Vec2>,
+  headless: Option<HeadlessSettings>,
   renderer: Option<VisRenderer>,
   imgui: Option<HalaImGui>,
 }
@@ -57,12 +112,87 @@ impl MyApplicationContext {
       None => "./logs/renderer.log"
     };
     let config_file = matches.get_one::<String>("config").with_context(|| "Failed to get the config file path.")?;
+    let camera_index = matches.get_one::<usize>("camera").copied().unwrap_or(0);
+    let gpu_timings_file = matches.get_one::<String>("gpu-timings").cloned();
+    let benchmark_path_file = matches.get_one::<String>("record-benchmark").cloned();
+    let regression = matches.get_one::<String>("regression").map(|golden_dir| RegressionSettings {
+      golden_dir: std::path::PathBuf::from(golden_dir),
+      update_golden: matches.get_flag("update-golden"),
+      tolerance: matches.get_one::<u8>("tolerance").copied().unwrap_or(2),
+      max_failed_ratio: matches.get_one::<f32>("max-failed-ratio").copied().unwrap_or(0.001),
+    });
+    let benchmark = match matches.get_one::<String>("benchmark") {
+      Some(benchmark_file) => Some(BenchmarkConfig::load(benchmark_file)?),
+      None => None,
+    };
+    // The regression and the benchmark always run in the headless mode.
+    let headless = if matches.get_flag("headless") || regression.is_some() || benchmark.is_some() {
+      // The two pass culling needs several frames to settle down the Hi-Z buffer.
+      let default_num_of_frames = if regression.is_some() { 4 } else { 1 };
+      Some(HeadlessSettings {
+        num_of_frames: matches.get_one::<u32>("frames").copied().unwrap_or(default_num_of_frames).max(1),
+        dump_targets: matches.get_flag("dump-targets"),
+        regression,
+        benchmark,
+      })
+    } else {
+      None
+    };
 
     // Load the configure.
     let config = config::load_app_config(config_file)?;
     log::debug!("Config: {:?}", config);
     config::validate_app_config(&config)?;
 
+    let settings = MySettings {
+      use_small_gbuffer: config.renderer.use_small_gbuffer,
+      use_async_compute: config.renderer.use_async_compute,
+      use_weighted_blended_oit: config.renderer.use_weighted_blended_oit,
+      dynamic_resolution: DynamicResolutionSettings {
+        enabled: config.renderer.dynamic_resolution.enabled,
+        gpu_budget: config.renderer.dynamic_resolution.gpu_budget,
+        min_scale: config.renderer.dynamic_resolution.min_scale,
+        max_scale: config.renderer.dynamic_resolution.max_scale,
+      },
+      shadow: ShadowSettings {
+        num_of_cascades: config.renderer.shadow.num_of_cascades,
+        resolution: config.renderer.shadow.resolution,
+        split_lambda: config.renderer.shadow.split_lambda,
+        max_distance: config.renderer.shadow.max_distance,
+      },
+      environment_intensity: config.renderer.environment.intensity,
+      tonemap: TonemapSettings {
+        operator: TonemapOperator::from_name(&config.renderer.tonemap.operator).unwrap_or(TonemapOperator::Aces),
+        auto_exposure: config.renderer.tonemap.auto_exposure,
+        exposure: config.renderer.tonemap.exposure,
+        exposure_compensation: config.renderer.tonemap.exposure_compensation,
+        min_log_luminance: config.renderer.tonemap.min_log_luminance,
+        max_log_luminance: config.renderer.tonemap.max_log_luminance,
+        adaptation_speed: config.renderer.tonemap.adaptation_speed,
+      },
+      taa: TaaSettings {
+        enabled: config.renderer.taa.enabled,
+        history_weight: config.renderer.taa.history_weight,
+      },
+      ssao: SsaoSettings {
+        enabled: config.renderer.ssao.enabled,
+        half_resolution: config.renderer.ssao.half_resolution,
+        blur: config.renderer.ssao.blur,
+        radius: config.renderer.ssao.radius,
+        intensity: config.renderer.ssao.intensity,
+      },
+      post_process: PostProcessSettings {
+        bloom: config.renderer.post_process.bloom,
+        bloom_intensity: config.renderer.post_process.bloom_intensity,
+        bloom_radius: config.renderer.post_process.bloom_radius,
+        vignette: config.renderer.post_process.vignette,
+        vignette_intensity: config.renderer.post_process.vignette_intensity,
+        chromatic_aberration: config.renderer.post_process.chromatic_aberration,
+        chromatic_aberration_intensity: config.renderer.post_process.chromatic_aberration_intensity,
+      },
+      ..Default::default()
+    };
+
     // Create out directory.
     std::fs::create_dir_all("./out")
       .with_context(|| "Failed to create the output directory: ./out")?;
@@ -70,12 +200,133 @@ impl MyApplicationContext {
     Ok(Self {
       log_file: log_file.to_string(),
       config,
-      settings: MySettings::default(),
+      settings,
+      camera_index,
+      camera_names: Vec::new(),
+      gpu_timings_file,
+      benchmark_keyframes: Vec::new(),
+      benchmark_path_file,
+      camera_controller: CameraController::default(),
+      is_ui_capturing_mouse: false,
+      is_ui_capturing_keyboard: false,
+      cursor_position: None,
+      pick_press_position: None,
+      headless,
       renderer: None,
       imgui: None,
     })
   }
 
+  /// Run before the headless mode, the renderer is created without a window.
+  /// return: The result.
+  pub(crate) fn before_run_headless(&mut self) -> Result<()> {
+    let mut scene = self.load_scene()?;
+
+    // Create the renderer, the output format and the frames in flight come from the config as there is no swapchain.
+    let headless = &self.config.headless;
+    let renderer = VisRenderer::new_headless(
+      "Visibility Renderer",
+      &self.get_gpu_requirements(),
+      HeadlessOutputSettings {
+        format: headless.get_output_format()
+          .with_context(|| format!("Unknown headless output format \"{}\".", headless.output_format))?,
+        num_of_frames_in_flight: headless.num_of_frames_in_flight as usize,
+      },
+    )?;
+
+    self.setup_renderer(renderer, &mut scene)
+  }
+
+  /// Load the scene of the config.
+  /// return: The scene in the CPU.
+  fn load_scene(&self) -> Result<scene::cpu::HalaScene> {
+    let now = std::time::Instant::now();
+    let scene = scene::cpu::HalaScene::new(&self.config.scene_file)?;
+    log::info!("Load scene used {}ms.", now.elapsed().as_millis());
+
+    Ok(scene)
+  }
+
+  /// Get the GPU requirements of the renderer.
+  /// return: The GPU requirements.
+  fn get_gpu_requirements(&self) -> hala_gfx::HalaGPURequirements {
+    hala_gfx::HalaGPURequirements {
+      width: self.config.window.width as u32,
+      height: self.config.window.height as u32,
+      version: (1, 3, 0),
+      require_srgb_surface: true,
+      require_mesh_shader: true,
+      require_ray_tracing: false,
+      require_10bits_output: false,
+      is_low_latency: true,
+      require_depth: true,
+      require_printf_in_shader: cfg!(debug_assertions),
+      require_depth_stencil_resolve: true,
+      ..Default::default()
+    }
+  }
+
+  /// Upload the scene to the created renderer and apply the settings to it.
+  /// param renderer: The renderer.
+  /// param scene: The scene in the CPU.
+  /// return: The result.
+  fn setup_renderer(&mut self, mut renderer: VisRenderer, scene: &mut scene::cpu::HalaScene) -> Result<()> {
+    let features = ["HALA_VISIBILITY_RENDERING", "GLOBAL_MESHLETS"];
+    let feature_folder_name = features.join("#");
+
+    let shaders_dir = if cfg!(debug_assertions) {
+      &format!("shaders/output/debug/hala-vis-renderer/{}", feature_folder_name)
+    } else {
+      &format!("shaders/output/release/hala-vis-renderer/{}", feature_folder_name)
+    };
+    HalaShaderCache::get_instance().borrow_mut().set_shader_dir(shaders_dir);
+
+    let light_ranges = load_light_ranges(&self.config.scene_file)?;
+    renderer.set_scene(scene, &light_ranges)?;
+    renderer.commit()?;
+    renderer.set_use_small_gbuffer(self.settings.use_small_gbuffer)?;
+    renderer.set_use_async_compute(self.settings.use_async_compute)?;
+    renderer.set_use_weighted_blended_oit(self.settings.use_weighted_blended_oit)?;
+    renderer.set_dynamic_resolution_settings(self.settings.dynamic_resolution);
+    renderer.set_shadow_settings(self.settings.shadow)?;
+    renderer.set_ssao_settings(self.settings.ssao)?;
+    renderer.load_gpu_programs(&self.config.programs_file)?;
+    // The environment map is prefiltered by the GPU programs.
+    if let Some(environment_file) = self.config.renderer.environment.file.as_ref() {
+      renderer.load_environment(environment_file)?;
+    }
+    renderer.set_environment_intensity(self.settings.environment_intensity);
+    renderer.set_tonemap_settings(self.settings.tonemap);
+    renderer.set_taa_settings(self.settings.taa)?;
+    renderer.set_post_process_settings(self.settings.post_process);
+    if let Some(gpu_timings_file) = self.gpu_timings_file.as_ref() {
+      renderer.get_gpu_profiler_mut().start_csv(gpu_timings_file)?;
+    }
+
+    self.camera_names = load_camera_names(&self.config.scene_file)?;
+    if self.camera_names.len() != renderer.get_num_of_scene_cameras() {
+      log::warn!("The camera names count \"{}\" is not equal to the cameras count \"{}\", the names are ignored.", self.camera_names.len(), renderer.get_num_of_scene_cameras());
+      self.camera_names.clear();
+    }
+
+    // Start the camera controller from the selected scene camera.
+    renderer.set_camera_index(self.camera_index)?;
+    let (v_mtx, p_mtx) = renderer.get_camera();
+    self.camera_controller.reset(&v_mtx, &p_mtx);
+
+    // Setup the imgui. There is no UI in the headless mode.
+    if self.headless.is_none() {
+      self.imgui = Some(HalaImGui::new(
+        std::rc::Rc::clone(&renderer.resources().context),
+        false,
+      )?);
+    }
+
+    self.renderer = Some(renderer);
+
+    Ok(())
+  }
+
 }
 
 
@@ -118,62 +369,23 @@ impl HalaApplicationContextTrait for MyApplicationContext {
   /// param window: The window.
   /// return: The result.
   fn before_run(&mut self, _width: u32, _height: u32, window: &winit::window::Window) -> Result<()> {
-    let now = std::time::Instant::now();
-    let mut scene = scene::cpu::HalaScene::new(&self.config.scene_file)?;
-    log::info!("Load scene used {}ms.", now.elapsed().as_millis());
-
-    // Setup the renderer.
-    let gpu_req = hala_gfx::HalaGPURequirements {
-      width: self.config.window.width as u32,
-      height: self.config.window.height as u32,
-      version: (1, 3, 0),
-      require_srgb_surface: true,
-      require_mesh_shader: true,
-      require_ray_tracing: false,
-      require_10bits_output: false,
-      is_low_latency: true,
-      require_depth: true,
-      require_printf_in_shader: cfg!(debug_assertions),
-      require_depth_stencil_resolve: true,
-      ..Default::default()
-    };
+    let mut scene = self.load_scene()?;
 
     // Create the renderer.
-    let mut renderer = VisRenderer::new(
+    let renderer = VisRenderer::new(
       "Visibility Renderer",
-      &gpu_req,
+      &self.get_gpu_requirements(),
       window,
     )?;
 
-    let features = ["HALA_VISIBILITY_RENDERING", "GLOBAL_MESHLETS"];
-    let feature_folder_name = features.join("#");
-
-    let shaders_dir = if cfg!(debug_assertions) {
-      &format!("shaders/output/debug/hala-vis-renderer/{}", feature_folder_name)
-    } else {
-      &format!("shaders/output/release/hala-vis-renderer/{}", feature_folder_name)
-    };
-    HalaShaderCache::get_instance().borrow_mut().set_shader_dir(shaders_dir);
-
-    renderer.set_scene(&mut scene)?;
-    renderer.commit()?;
-    renderer.load_gpu_programs(&self.config.programs_file)?;
-
-    // Setup the imgui.
-    self.imgui = Some(HalaImGui::new(
-      std::rc::Rc::clone(&renderer.resources().context),
-      false,
-    )?);
-
-    self.renderer = Some(renderer);
-
-    Ok(())
+    self.setup_renderer(renderer, &mut scene)
   }
 
   /// The after run function.
   fn after_run(&mut self) {
     if let Some(renderer) = &mut self.renderer.take() {
       renderer.wait_idle().expect("Failed to wait the renderer idle.");
+      renderer.get_gpu_profiler_mut().stop_csv();
       self.imgui = None;
     }
   }
@@ -188,6 +400,9 @@ impl HalaApplicationContextTrait for MyApplicationContext {
         width,
         height,
         |ui| -> Result<()> {
+          self.is_ui_capturing_mouse = ui.io().want_capture_mouse;
+          self.is_ui_capturing_keyboard = ui.io().want_capture_keyboard;
+
           if let Some(renderer) = self.renderer.as_mut() {
             ui.window("Visibility Renderer")
               .collapsed(false, imgui::Condition::FirstUseEver)
@@ -196,6 +411,99 @@ impl HalaApplicationContextTrait for MyApplicationContext {
               .build(|| -> Result<()> {
                 let mut is_debug_settings_changed = false;
 
+                ui.text("Camera:");
+                ui.separator();
+                let mut camera_mode_index = match self.camera_controller.settings.mode {
+                  CameraMode::FreeFly => 0,
+                  CameraMode::Orbit => 1,
+                };
+                let mut is_camera_mode_changed = ui.radio_button("Free Fly", &mut camera_mode_index, 0);
+                ui.same_line();
+                is_camera_mode_changed |= ui.radio_button("Orbit", &mut camera_mode_index, 1);
+                if is_camera_mode_changed {
+                  self.camera_controller.set_mode(if camera_mode_index == 1 { CameraMode::Orbit } else { CameraMode::FreeFly });
+                }
+                ui.slider("Move Speed", 0.1f32, 100.0f32, &mut self.camera_controller.settings.move_speed);
+                // The orthographic camera has no field of view, the wheel zooms its extents.
+                match self.camera_controller.settings.projection {
+                  CameraProjection::Perspective => {
+                    ui.slider("FOV", 10.0f32, 120.0f32, &mut self.camera_controller.settings.yfov);
+                  },
+                  CameraProjection::Orthographic { xmag, ymag, .. } => {
+                    ui.text(format!("Orthographic: {:.2} x {:.2}", xmag * 2.0, ymag * 2.0));
+                  },
+                }
+                ui.slider("Near", 0.001f32, 10.0f32, &mut self.camera_controller.settings.z_near);
+                let camera_names = (0..renderer.get_num_of_scene_cameras())
+                  .map(|index| match self.camera_names.get(index) {
+                    Some(name) if !name.is_empty() => name.clone(),
+                    _ => format!("Camera {}", index),
+                  })
+                  .collect::<Vec<_>>();
+                let mut camera_index = renderer.get_camera_index();
+                let mut is_camera_index_changed = ui.combo_simple_string("Scene Camera", &mut camera_index, &camera_names);
+                ui.same_line();
+                is_camera_index_changed |= ui.button("Reset Camera");
+                if is_camera_index_changed {
+                  renderer.set_camera_index(camera_index)?;
+                  self.camera_index = camera_index;
+                  let (v_mtx, p_mtx) = renderer.get_camera();
+                  self.camera_controller.reset(&v_mtx, &p_mtx);
+                }
+
+                ui.text("Benchmark Path:");
+                ui.separator();
+                if ui.button("Add Keyframe") {
+                  self.benchmark_keyframes.push(CameraKeyframe {
+                    position: self.camera_controller.position().into(),
+                    target: self.camera_controller.target().into(),
+                  });
+                }
+                ui.same_line();
+                if ui.button("Clear Keyframes") {
+                  self.benchmark_keyframes.clear();
+                }
+                ui.same_line();
+                ui.text(format!("{} keyframes", self.benchmark_keyframes.len()));
+                // The benchmark camera path needs at least 2 keyframes.
+                {
+                  let _disabled = ui.begin_disabled(self.benchmark_keyframes.len() < 2);
+                  if ui.button("Save Path") {
+                    let benchmark_path_file = self.benchmark_path_file.get_or_insert_with(|| "./out/benchmark.toml".to_string());
+                    BenchmarkConfig::from_keyframes(self.benchmark_keyframes.clone()).save(benchmark_path_file.as_str())?;
+                  }
+                }
+
+                ui.text("GPU Timings:");
+                ui.separator();
+                if let Some(_table) = ui.begin_table_with_flags("GPU Timings", 2, imgui::TableFlags::BORDERS | imgui::TableFlags::ROW_BG) {
+                  let profiler = renderer.get_gpu_profiler();
+                  let rows = GpuPass::ALL.iter()
+                    .map(|pass| (pass.name(), profiler.get_average(*pass)))
+                    .chain(std::iter::once(("Total", profiler.get_total_average())));
+                  for (name, timing) in rows {
+                    ui.table_next_row();
+                    ui.table_next_column();
+                    ui.text(name);
+                    ui.table_next_column();
+                    ui.text(format!("{:.3} ms", timing));
+                  }
+                }
+                let total_history = renderer.get_gpu_profiler().get_total_history();
+                ui.plot_lines("GPU Time", &total_history)
+                  .scale_min(0.0)
+                  .graph_size([0.0, 60.0])
+                  .build();
+                let mut is_writing_csv = renderer.get_gpu_profiler().is_writing_csv();
+                if ui.checkbox("Write CSV", &mut is_writing_csv) {
+                  if is_writing_csv {
+                    let gpu_timings_file = self.gpu_timings_file.get_or_insert_with(|| "./out/gpu_timings.csv".to_string());
+                    renderer.get_gpu_profiler_mut().start_csv(gpu_timings_file.as_str())?;
+                  } else {
+                    renderer.get_gpu_profiler_mut().stop_csv();
+                  }
+                }
+
                 ui.text("Culling:");
                 ui.separator();
                 let mut culling_index = if self.settings.debug_settings.disable_culling {
@@ -209,6 +517,112 @@ impl HalaApplicationContextTrait for MyApplicationContext {
                 is_debug_settings_changed |= ui.radio_button("One Pass Culling", &mut culling_index, 2);
                 is_debug_settings_changed |= ui.radio_button("Two Pass Culling", &mut culling_index, 0);
 
+                ui.text("G-Buffer:");
+                ui.separator();
+                if ui.checkbox("Small G-Buffer", &mut self.settings.use_small_gbuffer) {
+                  renderer.set_use_small_gbuffer(self.settings.use_small_gbuffer)?;
+                }
+
+                ui.text("Material Classification:");
+                ui.separator();
+                if ui.checkbox("Async Compute", &mut self.settings.use_async_compute) {
+                  renderer.set_use_async_compute(self.settings.use_async_compute)?;
+                }
+
+                ui.text("Transparency:");
+                ui.separator();
+                if ui.checkbox("Weighted Blended OIT", &mut self.settings.use_weighted_blended_oit) {
+                  renderer.set_use_weighted_blended_oit(self.settings.use_weighted_blended_oit)?;
+                }
+
+                ui.text("Environment:");
+                ui.separator();
+                if ui.slider("Intensity", 0.0f32, 4.0f32, &mut self.settings.environment_intensity) {
+                  renderer.set_environment_intensity(self.settings.environment_intensity);
+                }
+
+                ui.text("Ambient Occlusion:");
+                ui.separator();
+                let ssao = &mut self.settings.ssao;
+                let mut is_ssao_changed = ui.checkbox("SSAO", &mut ssao.enabled);
+                ui.same_line();
+                is_ssao_changed |= ui.checkbox("Half Resolution", &mut ssao.half_resolution);
+                ui.same_line();
+                is_ssao_changed |= ui.checkbox("Blur", &mut ssao.blur);
+                is_ssao_changed |= ui.slider("Radius", 0.05f32, 4.0f32, &mut ssao.radius);
+                is_ssao_changed |= ui.slider("AO Intensity", 0.0f32, 4.0f32, &mut ssao.intensity);
+                if is_ssao_changed {
+                  renderer.set_ssao_settings(*ssao)?;
+                }
+
+                ui.text("Tonemapping:");
+                ui.separator();
+                let tonemap = &mut self.settings.tonemap;
+                let operator_names = TonemapOperator::ALL.iter()
+                  .map(|operator| operator.name())
+                  .collect::<Vec<_>>();
+                let mut operator_index = TonemapOperator::ALL.iter()
+                  .position(|operator| *operator == tonemap.operator)
+                  .unwrap_or(0);
+                let mut is_tonemap_changed = ui.combo_simple_string("Operator", &mut operator_index, &operator_names);
+                if is_tonemap_changed {
+                  tonemap.operator = TonemapOperator::ALL[operator_index];
+                }
+                is_tonemap_changed |= ui.checkbox("Auto Exposure", &mut tonemap.auto_exposure);
+                if tonemap.auto_exposure {
+                  is_tonemap_changed |= ui.slider("Compensation (EV)", -5.0f32, 5.0f32, &mut tonemap.exposure_compensation);
+                  is_tonemap_changed |= ui.slider("Min Luminance (EV)", -16.0f32, 0.0f32, &mut tonemap.min_log_luminance);
+                  is_tonemap_changed |= ui.slider("Max Luminance (EV)", 0.0f32, 16.0f32, &mut tonemap.max_log_luminance);
+                  is_tonemap_changed |= ui.slider("Adaptation Speed", 0.1f32, 10.0f32, &mut tonemap.adaptation_speed);
+                } else {
+                  is_tonemap_changed |= ui.slider("Exposure (EV)", -10.0f32, 10.0f32, &mut tonemap.exposure);
+                }
+                if is_tonemap_changed {
+                  renderer.set_tonemap_settings(*tonemap);
+                }
+
+                ui.text("Post Processing:");
+                ui.separator();
+                let post_process = &mut self.settings.post_process;
+                let mut is_post_process_changed = ui.checkbox("Bloom", &mut post_process.bloom);
+                if post_process.bloom {
+                  is_post_process_changed |= ui.slider("Bloom Intensity", 0.0f32, 0.5f32, &mut post_process.bloom_intensity);
+                  is_post_process_changed |= ui.slider("Bloom Radius", 0.5f32, 4.0f32, &mut post_process.bloom_radius);
+                }
+                is_post_process_changed |= ui.checkbox("Vignette", &mut post_process.vignette);
+                if post_process.vignette {
+                  is_post_process_changed |= ui.slider("Vignette Intensity", 0.0f32, 1.0f32, &mut post_process.vignette_intensity);
+                }
+                is_post_process_changed |= ui.checkbox("Chromatic Aberration", &mut post_process.chromatic_aberration);
+                if post_process.chromatic_aberration {
+                  is_post_process_changed |= ui.slider("Aberration (%)", 0.0f32, 2.0f32, &mut post_process.chromatic_aberration_intensity);
+                }
+                if is_post_process_changed {
+                  renderer.set_post_process_settings(*post_process);
+                }
+
+                ui.text("Anti-aliasing:");
+                ui.separator();
+                let taa = &mut self.settings.taa;
+                let mut is_taa_changed = ui.checkbox("TAA", &mut taa.enabled);
+                is_taa_changed |= ui.slider("History Weight", 0.0f32, 0.98f32, &mut taa.history_weight);
+                if is_taa_changed {
+                  renderer.set_taa_settings(*taa)?;
+                }
+
+                ui.text("Dynamic Resolution:");
+                ui.separator();
+                let dynamic_resolution = &mut self.settings.dynamic_resolution;
+                let mut is_dynamic_resolution_changed = ui.checkbox("Enable", &mut dynamic_resolution.enabled);
+                is_dynamic_resolution_changed |= ui.slider("GPU Budget (ms)", 1.0f32, 50.0f32, &mut dynamic_resolution.gpu_budget);
+                is_dynamic_resolution_changed |= ui.slider("Min Scale", 0.25f32, 1.0f32, &mut dynamic_resolution.min_scale);
+                is_dynamic_resolution_changed |= ui.slider("Max Scale", 0.25f32, 1.0f32, &mut dynamic_resolution.max_scale);
+                if is_dynamic_resolution_changed {
+                  dynamic_resolution.max_scale = dynamic_resolution.max_scale.max(dynamic_resolution.min_scale);
+                  renderer.set_dynamic_resolution_settings(*dynamic_resolution);
+                }
+                ui.text(format!("Render Scale: {:.2}", renderer.get_render_scale()));
+
                 ui.text("Debug Views:");
                 ui.separator();
                 let mut debug_view_index = if self.settings.debug_settings.show_hiz {
@@ -225,6 +639,12 @@ impl HalaApplicationContextTrait for MyApplicationContext {
                   6
                 } else if self.settings.debug_settings.show_normal {
                   7
+                } else if self.settings.debug_settings.show_light_count {
+                  8
+                } else if self.settings.debug_settings.show_shadow_cascades {
+                  9
+                } else if self.settings.debug_settings.show_ambient_occlusion {
+                  10
                 } else {
                   0
                 };
@@ -233,7 +653,7 @@ impl HalaApplicationContextTrait for MyApplicationContext {
                 ui.same_line();
                 is_debug_settings_changed |= ui.radio_button("Hi-Z", &mut debug_view_index, 1);
 
-                is_debug_settings_changed |= ui.slider("Hi-Z Level", 0u32, 4u32, &mut self.settings.debug_settings.hiz_level);
+                is_debug_settings_changed |= ui.slider("Hi-Z Level", 0u32, renderer.get_num_of_hiz_levels() - 1, &mut self.settings.debug_settings.hiz_level);
 
                 is_debug_settings_changed |= ui.radio_button("Triangle", &mut debug_view_index, 2);
                 ui.same_line();
@@ -243,12 +663,18 @@ impl HalaApplicationContextTrait for MyApplicationContext {
                 ui.same_line();
                 is_debug_settings_changed |= ui.radio_button("Material Depth", &mut debug_view_index, 5);
 
+                is_debug_settings_changed |= ui.radio_button("Light Count", &mut debug_view_index, 8);
+                ui.same_line();
+                is_debug_settings_changed |= ui.radio_button("Shadow Cascades", &mut debug_view_index, 9);
+
                 ui.text("Debug Tile Settings:");
                 ui.separator();
 
                 is_debug_settings_changed |= ui.radio_button("Albedo", &mut debug_view_index, 6);
                 ui.same_line();
                 is_debug_settings_changed |= ui.radio_button("Normal", &mut debug_view_index, 7);
+                ui.same_line();
+                is_debug_settings_changed |= ui.radio_button("AO", &mut debug_view_index, 10);
 
                 is_debug_settings_changed |= ui.slider("Grid Line", 0u32, 4u32, &mut self.settings.debug_settings.grid_line_width);
 
@@ -263,6 +689,9 @@ impl HalaApplicationContextTrait for MyApplicationContext {
                   self.settings.debug_settings.show_material_depth = debug_view_index == 5;
                   self.settings.debug_settings.show_albedo = debug_view_index == 6;
                   self.settings.debug_settings.show_normal = debug_view_index == 7;
+                  self.settings.debug_settings.show_light_count = debug_view_index == 8;
+                  self.settings.debug_settings.show_shadow_cascades = debug_view_index == 9;
+                  self.settings.debug_settings.show_ambient_occlusion = debug_view_index == 10;
 
                   renderer.update_debug_settings(self.settings.debug_settings)?;
                 }
@@ -288,6 +717,33 @@ impl HalaApplicationContextTrait for MyApplicationContext {
                 Ok(())
               }
             );
+
+            // The inspector of the object picked by clicking on the scene.
+            let mut is_selection_cleared = false;
+            if let Some(picked_object) = renderer.get_picked_object() {
+              ui.window("Inspector")
+                .position([width as f32 - 10.0, 10.0], imgui::Condition::FirstUseEver)
+                .position_pivot([1.0, 0.0])
+                .always_auto_resize(true)
+                .build(|| {
+                  let node_name = if picked_object.node_name.is_empty() { "<Unnamed>" } else { picked_object.node_name.as_str() };
+                  ui.text(format!("Node: {}", node_name));
+                  ui.text(format!("Mesh: {}", picked_object.mesh_index));
+                  ui.text(format!("Primitive: {}", picked_object.primitive_index));
+                  ui.text(format!("Material: {}", picked_object.material_index));
+                  ui.text(format!("Alpha Mask: {}", picked_object.is_alpha_masked()));
+                  ui.text(format!("Double Sided: {}", picked_object.is_double_sided()));
+                  ui.separator();
+                  ui.text(format!("Draw: {}", picked_object.draw_index));
+                  ui.text(format!("Meshlet: {}", picked_object.meshlet_index));
+                  ui.text(format!("Triangle: {}", picked_object.triangle_index));
+                  ui.text(format!("Depth: {:.6}", picked_object.depth));
+                  is_selection_cleared = ui.button("Clear Selection");
+                });
+            }
+            if is_selection_cleared {
+              renderer.clear_selection()?;
+            }
           }
 
           Ok(())
@@ -297,6 +753,14 @@ impl HalaApplicationContextTrait for MyApplicationContext {
     }
 
     if let Some(renderer) = &mut self.renderer {
+      // Update the camera by the input.
+      self.camera_controller.update(delta_time);
+      let aspect = width as f32 / height.max(1) as f32;
+      renderer.set_camera(
+        self.camera_controller.view_matrix(),
+        self.camera_controller.proj_matrix(aspect),
+      );
+
       renderer.update(
         delta_time,
         width,
@@ -314,6 +778,42 @@ impl HalaApplicationContextTrait for MyApplicationContext {
     Ok(())
   }
 
+  /// The window event function.
+  /// param event: The window event.
+  /// return: The result.
+  fn on_window_event(&mut self, event: &winit::event::WindowEvent) -> Result<()> {
+    self.camera_controller.handle_window_event(
+      event,
+      self.is_ui_capturing_mouse,
+      self.is_ui_capturing_keyboard,
+    );
+
+    // A left click picks the object under the cursor, the dragging only moves the camera.
+    match event {
+      winit::event::WindowEvent::CursorMoved { position, .. } => {
+        self.cursor_position = Some(glam::Vec2::new(position.x as f32, position.y as f32));
+      },
+      winit::event::WindowEvent::CursorLeft { .. } => {
+        self.cursor_position = None;
+        self.pick_press_position = None;
+      },
+      winit::event::WindowEvent::MouseInput { state, button: winit::event::MouseButton::Left, .. } => {
+        if *state == winit::event::ElementState::Pressed {
+          self.pick_press_position = if self.is_ui_capturing_mouse { None } else { self.cursor_position };
+        } else if let (Some(press_position), Some(cursor_position)) = (self.pick_press_position.take(), self.cursor_position) {
+          if press_position.distance(cursor_position) <= MAX_PICK_CLICK_DISTANCE {
+            if let Some(renderer) = self.renderer.as_mut() {
+              renderer.request_pick(cursor_position.x, cursor_position.y);
+            }
+          }
+        }
+      },
+      _ => (),
+    }
+
+    Ok(())
+  }
+
   /// The render function.
   /// return: The result.
   fn render(&mut self) -> Result<()> {
@@ -334,6 +834,23 @@ fn cli() -> Command {
     .arg_required_else_help(true)
     .arg(arg!(-l --log <LOG_FILE> "The file path of the log file. Default is ./logs/renderer.log."))
     .arg(arg!(-c --config [CONFIG_FILE] "The file path of the config file."))
+    .arg(arg!(--camera [CAMERA_INDEX] "The index of the scene camera to start with. Default is 0.")
+      .value_parser(clap::value_parser!(usize)))
+    .arg(arg!(--headless "Render without the UI and write the final image to ./out."))
+    .arg(arg!(--frames [NUM_OF_FRAMES] "The number of frames to render in the headless mode. Default is 1.")
+      .value_parser(clap::value_parser!(u32)))
+    .arg(arg!(--"dump-targets" "Write the albedo, normal, visibility and Hi-Z targets to ./out in the headless mode."))
+    .arg(arg!(--regression [GOLDEN_DIR] "Render every debug view and culling mode headless and compare them against the reference images in the directory. Default is assets/golden. A case without a reference image fails the run, run with --update-golden to record them.")
+      .default_missing_value("assets/golden"))
+    .arg(arg!(--benchmark [BENCHMARK_FILE] "Play back the camera path in the benchmark file with each culling strategy headless and write a summary report."))
+    .arg(arg!(--"record-benchmark" [BENCHMARK_FILE] "The file path the camera keyframes recorded in the UI are saved to, which plays back with --benchmark. Default is ./out/benchmark.toml."))
+    .arg(arg!(--"gpu-timings" [CSV_FILE] "Write the per-frame GPU timings of each pass to the CSV file. Default is ./out/gpu_timings.csv.")
+      .default_missing_value("./out/gpu_timings.csv"))
+    .arg(arg!(--"update-golden" "Overwrite the reference images by the current results in the regression."))
+    .arg(arg!(--tolerance [TOLERANCE] "The maximum allowed difference per channel in the regression. Default is 2.")
+      .value_parser(clap::value_parser!(u8)))
+    .arg(arg!(--"max-failed-ratio" [RATIO] "The maximum allowed ratio of the pixels exceeding the tolerance in the regression. Default is 0.001.")
+      .value_parser(clap::value_parser!(f32)))
 }
 
 /// The normal main function.
@@ -342,6 +859,11 @@ fn main() -> Result<()> {
   let context = MyApplicationContext::new()?;
   context.init()?;
 
+  // Run the application without the UI.
+  if let Some(settings) = context.headless.clone() {
+    return headless::run(context, settings);
+  }
+
   // Run the application.
   let mut app = HalaApplication::new(Box::new(context));
   app.run()?;
@@ -0,0 +1,257 @@
+use std::path::{
+  Path,
+  PathBuf,
+};
+
+use anyhow::{
+  Result,
+  Context,
+};
+
+use crate::renderer::DebugSettings;
+
+/// The settings of the golden-image regression.
+#[derive(Debug, Clone)]
+pub(crate) struct RegressionSettings {
+  // The directory of the reference images.
+  pub golden_dir: PathBuf,
+  // Whether overwrite the reference images by the current results.
+  pub update_golden: bool,
+  // The maximum allowed difference per channel.
+  pub tolerance: u8,
+  // The maximum allowed ratio of the pixels exceeding the tolerance.
+  pub max_failed_ratio: f32,
+}
+
+/// The regression case.
+#[derive(Debug, Clone)]
+pub(crate) struct RegressionCase {
+  pub name: String,
+  pub debug_settings: DebugSettings,
+}
+
+/// The outcome of a regression case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RegressionOutcome {
+  Passed,
+  Failed,
+  // The reference image does not exist, which fails the run unless the reference images are updated.
+  Missing,
+}
+
+/// The result of comparing an image against its reference.
+pub(crate) struct RegressionResult {
+  pub num_of_failed_pixels: u64,
+  pub failed_ratio: f32,
+  pub diff_image: image::RgbaImage,
+}
+
+/// Build all regression cases.
+/// Every debug view which depends on the culling is rendered with each culling mode.
+/// The triangle and meshlet views draw the scene directly, so they are rendered once.
+/// return: The regression cases.
+pub(crate) fn build_regression_cases() -> Vec<RegressionCase> {
+  let culling_modes = [
+    ("no_culling", true, false),
+    ("one_pass", false, true),
+    ("two_pass", false, false),
+  ];
+  let views: [(&str, fn(&mut DebugSettings)); 9] = [
+    ("lit", |_| ()),
+    ("hiz", |s| s.show_hiz = true),
+    ("visibility", |s| s.show_visibility = true),
+    ("material_depth", |s| s.show_material_depth = true),
+    ("albedo", |s| s.show_albedo = true),
+    ("normal", |s| s.show_normal = true),
+    ("ambient_occlusion", |s| s.show_ambient_occlusion = true),
+    ("light_count", |s| s.show_light_count = true),
+    ("shadow_cascades", |s| s.show_shadow_cascades = true),
+  ];
+
+  let mut cases = Vec::new();
+  for (culling_name, disable_culling, one_pass_culling) in culling_modes {
+    for (view_name, setup) in views {
+      let mut debug_settings = DebugSettings {
+        disable_culling,
+        one_pass_culling,
+        ..Default::default()
+      };
+      setup(&mut debug_settings);
+      cases.push(RegressionCase {
+        name: format!("{}_{}", culling_name, view_name),
+        debug_settings,
+      });
+    }
+  }
+  cases.push(RegressionCase {
+    name: "triangle".to_string(),
+    debug_settings: DebugSettings { show_triangle: true, ..Default::default() },
+  });
+  cases.push(RegressionCase {
+    name: "meshlet".to_string(),
+    debug_settings: DebugSettings { show_meshlet: true, ..Default::default() },
+  });
+
+  cases
+}
+
+/// Compare the image against the reference image.
+/// param image: The rendered image.
+/// param golden: The reference image.
+/// param tolerance: The maximum allowed difference per channel.
+/// return: The comparison result.
+pub(crate) fn compare_images(image: &image::RgbaImage, golden: &image::RgbaImage, tolerance: u8) -> Result<RegressionResult> {
+  if image.dimensions() != golden.dimensions() {
+    return Err(anyhow::anyhow!(
+      "The image size {:?} does not match the reference size {:?}.",
+      image.dimensions(),
+      golden.dimensions()
+    ));
+  }
+
+  // Mark the failed pixels red over the dimmed reference image.
+  let mut num_of_failed_pixels = 0u64;
+  let diff_image = image::RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+    let a = image.get_pixel(x, y);
+    let b = golden.get_pixel(x, y);
+    let is_failed = a.0.iter().zip(b.0.iter()).any(|(a, b)| a.abs_diff(*b) > tolerance);
+    if is_failed {
+      num_of_failed_pixels += 1;
+      image::Rgba([255, 0, 0, 255])
+    } else {
+      let luma = ((b[0] as u32 * 77 + b[1] as u32 * 150 + b[2] as u32 * 29) >> 10) as u8;
+      image::Rgba([luma, luma, luma, 255])
+    }
+  });
+
+  Ok(RegressionResult {
+    num_of_failed_pixels,
+    failed_ratio: num_of_failed_pixels as f32 / (image.width() as f32 * image.height() as f32),
+    diff_image,
+  })
+}
+
+/// Check the rendered image of the case against its reference image.
+/// The rendered image is always written to the output directory, the diff image only on failure.
+/// The case without a reference image is missing, run with --update-golden to record the reference images.
+/// param settings: The regression settings.
+/// param case: The regression case.
+/// param image: The rendered image.
+/// param out_dir: The output directory.
+/// return: The outcome of the case.
+pub(crate) fn check_regression_case(
+  settings: &RegressionSettings,
+  case: &RegressionCase,
+  image: &image::RgbaImage,
+  out_dir: &Path,
+) -> Result<RegressionOutcome> {
+  let golden_path = settings.golden_dir.join(format!("{}.png", case.name));
+  let output_path = out_dir.join(format!("{}.png", case.name));
+  image.save(&output_path)
+    .with_context(|| format!("Failed to save the image: {:?}", output_path))?;
+
+  if settings.update_golden {
+    std::fs::create_dir_all(&settings.golden_dir)
+      .with_context(|| format!("Failed to create the golden directory: {:?}", settings.golden_dir))?;
+    image.save(&golden_path)
+      .with_context(|| format!("Failed to save the reference image: {:?}", golden_path))?;
+    log::info!("[{}] Updated the reference image.", case.name);
+    return Ok(RegressionOutcome::Passed);
+  }
+
+  if !golden_path.exists() {
+    log::error!("[{}] MISSING: The reference image {:?} is not found, run with --update-golden to record it.", case.name, golden_path);
+    return Ok(RegressionOutcome::Missing);
+  }
+  let golden = image::open(&golden_path)
+    .with_context(|| format!("Failed to load the reference image: {:?}", golden_path))?
+    .to_rgba8();
+
+  let result = compare_images(image, &golden, settings.tolerance)
+    .with_context(|| format!("Failed to compare the case \"{}\".", case.name))?;
+  if result.failed_ratio > settings.max_failed_ratio {
+    let diff_path = out_dir.join(format!("{}_diff.png", case.name));
+    result.diff_image.save(&diff_path)
+      .with_context(|| format!("Failed to save the diff image: {:?}", diff_path))?;
+    log::error!(
+      "[{}] FAILED: {} pixels({:.4}%) exceed the tolerance {}, see {:?}.",
+      case.name,
+      result.num_of_failed_pixels,
+      result.failed_ratio * 100.0,
+      settings.tolerance,
+      diff_path,
+    );
+    return Ok(RegressionOutcome::Failed);
+  }
+
+  log::info!("[{}] PASSED: {} pixels exceed the tolerance {}.", case.name, result.num_of_failed_pixels, settings.tolerance);
+  Ok(RegressionOutcome::Passed)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Create an image filled with a single color.
+  fn solid_image(width: u32, height: u32, color: [u8; 4]) -> image::RgbaImage {
+    image::RgbaImage::from_pixel(width, height, image::Rgba(color))
+  }
+
+  #[test]
+  fn compare_images_rejects_size_mismatch() {
+    let image = solid_image(4, 4, [128, 128, 128, 255]);
+    let golden = solid_image(4, 2, [128, 128, 128, 255]);
+
+    assert!(compare_images(&image, &golden, 2).is_err());
+  }
+
+  #[test]
+  fn compare_images_accepts_difference_within_tolerance() {
+    let image = solid_image(4, 4, [130, 126, 128, 255]);
+    let golden = solid_image(4, 4, [128, 128, 128, 255]);
+
+    let result = compare_images(&image, &golden, 2).unwrap();
+    assert_eq!(result.num_of_failed_pixels, 0);
+    assert_eq!(result.failed_ratio, 0.0);
+  }
+
+  #[test]
+  fn compare_images_counts_difference_over_tolerance() {
+    let mut image = solid_image(4, 4, [128, 128, 128, 255]);
+    let golden = solid_image(4, 4, [128, 128, 128, 255]);
+    image.put_pixel(1, 2, image::Rgba([131, 128, 128, 255]));
+    image.put_pixel(3, 0, image::Rgba([128, 128, 128, 250]));
+
+    let result = compare_images(&image, &golden, 2).unwrap();
+    assert_eq!(result.num_of_failed_pixels, 2);
+    assert_eq!(result.failed_ratio, 2.0 / 16.0);
+    assert_eq!(*result.diff_image.get_pixel(1, 2), image::Rgba([255, 0, 0, 255]));
+    assert_ne!(*result.diff_image.get_pixel(0, 0), image::Rgba([255, 0, 0, 255]));
+  }
+
+  #[test]
+  fn check_regression_case_fails_without_reference_unless_updated() {
+    let dir = std::env::temp_dir().join(format!("hala-vis-renderer-regression-{}", std::process::id()));
+    let out_dir = dir.join("out");
+    std::fs::create_dir_all(&out_dir).unwrap();
+    let mut settings = RegressionSettings {
+      golden_dir: dir.join("golden"),
+      update_golden: false,
+      tolerance: 2,
+      max_failed_ratio: 0.0,
+    };
+    let case = RegressionCase {
+      name: "lit".to_string(),
+      debug_settings: DebugSettings::default(),
+    };
+    let image = solid_image(4, 4, [128, 128, 128, 255]);
+
+    assert_eq!(check_regression_case(&settings, &case, &image, &out_dir).unwrap(), RegressionOutcome::Missing);
+    settings.update_golden = true;
+    assert_eq!(check_regression_case(&settings, &case, &image, &out_dir).unwrap(), RegressionOutcome::Passed);
+    settings.update_golden = false;
+    assert_eq!(check_regression_case(&settings, &case, &image, &out_dir).unwrap(), RegressionOutcome::Passed);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+}
@@ -0,0 +1,150 @@
+use std::rc::Rc;
+
+use hala_renderer::error::HalaRendererError;
+
+use super::render_graph::RGAsyncCompute;
+
+/// The command buffers and the semaphores to run the async compute passes on the compute queue.
+/// The frame is submitted in four parts before the main graphics command buffers,
+/// the last one waits the compute queue so the fence of the main submission also covers the async compute passes.
+pub(crate) struct AsyncCompute {
+  // The graphics passes before the async compute passes.
+  pub(crate) pre_graphics_command_buffers: hala_gfx::HalaCommandBufferSet,
+  // The graphics passes running alongside the async compute passes.
+  pub(crate) overlap_graphics_command_buffers: hala_gfx::HalaCommandBufferSet,
+  // The ownership acquiring of the async compute results.
+  pub(crate) join_graphics_command_buffers: hala_gfx::HalaCommandBufferSet,
+  // Signaled by the pre graphics submission, waited by the compute submission.
+  pub(crate) pre_graphics_semaphores: Vec<hala_gfx::HalaSemaphore>,
+  // Signaled by the compute submission, waited by the join graphics submission.
+  pub(crate) compute_semaphores: Vec<hala_gfx::HalaSemaphore>,
+  pub(crate) graphics_queue_family_index: u32,
+  pub(crate) compute_queue_family_index: u32,
+}
+
+/// The implementation of the async compute.
+impl AsyncCompute {
+
+  /// Create a new async compute.
+  /// param context: The context.
+  /// param num_of_images: The number of the frames in flight.
+  /// return: The async compute.
+  pub fn new(context: &hala_gfx::HalaContext, num_of_images: usize) -> Result<Self, HalaRendererError> {
+    let create_command_buffers = |name: &str| hala_gfx::HalaCommandBufferSet::new(
+      Rc::clone(&context.logical_device),
+      Rc::clone(&context.pools),
+      hala_gfx::HalaCommandBufferType::GRAPHICS,
+      hala_gfx::HalaCommandBufferLevel::PRIMARY,
+      num_of_images,
+      name,
+    );
+    let create_semaphores = |name: &str| (0..num_of_images).map(|index| hala_gfx::HalaSemaphore::new(
+      Rc::clone(&context.logical_device),
+      &format!("{}_{}.semaphore", name, index),
+    )).collect::<Result<Vec<_>, _>>();
+
+    let logical_device = context.logical_device.borrow();
+    Ok(Self {
+      pre_graphics_command_buffers: create_command_buffers("async_compute.pre_graphics.command_buffers")?,
+      overlap_graphics_command_buffers: create_command_buffers("async_compute.overlap_graphics.command_buffers")?,
+      join_graphics_command_buffers: create_command_buffers("async_compute.join_graphics.command_buffers")?,
+      pre_graphics_semaphores: create_semaphores("async_compute.pre_graphics")?,
+      compute_semaphores: create_semaphores("async_compute.compute")?,
+      graphics_queue_family_index: logical_device.graphics_queue_family_index,
+      compute_queue_family_index: logical_device.compute_queue_family_index,
+    })
+  }
+
+  /// Get the command buffers for the render graph.
+  /// param compute_command_buffers: The compute command buffers.
+  /// return: The command buffers of the async compute.
+  pub fn get_graph_command_buffers<'b>(&'b self, compute_command_buffers: &'b hala_gfx::HalaCommandBufferSet) -> RGAsyncCompute<'b> {
+    RGAsyncCompute {
+      pre_graphics: &self.pre_graphics_command_buffers,
+      overlap_graphics: &self.overlap_graphics_command_buffers,
+      join_graphics: &self.join_graphics_command_buffers,
+      compute: compute_command_buffers,
+      graphics_queue_family_index: self.graphics_queue_family_index,
+      compute_queue_family_index: self.compute_queue_family_index,
+    }
+  }
+
+  /// Reset and begin all command buffers of the image.
+  /// param index: The index of the current image.
+  /// param compute_command_buffers: The compute command buffers.
+  /// return: The result.
+  pub fn begin(&self, index: usize, compute_command_buffers: &hala_gfx::HalaCommandBufferSet) -> Result<(), HalaRendererError> {
+    for command_buffers in [
+      &self.pre_graphics_command_buffers,
+      compute_command_buffers,
+      &self.overlap_graphics_command_buffers,
+      &self.join_graphics_command_buffers,
+    ] {
+      command_buffers.reset(index, false)?;
+      command_buffers.begin(index, hala_gfx::HalaCommandBufferUsageFlags::empty())?;
+    }
+
+    Ok(())
+  }
+
+  /// End all command buffers of the image.
+  /// param index: The index of the current image.
+  /// param compute_command_buffers: The compute command buffers.
+  /// return: The result.
+  pub fn end(&self, index: usize, compute_command_buffers: &hala_gfx::HalaCommandBufferSet) -> Result<(), HalaRendererError> {
+    for command_buffers in [
+      &self.pre_graphics_command_buffers,
+      compute_command_buffers,
+      &self.overlap_graphics_command_buffers,
+      &self.join_graphics_command_buffers,
+    ] {
+      command_buffers.end(index)?;
+    }
+
+    Ok(())
+  }
+
+  /// Submit the command buffers of the image, the main graphics command buffers are submitted after them.
+  /// param context: The context.
+  /// param index: The index of the current image.
+  /// param compute_command_buffers: The compute command buffers.
+  /// return: The result.
+  pub fn submit(
+    &self,
+    context: &hala_gfx::HalaContext,
+    index: usize,
+    compute_command_buffers: &hala_gfx::HalaCommandBufferSet,
+  ) -> Result<(), HalaRendererError> {
+    let logical_device = context.logical_device.borrow();
+    let pre_graphics_semaphore = &self.pre_graphics_semaphores[index];
+    let compute_semaphore = &self.compute_semaphores[index];
+
+    logical_device.graphics_submit(
+      &self.pre_graphics_command_buffers,
+      index,
+      &[],
+      &[pre_graphics_semaphore],
+    )?;
+    logical_device.compute_submit(
+      compute_command_buffers,
+      index,
+      &[(pre_graphics_semaphore, hala_gfx::HalaPipelineStageFlags2::ALL_COMMANDS)],
+      &[compute_semaphore],
+    )?;
+    logical_device.graphics_submit(
+      &self.overlap_graphics_command_buffers,
+      index,
+      &[],
+      &[],
+    )?;
+    logical_device.graphics_submit(
+      &self.join_graphics_command_buffers,
+      index,
+      &[(compute_semaphore, hala_gfx::HalaPipelineStageFlags2::ALL_COMMANDS)],
+      &[],
+    )?;
+
+    Ok(())
+  }
+
+}
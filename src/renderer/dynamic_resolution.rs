@@ -0,0 +1,127 @@
+/// The dynamic resolution settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DynamicResolutionSettings {
+  pub enabled: bool,
+  // The GPU time budget of a frame in milliseconds.
+  pub gpu_budget: f32,
+  // The range of the render scale relative to the window size.
+  pub min_scale: f32,
+  pub max_scale: f32,
+}
+
+/// The default implementation of the dynamic resolution settings.
+impl Default for DynamicResolutionSettings {
+
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      gpu_budget: 8.0,
+      min_scale: 0.5,
+      max_scale: 1.0,
+    }
+  }
+
+}
+
+/// The controller choosing the render scale by the measured GPU time.
+/// The render targets are allocated at the maximum scale and only the rendered viewport follows the scale,
+/// the scale still moves in steps and waits some frames between the changes to let the timings settle.
+#[derive(Debug, Clone)]
+pub(crate) struct DynamicResolution {
+  pub settings: DynamicResolutionSettings,
+  scale: f32,
+  // The exponential moving average of the GPU time in milliseconds.
+  smoothed_gpu_time: Option<f32>,
+  // The number of frames to wait before the next change.
+  cooldown: u32,
+}
+
+/// The implementation of the dynamic resolution controller.
+impl DynamicResolution {
+
+  /// The granularity of the render scale.
+  const SCALE_STEP: f32 = 0.05;
+  /// The weight of the new GPU time in the moving average.
+  const SMOOTHING: f32 = 0.1;
+  /// The scale only rises when the GPU time is below this ratio of the budget.
+  const HEADROOM: f32 = 0.85;
+  /// The number of frames to wait after a change, the timings of the frames in flight still use the old scale.
+  const COOLDOWN_FRAMES: u32 = 30;
+
+  /// Create a new dynamic resolution controller.
+  /// param settings: The settings.
+  /// return: The controller.
+  pub fn new(settings: DynamicResolutionSettings) -> Self {
+    Self {
+      settings,
+      scale: settings.max_scale,
+      smoothed_gpu_time: None,
+      cooldown: Self::COOLDOWN_FRAMES,
+    }
+  }
+
+  /// Get the current render scale.
+  /// return: The render scale, 1.0 if the dynamic resolution is disabled.
+  pub fn get_scale(&self) -> f32 {
+    if self.settings.enabled {
+      self.scale
+    } else {
+      1.0
+    }
+  }
+
+  /// Get the maximum render scale, which the render targets are allocated at.
+  /// return: The maximum render scale, 1.0 if the dynamic resolution is disabled.
+  pub fn get_max_scale(&self) -> f32 {
+    if self.settings.enabled {
+      self.settings.max_scale
+    } else {
+      1.0
+    }
+  }
+
+  /// Set the settings and restart the control from the maximum scale.
+  /// param settings: The settings.
+  pub fn set_settings(&mut self, settings: DynamicResolutionSettings) {
+    *self = Self::new(settings);
+  }
+
+  /// Update the render scale by the GPU time of the last resolved frame.
+  /// param gpu_time: The GPU time from the frame begin to the frame end in milliseconds.
+  /// return: Whether the render scale is changed.
+  pub fn update(&mut self, gpu_time: f32) -> bool {
+    if !self.settings.enabled {
+      return false;
+    }
+    let smoothed_gpu_time = self.smoothed_gpu_time
+      .map_or(gpu_time, |smoothed| smoothed + (gpu_time - smoothed) * Self::SMOOTHING);
+    self.smoothed_gpu_time = Some(smoothed_gpu_time);
+    if self.cooldown > 0 {
+      self.cooldown -= 1;
+      return false;
+    }
+
+    let budget = self.settings.gpu_budget.max(f32::EPSILON);
+    let scale = if smoothed_gpu_time > budget {
+      // The GPU time is roughly proportional to the number of pixels, drop directly to the estimated scale.
+      let estimated_scale = self.scale * (budget / smoothed_gpu_time).sqrt();
+      (estimated_scale / Self::SCALE_STEP).floor() * Self::SCALE_STEP
+    } else if smoothed_gpu_time < budget * Self::HEADROOM {
+      // Rise one step at a time to avoid the oscillation.
+      self.scale + Self::SCALE_STEP
+    } else {
+      self.scale
+    };
+    let scale = scale.clamp(self.settings.min_scale, self.settings.max_scale);
+    if (scale - self.scale).abs() < Self::SCALE_STEP * 0.5 {
+      return false;
+    }
+
+    log::debug!("The render scale is changed from {:.2} to {:.2}, the GPU time is {:.3}ms.", self.scale, scale, smoothed_gpu_time);
+    self.scale = scale;
+    self.smoothed_gpu_time = None;
+    self.cooldown = Self::COOLDOWN_FRAMES;
+    true
+  }
+
+}
@@ -0,0 +1,545 @@
+use std::rc::Rc;
+
+use hala_renderer::{
+  error::HalaRendererError,
+  renderer::{
+    HalaRendererResources,
+    HalaRendererTrait,
+  },
+};
+
+use super::VisRenderer;
+
+/// The width and height of the first mip level of the prefiltered specular environment.
+const SPECULAR_SIZE: u32 = 256;
+/// The number of the mip levels of the prefiltered specular environment, the roughness is linear in the mip levels.
+/// NOTICE: Keep it same as ENVIRONMENT_SPECULAR_MIP_LEVELS in environment.hlsl.
+const SPECULAR_MIP_LEVELS: u32 = 6;
+/// The width and height of the irradiance environment.
+const IRRADIANCE_SIZE: u32 = 32;
+
+/// The environment of the image based lighting and the skybox.
+/// The specular and irradiance environments are octahedral mapped, prefiltered from the equirectangular image on the GPU.
+pub struct Environment {
+  // The equirectangular HDR image with the full mip chain, drawn by the skybox.
+  // The prefilter passes read its mip levels by the solid angles of their samples.
+  pub(crate) equirect_image: hala_gfx::HalaImage,
+  // The GGX prefiltered radiance, from the roughness 0 at the first mip level to 1 at the last one.
+  pub(crate) specular_image: hala_gfx::HalaImage,
+  // The cosine weighted average radiance around the normal.
+  pub(crate) irradiance_image: hala_gfx::HalaImage,
+  // Repeat horizontally across the seam of the equirectangular image, trilinear filtered between the mip levels.
+  pub(crate) equirect_sampler: hala_gfx::HalaSampler,
+  // Trilinear filtered between the roughness levels of the specular environment.
+  pub(crate) sampler: hala_gfx::HalaSampler,
+  // Whether the images are loaded from an environment map, otherwise they are the black placeholders.
+  pub(crate) is_loaded: bool,
+}
+
+/// The implementation of the environment.
+impl Environment {
+
+  /// Create the black placeholders, so the descriptor sets are valid without an environment map.
+  /// param resources: The renderer resources.
+  /// return: The environment.
+  pub fn new_placeholder(resources: &HalaRendererResources) -> Result<Self, HalaRendererError> {
+    let equirect_image = Self::create_image(resources, hala_gfx::HalaFormat::R32G32B32A32_SFLOAT, 1, 1, "environment_equirect.image")?;
+    Self::upload_image(resources, &equirect_image, &[[0f32; 4]])?;
+    let specular_image = Self::create_image(resources, hala_gfx::HalaFormat::R16G16B16A16_SFLOAT, 1, 1, "environment_specular.image")?;
+    Self::upload_image(resources, &specular_image, &[[0u16; 4]])?;
+    let irradiance_image = Self::create_image(resources, hala_gfx::HalaFormat::R16G16B16A16_SFLOAT, 1, 1, "environment_irradiance.image")?;
+    Self::upload_image(resources, &irradiance_image, &[[0u16; 4]])?;
+    let (equirect_sampler, sampler) = Self::create_samplers(resources, 1)?;
+
+    Ok(Self {
+      equirect_image,
+      specular_image,
+      irradiance_image,
+      equirect_sampler,
+      sampler,
+      is_loaded: false,
+    })
+  }
+
+  /// Load the equirectangular HDR image, the specular and irradiance environments are left to be prefiltered.
+  /// param resources: The renderer resources.
+  /// param path: The path to the HDR image.
+  /// return: The environment.
+  pub fn load(resources: &HalaRendererResources, path: &std::path::Path) -> Result<Self, HalaRendererError> {
+    let hdr_image = match image::open(path) {
+      Ok(hdr_image) => hdr_image.into_rgba32f(),
+      Err(err) => {
+        log::error!("Failed to load the environment map \"{}\": {:?}", path.display(), err);
+        return Err(HalaRendererError::new("Failed to load the environment map.", None));
+      }
+    };
+    let (width, height) = hdr_image.dimensions();
+    let pixels = hdr_image.into_raw();
+
+    // The first mip level is uploaded, the others are downsampled on the GPU before prefiltering.
+    // NOTICE: Keep the format same as the environment downsample program in programs.toml.
+    let equirect_mip_levels = u32::BITS - width.max(height).leading_zeros();
+    let equirect_image = hala_gfx::HalaImage::new_2d_with_seperate_views(
+      Rc::clone(&resources.context.borrow().logical_device),
+      hala_gfx::HalaImageUsageFlags::COLOR_ATTACHMENT | hala_gfx::HalaImageUsageFlags::SAMPLED | hala_gfx::HalaImageUsageFlags::TRANSFER_DST,
+      hala_gfx::HalaFormat::R32G32B32A32_SFLOAT,
+      width,
+      height,
+      equirect_mip_levels,
+      1,
+      hala_gfx::HalaMemoryLocation::GpuOnly,
+      "environment_equirect.image",
+    )?;
+    Self::upload_image(resources, &equirect_image, pixels.as_slice())?;
+
+    let specular_image = hala_gfx::HalaImage::new_2d_with_seperate_views(
+      Rc::clone(&resources.context.borrow().logical_device),
+      hala_gfx::HalaImageUsageFlags::COLOR_ATTACHMENT | hala_gfx::HalaImageUsageFlags::SAMPLED,
+      hala_gfx::HalaFormat::R16G16B16A16_SFLOAT,
+      SPECULAR_SIZE,
+      SPECULAR_SIZE,
+      SPECULAR_MIP_LEVELS,
+      1,
+      hala_gfx::HalaMemoryLocation::GpuOnly,
+      "environment_specular.image",
+    )?;
+    let irradiance_image = Self::create_image(resources, hala_gfx::HalaFormat::R16G16B16A16_SFLOAT, IRRADIANCE_SIZE, IRRADIANCE_SIZE, "environment_irradiance.image")?;
+    let (equirect_sampler, sampler) = Self::create_samplers(resources, equirect_mip_levels)?;
+
+    log::info!("Load the environment map \"{}\"[{} x {}].", path.display(), width, height);
+    Ok(Self {
+      equirect_image,
+      specular_image,
+      irradiance_image,
+      equirect_sampler,
+      sampler,
+      is_loaded: true,
+    })
+  }
+
+  /// Create an image of the environment, rendered by the prefilter passes or uploaded.
+  /// param resources: The renderer resources.
+  /// param format: The format of the image.
+  /// param width: The width of the image.
+  /// param height: The height of the image.
+  /// param debug_name: The debug name of the image.
+  /// return: The image.
+  fn create_image(
+    resources: &HalaRendererResources,
+    format: hala_gfx::HalaFormat,
+    width: u32,
+    height: u32,
+    debug_name: &str,
+  ) -> Result<hala_gfx::HalaImage, HalaRendererError> {
+    let image = hala_gfx::HalaImage::new_2d(
+      Rc::clone(&resources.context.borrow().logical_device),
+      hala_gfx::HalaImageUsageFlags::COLOR_ATTACHMENT | hala_gfx::HalaImageUsageFlags::SAMPLED | hala_gfx::HalaImageUsageFlags::TRANSFER_DST,
+      format,
+      width,
+      height,
+      1,
+      1,
+      hala_gfx::HalaMemoryLocation::GpuOnly,
+      debug_name,
+    )?;
+
+    Ok(image)
+  }
+
+  /// Upload the pixels to the first mip level of the image, the image is left in the shader read layout.
+  /// param resources: The renderer resources.
+  /// param image: The image.
+  /// param pixels: The pixels.
+  /// return: The result.
+  fn upload_image<T: Copy>(
+    resources: &HalaRendererResources,
+    image: &hala_gfx::HalaImage,
+    pixels: &[T],
+  ) -> Result<(), HalaRendererError> {
+    let context = resources.context.borrow();
+
+    // The transfer staging buffer is sized for the scene uploading, so use a dedicated one for the image.
+    let staging_buffer = hala_gfx::HalaBuffer::new(
+      Rc::clone(&context.logical_device),
+      std::mem::size_of_val(pixels) as u64,
+      hala_gfx::HalaBufferUsageFlags::TRANSFER_SRC,
+      hala_gfx::HalaMemoryLocation::CpuToGpu,
+      "environment_staging.buffer",
+    )?;
+    image.update_gpu_memory_with_buffer(
+      pixels,
+      hala_gfx::HalaPipelineStageFlags2::FRAGMENT_SHADER,
+      &staging_buffer,
+      &resources.graphics_command_buffers,
+    )?;
+
+    Ok(())
+  }
+
+  /// Create the samplers of the equirectangular image and the octahedral environments.
+  /// param resources: The renderer resources.
+  /// param equirect_mip_levels: The number of the mip levels of the equirectangular image.
+  /// return: The equirectangular sampler and the environment sampler.
+  fn create_samplers(
+    resources: &HalaRendererResources,
+    equirect_mip_levels: u32,
+  ) -> Result<(hala_gfx::HalaSampler, hala_gfx::HalaSampler), HalaRendererError> {
+    let equirect_sampler = hala_gfx::HalaSampler::new(
+      Rc::clone(&resources.context.borrow().logical_device),
+      (hala_gfx::HalaFilter::LINEAR, hala_gfx::HalaFilter::LINEAR),
+      hala_gfx::HalaSamplerMipmapMode::LINEAR,
+      (hala_gfx::HalaSamplerAddressMode::REPEAT, hala_gfx::HalaSamplerAddressMode::CLAMP_TO_EDGE, hala_gfx::HalaSamplerAddressMode::CLAMP_TO_EDGE),
+      0.0,
+      false,
+      0.0,
+      (0.0, (equirect_mip_levels - 1) as f32),
+      "environment_equirect.sampler",
+    )?;
+
+    let sampler = hala_gfx::HalaSampler::new(
+      Rc::clone(&resources.context.borrow().logical_device),
+      (hala_gfx::HalaFilter::LINEAR, hala_gfx::HalaFilter::LINEAR),
+      hala_gfx::HalaSamplerMipmapMode::LINEAR,
+      (hala_gfx::HalaSamplerAddressMode::CLAMP_TO_EDGE, hala_gfx::HalaSamplerAddressMode::CLAMP_TO_EDGE, hala_gfx::HalaSamplerAddressMode::CLAMP_TO_EDGE),
+      0.0,
+      false,
+      0.0,
+      (0.0, (SPECULAR_MIP_LEVELS - 1) as f32),
+      "environment.sampler",
+    )?;
+
+    Ok((equirect_sampler, sampler))
+  }
+
+}
+
+/// The environment implementation of the visibility renderer.
+impl VisRenderer {
+
+  /// Load the equirectangular HDR environment map, then prefilter the specular and irradiance environments on the GPU.
+  /// The GPU programs must be loaded first.
+  /// param path: The path to the HDR image.
+  /// return: The result.
+  pub fn load_environment<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), HalaRendererError> {
+    if self.graphics_programs.is_empty() {
+      return Err(HalaRendererError::new("The GPU programs must be loaded before the environment map.", None));
+    }
+
+    // The old environment and the command buffers may be still used by the frames in flight.
+    self.wait_idle()?;
+    let environment = Environment::load(&self.resources, path.as_ref())?;
+    unsafe {
+      std::mem::ManuallyDrop::drop(&mut self.environment);
+    }
+    self.environment = std::mem::ManuallyDrop::new(environment);
+
+    let now = std::time::Instant::now();
+    self.prefilter_environment()?;
+    log::info!("Prefilter the environment map used {}ms.", now.elapsed().as_millis());
+
+    self.setup_visibility()?;
+
+    Ok(())
+  }
+
+  /// Set the intensity of the environment lighting and the skybox.
+  /// param intensity: The intensity.
+  pub fn set_environment_intensity(&mut self, intensity: f32) {
+    self.environment_intensity = intensity.max(0.0);
+  }
+
+  /// Get the intensity of the environment lighting and the skybox.
+  /// return: The intensity.
+  pub fn get_environment_intensity(&self) -> f32 {
+    self.environment_intensity
+  }
+
+  /// Get the intensity passed to the shaders, 0 without an environment map for the constant ambient.
+  /// return: The intensity.
+  pub(crate) fn get_shading_environment_intensity(&self) -> f32 {
+    if self.environment.is_loaded {
+      self.environment_intensity
+    } else {
+      0.0
+    }
+  }
+
+  /// Create the descriptor sets of the environment downsample program, each one reads a mip level of the equirectangular image.
+  /// NOTICE: Keep the bindings same as the environment downsample program in programs.toml.
+  /// return: The descriptor sets.
+  fn create_environment_downsample_descriptor_sets(&self) -> Result<Vec<hala_gfx::HalaDescriptorSet>, HalaRendererError> {
+    let logical_device = Rc::clone(&self.resources.context.borrow().logical_device);
+    let environment: &Environment = &self.environment;
+
+    let mut descriptor_sets = Vec::with_capacity(environment.equirect_image.mip_levels as usize);
+    for mip_level in 0..environment.equirect_image.mip_levels - 1 {
+      let descriptor_set = hala_gfx::HalaDescriptorSet::new_static(
+        Rc::clone(&logical_device),
+        Rc::clone(&self.resources.descriptor_pool),
+        hala_gfx::HalaDescriptorSetLayout::new(
+          Rc::clone(&logical_device),
+          &[
+            hala_gfx::HalaDescriptorSetLayoutBinding {
+              binding_index: 0,
+              descriptor_type: hala_gfx::HalaDescriptorType::SAMPLED_IMAGE,
+              descriptor_count: 1,
+              stage_flags: hala_gfx::HalaShaderStageFlags::FRAGMENT,
+              binding_flags: hala_gfx::HalaDescriptorBindingFlags::PARTIALLY_BOUND
+            },
+            hala_gfx::HalaDescriptorSetLayoutBinding {
+              binding_index: 1,
+              descriptor_type: hala_gfx::HalaDescriptorType::SAMPLER,
+              descriptor_count: 1,
+              stage_flags: hala_gfx::HalaShaderStageFlags::FRAGMENT,
+              binding_flags: hala_gfx::HalaDescriptorBindingFlags::PARTIALLY_BOUND
+            },
+          ],
+          &format!("environment_downsample[{}].descriptor_set_layout", mip_level),
+        )?,
+        0,
+        &format!("environment_downsample[{}].descriptor_set", mip_level),
+      )?;
+      descriptor_set.update_sampled_images_with_view(
+        0,
+        0,
+        &[environment.equirect_image.mip_views[mip_level as usize]],
+      );
+      descriptor_set.update_samplers(
+        0,
+        1,
+        &[&environment.equirect_sampler],
+      );
+      descriptor_sets.push(descriptor_set);
+    }
+
+    Ok(descriptor_sets)
+  }
+
+  /// Prefilter the specular and irradiance environments from the equirectangular image.
+  /// The mip chain of the equirectangular image is downsampled first, the samples of the wide lobes read the lower mip levels.
+  /// The commands are recorded to the first graphics command buffer and waited until finished.
+  /// return: The result.
+  fn prefilter_environment(&self) -> Result<(), HalaRendererError> {
+    let downsample_descriptor_sets = self.create_environment_downsample_descriptor_sets()?;
+    let context = self.resources.context.borrow();
+    let command_buffers = &self.resources.graphics_command_buffers;
+    let environment: &Environment = &self.environment;
+    let equirect_image = &environment.equirect_image;
+    let equirect_extent = (equirect_image.extent.width, equirect_image.extent.height);
+
+    let environment_downsample_program = self.graphics_programs.get("environment_downsample")
+      .ok_or(HalaRendererError::new("Failed to find the environment downsample program.", None))?;
+
+    let environment_prefilter_program = self.graphics_programs.get("environment_prefilter")
+      .ok_or(HalaRendererError::new("Failed to find the environment prefilter program.", None))?;
+    let environment_prefilter_descriptor_set = self.graphics_descriptor_sets.get("environment_prefilter")
+      .ok_or(HalaRendererError::new("Failed to find the environment prefilter descriptor set.", None))?;
+    let environment_irradiance_program = self.graphics_programs.get("environment_irradiance")
+      .ok_or(HalaRendererError::new("Failed to find the environment irradiance program.", None))?;
+    let environment_irradiance_descriptor_set = self.graphics_descriptor_sets.get("environment_irradiance")
+      .ok_or(HalaRendererError::new("Failed to find the environment irradiance descriptor set.", None))?;
+    for descriptor_set in [environment_prefilter_descriptor_set, environment_irradiance_descriptor_set] {
+      descriptor_set.update_combined_image_samplers(
+        0,
+        0,
+        &[(&environment.equirect_image, environment.equirect_sampler.as_ref())],
+      );
+    }
+
+    command_buffers.reset(0, false)?;
+    command_buffers.begin(0, hala_gfx::HalaCommandBufferUsageFlags::ONE_TIME_SUBMIT)?;
+
+    // The first mip level is left in the shader read layout by the uploading.
+    // Each smaller one is rendered from the larger one, then read by the next one and the prefilter passes.
+    let set_equirect_barrier = |mip_level: u32, old_layout, new_layout, src_access_mask, dst_access_mask, src_stage_mask, dst_stage_mask| {
+      command_buffers.set_image_barriers(
+        0,
+        &[hala_gfx::HalaImageBarrierInfo {
+          old_layout,
+          new_layout,
+          src_access_mask,
+          dst_access_mask,
+          src_stage_mask,
+          dst_stage_mask,
+          aspect_mask: hala_gfx::HalaImageAspectFlags::COLOR,
+          image: equirect_image.raw,
+          base_mip_level: mip_level,
+          ..Default::default()
+        }],
+      );
+    };
+    for mip_level in 1..equirect_image.mip_levels {
+      let width = (equirect_extent.0 >> mip_level).max(1);
+      let height = (equirect_extent.1 >> mip_level).max(1);
+      let downsample_descriptor_set = &downsample_descriptor_sets[mip_level as usize - 1];
+
+      set_equirect_barrier(
+        mip_level,
+        hala_gfx::HalaImageLayout::UNDEFINED,
+        hala_gfx::HalaImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+        hala_gfx::HalaAccessFlags2::NONE,
+        hala_gfx::HalaAccessFlags2::COLOR_ATTACHMENT_WRITE,
+        hala_gfx::HalaPipelineStageFlags2::TOP_OF_PIPE,
+        hala_gfx::HalaPipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+      );
+      command_buffers.set_viewport(0, 0, &[(0., 0., width as f32, height as f32, 0., 1.)]);
+      command_buffers.set_scissor(0, 0, &[(0, 0, width, height)]);
+      command_buffers.begin_rendering_with_view_ex(
+        0,
+        &[equirect_image.mip_views[mip_level as usize]],
+        None,
+        (0, 0, width, height),
+        &[None],
+        None,
+        None,
+        &[hala_gfx::HalaAttachmentLoadOp::DONT_CARE],
+        hala_gfx::HalaAttachmentLoadOp::DONT_CARE,
+        &[hala_gfx::HalaAttachmentStoreOp::STORE],
+        hala_gfx::HalaAttachmentStoreOp::DONT_CARE,
+      );
+      self.draw_screen_quad(
+        0,
+        command_buffers,
+        environment_downsample_program,
+        Some(downsample_descriptor_set),
+      )?;
+      command_buffers.end_rendering(0);
+      set_equirect_barrier(
+        mip_level,
+        hala_gfx::HalaImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+        hala_gfx::HalaImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        hala_gfx::HalaAccessFlags2::COLOR_ATTACHMENT_WRITE,
+        hala_gfx::HalaAccessFlags2::SHADER_READ,
+        hala_gfx::HalaPipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+        hala_gfx::HalaPipelineStageFlags2::FRAGMENT_SHADER,
+      );
+    }
+
+    // All mip levels are rendered, then sampled by the lighting.
+    let get_barriers = |old_layout, new_layout, src_access_mask, dst_access_mask, src_stage_mask, dst_stage_mask| {
+      [&environment.specular_image, &environment.irradiance_image].iter().flat_map(|image| {
+        (0..image.mip_levels).map(move |mip_level| hala_gfx::HalaImageBarrierInfo {
+          old_layout,
+          new_layout,
+          src_access_mask,
+          dst_access_mask,
+          src_stage_mask,
+          dst_stage_mask,
+          aspect_mask: hala_gfx::HalaImageAspectFlags::COLOR,
+          image: image.raw,
+          base_mip_level: mip_level,
+          ..Default::default()
+        })
+      }).collect::<Vec<_>>()
+    };
+    command_buffers.set_image_barriers(
+      0,
+      &get_barriers(
+        hala_gfx::HalaImageLayout::UNDEFINED,
+        hala_gfx::HalaImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+        hala_gfx::HalaAccessFlags2::NONE,
+        hala_gfx::HalaAccessFlags2::COLOR_ATTACHMENT_WRITE,
+        hala_gfx::HalaPipelineStageFlags2::TOP_OF_PIPE,
+        hala_gfx::HalaPipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+      ),
+    );
+
+    // Each mip level of the specular environment is filtered with its roughness from the equirectangular image.
+    let specular_image = &environment.specular_image;
+    for mip_level in 0..specular_image.mip_levels {
+      let size = (specular_image.extent.width >> mip_level).max(1);
+      let roughness = mip_level as f32 / (specular_image.mip_levels - 1).max(1) as f32;
+      let mut push_constants = Vec::new();
+      push_constants.extend_from_slice(&size.to_le_bytes());
+      push_constants.extend_from_slice(&roughness.to_le_bytes());
+      push_constants.extend_from_slice(&equirect_extent.0.to_le_bytes());
+      push_constants.extend_from_slice(&equirect_extent.1.to_le_bytes());
+
+      command_buffers.set_viewport(0, 0, &[(0., 0., size as f32, size as f32, 0., 1.)]);
+      command_buffers.set_scissor(0, 0, &[(0, 0, size, size)]);
+      command_buffers.begin_rendering_with_view_ex(
+        0,
+        &[specular_image.mip_views[mip_level as usize]],
+        None,
+        (0, 0, size, size),
+        &[None],
+        None,
+        None,
+        &[hala_gfx::HalaAttachmentLoadOp::DONT_CARE],
+        hala_gfx::HalaAttachmentLoadOp::DONT_CARE,
+        &[hala_gfx::HalaAttachmentStoreOp::STORE],
+        hala_gfx::HalaAttachmentStoreOp::DONT_CARE,
+      );
+      environment_prefilter_program.push_constants(
+        0,
+        command_buffers,
+        0,
+        push_constants.as_slice(),
+      );
+      self.draw_screen_quad(
+        0,
+        command_buffers,
+        environment_prefilter_program,
+        Some(environment_prefilter_descriptor_set),
+      )?;
+      command_buffers.end_rendering(0);
+    }
+
+    let irradiance_image = &environment.irradiance_image;
+    let size = irradiance_image.extent.width;
+    command_buffers.set_viewport(0, 0, &[(0., 0., size as f32, size as f32, 0., 1.)]);
+    command_buffers.set_scissor(0, 0, &[(0, 0, size, size)]);
+    command_buffers.begin_rendering_with_ex(
+      0,
+      &[irradiance_image],
+      None,
+      (0, 0, size, size),
+      &[None],
+      None,
+      None,
+      &[hala_gfx::HalaAttachmentLoadOp::DONT_CARE],
+      hala_gfx::HalaAttachmentLoadOp::DONT_CARE,
+      hala_gfx::HalaAttachmentLoadOp::DONT_CARE,
+      &[hala_gfx::HalaAttachmentStoreOp::STORE],
+      hala_gfx::HalaAttachmentStoreOp::DONT_CARE,
+      hala_gfx::HalaAttachmentStoreOp::DONT_CARE,
+    );
+    let mut push_constants = Vec::new();
+    push_constants.extend_from_slice(&size.to_le_bytes());
+    push_constants.extend_from_slice(&equirect_extent.0.to_le_bytes());
+    push_constants.extend_from_slice(&equirect_extent.1.to_le_bytes());
+    environment_irradiance_program.push_constants(
+      0,
+      command_buffers,
+      0,
+      push_constants.as_slice(),
+    );
+    self.draw_screen_quad(
+      0,
+      command_buffers,
+      environment_irradiance_program,
+      Some(environment_irradiance_descriptor_set),
+    )?;
+    command_buffers.end_rendering(0);
+
+    command_buffers.set_image_barriers(
+      0,
+      &get_barriers(
+        hala_gfx::HalaImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+        hala_gfx::HalaImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        hala_gfx::HalaAccessFlags2::COLOR_ATTACHMENT_WRITE,
+        hala_gfx::HalaAccessFlags2::SHADER_READ,
+        hala_gfx::HalaPipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+        hala_gfx::HalaPipelineStageFlags2::FRAGMENT_SHADER,
+      ),
+    );
+
+    command_buffers.end(0)?;
+    context.logical_device.borrow().graphics_submit(command_buffers, 0, &[], &[])?;
+    drop(context);
+    self.wait_idle()?;
+
+    Ok(())
+  }
+
+}
@@ -0,0 +1,468 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::Write;
+
+use hala_renderer::error::HalaRendererError;
+
+/// The GPU passes measured by the profiler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpuPass {
+  Shadow,
+  Culling,
+  DepthReduction,
+  VisibilityBuffer,
+  MaterialDepth,
+  ClearIndirect,
+  Classification,
+  MaterialTile,
+  LightCulling,
+  SSAO,
+  Lighting,
+  DebugDraw,
+  Transparency,
+  TAA,
+  Bloom,
+  Exposure,
+  Tonemap,
+  UI,
+}
+
+/// The implementation of the GPU pass.
+impl GpuPass {
+
+  /// All passes in the order of displaying.
+  pub const ALL: [GpuPass; 18] = [
+    GpuPass::Shadow,
+    GpuPass::Culling,
+    GpuPass::DepthReduction,
+    GpuPass::VisibilityBuffer,
+    GpuPass::MaterialDepth,
+    GpuPass::ClearIndirect,
+    GpuPass::Classification,
+    GpuPass::MaterialTile,
+    GpuPass::LightCulling,
+    GpuPass::SSAO,
+    GpuPass::Lighting,
+    GpuPass::DebugDraw,
+    GpuPass::Transparency,
+    GpuPass::TAA,
+    GpuPass::Bloom,
+    GpuPass::Exposure,
+    GpuPass::Tonemap,
+    GpuPass::UI,
+  ];
+
+  /// Get the name of the pass.
+  /// return: The name.
+  pub fn name(&self) -> &'static str {
+    match self {
+      GpuPass::Shadow => "Shadow",
+      GpuPass::Culling => "Culling",
+      GpuPass::DepthReduction => "Depth Reduction",
+      GpuPass::VisibilityBuffer => "Visibility Buffer",
+      GpuPass::MaterialDepth => "Material Depth",
+      GpuPass::ClearIndirect => "Clear Indirect",
+      GpuPass::Classification => "Classification",
+      GpuPass::MaterialTile => "Material Tile",
+      GpuPass::LightCulling => "Light Culling",
+      GpuPass::SSAO => "SSAO",
+      GpuPass::Lighting => "Lighting",
+      GpuPass::DebugDraw => "Debug Draw",
+      GpuPass::Transparency => "Transparency",
+      GpuPass::TAA => "TAA",
+      GpuPass::Bloom => "Bloom",
+      GpuPass::Exposure => "Exposure",
+      GpuPass::Tonemap => "Tonemap",
+      GpuPass::UI => "UI",
+    }
+  }
+
+}
+
+/// The GPU timings accumulated over a range of frames.
+#[derive(Debug, Clone, Default)]
+pub struct GpuTimingSummary {
+  // The summed timings in milliseconds of each pass.
+  pub pass_timings: [f64; GpuPass::ALL.len()],
+  // The total GPU time in milliseconds of each frame.
+  pub frame_timings: Vec<f32>,
+}
+
+/// The implementation of the GPU timing summary.
+impl GpuTimingSummary {
+
+  /// Get the average timing of the pass per frame in milliseconds.
+  /// param pass: The pass.
+  /// return: The average timing.
+  pub fn get_pass_average(&self, pass: GpuPass) -> f64 {
+    if self.frame_timings.is_empty() {
+      0.0
+    } else {
+      self.pass_timings[pass as usize] / self.frame_timings.len() as f64
+    }
+  }
+
+  /// Get the percentile of the frame timings in milliseconds.
+  /// param percentile: The percentile in [0, 1], 0 is the minimum and 1 is the maximum.
+  /// return: The timing.
+  pub fn get_frame_percentile(&self, percentile: f32) -> f32 {
+    if self.frame_timings.is_empty() {
+      return 0.0;
+    }
+    let mut frame_timings = self.frame_timings.clone();
+    frame_timings.sort_by(|a, b| a.total_cmp(b));
+    let index = ((frame_timings.len() - 1) as f32 * percentile.clamp(0.0, 1.0)).round() as usize;
+    frame_timings[index]
+  }
+
+  /// Get the average frame timing in milliseconds.
+  /// return: The average timing.
+  pub fn get_frame_average(&self) -> f32 {
+    if self.frame_timings.is_empty() {
+      0.0
+    } else {
+      self.frame_timings.iter().sum::<f32>() / self.frame_timings.len() as f32
+    }
+  }
+
+}
+
+/// The recorded timing scope.
+#[derive(Debug, Clone, Copy)]
+struct GpuScope {
+  pass: GpuPass,
+  // The index of the begin query, the end query follows it.
+  query_index: u32,
+}
+
+/// The GPU profiler.
+/// Every scope takes a pair of timestamps, a pass may be measured several times in one frame and its times are summed.
+/// The scopes may nest, a nested scope is counted by its pass.
+/// The total is the time between the frame begin and the frame end timestamps, which also covers the gaps between the scopes.
+/// The results of an image are read back when the image is recorded again, so they are always complete.
+pub struct GpuProfiler {
+  query_pool: hala_gfx::HalaQueryPool,
+  timestamp_period: f64,
+
+  // The recorded scopes of each image.
+  scopes: RefCell<Vec<Vec<GpuScope>>>,
+  // The begun but not ended scopes of each image, None for the scope ignored by the limit.
+  open_scopes: RefCell<Vec<Vec<Option<usize>>>>,
+  // Whether the frame begin and the frame end timestamps of each image are written.
+  is_frame_ended: RefCell<Vec<bool>>,
+
+  // The last frames' timings in milliseconds of each pass, used for averaging and the graph.
+  histories: Vec<VecDeque<f32>>,
+  total_history: VecDeque<f32>,
+  num_of_resolved_frames: u64,
+
+  accumulation: Option<GpuTimingSummary>,
+
+  csv_writer: Option<std::io::BufWriter<std::fs::File>>,
+}
+
+/// The implementation of the GPU profiler.
+impl GpuProfiler {
+
+  /// The maximum number of scopes in one frame.
+  const MAX_SCOPES_PER_FRAME: u32 = 32;
+  /// The number of queries of one frame, the scope pairs followed by the frame begin and the frame end.
+  const QUERIES_PER_FRAME: u32 = Self::MAX_SCOPES_PER_FRAME * 2 + 2;
+  /// The number of frames kept in the histories.
+  pub const HISTORY_LENGTH: usize = 120;
+
+  /// Create a new GPU profiler.
+  /// param context: The context.
+  /// param num_of_images: The number of the frames in flight.
+  /// return: The GPU profiler.
+  pub fn new(context: &hala_gfx::HalaContext, num_of_images: usize) -> Result<Self, HalaRendererError> {
+    let query_pool = hala_gfx::HalaQueryPool::new_timestamp(
+      Rc::clone(&context.logical_device),
+      num_of_images as u32 * Self::QUERIES_PER_FRAME,
+      "gpu_profiler.query_pool",
+    )?;
+
+    Ok(Self {
+      query_pool,
+      timestamp_period: context.physical_device.timestamp_period as f64,
+      scopes: RefCell::new(vec![Vec::new(); num_of_images]),
+      open_scopes: RefCell::new(vec![Vec::new(); num_of_images]),
+      is_frame_ended: RefCell::new(vec![false; num_of_images]),
+      histories: vec![VecDeque::with_capacity(Self::HISTORY_LENGTH); GpuPass::ALL.len()],
+      total_history: VecDeque::with_capacity(Self::HISTORY_LENGTH),
+      num_of_resolved_frames: 0,
+      accumulation: None,
+      csv_writer: None,
+    })
+  }
+
+  /// Reset the queries of the image and write the frame begin timestamp.
+  /// It must be recorded first in the frame.
+  /// param index: The index of the current image.
+  /// param command_buffers: The command buffers.
+  pub fn begin_frame(&self, index: usize, command_buffers: &hala_gfx::HalaCommandBufferSet) {
+    self.scopes.borrow_mut()[index].clear();
+    self.open_scopes.borrow_mut()[index].clear();
+    self.is_frame_ended.borrow_mut()[index] = false;
+    command_buffers.reset_query_pool(
+      index,
+      &self.query_pool,
+      index as u32 * Self::QUERIES_PER_FRAME,
+      Self::QUERIES_PER_FRAME,
+    );
+    command_buffers.write_timestamp(
+      index,
+      hala_gfx::HalaPipelineStageFlags2::TOP_OF_PIPE,
+      &self.query_pool,
+      Self::get_frame_query_index(index),
+    );
+  }
+
+  /// Write the frame end timestamp.
+  /// It must be recorded last in the frame, after all other work of the frame is finished.
+  /// param index: The index of the current image.
+  /// param command_buffers: The command buffers.
+  pub fn end_frame(&self, index: usize, command_buffers: &hala_gfx::HalaCommandBufferSet) {
+    command_buffers.write_timestamp(
+      index,
+      hala_gfx::HalaPipelineStageFlags2::BOTTOM_OF_PIPE,
+      &self.query_pool,
+      Self::get_frame_query_index(index) + 1,
+    );
+    self.is_frame_ended.borrow_mut()[index] = true;
+  }
+
+  /// Begin a timing scope.
+  /// param index: The index of the current image.
+  /// param command_buffers: The command buffers.
+  /// param pass: The measured pass.
+  pub fn begin_scope(&self, index: usize, command_buffers: &hala_gfx::HalaCommandBufferSet, pass: GpuPass) {
+    let mut scopes = self.scopes.borrow_mut();
+    let scopes = &mut scopes[index];
+    let mut open_scopes = self.open_scopes.borrow_mut();
+    let open_scopes = &mut open_scopes[index];
+    if scopes.len() as u32 >= Self::MAX_SCOPES_PER_FRAME {
+      log::warn!("Too many GPU profiler scopes in one frame, \"{}\" is ignored.", pass.name());
+      open_scopes.push(None);
+      return;
+    }
+    let query_index = index as u32 * Self::QUERIES_PER_FRAME + scopes.len() as u32 * 2;
+    open_scopes.push(Some(scopes.len()));
+    scopes.push(GpuScope {
+      pass,
+      query_index,
+    });
+    command_buffers.write_timestamp(
+      index,
+      hala_gfx::HalaPipelineStageFlags2::TOP_OF_PIPE,
+      &self.query_pool,
+      query_index,
+    );
+  }
+
+  /// End the innermost open timing scope.
+  /// The end query is always written, a pass not matching the scope is reported as an error.
+  /// param index: The index of the current image.
+  /// param command_buffers: The command buffers.
+  /// param pass: The measured pass, it should match the innermost open scope.
+  pub fn end_scope(&self, index: usize, command_buffers: &hala_gfx::HalaCommandBufferSet, pass: GpuPass) {
+    let scope_index = match self.open_scopes.borrow_mut()[index].pop() {
+      Some(Some(scope_index)) => scope_index,
+      // The scope is ignored by the limit.
+      Some(None) => return,
+      None => {
+        log::error!("The GPU profiler scope \"{}\" is ended without being begun.", pass.name());
+        return;
+      }
+    };
+
+    let scope = self.scopes.borrow()[index][scope_index];
+    if scope.pass != pass {
+      log::error!(
+        "The GPU profiler scope \"{}\" is ended by \"{}\", the scopes must be ended in the reverse order of beginning.",
+        scope.pass.name(),
+        pass.name(),
+      );
+    }
+    command_buffers.write_timestamp(
+      index,
+      hala_gfx::HalaPipelineStageFlags2::BOTTOM_OF_PIPE,
+      &self.query_pool,
+      scope.query_index + 1,
+    );
+  }
+
+  /// Read back the timings of the last frame rendered with the image.
+  /// It must be called after the image's fence is signaled and before it is recorded again.
+  /// param index: The index of the current image.
+  /// return: The GPU time from the frame begin to the frame end in milliseconds, None if no frame is resolved.
+  pub fn resolve(&mut self, index: usize) -> Result<Option<f32>, HalaRendererError> {
+    let scopes = std::mem::take(&mut self.scopes.borrow_mut()[index]);
+    let open_scopes = std::mem::take(&mut self.open_scopes.borrow_mut()[index]);
+    // The image is not recorded yet.
+    if !std::mem::take(&mut self.is_frame_ended.borrow_mut()[index]) {
+      return Ok(None);
+    }
+    // The end queries of the unterminated scopes are never written, so the timings of the frame can not be read back.
+    let num_of_open_scopes = open_scopes.iter().filter(|scope_index| scope_index.is_some()).count();
+    if num_of_open_scopes > 0 {
+      log::error!("{} GPU profiler scopes are not ended, the timings of the frame are dropped.", num_of_open_scopes);
+      return Ok(None);
+    }
+
+    let mut timings = [0f32; GpuPass::ALL.len()];
+    if !scopes.is_empty() {
+      let first_query = index as u32 * Self::QUERIES_PER_FRAME;
+      let timestamps = self.query_pool.wait(first_query, scopes.len() as u32 * 2)?;
+      for scope in scopes.iter() {
+        let offset = (scope.query_index - first_query) as usize;
+        timings[scope.pass as usize] += self.ticks_to_milliseconds(timestamps[offset], timestamps[offset + 1]);
+      }
+    }
+    let frame_timestamps = self.query_pool.wait(Self::get_frame_query_index(index), 2)?;
+    let total = self.ticks_to_milliseconds(frame_timestamps[0], frame_timestamps[1]);
+
+    for (history, timing) in self.histories.iter_mut().zip(timings.iter()) {
+      if history.len() >= Self::HISTORY_LENGTH {
+        history.pop_front();
+      }
+      history.push_back(*timing);
+    }
+    if self.total_history.len() >= Self::HISTORY_LENGTH {
+      self.total_history.pop_front();
+    }
+    self.total_history.push_back(total);
+
+    if let Some(accumulation) = self.accumulation.as_mut() {
+      for (pass_timing, timing) in accumulation.pass_timings.iter_mut().zip(timings.iter()) {
+        *pass_timing += *timing as f64;
+      }
+      accumulation.frame_timings.push(total);
+    }
+
+    if let Some(writer) = self.csv_writer.as_mut() {
+      let row = timings.iter()
+        .chain(std::iter::once(&total))
+        .map(|timing| format!("{:.4}", timing))
+        .collect::<Vec<_>>()
+        .join(",");
+      writeln!(writer, "{},{}", self.num_of_resolved_frames, row).map_err(|err| {
+        log::error!("Failed to write the GPU timings: {}", err);
+        HalaRendererError::new("Failed to write the GPU timings.", None)
+      })?;
+    }
+    self.num_of_resolved_frames += 1;
+
+    Ok(Some(total))
+  }
+
+  /// Read back the timings of all images.
+  /// It must be called when the device is idle.
+  /// return: The result.
+  pub fn resolve_all(&mut self) -> Result<(), HalaRendererError> {
+    for index in 0..self.scopes.borrow().len() {
+      self.resolve(index)?;
+    }
+
+    Ok(())
+  }
+
+  /// Start accumulating the timings of the following resolved frames.
+  pub fn start_accumulation(&mut self) {
+    self.accumulation = Some(GpuTimingSummary::default());
+  }
+
+  /// Stop accumulating and take the accumulated timings.
+  /// return: The accumulated timings.
+  pub fn take_accumulation(&mut self) -> GpuTimingSummary {
+    self.accumulation.take().unwrap_or_default()
+  }
+
+  /// Get the average timing of the pass in milliseconds over the histories.
+  /// param pass: The pass.
+  /// return: The average timing.
+  pub fn get_average(&self, pass: GpuPass) -> f32 {
+    Self::average(&self.histories[pass as usize])
+  }
+
+  /// Get the average GPU time from the frame begin to the frame end in milliseconds over the histories.
+  /// return: The average timing.
+  pub fn get_total_average(&self) -> f32 {
+    Self::average(&self.total_history)
+  }
+
+  /// Get the history of the total GPU time in milliseconds.
+  /// return: The history.
+  pub fn get_total_history(&self) -> Vec<f32> {
+    self.total_history.iter().copied().collect()
+  }
+
+  /// Start writing the per-frame timings to a CSV file.
+  /// param path: The file path.
+  /// return: The result.
+  pub fn start_csv<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), HalaRendererError> {
+    let path = path.as_ref();
+    let file = std::fs::File::create(path).map_err(|err| {
+      log::error!("Failed to create the GPU timings file \"{}\": {}", path.display(), err);
+      HalaRendererError::new("Failed to create the GPU timings file.", None)
+    })?;
+    let mut writer = std::io::BufWriter::new(file);
+    let header = GpuPass::ALL.iter()
+      .map(|pass| pass.name())
+      .chain(std::iter::once("Total"))
+      .collect::<Vec<_>>()
+      .join(",");
+    writeln!(writer, "Frame,{}", header).map_err(|err| {
+      log::error!("Failed to write the GPU timings: {}", err);
+      HalaRendererError::new("Failed to write the GPU timings.", None)
+    })?;
+    self.csv_writer = Some(writer);
+    log::info!("Start writing the GPU timings to \"{}\".", path.display());
+
+    Ok(())
+  }
+
+  /// Stop writing the per-frame timings.
+  pub fn stop_csv(&mut self) {
+    if let Some(mut writer) = self.csv_writer.take() {
+      if let Err(err) = writer.flush() {
+        log::error!("Failed to flush the GPU timings: {}", err);
+      }
+      log::info!("Stop writing the GPU timings.");
+    }
+  }
+
+  /// Whether the per-frame timings are being written.
+  /// return: True if writing.
+  pub fn is_writing_csv(&self) -> bool {
+    self.csv_writer.is_some()
+  }
+
+  /// Get the index of the frame begin query of the image, the frame end query follows it.
+  /// param index: The index of the image.
+  /// return: The query index.
+  fn get_frame_query_index(index: usize) -> u32 {
+    index as u32 * Self::QUERIES_PER_FRAME + Self::MAX_SCOPES_PER_FRAME * 2
+  }
+
+  /// Convert the difference of two timestamps to milliseconds.
+  /// param begin: The begin timestamp.
+  /// param end: The end timestamp.
+  /// return: The timing in milliseconds.
+  fn ticks_to_milliseconds(&self, begin: u64, end: u64) -> f32 {
+    (end.saturating_sub(begin) as f64 * self.timestamp_period / 1_000_000.0) as f32
+  }
+
+  /// Calculate the average of the history.
+  /// param history: The history.
+  /// return: The average.
+  fn average(history: &VecDeque<f32>) -> f32 {
+    if history.is_empty() {
+      0.0
+    } else {
+      history.iter().sum::<f32>() / history.len() as f32
+    }
+  }
+
+}
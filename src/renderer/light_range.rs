@@ -0,0 +1,51 @@
+use hala_renderer::error::HalaRendererError;
+
+use super::GlobalConstants;
+
+/// Load the ranges of the punctual lights from the glTF file.
+/// NOTICE: The scene light keeps no range of KHR_lights_punctual, so it is read from the glTF document.
+/// The lights are uploaded per light node, the ranges follow the order of the light nodes in the document.
+/// param scene_file: The glTF file of the scene.
+/// return: The range of each light node, none if the light has no range.
+pub fn load_light_ranges<P: AsRef<std::path::Path>>(scene_file: P) -> Result<Vec<Option<f32>>, HalaRendererError> {
+  let document = gltf::Gltf::open(scene_file.as_ref())
+    .map_err(|err| HalaRendererError::new(&format!("Failed to open the glTF file \"{}\": {}", scene_file.as_ref().display(), err), None))?;
+
+  Ok(document.nodes().filter_map(|node| node.light()).map(|light| light.range()).collect())
+}
+
+/// Collect the ranges of the uploaded lights for the light range buffer.
+/// param light_ranges: The range of each light node.
+/// param num_of_lights: The number of the uploaded lights.
+/// return: The range of each light slot, 0 for the light without the range.
+pub(crate) fn collect_light_ranges(light_ranges: &[Option<f32>], num_of_lights: usize) -> Vec<f32> {
+  let mut ranges = vec![0.0f32; GlobalConstants::MAX_LIGHTS as usize];
+  if light_ranges.len() != num_of_lights {
+    // The ranges can not be matched to the lights, all lights fall back to the intensity cutoff.
+    log::warn!("The light ranges count \"{}\" is not equal to the lights count \"{}\", the ranges are ignored.", light_ranges.len(), num_of_lights);
+    return ranges;
+  }
+
+  for (range, light_range) in ranges.iter_mut().zip(light_ranges.iter()) {
+    *range = light_range.filter(|range| *range > 0.0).unwrap_or(0.0);
+  }
+  ranges
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn missing_ranges_fall_back_to_cutoff() {
+    let ranges = collect_light_ranges(&[Some(5.0), None, Some(0.0)], 3);
+    assert_eq!(ranges.len(), GlobalConstants::MAX_LIGHTS as usize);
+    assert_eq!(&ranges[..4], &[5.0, 0.0, 0.0, 0.0]);
+  }
+
+  #[test]
+  fn mismatched_ranges_are_ignored() {
+    let ranges = collect_light_ranges(&[Some(5.0)], 2);
+    assert!(ranges.iter().all(|range| *range == 0.0));
+  }
+}
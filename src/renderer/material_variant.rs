@@ -0,0 +1,154 @@
+use hala_renderer::error::HalaRendererError;
+use hala_renderer::scene::cpu;
+
+use crate::config::GPUProgramsConfig;
+
+/// The draw discards the texels whose base color alpha is below the alpha cutoff.
+pub(crate) const DRAW_FLAG_ALPHA_MASK: u32 = 1;
+/// The draw renders both faces of the triangles.
+pub(crate) const DRAW_FLAG_DOUBLE_SIDED: u32 = 2;
+/// The draw is blended by the forward transparent pass instead of the visibility buffer.
+pub(crate) const DRAW_FLAG_TRANSPARENT: u32 = 4;
+
+/// The number of the material variants, each combination of the draw flags has its own pipelines.
+pub(crate) const NUM_OF_MATERIAL_VARIANTS: u32 = 4;
+
+/// The programs rendering the visibility buffer, each one is derived into the material variants.
+pub(crate) const VISIBILITY_PROGRAMS: [&str; 4] = ["pre_culling", "visibility_buffer", "one_pass_culling", "no_culling_visibility_buffer"];
+
+/// The transparent programs, each one is derived into the double-sided variant.
+pub(crate) const TRANSPARENT_PROGRAMS: [&str; 2] = ["transparent", "transparent_oit"];
+
+/// The fragment shader testing the base color alpha of the masked variants.
+const MASKED_FRAGMENT_SHADER_FILE_PATH: &str = "visibility_buffer_masked.frag.spv";
+
+/// The texture index of the draw without the occlusion map.
+/// NOTICE: Keep it same as INVALID_INDEX in defines.hlsl.
+const INVALID_TEXTURE_INDEX: u32 = u32::MAX;
+
+/// The material flags of a draw, indexed by the draw index of the meshlets.
+/// It also carries the material parameters which are not in the material buffer of the scene.
+#[repr(C, align(4))]
+#[derive(Debug, Clone, Copy)]
+pub struct DrawMaterialFlags {
+  // The DRAW_FLAG_* bits, the lower bits are the material variant of the opaque draw.
+  pub flags: u32,
+  // The alpha cutoff of the masked draw.
+  pub alpha_cutoff: f32,
+  // The texture index of the occlusion map, whose R channel is the baked occlusion as glTF.
+  pub occlusion_map_index: u32,
+  // The strength scaling the occlusion from the map.
+  pub occlusion_strength: f32,
+}
+
+/// The default implementation of the draw material flags.
+impl Default for DrawMaterialFlags {
+
+  fn default() -> Self {
+    Self {
+      flags: 0,
+      alpha_cutoff: 0.0,
+      occlusion_map_index: INVALID_TEXTURE_INDEX,
+      occlusion_strength: 1.0,
+    }
+  }
+
+}
+
+/// Collect the material flags of the draws, in the order of the draw indices.
+/// param scene: The scene in the CPU.
+/// return: The material flags of the draws.
+pub(crate) fn collect_draw_material_flags(scene: &cpu::HalaScene) -> Vec<DrawMaterialFlags> {
+  let mut draw_material_flags = Vec::new();
+  for mesh in scene.meshes.iter() {
+    for primitive in mesh.primitives.iter() {
+      let flags = match scene.materials.get(primitive.material_index as usize) {
+        Some(material) => {
+          let mut flags = 0;
+          if material.alpha_mode == cpu::HalaAlphaMode::Mask {
+            flags |= DRAW_FLAG_ALPHA_MASK;
+          }
+          if material.double_sided {
+            flags |= DRAW_FLAG_DOUBLE_SIDED;
+          }
+          if material.alpha_mode == cpu::HalaAlphaMode::Blend {
+            flags |= DRAW_FLAG_TRANSPARENT;
+          }
+          DrawMaterialFlags {
+            flags,
+            alpha_cutoff: material.alpha_cutoff,
+            occlusion_map_index: material.occlusion_map_index,
+            occlusion_strength: material.occlusion_strength,
+          }
+        },
+        None => DrawMaterialFlags::default(),
+      };
+      draw_material_flags.push(flags);
+    }
+  }
+
+  draw_material_flags
+}
+
+/// Get the material variants used by the opaque draws.
+/// param draw_material_flags: The material flags of the draws.
+/// return: The bit mask of the used variants.
+pub(crate) fn get_used_material_variants(draw_material_flags: &[DrawMaterialFlags]) -> u32 {
+  draw_material_flags.iter()
+    .filter(|flags| flags.flags & DRAW_FLAG_TRANSPARENT == 0)
+    .fold(0, |mask, flags| mask | (1 << (flags.flags & (NUM_OF_MATERIAL_VARIANTS - 1))))
+}
+
+/// Get the name of the program rendering the material variant.
+/// param name: The name of the opaque single-sided program.
+/// param material_variant: The material variant.
+/// return: The program name.
+pub(crate) fn get_variant_program_name(name: &str, material_variant: u32) -> String {
+  let is_masked = material_variant & DRAW_FLAG_ALPHA_MASK != 0;
+  let is_double_sided = material_variant & DRAW_FLAG_DOUBLE_SIDED != 0;
+  match (is_masked, is_double_sided) {
+    (false, false) => name.to_string(),
+    (true, false) => format!("{}.masked", name),
+    (false, true) => format!("{}.double_sided", name),
+    (true, true) => format!("{}.masked_double_sided", name),
+  }
+}
+
+/// Derive the material variants of the visibility programs and the transparent programs.
+/// The masked variants test the alpha in the fragment shader, the double-sided variants disable the face culling.
+/// param config: The GPU programs configure.
+/// return: The result.
+pub(crate) fn add_material_variant_programs(config: &mut GPUProgramsConfig) -> Result<(), HalaRendererError> {
+  for name in VISIBILITY_PROGRAMS.iter() {
+    let desc = config.graphics_programs.get(*name)
+      .ok_or_else(|| {
+        log::error!("Failed to find the \"{}\" program configure.", name);
+        HalaRendererError::new("Failed to find the visibility program configure.", None)
+      })?
+      .clone();
+    for material_variant in 1..NUM_OF_MATERIAL_VARIANTS {
+      let mut variant_desc = desc.clone();
+      if material_variant & DRAW_FLAG_ALPHA_MASK != 0 {
+        variant_desc.fragment_shader_file_path = MASKED_FRAGMENT_SHADER_FILE_PATH.to_string();
+      }
+      if material_variant & DRAW_FLAG_DOUBLE_SIDED != 0 {
+        variant_desc.rasterizer_info.cull_mode = hala_gfx::HalaCullModeFlags::NONE;
+      }
+      config.graphics_programs.insert(get_variant_program_name(name, material_variant), variant_desc);
+    }
+  }
+
+  // The transparent draws blend the alpha, so they have no masked variant.
+  for name in TRANSPARENT_PROGRAMS.iter() {
+    let mut variant_desc = config.graphics_programs.get(*name)
+      .ok_or_else(|| {
+        log::error!("Failed to find the \"{}\" program configure.", name);
+        HalaRendererError::new("Failed to find the transparent program configure.", None)
+      })?
+      .clone();
+    variant_desc.rasterizer_info.cull_mode = hala_gfx::HalaCullModeFlags::NONE;
+    config.graphics_programs.insert(get_variant_program_name(name, DRAW_FLAG_DOUBLE_SIDED), variant_desc);
+  }
+
+  Ok(())
+}
@@ -3,6 +3,36 @@ mod renderer_impl;
 mod renderer_setup;
 mod renderer_pass;
 mod renderer_debug;
+mod renderer_capture;
+mod gpu_profiler;
+mod render_graph;
+mod transient;
+mod async_compute;
+mod dynamic_resolution;
+mod shadow;
+mod tangent;
+mod light_range;
+mod material_variant;
+mod transparency;
+mod environment;
+mod tonemap;
+mod taa;
+mod ssao;
+mod post_process;
+mod picking;
+
+pub use gpu_profiler::*;
+pub use dynamic_resolution::DynamicResolutionSettings;
+pub use shadow::ShadowSettings;
+pub use tonemap::{
+  TonemapOperator,
+  TonemapSettings,
+};
+pub use taa::TaaSettings;
+pub use ssao::SsaoSettings;
+pub use post_process::PostProcessSettings;
+pub use picking::PickedObject;
+pub use light_range::load_light_ranges;
 
 use std::collections::HashMap;
 
@@ -30,11 +60,23 @@ pub struct DebugSettings {
   pub show_material_depth: bool,
   pub show_albedo: bool,
   pub show_normal: bool,
+  pub show_ambient_occlusion: bool,
+  pub show_light_count: bool,
+  pub show_shadow_cascades: bool,
   pub grid_line_width: u32,
   pub disable_culling: bool,
   pub one_pass_culling: bool,
 }
 
+/// The final output of the renderer created without a window, there is no swapchain to take it from.
+#[derive(Debug, Clone, Copy)]
+pub struct HeadlessOutputSettings {
+  // The format of the final images.
+  pub format: hala_gfx::HalaFormat,
+  // The number of the frames recorded ahead of the GPU, each of them has its own final images.
+  pub num_of_frames_in_flight: usize,
+}
+
 /// The global uniform.
 #[repr(C, align(4))]
 #[derive(Debug, Clone, Copy)]
@@ -50,6 +92,19 @@ pub struct GlobalUniform {
 
   // The camera frustum planes.
   pub frustum_planes: [glam::Vec4; 6],
+
+  // The camera position in world space.
+  pub camera_position: glam::Vec4,
+
+  // The sub-pixel jitter of the projection matrix, xy in NDC and zw in UV.
+  pub jitter: glam::Vec4,
+  // The view-projection matrix of the last frame, without the jitter.
+  pub prev_vp_mtx: glam::Mat4,
+
+  // The render size of this frame in xy and of the last frame in zw.
+  pub render_size: glam::Vec4,
+  // The reciprocal of the size of the render targets in xy, they are allocated at the maximum render scale.
+  pub inv_target_size: glam::Vec4,
 }
 
 /// The per-object uniform.
@@ -68,6 +123,8 @@ pub struct ObjectUniform {
   pub it_mv_mtx: glam::Mat4,
   // The model-view-projection matrix.
   pub mvp_mtx: glam::Mat4,
+  // The model-view-projection matrix of the last frame, without the jitter.
+  pub prev_mvp_mtx: glam::Mat4,
 }
 
 /// The global constants.
@@ -81,6 +138,10 @@ impl GlobalConstants {
   pub const CLASSIFY_THREAD_WIDTH: u32 = 16;
   pub const CLASSIFY_NUM_OF_MATERIALS_PER_GROUP: u32 = Self::CLASSIFY_THREAD_WIDTH * Self::CLASSIFY_THREAD_WIDTH;
   pub const CLASSIFY_DEPTH_RANGE: u32 = Self::CLASSIFY_NUM_OF_MATERIALS_PER_GROUP * 32;
+  pub const MAX_HIZ_MIP_LEVELS: u32 = 5;
+  pub const MAX_LIGHTS: u32 = 16;
+  pub const LIGHT_TILE_SIZE: u32 = 32;
+  pub const LIGHT_NUM_OF_SLICES: u32 = 16;
 }
 
 /// The visibility renderer.
@@ -91,12 +152,62 @@ pub struct VisRenderer {
   pub(crate) statistics: HalaRendererStatistics,
 
   pub(crate) debug_settings: DebugSettings,
+  // Use the RGBA8 albedo and material, A2R10G10B10 normal and RGBA16F emissive G-Buffer.
+  pub(crate) use_small_gbuffer: bool,
+  // Run the material classification on the async compute queue.
+  pub(crate) use_async_compute: bool,
+  // Blend the transparent draws by the weighted blended OIT instead of sorting them.
+  pub(crate) use_weighted_blended_oit: bool,
+  // Whether the Hi-Z buffer holds the depth of the last frame, otherwise it is cleared before the culling.
+  pub(crate) is_hiz_valid: bool,
+
+  // The size rendered into the top left of the offscreen targets, scaled from the window size by the dynamic resolution.
+  pub(crate) render_width: u32,
+  pub(crate) render_height: u32,
+  // The size of the offscreen targets, scaled from the window size by the maximum render scale.
+  pub(crate) target_width: u32,
+  pub(crate) target_height: u32,
+  // The render size of the last frame, which the Hi-Z buffer and the TAA history are rendered at.
+  pub(crate) prev_render_size: (u32, u32),
+  pub(crate) dynamic_resolution: dynamic_resolution::DynamicResolution,
+
+  pub(crate) shadow_settings: ShadowSettings,
+  // The scale of the environment lighting and the skybox.
+  pub(crate) environment_intensity: f32,
+  pub(crate) tonemap_settings: TonemapSettings,
+  // The blend weight of the target luminance in the eye adaptation of this frame.
+  pub(crate) exposure_adaptation: f32,
+  // Whether the exposure buffer holds the adapted luminance of the last frame, otherwise the exposure jumps to the target.
+  pub(crate) is_exposure_valid: bool,
+  pub(crate) taa_settings: TaaSettings,
+  // The number of the frames resolved by the temporal anti-aliasing, which selects the jitter and the history image.
+  pub(crate) taa_frame_index: u32,
+  // Whether the history image holds the resolved color of the last frame, otherwise it is dropped.
+  pub(crate) is_taa_history_valid: bool,
+  // The view-projection matrix and the model-view-projection matrices of the meshes of the last frame, without the jitter.
+  pub(crate) prev_vp_mtx: glam::Mat4,
+  pub(crate) prev_mvp_mtxs: Vec<glam::Mat4>,
+  pub(crate) ssao_settings: SsaoSettings,
+  pub(crate) post_process_settings: PostProcessSettings,
+  // The texel in the render size to pick in the next frame.
+  pub(crate) pick_request: Option<(u32, u32)>,
+  // The index of the image whose frame in flight picks, the result is read back when it is finished.
+  pub(crate) pick_image_index: Option<usize>,
+  // The selected object, whose outline is drawn onto the final target.
+  pub(crate) picked_object: Option<picking::PickedObject>,
+  // The index and the direction of the first directional light, which casts the cascaded shadow.
+  pub(crate) shadow_light: Option<(u32, glam::Vec3)>,
+
+  pub(crate) camera_index: usize,
+  pub(crate) camera_v_mtx: glam::Mat4,
+  pub(crate) camera_p_mtx: glam::Mat4,
 
   pub(crate) static_descriptor_set: hala_gfx::HalaDescriptorSet,
   pub(crate) dynamic_descriptor_set: Option<hala_gfx::HalaDescriptorSet>,
   pub(crate) textures_descriptor_set: Option<hala_gfx::HalaDescriptorSet>,
 
   pub(crate) global_uniform_buffer: hala_gfx::HalaBuffer,
+  pub(crate) shadow_uniform_buffer: hala_gfx::HalaBuffer,
   pub(crate) object_uniform_buffers: Vec<Vec<hala_gfx::HalaBuffer>>,
 
   pub(crate) scene_in_gpu: Option<gpu::HalaScene>,
@@ -106,22 +217,100 @@ pub struct VisRenderer {
 
   pub(crate) compute_programs: HashMap<String, HalaComputeProgram>,
   pub(crate) compute_descriptor_sets: HashMap<String, hala_gfx::HalaDescriptorSet>,
-
-  pub(crate) visibility_image: std::mem::ManuallyDrop<hala_gfx::HalaImage>,
-  pub(crate) depth_image: std::mem::ManuallyDrop<hala_gfx::HalaImage>,
-  pub(crate) material_depth_image: std::mem::ManuallyDrop<hala_gfx::HalaImage>,
-  pub(crate) albedo_image: std::mem::ManuallyDrop<hala_gfx::HalaImage>,
-  pub(crate) normal_image: std::mem::ManuallyDrop<hala_gfx::HalaImage>,
+  // The GPU programs configure file, used to recreate the programs depending on the settings.
+  pub(crate) programs_file: Option<std::path::PathBuf>,
+
+  pub(crate) visibility_image: std::mem::ManuallyDrop<std::rc::Rc<hala_gfx::HalaImage>>,
+  pub(crate) depth_image: std::mem::ManuallyDrop<std::rc::Rc<hala_gfx::HalaImage>>,
+  pub(crate) material_depth_image: std::mem::ManuallyDrop<std::rc::Rc<hala_gfx::HalaImage>>,
+  pub(crate) albedo_image: std::mem::ManuallyDrop<std::rc::Rc<hala_gfx::HalaImage>>,
+  pub(crate) normal_image: std::mem::ManuallyDrop<std::rc::Rc<hala_gfx::HalaImage>>,
+  // The metallic, roughness and occlusion.
+  pub(crate) material_image: std::mem::ManuallyDrop<std::rc::Rc<hala_gfx::HalaImage>>,
+  pub(crate) emissive_image: std::mem::ManuallyDrop<std::rc::Rc<hala_gfx::HalaImage>>,
   pub(crate) hiz_image: std::mem::ManuallyDrop<hala_gfx::HalaImage>,
+  // The cascades are placed side by side in the shadow map.
+  pub(crate) shadow_map_image: std::mem::ManuallyDrop<hala_gfx::HalaImage>,
+  // The images of the image based lighting and the skybox, the black placeholders without an environment map.
+  pub(crate) environment: std::mem::ManuallyDrop<environment::Environment>,
 
   pub(crate) pre_culling_flags: Option<hala_gfx::HalaBuffer>,
+  // The alpha mask and double-sided flags of the draws.
+  pub(crate) draw_material_flags_buffer: Option<hala_gfx::HalaBuffer>,
+  // The handedness of the tangent frames, one bit per vertex of each draw.
+  pub(crate) tangent_signs_buffer: Option<hala_gfx::HalaBuffer>,
+  // The glTF range of each light, 0 for the light without the range.
+  pub(crate) light_ranges_buffer: Option<hala_gfx::HalaBuffer>,
+  // The bit mask of the material variants used by the scene, each one is drawn by its own pipeline.
+  pub(crate) material_variants: u32,
+  // The transparent draws, which are excluded from the visibility buffer.
+  pub(crate) transparent_draws: Vec<transparency::TransparentDraw>,
+  // The scene objects of the draws, which resolve the picked draw index.
+  pub(crate) draw_infos: Vec<picking::DrawInfo>,
 
   pub(crate) hiz_descriptor_sets: Vec<hala_gfx::HalaDescriptorSet>,
 
   pub(crate) point_sampler: hala_gfx::HalaSampler,
+  pub(crate) linear_sampler: hala_gfx::HalaSampler,
 
   pub(crate) indirect_draw_buffer: hala_gfx::HalaBuffer,
   pub(crate) tile_index_buffer: std::mem::ManuallyDrop<hala_gfx::HalaBuffer>,
+  // The light masks of the clusters and the farthest view distance of the tiles.
+  pub(crate) light_cluster_buffer: std::mem::ManuallyDrop<hala_gfx::HalaBuffer>,
+  pub(crate) render_statistics_buffer: hala_gfx::HalaBuffer,
+  // The log luminance histogram of the scene color, cleared by the exposure pass after reading.
+  pub(crate) luminance_histogram_buffer: hala_gfx::HalaBuffer,
+  // The adapted average luminance of the automatic exposure.
+  pub(crate) exposure_buffer: hala_gfx::HalaBuffer,
+  // The draw index, the meshlet index, the triangle index and the depth under the cursor, written by the pick pass.
+  pub(crate) pick_buffer: hala_gfx::HalaBuffer,
+
+  // Whether the renderer is created without a window, there is no swapchain image to present.
+  pub(crate) is_headless: bool,
+  // The number of the frames in flight, the swapchain images or the headless frames.
+  pub(crate) num_of_frames: usize,
+  // The formats of the final color and depth targets.
+  pub(crate) output_format: hala_gfx::HalaFormat,
+  pub(crate) output_depth_format: hala_gfx::HalaFormat,
+  // The offscreen final targets of each frame in flight, used instead of the swapchain images if they are not empty.
+  pub(crate) final_images: Vec<hala_gfx::HalaImage>,
+  pub(crate) final_depth_images: Vec<hala_gfx::HalaImage>,
+  // The index of the frame in flight which rendered the final images last.
+  pub(crate) last_final_image_index: usize,
+
+  // The HDR targets of the final view at the render size, tonemapped and upscaled to the final target.
+  pub(crate) scene_color_image: Option<hala_gfx::HalaImage>,
+  pub(crate) scene_depth_image: Option<hala_gfx::HalaImage>,
+
+  // The UV offsets to the last frame written by the material tile pass, and the history of the temporal anti-aliasing.
+  pub(crate) motion_image: Option<hala_gfx::HalaImage>,
+  pub(crate) taa_history_images: Vec<hala_gfx::HalaImage>,
+  // The descriptor sets of the taa and the tonemap programs reading each history image.
+  pub(crate) taa_descriptor_sets: Vec<hala_gfx::HalaDescriptorSet>,
+  pub(crate) taa_tonemap_descriptor_sets: Vec<hala_gfx::HalaDescriptorSet>,
+
+  // The raw and the blurred ambient occlusion, in the half resolution by the settings.
+  pub(crate) ssao_raw_image: Option<hala_gfx::HalaImage>,
+  pub(crate) ssao_image: Option<hala_gfx::HalaImage>,
+
+  // The mip chain of the bloom at the half render size, the first mip level holds the blurred result.
+  pub(crate) bloom_image: Option<hala_gfx::HalaImage>,
+  // The descriptor sets of the bloom programs reading the scene color, each history image and each mip level.
+  pub(crate) bloom_source_descriptor_sets: Vec<hala_gfx::HalaDescriptorSet>,
+  pub(crate) bloom_descriptor_sets: Vec<hala_gfx::HalaDescriptorSet>,
+
+  // The weighted colors with the revealage in alpha, and the weights of the weighted blended OIT.
+  pub(crate) oit_accumulation_image: Option<hala_gfx::HalaImage>,
+  pub(crate) oit_weight_image: Option<hala_gfx::HalaImage>,
+
+  pub(crate) gpu_profiler: GpuProfiler,
+
+  pub(crate) async_compute: async_compute::AsyncCompute,
+
+  pub(crate) aliasing_plan: transient::AliasingPlan,
+  // The heap of the transient offscreen targets, None if they have their own memory.
+  // NOTICE: It must be dropped after the offscreen images placed in it.
+  pub(crate) transient_memory: Option<hala_gfx::HalaMemory>,
 
   pub(crate) resources: HalaRendererResources,
 
@@ -131,9 +320,28 @@ pub struct VisRenderer {
 impl Drop for VisRenderer {
 
   fn drop(&mut self) {
+    self.oit_weight_image = None;
+    self.oit_accumulation_image = None;
+    self.bloom_descriptor_sets.clear();
+    self.bloom_source_descriptor_sets.clear();
+    self.bloom_image = None;
+    self.ssao_image = None;
+    self.ssao_raw_image = None;
+    self.taa_tonemap_descriptor_sets.clear();
+    self.taa_descriptor_sets.clear();
+    self.taa_history_images.clear();
+    self.motion_image = None;
+    self.scene_depth_image = None;
+    self.scene_color_image = None;
+    self.final_depth_images.clear();
+    self.final_images.clear();
+
     self.hiz_descriptor_sets.clear();
 
     self.pre_culling_flags = None;
+    self.draw_material_flags_buffer = None;
+    self.tangent_signs_buffer = None;
+    self.light_ranges_buffer = None;
 
     self.scene_in_gpu = None;
 
@@ -151,9 +359,14 @@ impl Drop for VisRenderer {
     HalaShaderCache::get_instance().borrow_mut().clear();
 
     unsafe {
+      std::mem::ManuallyDrop::drop(&mut self.light_cluster_buffer);
       std::mem::ManuallyDrop::drop(&mut self.tile_index_buffer);
 
+      std::mem::ManuallyDrop::drop(&mut self.environment);
+      std::mem::ManuallyDrop::drop(&mut self.shadow_map_image);
       std::mem::ManuallyDrop::drop(&mut self.hiz_image);
+      std::mem::ManuallyDrop::drop(&mut self.emissive_image);
+      std::mem::ManuallyDrop::drop(&mut self.material_image);
       std::mem::ManuallyDrop::drop(&mut self.normal_image);
       std::mem::ManuallyDrop::drop(&mut self.albedo_image);
       std::mem::ManuallyDrop::drop(&mut self.material_depth_image);
@@ -0,0 +1,223 @@
+use std::rc::Rc;
+
+use hala_renderer::{
+  error::HalaRendererError,
+  renderer::HalaRendererResources,
+  scene::cpu,
+};
+
+use super::{
+  material_variant::{
+    DrawMaterialFlags,
+    DRAW_FLAG_ALPHA_MASK,
+    DRAW_FLAG_DOUBLE_SIDED,
+  },
+  VisRenderer,
+};
+
+/// The draw index written by the pick pass when the cursor is over the background.
+/// NOTICE: Keep it same as PICK_NONE in pick.cs_6_8.hlsl.
+const PICK_NONE: u32 = u32::MAX;
+
+/// The width of the selection outline in pixels of the final target.
+const SELECTION_OUTLINE_WIDTH: f32 = 2.0;
+
+/// The scene objects drawn by a draw.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DrawInfo {
+  pub(crate) mesh_index: usize,
+  pub(crate) primitive_index: usize,
+  pub(crate) material_index: u32,
+  // The name of the node instancing the mesh, empty if there is no such node.
+  pub(crate) node_name: String,
+  // The DRAW_FLAG_* bits of the draw.
+  pub(crate) flags: u32,
+}
+
+/// The object picked from the visibility buffer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PickedObject {
+  pub draw_index: u32,
+  pub meshlet_index: u32,
+  pub triangle_index: u32,
+  pub mesh_index: usize,
+  pub primitive_index: usize,
+  pub material_index: u32,
+  // The name of the node instancing the mesh, empty if there is no such node.
+  pub node_name: String,
+  // The reversed-Z depth under the cursor.
+  pub depth: f32,
+  // The DRAW_FLAG_* bits of the draw.
+  flags: u32,
+}
+
+/// The implementation of the picked object.
+impl PickedObject {
+
+  /// Whether the draw discards the texels below the alpha cutoff.
+  /// return: The result.
+  pub fn is_alpha_masked(&self) -> bool {
+    self.flags & DRAW_FLAG_ALPHA_MASK != 0
+  }
+
+  /// Whether the draw renders both faces of the triangles.
+  /// return: The result.
+  pub fn is_double_sided(&self) -> bool {
+    self.flags & DRAW_FLAG_DOUBLE_SIDED != 0
+  }
+
+}
+
+/// Collect the scene objects of the draws, in the order of the draw indices.
+/// param scene: The scene in the CPU.
+/// param draw_material_flags: The material flags of the draws.
+/// return: The scene objects of the draws.
+pub(crate) fn collect_draw_infos(scene: &cpu::HalaScene, draw_material_flags: &[DrawMaterialFlags]) -> Vec<DrawInfo> {
+  let mut draw_infos = Vec::new();
+  let mut draw_index = 0;
+  for (mesh_index, mesh) in scene.meshes.iter().enumerate() {
+    let node_name = scene.nodes.iter()
+      .find(|node| node.mesh_index as usize == mesh_index)
+      .map_or(String::new(), |node| node.name.clone());
+    for (primitive_index, primitive) in mesh.primitives.iter().enumerate() {
+      draw_infos.push(DrawInfo {
+        mesh_index,
+        primitive_index,
+        material_index: primitive.material_index,
+        node_name: node_name.clone(),
+        flags: draw_material_flags.get(draw_index).map_or(0, |flags| flags.flags),
+      });
+      draw_index += 1;
+    }
+  }
+
+  draw_infos
+}
+
+/// The implementation of the visibility renderer.
+impl VisRenderer {
+
+  /// Create the buffer of the pick pass.
+  /// It holds the draw index, the meshlet index, the triangle index and the depth under the cursor.
+  /// param resources: The renderer resources.
+  /// return: The buffer.
+  pub(crate) fn create_pick_buffer(
+    resources: &HalaRendererResources,
+  ) -> Result<hala_gfx::HalaBuffer, HalaRendererError> {
+    let buffer = hala_gfx::HalaBuffer::new(
+      Rc::clone(&resources.context.borrow().logical_device),
+      std::mem::size_of::<u32>() as u64 * 4,
+      hala_gfx::HalaBufferUsageFlags::STORAGE_BUFFER | hala_gfx::HalaBufferUsageFlags::TRANSFER_SRC,
+      hala_gfx::HalaMemoryLocation::GpuOnly,
+      "pick.buffer",
+    )?;
+
+    Ok(buffer)
+  }
+
+  /// Pick the object under the cursor in the next frame, the result is read back when the frame is finished.
+  /// The transparent draws are not in the visibility buffer, so the objects behind them are picked.
+  /// param x: The cursor X in pixels of the window.
+  /// param y: The cursor Y in pixels of the window.
+  pub fn request_pick(&mut self, x: f32, y: f32) {
+    if self.info.width == 0 || self.info.height == 0 {
+      return;
+    }
+
+    // The visibility buffer is at the render size, which is scaled from the window size.
+    let texel_x = (x / self.info.width as f32 * self.render_width as f32).max(0.0) as u32;
+    let texel_y = (y / self.info.height as f32 * self.render_height as f32).max(0.0) as u32;
+    self.pick_request = Some((texel_x.min(self.render_width - 1), texel_y.min(self.render_height - 1)));
+  }
+
+  /// Get the texel picked by this frame.
+  /// Only one pick is in flight, and the triangle and meshlet views do not write the visibility buffer.
+  /// return: The texel in the render size, None if this frame does not pick.
+  pub(crate) fn get_pick_texel(&self) -> Option<(u32, u32)> {
+    if self.pick_image_index.is_some() || self.debug_settings.show_triangle || self.debug_settings.show_meshlet {
+      return None;
+    }
+    self.pick_request
+  }
+
+  /// Read back the picked object if the finished frame of this image picked it, then select it.
+  /// param index: The index of the image, whose last frame is finished.
+  /// return: The result.
+  pub(crate) fn resolve_pick(&mut self, index: usize) -> Result<(), HalaRendererError> {
+    if self.pick_image_index != Some(index) {
+      return Ok(());
+    }
+    self.pick_image_index = None;
+
+    let mut pick_result = [0u32; 4];
+    self.pick_buffer.download_gpu_memory_with_buffer(
+      &mut pick_result,
+      &self.resources.transfer_staging_buffer,
+      &self.resources.compute_command_buffers,
+    )?;
+    let [draw_index, meshlet_index, triangle_index, depth] = pick_result;
+
+    let picked_object = if draw_index == PICK_NONE {
+      None
+    } else {
+      self.draw_infos.get(draw_index as usize).map(|draw_info| PickedObject {
+        draw_index,
+        meshlet_index,
+        triangle_index,
+        mesh_index: draw_info.mesh_index,
+        primitive_index: draw_info.primitive_index,
+        material_index: draw_info.material_index,
+        node_name: draw_info.node_name.clone(),
+        depth: f32::from_bits(depth),
+        flags: draw_info.flags,
+      })
+    };
+    match picked_object.as_ref() {
+      Some(picked_object) => log::debug!("Picked: {:?}", picked_object),
+      None => log::debug!("Picked nothing."),
+    }
+
+    self.set_picked_object(picked_object)
+  }
+
+  /// Select the picked object, whose outline is drawn onto the final target.
+  /// param picked_object: The picked object, None to clear the selection.
+  /// return: The result.
+  fn set_picked_object(&mut self, picked_object: Option<PickedObject>) -> Result<(), HalaRendererError> {
+    let is_graph_changed = picked_object.is_some() != self.picked_object.is_some();
+    self.picked_object = picked_object;
+
+    // The selection outline reads the visibility and the depth at last, which changes the lifetimes of the offscreen targets.
+    if is_graph_changed && self.programs_file.is_some() {
+      self.update_offscreen_aliasing()?;
+    }
+
+    Ok(())
+  }
+
+  /// Get the selected object.
+  /// return: The picked object, None if nothing is selected.
+  pub fn get_picked_object(&self) -> Option<&PickedObject> {
+    self.picked_object.as_ref()
+  }
+
+  /// Clear the selection.
+  /// return: The result.
+  pub fn clear_selection(&mut self) -> Result<(), HalaRendererError> {
+    self.pick_request = None;
+    self.set_picked_object(None)
+  }
+
+  /// Whether the outline of the selected object is drawn this frame, the debug views are drawn without it.
+  /// return: The result.
+  pub(crate) fn is_selection_outline_active(&self) -> bool {
+    self.picked_object.is_some() && self.is_tonemapped_view()
+  }
+
+  /// Get the offset of the selection outline in UV.
+  /// return: The offset.
+  pub(crate) fn get_selection_outline_offset(&self) -> glam::Vec2 {
+    SELECTION_OUTLINE_WIDTH / glam::vec2(self.info.width.max(1) as f32, self.info.height.max(1) as f32)
+  }
+
+}
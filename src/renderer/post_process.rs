@@ -0,0 +1,176 @@
+use std::rc::Rc;
+
+use hala_renderer::{
+  error::HalaRendererError,
+  renderer::HalaRendererResources,
+};
+
+use super::VisRenderer;
+
+/// The max number of the mip levels of the bloom chain, the first one is at the half render size.
+const BLOOM_MAX_MIP_LEVELS: u32 = 6;
+
+/// The post-processing settings, applied to the HDR scene color before the tonemapping.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PostProcessSettings {
+  pub bloom: bool,
+  // The blend weight of the bloom over the scene color.
+  pub bloom_intensity: f32,
+  // The radius of the upsampling filter in texels of each mip level, larger is wider.
+  pub bloom_radius: f32,
+  pub vignette: bool,
+  // The darkening of the screen corners, 1 follows the natural cos^4 falloff.
+  pub vignette_intensity: f32,
+  pub chromatic_aberration: bool,
+  // The offset of the red and blue channels at the screen corners, in percent of the screen size.
+  pub chromatic_aberration_intensity: f32,
+}
+
+/// The default implementation of the post-processing settings.
+impl Default for PostProcessSettings {
+
+  fn default() -> Self {
+    Self {
+      bloom: true,
+      bloom_intensity: 0.04,
+      bloom_radius: 1.0,
+      vignette: true,
+      vignette_intensity: 0.5,
+      chromatic_aberration: false,
+      chromatic_aberration_intensity: 0.5,
+    }
+  }
+
+}
+
+/// The implementation of the visibility renderer.
+impl VisRenderer {
+
+  /// Create the mip chain of the bloom at the half render size.
+  /// The mip chain stops before any side of the mip level becomes zero.
+  /// NOTICE: Keep the format same as the bloom programs in programs.toml.
+  /// param resources: The renderer resources.
+  /// param width: The render width.
+  /// param height: The render height.
+  /// return: The image.
+  pub(crate) fn create_bloom_image(
+    resources: &HalaRendererResources,
+    width: u32,
+    height: u32,
+  ) -> Result<hala_gfx::HalaImage, HalaRendererError> {
+    let (bloom_width, bloom_height) = (((width + 1) / 2).max(1), ((height + 1) / 2).max(1));
+    let mip_levels = (u32::BITS - bloom_width.min(bloom_height).leading_zeros()).min(BLOOM_MAX_MIP_LEVELS);
+
+    let image = hala_gfx::HalaImage::new_2d_with_seperate_views(
+      Rc::clone(&resources.context.borrow().logical_device),
+      hala_gfx::HalaImageUsageFlags::COLOR_ATTACHMENT | hala_gfx::HalaImageUsageFlags::SAMPLED,
+      hala_gfx::HalaFormat::R16G16B16A16_SFLOAT,
+      bloom_width,
+      bloom_height,
+      mip_levels,
+      1,
+      hala_gfx::HalaMemoryLocation::GpuOnly,
+      "bloom.image",
+    )?;
+
+    Ok(image)
+  }
+
+  /// Create the descriptor sets of the bloom programs.
+  /// NOTICE: Keep the bindings same as the bloom programs in programs.toml.
+  /// param resources: The renderer resources.
+  /// param bloom_image: The bloom image.
+  /// param linear_sampler: The linear sampler.
+  /// return: The descriptor sets reading the scene color and each history image of the TAA,
+  /// and the descriptor sets reading each mip level of the bloom image.
+  pub(crate) fn create_bloom_descriptor_sets(
+    resources: &HalaRendererResources,
+    bloom_image: &hala_gfx::HalaImage,
+    linear_sampler: &hala_gfx::HalaSampler,
+  ) -> Result<(Vec<hala_gfx::HalaDescriptorSet>, Vec<hala_gfx::HalaDescriptorSet>), HalaRendererError> {
+    let logical_device = Rc::clone(&resources.context.borrow().logical_device);
+    let create_descriptor_set = |name: &str| -> Result<hala_gfx::HalaDescriptorSet, HalaRendererError> {
+      let descriptor_set = hala_gfx::HalaDescriptorSet::new_static(
+        Rc::clone(&logical_device),
+        Rc::clone(&resources.descriptor_pool),
+        hala_gfx::HalaDescriptorSetLayout::new(
+          Rc::clone(&logical_device),
+          &[
+            hala_gfx::HalaDescriptorSetLayoutBinding {
+              binding_index: 0,
+              descriptor_type: hala_gfx::HalaDescriptorType::SAMPLED_IMAGE,
+              descriptor_count: 1,
+              stage_flags: hala_gfx::HalaShaderStageFlags::FRAGMENT | hala_gfx::HalaShaderStageFlags::VERTEX | hala_gfx::HalaShaderStageFlags::TASK | hala_gfx::HalaShaderStageFlags::MESH,
+              binding_flags: hala_gfx::HalaDescriptorBindingFlags::PARTIALLY_BOUND
+            },
+            hala_gfx::HalaDescriptorSetLayoutBinding {
+              binding_index: 1,
+              descriptor_type: hala_gfx::HalaDescriptorType::SAMPLER,
+              descriptor_count: 1,
+              stage_flags: hala_gfx::HalaShaderStageFlags::FRAGMENT | hala_gfx::HalaShaderStageFlags::VERTEX | hala_gfx::HalaShaderStageFlags::TASK | hala_gfx::HalaShaderStageFlags::MESH,
+              binding_flags: hala_gfx::HalaDescriptorBindingFlags::PARTIALLY_BOUND
+            },
+          ],
+          &format!("{}.descriptor_set_layout", name),
+        )?,
+        0,
+        &format!("{}.descriptor_set", name),
+      )?;
+      descriptor_set.update_samplers(
+        0,
+        1,
+        &[linear_sampler],
+      );
+      Ok(descriptor_set)
+    };
+
+    // The source images are recreated with the render size, so they are bound when setting up the visibility resources.
+    let mut source_descriptor_sets = Vec::with_capacity(3);
+    for index in 0..3 {
+      source_descriptor_sets.push(create_descriptor_set(&format!("bloom_source[{}]", index))?);
+    }
+
+    let mut mip_descriptor_sets = Vec::with_capacity(bloom_image.mip_levels as usize);
+    for mip_level in 0..bloom_image.mip_levels {
+      let descriptor_set = create_descriptor_set(&format!("bloom[{}]", mip_level))?;
+      descriptor_set.update_sampled_images_with_view(
+        0,
+        0,
+        &[bloom_image.mip_views[mip_level as usize]],
+      );
+      mip_descriptor_sets.push(descriptor_set);
+    }
+
+    Ok((source_descriptor_sets, mip_descriptor_sets))
+  }
+
+  /// Set the post-processing settings.
+  /// param settings: The settings.
+  pub fn set_post_process_settings(&mut self, settings: PostProcessSettings) {
+    self.post_process_settings = settings;
+  }
+
+  /// Get the post-processing settings.
+  /// return: The settings.
+  pub fn get_post_process_settings(&self) -> PostProcessSettings {
+    self.post_process_settings
+  }
+
+  /// Whether the bloom is drawn this frame, the debug views are drawn without it.
+  /// return: The result.
+  pub(crate) fn is_bloom_active(&self) -> bool {
+    self.post_process_settings.bloom && self.is_tonemapped_view()
+  }
+
+  /// Get the descriptor set of the bloom reading the color to be tonemapped this frame.
+  /// return: The descriptor set.
+  pub(crate) fn get_bloom_source_descriptor_set(&self) -> Option<&hala_gfx::HalaDescriptorSet> {
+    // The resolved color of the TAA is in the history image written by this frame.
+    if self.is_taa_active() {
+      self.bloom_source_descriptor_sets.get(1 + self.get_taa_history_indices().1)
+    } else {
+      self.bloom_source_descriptor_sets.first()
+    }
+  }
+
+}
@@ -0,0 +1,967 @@
+use hala_renderer::error::HalaRendererError;
+
+use super::{
+  GpuPass,
+  GpuProfiler,
+};
+
+/// The way a pass accesses a resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RGAccess {
+  // Written as a color attachment.
+  ColorAttachment,
+  // Written or tested as a depth attachment.
+  DepthAttachment,
+  // Read as a depth input attachment, a sampled depth image or a read-only depth attachment.
+  DepthRead,
+  // Read as an input attachment or a sampled image.
+  ShaderRead,
+  // Read as a storage buffer or a storage image.
+  StorageRead,
+  // Written as a storage buffer or a storage image.
+  StorageWrite,
+  // Read as the indirect draw arguments.
+  IndirectRead,
+}
+
+/// The implementation of the resource access.
+impl RGAccess {
+
+  /// The stages which may access the resources in shaders.
+  fn shader_stages() -> hala_gfx::HalaPipelineStageFlags2 {
+    hala_gfx::HalaPipelineStageFlags2::TASK_SHADER
+      | hala_gfx::HalaPipelineStageFlags2::MESH_SHADER
+      | hala_gfx::HalaPipelineStageFlags2::VERTEX_SHADER
+      | hala_gfx::HalaPipelineStageFlags2::FRAGMENT_SHADER
+      | hala_gfx::HalaPipelineStageFlags2::COMPUTE_SHADER
+  }
+
+  /// Get the image layout of the access.
+  /// return: The image layout.
+  pub fn layout(&self) -> hala_gfx::HalaImageLayout {
+    match self {
+      RGAccess::ColorAttachment => hala_gfx::HalaImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+      RGAccess::DepthAttachment => hala_gfx::HalaImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+      RGAccess::DepthRead => hala_gfx::HalaImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+      RGAccess::ShaderRead => hala_gfx::HalaImageLayout::SHADER_READ_ONLY_OPTIMAL,
+      RGAccess::StorageRead | RGAccess::StorageWrite => hala_gfx::HalaImageLayout::GENERAL,
+      _ => hala_gfx::HalaImageLayout::UNDEFINED,
+    }
+  }
+
+  /// Get the pipeline stages of the access.
+  /// return: The pipeline stages.
+  pub fn stage_mask(&self) -> hala_gfx::HalaPipelineStageFlags2 {
+    match self {
+      RGAccess::ColorAttachment => hala_gfx::HalaPipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+      RGAccess::DepthAttachment => hala_gfx::HalaPipelineStageFlags2::EARLY_FRAGMENT_TESTS | hala_gfx::HalaPipelineStageFlags2::LATE_FRAGMENT_TESTS,
+      RGAccess::DepthRead => hala_gfx::HalaPipelineStageFlags2::EARLY_FRAGMENT_TESTS
+        | hala_gfx::HalaPipelineStageFlags2::LATE_FRAGMENT_TESTS
+        | hala_gfx::HalaPipelineStageFlags2::FRAGMENT_SHADER
+        | hala_gfx::HalaPipelineStageFlags2::COMPUTE_SHADER,
+      RGAccess::ShaderRead | RGAccess::StorageRead | RGAccess::StorageWrite => Self::shader_stages(),
+      RGAccess::IndirectRead => hala_gfx::HalaPipelineStageFlags2::DRAW_INDIRECT,
+    }
+  }
+
+  /// Get the memory accesses of the access.
+  /// return: The memory accesses.
+  pub fn access_mask(&self) -> hala_gfx::HalaAccessFlags2 {
+    match self {
+      RGAccess::ColorAttachment => hala_gfx::HalaAccessFlags2::COLOR_ATTACHMENT_READ | hala_gfx::HalaAccessFlags2::COLOR_ATTACHMENT_WRITE,
+      RGAccess::DepthAttachment => hala_gfx::HalaAccessFlags2::DEPTH_STENCIL_ATTACHMENT_READ | hala_gfx::HalaAccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE,
+      RGAccess::DepthRead => hala_gfx::HalaAccessFlags2::DEPTH_STENCIL_ATTACHMENT_READ | hala_gfx::HalaAccessFlags2::INPUT_ATTACHMENT_READ | hala_gfx::HalaAccessFlags2::SHADER_READ,
+      RGAccess::ShaderRead => hala_gfx::HalaAccessFlags2::INPUT_ATTACHMENT_READ | hala_gfx::HalaAccessFlags2::SHADER_READ,
+      RGAccess::StorageRead => hala_gfx::HalaAccessFlags2::SHADER_READ,
+      RGAccess::StorageWrite => hala_gfx::HalaAccessFlags2::SHADER_READ | hala_gfx::HalaAccessFlags2::SHADER_WRITE,
+      RGAccess::IndirectRead => hala_gfx::HalaAccessFlags2::INDIRECT_COMMAND_READ,
+    }
+  }
+
+  /// Whether the access writes the resource.
+  /// return: The result.
+  pub fn is_write(&self) -> bool {
+    matches!(self, RGAccess::ColorAttachment | RGAccess::DepthAttachment | RGAccess::StorageWrite)
+  }
+
+}
+
+/// The queue a pass runs on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RGQueue {
+  Graphics,
+  Compute,
+}
+
+/// The command buffer a pass is recorded into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RGSegment {
+  // The graphics passes before the async compute passes.
+  Pre,
+  // The async compute passes.
+  Compute,
+  // The graphics passes running alongside the async compute passes.
+  Overlap,
+  // The graphics passes after the async compute passes joined, or all passes without async compute.
+  Main,
+}
+
+/// The command buffers to run the async compute passes on the compute queue.
+/// The submissions are expected in the order: the pre graphics command buffers signal the compute ones to start,
+/// the overlap graphics command buffers run alongside the compute ones, the join graphics command buffers wait the compute ones,
+/// then the main graphics command buffers.
+pub(crate) struct RGAsyncCompute<'b> {
+  pub pre_graphics: &'b hala_gfx::HalaCommandBufferSet,
+  pub overlap_graphics: &'b hala_gfx::HalaCommandBufferSet,
+  pub join_graphics: &'b hala_gfx::HalaCommandBufferSet,
+  pub compute: &'b hala_gfx::HalaCommandBufferSet,
+  pub graphics_queue_family_index: u32,
+  pub compute_queue_family_index: u32,
+}
+
+/// The handle of a resource in the render graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RGHandle(usize);
+
+/// The resource imported into the render graph.
+enum RGResource<'a> {
+  Image(&'a hala_gfx::HalaImage),
+  Buffer(&'a hala_gfx::HalaBuffer),
+}
+
+/// The tracked state of a resource while executing the render graph.
+struct RGResourceState {
+  // The current image layout.
+  layout: hala_gfx::HalaImageLayout,
+  // The stages and accesses of the last write, empty if the content is not written in this frame.
+  write_stage_mask: hala_gfx::HalaPipelineStageFlags2,
+  write_access_mask: hala_gfx::HalaAccessFlags2,
+  // The stages which already see the last write.
+  read_stage_mask: hala_gfx::HalaPipelineStageFlags2,
+  // The queue owning the resource.
+  queue: RGQueue,
+}
+
+/// The resource usage declared by a pass.
+struct RGUsage {
+  handle: RGHandle,
+  // The access when the pass begins.
+  access: RGAccess,
+  // The access the pass leaves the resource in, differs from the begin access only if the pass transitions it itself.
+  end_access: RGAccess,
+  // Whether the previous content is discarded.
+  is_discard: bool,
+}
+
+/// The pass of the render graph.
+struct RGPass<'a> {
+  name: &'static str,
+  profile: Option<GpuPass>,
+  usages: Vec<RGUsage>,
+  is_output: bool,
+  queue: RGQueue,
+  execute: Box<dyn FnOnce(usize, &hala_gfx::HalaCommandBufferSet) -> Result<(), HalaRendererError> + 'a>,
+}
+
+/// The render graph.
+/// Passes declare the resources they read and write, the graph derives the layouts and barriers between them,
+/// and culls the passes which do not contribute to an output pass or an exported resource.
+/// The declaration order decides what version of a resource a pass sees, the passes are executed in a topological order
+/// of the read after write, write after read and write after write dependencies between them, per backing object and its memory aliases.
+/// Several resources may be backed by the same image or buffer if their lifetimes do not overlap, the barriers are tracked per backing object.
+/// The backing objects placed in overlapping memory keep their own states, the first access of one waits for the accesses of the others.
+pub(crate) struct RenderGraph<'a> {
+  names: Vec<&'static str>,
+  resources: Vec<RGResource<'a>>,
+  // The index of the first resource with the same backing object.
+  physical_indices: Vec<usize>,
+  // The backing objects whose memory overlaps each backing object, indexed by the physical index.
+  memory_aliases: Vec<Vec<usize>>,
+  initial_accesses: Vec<Option<RGAccess>>,
+  exported: Vec<bool>,
+  passes: Vec<RGPass<'a>>,
+}
+
+/// The builder of a render graph pass.
+pub(crate) struct RGPassBuilder<'g, 'a> {
+  graph: &'g mut RenderGraph<'a>,
+  name: &'static str,
+  profile: Option<GpuPass>,
+  usages: Vec<RGUsage>,
+  is_output: bool,
+  queue: RGQueue,
+}
+
+/// The implementation of the render graph pass builder.
+impl<'g, 'a> RGPassBuilder<'g, 'a> {
+
+  /// Read the resource.
+  /// param handle: The resource handle.
+  /// param access: The access.
+  /// return: The builder.
+  pub fn read(mut self, handle: RGHandle, access: RGAccess) -> Self {
+    self.usages.push(RGUsage { handle, access, end_access: access, is_discard: false });
+    self
+  }
+
+  /// Write the resource and discard its previous content.
+  /// param handle: The resource handle.
+  /// param access: The access.
+  /// return: The builder.
+  pub fn write(mut self, handle: RGHandle, access: RGAccess) -> Self {
+    self.usages.push(RGUsage { handle, access, end_access: access, is_discard: true });
+    self
+  }
+
+  /// Write the resource and keep its previous content.
+  /// param handle: The resource handle.
+  /// param access: The access.
+  /// return: The builder.
+  pub fn modify(mut self, handle: RGHandle, access: RGAccess) -> Self {
+    self.usages.push(RGUsage { handle, access, end_access: access, is_discard: false });
+    self
+  }
+
+  /// Write the resource and discard its previous content, the pass transitions it to the end access itself.
+  /// param handle: The resource handle.
+  /// param access: The access when the pass begins.
+  /// param end_access: The access when the pass ends.
+  /// return: The builder.
+  pub fn write_and_transition(mut self, handle: RGHandle, access: RGAccess, end_access: RGAccess) -> Self {
+    self.usages.push(RGUsage { handle, access, end_access, is_discard: true });
+    self
+  }
+
+  /// Mark the pass as an output, it is never culled.
+  /// return: The builder.
+  pub fn as_output(mut self) -> Self {
+    self.is_output = true;
+    self
+  }
+
+  /// Run the pass on the async compute queue if the graph is executed with it.
+  /// The pass must only dispatch and use the shader storage, sampled and indirect accesses.
+  /// return: The builder.
+  pub fn on_async_compute(mut self) -> Self {
+    self.queue = RGQueue::Compute;
+    self
+  }
+
+  /// Add the pass to the graph with its recording function.
+  /// param execute: The function records the pass.
+  pub fn execute<F>(self, execute: F)
+    where F: FnOnce(usize, &hala_gfx::HalaCommandBufferSet) -> Result<(), HalaRendererError> + 'a
+  {
+    self.graph.passes.push(RGPass {
+      name: self.name,
+      profile: self.profile,
+      usages: self.usages,
+      is_output: self.is_output,
+      queue: self.queue,
+      execute: Box::new(execute),
+    });
+  }
+
+}
+
+/// The implementation of the render graph.
+impl<'a> RenderGraph<'a> {
+
+  /// Create a new render graph.
+  /// return: The render graph.
+  pub fn new() -> Self {
+    Self {
+      names: Vec::new(),
+      resources: Vec::new(),
+      physical_indices: Vec::new(),
+      memory_aliases: Vec::new(),
+      initial_accesses: Vec::new(),
+      exported: Vec::new(),
+      passes: Vec::new(),
+    }
+  }
+
+  /// Import an image into the graph.
+  /// param name: The name of the resource.
+  /// param image: The image.
+  /// param initial_access: The access the image was left in by the last frame, None if the content is undefined.
+  /// return: The resource handle.
+  pub fn import_image(&mut self, name: &'static str, image: &'a hala_gfx::HalaImage, initial_access: Option<RGAccess>) -> RGHandle {
+    let physical_index = self.resources.iter()
+      .position(|resource| matches!(resource, RGResource::Image(other) if other.raw == image.raw))
+      .unwrap_or(self.resources.len());
+    self.import(name, RGResource::Image(image), physical_index, initial_access)
+  }
+
+  /// Import a buffer into the graph.
+  /// param name: The name of the resource.
+  /// param buffer: The buffer.
+  /// param initial_access: The access the buffer was left in by the last frame, None if the content is not kept.
+  /// return: The resource handle.
+  pub fn import_buffer(&mut self, name: &'static str, buffer: &'a hala_gfx::HalaBuffer, initial_access: Option<RGAccess>) -> RGHandle {
+    let physical_index = self.resources.iter()
+      .position(|resource| matches!(resource, RGResource::Buffer(other) if other.raw == buffer.raw))
+      .unwrap_or(self.resources.len());
+    self.import(name, RGResource::Buffer(buffer), physical_index, initial_access)
+  }
+
+  /// Import a resource into the graph.
+  /// param name: The name of the resource.
+  /// param resource: The resource.
+  /// param physical_index: The index of the first resource with the same backing object.
+  /// param initial_access: The initial access.
+  /// return: The resource handle.
+  fn import(&mut self, name: &'static str, resource: RGResource<'a>, physical_index: usize, initial_access: Option<RGAccess>) -> RGHandle {
+    self.names.push(name);
+    self.resources.push(resource);
+    self.physical_indices.push(physical_index);
+    self.memory_aliases.push(Vec::new());
+    self.initial_accesses.push(initial_access);
+    self.exported.push(false);
+    RGHandle(self.names.len() - 1)
+  }
+
+  /// Mark the memory of the resource as overlapping the other one.
+  /// The images placed in one heap are different objects, so the graph can not find their aliasing by itself.
+  /// The first access of either resource in the frame waits for the accesses of the other one so far and discards its content.
+  /// A resource may overlap several others, each of them is marked separately.
+  /// param handle: The resource handle.
+  /// param other: The other resource handle.
+  pub fn alias_memory(&mut self, handle: RGHandle, other: RGHandle) {
+    let (physical_index, other_physical_index) = (self.physical_indices[handle.0], self.physical_indices[other.0]);
+    if physical_index == other_physical_index || self.memory_aliases[physical_index].contains(&other_physical_index) {
+      return;
+    }
+    self.memory_aliases[physical_index].push(other_physical_index);
+    self.memory_aliases[other_physical_index].push(physical_index);
+  }
+
+  /// Export the resource, its content is used after the frame so the last writer is never culled.
+  /// param handle: The resource handle.
+  pub fn export(&mut self, handle: RGHandle) {
+    self.exported[handle.0] = true;
+  }
+
+  /// Begin adding a pass.
+  /// param name: The name of the pass.
+  /// param profile: The GPU profiler scope of the pass, None if the pass is not measured or measures itself.
+  /// return: The pass builder.
+  pub fn add_pass<'g>(&'g mut self, name: &'static str, profile: Option<GpuPass>) -> RGPassBuilder<'g, 'a> {
+    RGPassBuilder {
+      graph: self,
+      name,
+      profile,
+      usages: Vec::new(),
+      is_output: false,
+      queue: RGQueue::Graphics,
+    }
+  }
+
+  /// Find the passes which contribute to the outputs.
+  /// return: Whether each pass is alive.
+  fn cull(&self) -> Vec<bool> {
+    let mut is_alive = vec![false; self.passes.len()];
+    // Whether the current version of the resource is needed by a later alive pass.
+    let mut is_needed = self.exported.clone();
+
+    for (pass_index, pass) in self.passes.iter().enumerate().rev() {
+      let is_writing_needed = pass.usages.iter()
+        .any(|usage| usage.access.is_write() && is_needed[usage.handle.0]);
+      if !pass.is_output && !is_writing_needed {
+        continue;
+      }
+      is_alive[pass_index] = true;
+
+      // The discarded resources are produced here, the others are consumed from the earlier passes.
+      for usage in pass.usages.iter() {
+        is_needed[usage.handle.0] = !usage.is_discard;
+      }
+    }
+
+    is_alive
+  }
+
+  /// Sort the alive passes by their dependencies.
+  /// The graphics passes the async compute passes depend on are scheduled first, then the async compute passes,
+  /// then the graphics passes not waiting for them, so more graphics passes overlap with the async compute.
+  /// The ties are broken by the declaration order.
+  /// The output passes may use the resources outside the graph, so they run after all passes declared before them.
+  /// param is_alive: Whether each pass is alive.
+  /// return: The indices of the alive passes in the execution order.
+  fn sort(&self, is_alive: &[bool]) -> Vec<usize> {
+    // The passes each pass depends on.
+    let mut dependencies = vec![Vec::new(); self.passes.len()];
+    // The last writer and the readers since then of each backing object.
+    let mut last_writers: Vec<Option<usize>> = vec![None; self.names.len()];
+    let mut readers = vec![Vec::new(); self.names.len()];
+    for (pass_index, pass) in self.passes.iter().enumerate().filter(|(pass_index, _)| is_alive[*pass_index]) {
+      let pass_dependencies = &mut dependencies[pass_index];
+      if pass.is_output {
+        pass_dependencies.extend((0..pass_index).filter(|other_index| is_alive[*other_index]));
+      }
+      for usage in pass.usages.iter() {
+        // The resources backed by the same object or by overlapping memory must not be reordered across each other.
+        let physical_index = self.physical_indices[usage.handle.0];
+        let memory_aliases = &self.memory_aliases[physical_index];
+        for other_physical_index in std::iter::once(&physical_index).chain(memory_aliases.iter()) {
+          // Read after write, and write after write.
+          if let Some(last_writer) = last_writers[*other_physical_index] {
+            pass_dependencies.push(last_writer);
+          }
+          // Write after read.
+          if usage.access.is_write() {
+            pass_dependencies.extend(readers[*other_physical_index].iter().copied());
+          }
+        }
+        if usage.access.is_write() {
+          readers[physical_index].clear();
+          last_writers[physical_index] = Some(pass_index);
+        } else {
+          readers[physical_index].push(pass_index);
+        }
+      }
+      pass_dependencies.retain(|other_index| *other_index != pass_index);
+    }
+
+    // Whether an async compute pass waits for the pass, or the pass waits for an async compute pass, directly or indirectly.
+    // The dependencies are always declared earlier.
+    let mut is_feeding_compute = vec![false; self.passes.len()];
+    for pass_index in (0..self.passes.len()).rev() {
+      if self.passes[pass_index].queue == RGQueue::Compute || is_feeding_compute[pass_index] {
+        for other_index in dependencies[pass_index].iter() {
+          is_feeding_compute[*other_index] = true;
+        }
+      }
+    }
+    let mut is_waiting_compute = vec![false; self.passes.len()];
+    for pass_index in 0..self.passes.len() {
+      is_waiting_compute[pass_index] = dependencies[pass_index].iter()
+        .any(|other_index| self.passes[*other_index].queue == RGQueue::Compute || is_waiting_compute[*other_index]);
+    }
+    let get_priority = |pass_index: usize| match self.passes[pass_index].queue {
+      RGQueue::Graphics if is_feeding_compute[pass_index] => 0,
+      RGQueue::Compute => 1,
+      RGQueue::Graphics if !is_waiting_compute[pass_index] => 2,
+      RGQueue::Graphics => 3,
+    };
+
+    let mut is_scheduled = vec![false; self.passes.len()];
+    let mut order = Vec::with_capacity(self.passes.len());
+    loop {
+      let next_pass_index = (0..self.passes.len())
+        .filter(|pass_index| is_alive[*pass_index] && !is_scheduled[*pass_index])
+        .filter(|pass_index| dependencies[*pass_index].iter().all(|other_index| is_scheduled[*other_index]))
+        .min_by_key(|pass_index| (get_priority(*pass_index), *pass_index));
+      match next_pass_index {
+        Some(pass_index) => {
+          is_scheduled[pass_index] = true;
+          order.push(pass_index);
+        },
+        None => break,
+      }
+    }
+
+    order
+  }
+
+  /// Get the lifetimes of the resources.
+  /// The lifetime is the range of the alive passes using the resource, in the execution order.
+  /// The imported contents and the exported resources live from the beginning or to the end of the frame.
+  /// With async compute, the async compute passes run alongside the overlapping graphics passes,
+  /// so the resources used by any of them live through all of them.
+  /// param use_async_compute: Whether run the async compute passes on the compute queue.
+  /// return: The name and the lifetime of each resource, None if no alive pass uses it.
+  pub fn get_lifetimes(&self, use_async_compute: bool) -> Result<Vec<(&'static str, Option<(usize, usize)>)>, HalaRendererError> {
+    let is_alive = self.cull();
+    let order = self.sort(&is_alive);
+    let segments = self.get_segments(&order, use_async_compute)?;
+    let num_of_alive_passes = order.len();
+    let mut lifetimes = vec![None; self.names.len()];
+
+    let is_concurrent = |segment: &RGSegment| matches!(segment, RGSegment::Compute | RGSegment::Overlap);
+    let concurrent_range = segments.iter().position(is_concurrent)
+      .zip(segments.iter().rposition(is_concurrent));
+    for (pass_index, (pass, segment)) in order.iter().map(|pass_index| &self.passes[*pass_index]).zip(segments.iter()).enumerate() {
+      let (first, last) = match concurrent_range {
+        Some(range) if is_concurrent(segment) => range,
+        _ => (pass_index, pass_index),
+      };
+      for usage in pass.usages.iter() {
+        let lifetime: &mut Option<(usize, usize)> = &mut lifetimes[usage.handle.0];
+        *lifetime = Some(lifetime.map_or((first, last), |(other_first, other_last)| (other_first.min(first), other_last.max(last))));
+      }
+    }
+    for (handle, lifetime) in lifetimes.iter_mut().enumerate() {
+      if self.initial_accesses[handle].is_some() {
+        *lifetime = Some((0, lifetime.map_or(0, |(_, last)| last)));
+      }
+      if self.exported[handle] {
+        *lifetime = Some((lifetime.map_or(0, |(first, _)| first), num_of_alive_passes));
+      }
+    }
+
+    Ok(self.names.iter().copied().zip(lifetimes).collect())
+  }
+
+  /// Get the image aspect of the image.
+  /// param image: The image.
+  /// return: The image aspect.
+  fn get_aspect_mask(image: &hala_gfx::HalaImage) -> hala_gfx::HalaImageAspectFlags {
+    match image.format {
+      hala_gfx::HalaFormat::D16_UNORM | hala_gfx::HalaFormat::D32_SFLOAT => hala_gfx::HalaImageAspectFlags::DEPTH,
+      hala_gfx::HalaFormat::D16_UNORM_S8_UINT | hala_gfx::HalaFormat::D24_UNORM_S8_UINT | hala_gfx::HalaFormat::D32_SFLOAT_S8_UINT =>
+        hala_gfx::HalaImageAspectFlags::DEPTH | hala_gfx::HalaImageAspectFlags::STENCIL,
+      _ => hala_gfx::HalaImageAspectFlags::COLOR,
+    }
+  }
+
+  /// Get the stages and the memory accesses of the access on the queue.
+  /// The compute queue only supports the compute shader and the indirect stages.
+  /// param access: The access.
+  /// param queue: The queue.
+  /// return: The pipeline stages and the memory accesses.
+  fn get_masks(access: RGAccess, queue: RGQueue) -> (hala_gfx::HalaPipelineStageFlags2, hala_gfx::HalaAccessFlags2) {
+    match queue {
+      RGQueue::Graphics => (access.stage_mask(), access.access_mask()),
+      RGQueue::Compute => (
+        access.stage_mask() & (hala_gfx::HalaPipelineStageFlags2::COMPUTE_SHADER | hala_gfx::HalaPipelineStageFlags2::DRAW_INDIRECT),
+        access.access_mask() & (
+          hala_gfx::HalaAccessFlags2::SHADER_READ | hala_gfx::HalaAccessFlags2::SHADER_WRITE | hala_gfx::HalaAccessFlags2::INDIRECT_COMMAND_READ
+        ),
+      ),
+    }
+  }
+
+  /// Assign the passes to the command buffers.
+  /// Without async compute, or if no async compute pass is alive, all passes are recorded into the main command buffers.
+  /// Otherwise the graphics passes after the first async compute pass overlap with the async compute passes,
+  /// until the first graphics pass using a resource of the async compute passes.
+  /// param order: The indices of the alive passes in the execution order.
+  /// param use_async_compute: Whether run the async compute passes on the compute queue.
+  /// return: The segment of each pass in the execution order.
+  fn get_segments(&self, order: &[usize], use_async_compute: bool) -> Result<Vec<RGSegment>, HalaRendererError> {
+    let mut is_compute_resource = vec![false; self.names.len()];
+    let mut has_compute_pass = false;
+    for pass in order.iter().map(|pass_index| &self.passes[*pass_index]).filter(|pass| pass.queue == RGQueue::Compute) {
+      has_compute_pass = true;
+      // The graphics passes reusing the memory of the async compute resources must also wait for the async compute.
+      for usage in pass.usages.iter() {
+        let physical_index = self.physical_indices[usage.handle.0];
+        is_compute_resource[physical_index] = true;
+        for other_physical_index in self.memory_aliases[physical_index].iter() {
+          is_compute_resource[*other_physical_index] = true;
+        }
+      }
+    }
+    if !use_async_compute || !has_compute_pass {
+      return Ok(vec![RGSegment::Main; order.len()]);
+    }
+
+    let mut segments = Vec::with_capacity(order.len());
+    let mut is_compute_started = false;
+    let mut is_joined = false;
+    for pass in order.iter().map(|pass_index| &self.passes[*pass_index]) {
+      let segment = if pass.queue == RGQueue::Compute {
+        if is_joined {
+          log::error!("The async compute pass \"{}\" depends on the passes waiting for the async compute.", pass.name);
+          return Err(HalaRendererError::new("The async compute passes must not depend on the passes using their results.", None));
+        }
+        is_compute_started = true;
+        RGSegment::Compute
+      } else if !is_compute_started {
+        RGSegment::Pre
+      } else if !is_joined && !pass.usages.iter().any(|usage| is_compute_resource[self.physical_indices[usage.handle.0]]) {
+        RGSegment::Overlap
+      } else {
+        is_joined = true;
+        RGSegment::Main
+      };
+      segments.push(segment);
+    }
+
+    Ok(segments)
+  }
+
+  /// Make the barriers of the resource.
+  /// param resource: The resource.
+  /// param layouts: The old and the new image layouts.
+  /// param src_masks: The source stages and memory accesses.
+  /// param dst_masks: The destination stages and memory accesses.
+  /// param queue_family_indices: The source and the destination queue family indices of the ownership transfer, None if the ownership is kept.
+  /// return: The image barriers and the buffer barriers.
+  fn make_barriers(
+    resource: &RGResource,
+    layouts: (hala_gfx::HalaImageLayout, hala_gfx::HalaImageLayout),
+    src_masks: (hala_gfx::HalaPipelineStageFlags2, hala_gfx::HalaAccessFlags2),
+    dst_masks: (hala_gfx::HalaPipelineStageFlags2, hala_gfx::HalaAccessFlags2),
+    queue_family_indices: Option<(u32, u32)>,
+  ) -> (Vec<hala_gfx::HalaImageBarrierInfo>, Vec<hala_gfx::HalaBufferBarrierInfo>) {
+    match resource {
+      RGResource::Image(image) => {
+        // One barrier per mip level, as the passes may access the mip levels separately.
+        let image_barriers = (0..image.mip_levels).map(|mip_level| {
+          let mut barrier = hala_gfx::HalaImageBarrierInfo {
+            old_layout: layouts.0,
+            new_layout: layouts.1,
+            src_access_mask: src_masks.1,
+            dst_access_mask: dst_masks.1,
+            src_stage_mask: src_masks.0,
+            dst_stage_mask: dst_masks.0,
+            aspect_mask: Self::get_aspect_mask(image),
+            image: image.raw,
+            base_mip_level: mip_level,
+            ..Default::default()
+          };
+          if let Some((src_queue_family_index, dst_queue_family_index)) = queue_family_indices {
+            barrier.src_queue_family_index = src_queue_family_index;
+            barrier.dst_queue_family_index = dst_queue_family_index;
+          }
+          barrier
+        }).collect();
+        (image_barriers, Vec::new())
+      },
+      RGResource::Buffer(buffer) => {
+        let mut barrier = hala_gfx::HalaBufferBarrierInfo {
+          src_stage_mask: src_masks.0,
+          dst_stage_mask: dst_masks.0,
+          src_access_mask: src_masks.1,
+          dst_access_mask: dst_masks.1,
+          buffer: buffer.raw,
+          size: buffer.size,
+          ..Default::default()
+        };
+        if let Some((src_queue_family_index, dst_queue_family_index)) = queue_family_indices {
+          barrier.src_queue_family_index = src_queue_family_index;
+          barrier.dst_queue_family_index = dst_queue_family_index;
+        }
+        (Vec::new(), vec![barrier])
+      },
+    }
+  }
+
+  /// Record the barriers.
+  /// param index: The index of the current image.
+  /// param command_buffers: The command buffers.
+  /// param barriers: The image barriers and the buffer barriers.
+  fn record_barriers(
+    index: usize,
+    command_buffers: &hala_gfx::HalaCommandBufferSet,
+    barriers: (Vec<hala_gfx::HalaImageBarrierInfo>, Vec<hala_gfx::HalaBufferBarrierInfo>),
+  ) {
+    let (image_barriers, buffer_barriers) = barriers;
+    if !buffer_barriers.is_empty() {
+      command_buffers.set_buffer_barriers(index, buffer_barriers.as_slice());
+    }
+    if !image_barriers.is_empty() {
+      command_buffers.set_image_barriers(index, image_barriers.as_slice());
+    }
+  }
+
+  /// Execute the render graph.
+  /// param index: The index of the current image.
+  /// param command_buffers: The main command buffers.
+  /// param async_compute: The command buffers of the async compute, None to run all passes on the main command buffers.
+  /// param profiler: The GPU profiler.
+  /// return: The result.
+  pub fn execute(
+    self,
+    index: usize,
+    command_buffers: &hala_gfx::HalaCommandBufferSet,
+    async_compute: Option<&RGAsyncCompute>,
+    profiler: &GpuProfiler,
+  ) -> Result<(), HalaRendererError> {
+    let is_alive = self.cull();
+    let order = self.sort(&is_alive);
+    let segments = self.get_segments(&order, async_compute.is_some())?;
+    // Only the states of the first resources of the backing objects are used.
+    // The writes of the last frame are made visible to the first accesses of this frame.
+    let mut states = self.initial_accesses.iter()
+      .map(|access| match access {
+        Some(access) if access.is_write() => RGResourceState {
+          layout: access.layout(),
+          write_stage_mask: access.stage_mask(),
+          write_access_mask: access.access_mask(),
+          read_stage_mask: hala_gfx::HalaPipelineStageFlags2::NONE,
+          queue: RGQueue::Graphics,
+        },
+        _ => RGResourceState {
+          layout: access.map_or(hala_gfx::HalaImageLayout::UNDEFINED, |access| access.layout()),
+          write_stage_mask: hala_gfx::HalaPipelineStageFlags2::NONE,
+          write_access_mask: hala_gfx::HalaAccessFlags2::NONE,
+          read_stage_mask: access.map_or(hala_gfx::HalaPipelineStageFlags2::NONE, |access| access.stage_mask()),
+          queue: RGQueue::Graphics,
+        },
+      })
+      .collect::<Vec<_>>();
+    // Whether the backing object is accessed in this frame, its first access waits for the accesses of its memory aliases.
+    let mut is_accessed = vec![false; self.resources.len()];
+
+    let mut passes = self.passes.into_iter().map(Some).collect::<Vec<_>>();
+    for (pass_index, segment) in order.into_iter().zip(segments) {
+      let pass = passes[pass_index].take()
+        .ok_or(HalaRendererError::new("The render graph pass is scheduled twice.", None))?;
+      let (pass_command_buffers, queue) = match (segment, async_compute) {
+        (RGSegment::Pre, Some(async_compute)) => (async_compute.pre_graphics, RGQueue::Graphics),
+        (RGSegment::Compute, Some(async_compute)) => (async_compute.compute, RGQueue::Compute),
+        (RGSegment::Overlap, Some(async_compute)) => (async_compute.overlap_graphics, RGQueue::Graphics),
+        _ => (command_buffers, RGQueue::Graphics),
+      };
+
+      let mut image_barriers = Vec::new();
+      let mut buffer_barriers = Vec::new();
+      for usage in pass.usages.iter() {
+        let physical_index = self.physical_indices[usage.handle.0];
+        // The memory aliases accessed on the other queue are ordered by the semaphores between the submissions.
+        let (alias_stage_mask, alias_access_mask) = if is_accessed[physical_index] {
+          (hala_gfx::HalaPipelineStageFlags2::NONE, hala_gfx::HalaAccessFlags2::NONE)
+        } else {
+          self.memory_aliases[physical_index].iter()
+            .filter(|other_physical_index| is_accessed[**other_physical_index] && states[**other_physical_index].queue == queue)
+            .map(|other_physical_index| &states[*other_physical_index])
+            .fold(
+              (hala_gfx::HalaPipelineStageFlags2::NONE, hala_gfx::HalaAccessFlags2::NONE),
+              |(stage_mask, access_mask), other| (
+                stage_mask | other.write_stage_mask | other.read_stage_mask,
+                access_mask | other.write_access_mask,
+              ),
+            )
+        };
+        let is_memory_reused = !alias_stage_mask.is_empty();
+        is_accessed[physical_index] = true;
+
+        let state = &mut states[physical_index];
+        let resource = &self.resources[usage.handle.0];
+        let (stage_mask, access_mask) = Self::get_masks(usage.access, queue);
+        let layout = match resource {
+          RGResource::Image(_) => usage.access.layout(),
+          RGResource::Buffer(_) => hala_gfx::HalaImageLayout::UNDEFINED,
+        };
+        // The content left in the reused memory is undefined.
+        let old_layout = if usage.is_discard || is_memory_reused { hala_gfx::HalaImageLayout::UNDEFINED } else { state.layout };
+        let mut src_stage_mask = state.write_stage_mask | state.read_stage_mask | alias_stage_mask;
+        let src_access_mask = state.write_access_mask | alias_access_mask;
+        if src_stage_mask.is_empty() {
+          src_stage_mask = hala_gfx::HalaPipelineStageFlags2::TOP_OF_PIPE;
+        }
+
+        if state.queue != queue {
+          // The semaphores between the submissions order the accesses of the two queues.
+          // The ownership is released at the end of the source queue's command buffers before the semaphore,
+          // and acquired by the command buffers waiting the semaphore, only if the content is kept and the queue families differ.
+          let async_compute = async_compute
+            .ok_or(HalaRendererError::new("The resource is used by the async compute without the async compute command buffers.", None))?;
+          let queue_family_index = |queue| match queue {
+            RGQueue::Graphics => async_compute.graphics_queue_family_index,
+            RGQueue::Compute => async_compute.compute_queue_family_index,
+          };
+          let (release_command_buffers, acquire_command_buffers) = match queue {
+            RGQueue::Compute => (async_compute.pre_graphics, async_compute.compute),
+            RGQueue::Graphics => (async_compute.compute, async_compute.join_graphics),
+          };
+          let queue_family_indices = (queue_family_index(state.queue), queue_family_index(queue));
+          let queue_family_indices = if !usage.is_discard && queue_family_indices.0 != queue_family_indices.1 {
+            Self::record_barriers(index, release_command_buffers, Self::make_barriers(
+              resource,
+              (old_layout, layout),
+              (src_stage_mask, src_access_mask),
+              (hala_gfx::HalaPipelineStageFlags2::NONE, hala_gfx::HalaAccessFlags2::NONE),
+              Some(queue_family_indices),
+            ));
+            Some(queue_family_indices)
+          } else {
+            None
+          };
+          Self::record_barriers(index, acquire_command_buffers, Self::make_barriers(
+            resource,
+            (old_layout, layout),
+            (hala_gfx::HalaPipelineStageFlags2::ALL_COMMANDS, hala_gfx::HalaAccessFlags2::NONE),
+            (stage_mask, access_mask),
+            queue_family_indices,
+          ));
+
+          state.queue = queue;
+          state.layout = layout;
+          state.write_stage_mask = stage_mask;
+          state.write_access_mask = if usage.access.is_write() { access_mask } else { hala_gfx::HalaAccessFlags2::NONE };
+          state.read_stage_mask = if usage.access.is_write() { hala_gfx::HalaPipelineStageFlags2::NONE } else { stage_mask };
+          continue;
+        }
+
+        // Writes, layout transitions and reused memory wait for all earlier accesses, reads only wait for the last write.
+        let need_barrier = usage.access.is_write() || is_memory_reused || layout != state.layout || (
+          !state.write_stage_mask.is_empty() && !state.read_stage_mask.contains(stage_mask)
+        );
+        if need_barrier {
+          let (mut images, mut buffers) = Self::make_barriers(
+            resource,
+            (old_layout, layout),
+            (src_stage_mask, src_access_mask),
+            (stage_mask, access_mask),
+            None,
+          );
+          image_barriers.append(&mut images);
+          buffer_barriers.append(&mut buffers);
+        }
+
+        if usage.access.is_write() {
+          state.write_stage_mask = stage_mask;
+          state.write_access_mask = access_mask;
+          state.read_stage_mask = hala_gfx::HalaPipelineStageFlags2::NONE;
+        } else if layout != state.layout {
+          // The later reads in the other stages wait for the layout transition.
+          state.write_stage_mask = stage_mask;
+          state.write_access_mask = hala_gfx::HalaAccessFlags2::NONE;
+          state.read_stage_mask = stage_mask;
+        } else if need_barrier {
+          state.read_stage_mask |= stage_mask;
+        }
+        state.layout = layout;
+      }
+      Self::record_barriers(index, pass_command_buffers, (image_barriers, buffer_barriers));
+
+      if cfg!(debug_assertions) {
+        pass_command_buffers.begin_debug_label(index, pass.name, [1.0, 1.0, 1.0, 1.0]);
+      }
+      if let Some(profile) = pass.profile {
+        profiler.begin_scope(index, pass_command_buffers, profile);
+      }
+      (pass.execute)(index, pass_command_buffers)?;
+      if let Some(profile) = pass.profile {
+        profiler.end_scope(index, pass_command_buffers, profile);
+      }
+      if cfg!(debug_assertions) {
+        pass_command_buffers.end_debug_label(index);
+      }
+
+      // The pass transitioned the resource itself, the end access already sees its writes.
+      for usage in pass.usages.iter().filter(|usage| usage.end_access != usage.access) {
+        let state = &mut states[self.physical_indices[usage.handle.0]];
+        if let RGResource::Image(_) = self.resources[usage.handle.0] {
+          state.layout = usage.end_access.layout();
+        }
+        state.read_stage_mask = Self::get_masks(usage.end_access, queue).0;
+      }
+    }
+
+    Ok(())
+  }
+
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Import a resource without a backing object, only the scheduling of the graph is tested with it.
+  /// param graph: The render graph.
+  /// param name: The name of the resource.
+  /// param backing: The resource sharing the backing object, None for a new backing object.
+  /// return: The resource handle.
+  fn import_virtual(graph: &mut RenderGraph, name: &'static str, backing: Option<RGHandle>) -> RGHandle {
+    let physical_index = backing.map_or(graph.names.len(), |handle| graph.physical_indices[handle.0]);
+    graph.names.push(name);
+    graph.physical_indices.push(physical_index);
+    graph.memory_aliases.push(Vec::new());
+    graph.initial_accesses.push(None);
+    graph.exported.push(false);
+    RGHandle(graph.names.len() - 1)
+  }
+
+  /// Get the names of the passes in the execution order.
+  /// param graph: The render graph.
+  /// return: The pass names.
+  fn get_order(graph: &RenderGraph) -> Vec<&'static str> {
+    let is_alive = graph.cull();
+    graph.sort(&is_alive).into_iter().map(|pass_index| graph.passes[pass_index].name).collect()
+  }
+
+  #[test]
+  fn cull_keeps_only_contributing_passes() {
+    let mut graph = RenderGraph::new();
+    let a = import_virtual(&mut graph, "a", None);
+    let b = import_virtual(&mut graph, "b", None);
+    let c = import_virtual(&mut graph, "c", None);
+    graph.add_pass("overwritten", None).write(a, RGAccess::ColorAttachment).execute(|_, _| Ok(()));
+    graph.add_pass("write_a", None).write(a, RGAccess::ColorAttachment).execute(|_, _| Ok(()));
+    graph.add_pass("read_a", None).read(a, RGAccess::ShaderRead).write(b, RGAccess::ColorAttachment).execute(|_, _| Ok(()));
+    graph.add_pass("unused", None).write(c, RGAccess::ColorAttachment).execute(|_, _| Ok(()));
+    graph.add_pass("output", None).as_output().execute(|_, _| Ok(()));
+    graph.export(b);
+
+    assert_eq!(graph.cull(), vec![false, true, true, false, true]);
+  }
+
+  #[test]
+  fn cull_keeps_writers_of_modified_resources() {
+    let mut graph = RenderGraph::new();
+    let a = import_virtual(&mut graph, "a", None);
+    graph.add_pass("write_a", None).write(a, RGAccess::StorageWrite).execute(|_, _| Ok(()));
+    graph.add_pass("modify_a", None).modify(a, RGAccess::StorageWrite).execute(|_, _| Ok(()));
+    graph.export(a);
+
+    assert_eq!(graph.cull(), vec![true, true]);
+  }
+
+  #[test]
+  fn sort_follows_dependencies_and_schedules_async_compute_early() {
+    let mut graph = RenderGraph::new();
+    let a = import_virtual(&mut graph, "a", None);
+    let b = import_virtual(&mut graph, "b", None);
+    let c = import_virtual(&mut graph, "c", None);
+    graph.add_pass("write_a", None).write(a, RGAccess::StorageWrite).execute(|_, _| Ok(()));
+    graph.add_pass("write_c", None).write(c, RGAccess::ColorAttachment).execute(|_, _| Ok(()));
+    graph.add_pass("compute_b", None).on_async_compute()
+      .read(a, RGAccess::StorageRead)
+      .write(b, RGAccess::StorageWrite)
+      .execute(|_, _| Ok(()));
+    graph.add_pass("output", None).as_output()
+      .read(b, RGAccess::ShaderRead)
+      .read(c, RGAccess::ShaderRead)
+      .execute(|_, _| Ok(()));
+
+    assert_eq!(get_order(&graph), vec!["write_a", "compute_b", "write_c", "output"]);
+  }
+
+  #[test]
+  fn sort_keeps_the_users_of_a_shared_backing_object_in_order() {
+    let mut graph = RenderGraph::new();
+    let first = import_virtual(&mut graph, "first", None);
+    let second = import_virtual(&mut graph, "second", Some(first));
+    let y = import_virtual(&mut graph, "y", None);
+    graph.add_pass("write_first", None).write(first, RGAccess::ColorAttachment).execute(|_, _| Ok(()));
+    graph.add_pass("read_first", None).read(first, RGAccess::ShaderRead).write(y, RGAccess::ColorAttachment).execute(|_, _| Ok(()));
+    // The async compute pass is scheduled early, but it must not overwrite the object before its last reader.
+    graph.add_pass("compute_second", None).on_async_compute().write(second, RGAccess::StorageWrite).execute(|_, _| Ok(()));
+    graph.add_pass("output", None).as_output()
+      .read(y, RGAccess::ShaderRead)
+      .read(second, RGAccess::ShaderRead)
+      .execute(|_, _| Ok(()));
+
+    assert_eq!(get_order(&graph), vec!["write_first", "read_first", "compute_second", "output"]);
+  }
+
+  #[test]
+  fn sort_keeps_the_users_of_overlapping_memory_in_order() {
+    let mut graph = RenderGraph::new();
+    let first = import_virtual(&mut graph, "first", None);
+    let other = import_virtual(&mut graph, "other", None);
+    let second = import_virtual(&mut graph, "second", None);
+    let y = import_virtual(&mut graph, "y", None);
+    graph.alias_memory(second, first);
+    graph.alias_memory(second, other);
+    graph.add_pass("write_first", None).write(first, RGAccess::ColorAttachment).execute(|_, _| Ok(()));
+    graph.add_pass("write_other", None).write(other, RGAccess::ColorAttachment).execute(|_, _| Ok(()));
+    graph.add_pass("read_both", None)
+      .read(first, RGAccess::ShaderRead)
+      .read(other, RGAccess::ShaderRead)
+      .write(y, RGAccess::ColorAttachment)
+      .execute(|_, _| Ok(()));
+    graph.add_pass("compute_second", None).on_async_compute().write(second, RGAccess::StorageWrite).execute(|_, _| Ok(()));
+    graph.add_pass("output", None).as_output()
+      .read(y, RGAccess::ShaderRead)
+      .read(second, RGAccess::ShaderRead)
+      .execute(|_, _| Ok(()));
+
+    assert_eq!(get_order(&graph), vec!["write_first", "write_other", "read_both", "compute_second", "output"]);
+  }
+}
@@ -0,0 +1,211 @@
+use std::rc::Rc;
+
+use hala_renderer::error::HalaRendererError;
+
+use super::VisRenderer;
+
+/// The capture implementation of the visibility renderer.
+/// NOTICE: All captures assume the renderer is idle and the last frame was rendered with the default lighting path.
+impl VisRenderer {
+
+  /// Download the top left of the first mip level of the image to the CPU.
+  /// The offscreen targets are allocated at the maximum render scale, so only the rendered part is kept.
+  /// param image: The image.
+  /// param layout: The current layout of the image.
+  /// param width: The width of the part to keep.
+  /// param height: The height of the part to keep.
+  /// return: The pixels of the part.
+  fn download_image<T: Default + Copy>(
+    &self,
+    image: &hala_gfx::HalaImage,
+    layout: hala_gfx::HalaImageLayout,
+    width: u32,
+    height: u32,
+  ) -> Result<Vec<T>, HalaRendererError> {
+    let context = self.resources.context.borrow();
+
+    let mut pixels = vec![T::default(); image.extent.width as usize * image.extent.height as usize];
+    // The transfer staging buffer is sized for the scene uploading, so use a dedicated one for the image.
+    let staging_buffer = hala_gfx::HalaBuffer::new(
+      Rc::clone(&context.logical_device),
+      std::mem::size_of_val(pixels.as_slice()) as u64,
+      hala_gfx::HalaBufferUsageFlags::TRANSFER_DST,
+      hala_gfx::HalaMemoryLocation::GpuToCpu,
+      "capture_staging.buffer",
+    )?;
+    image.download_gpu_memory_with_buffer(
+      &mut pixels,
+      layout,
+      &staging_buffer,
+      &self.resources.graphics_command_buffers,
+    )?;
+
+    let pixels = pixels.chunks(image.extent.width as usize)
+      .take(height as usize)
+      .flat_map(|row| row[..width as usize].iter().copied())
+      .collect();
+
+    Ok(pixels)
+  }
+
+  /// Download the final image of the last rendered frame to the CPU.
+  /// return: The RGBA8 final image.
+  pub fn download_final_image(&self) -> Result<image::RgbaImage, HalaRendererError> {
+    let final_image = self.final_images.get(self.last_final_image_index)
+      .ok_or(HalaRendererError::new("The offscreen output is not enabled.", None))?;
+    let is_bgra = match final_image.format {
+      hala_gfx::HalaFormat::B8G8R8A8_UNORM | hala_gfx::HalaFormat::B8G8R8A8_SRGB => true,
+      hala_gfx::HalaFormat::R8G8B8A8_UNORM | hala_gfx::HalaFormat::R8G8B8A8_SRGB => false,
+      _ => {
+        log::error!("Unsupported final image format \"{:?}\" for capturing.", final_image.format);
+        return Err(HalaRendererError::new("Unsupported final image format for capturing.", None));
+      }
+    };
+
+    let pixels = self.download_image::<[u8; 4]>(
+      final_image,
+      hala_gfx::HalaImageLayout::SHADER_READ_ONLY_OPTIMAL,
+      self.info.width,
+      self.info.height,
+    )?;
+    let bytes = pixels.iter()
+      .flat_map(|p| if is_bgra { [p[2], p[1], p[0], p[3]] } else { *p })
+      .collect::<Vec<_>>();
+
+    image::RgbaImage::from_raw(self.info.width, self.info.height, bytes)
+      .ok_or(HalaRendererError::new("The size of the pixels does not match the image.", None))
+  }
+
+  /// Save the final image to a PNG file.
+  /// param path: The file path.
+  /// return: The result.
+  pub fn save_final_image<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), HalaRendererError> {
+    let image = self.download_final_image()?;
+
+    Self::save_rgba8(path.as_ref(), image.width(), image.height(), image.into_raw())
+  }
+
+  /// Save the offscreen render targets(albedo, normal, material, visibility and Hi-Z) to the directory.
+  /// The albedo, decoded normal, material and Hi-Z images are saved as EXR files, the visibility IDs are packed into a RGBA8 PNG file.
+  /// param dir: The output directory.
+  /// param prefix: The prefix of the file names.
+  /// return: The result.
+  pub fn save_offscreen_targets<P: AsRef<std::path::Path>>(&self, dir: P, prefix: &str) -> Result<(), HalaRendererError> {
+    let dir = dir.as_ref();
+    let (width, height) = (self.render_width, self.render_height);
+
+    for (name, image) in [
+      ("albedo", self.albedo_image.as_ref()),
+      ("normal", self.normal_image.as_ref()),
+      ("material", self.material_image.as_ref()),
+    ] {
+      let pixels = match image.format {
+        hala_gfx::HalaFormat::R32G32B32A32_SFLOAT => {
+          self.download_image::<[f32; 4]>(image, hala_gfx::HalaImageLayout::SHADER_READ_ONLY_OPTIMAL, width, height)?
+        },
+        hala_gfx::HalaFormat::R8G8B8A8_UNORM => {
+          self.download_image::<[u8; 4]>(image, hala_gfx::HalaImageLayout::SHADER_READ_ONLY_OPTIMAL, width, height)?
+            .iter()
+            .map(|p| p.map(|c| c as f32 / 255.0))
+            .collect()
+        },
+        hala_gfx::HalaFormat::A2R10G10B10_UNORM_PACK32 => {
+          self.download_image::<u32>(image, hala_gfx::HalaImageLayout::SHADER_READ_ONLY_OPTIMAL, width, height)?
+            .iter()
+            .map(|p| [
+              ((p >> 20) & 0x3ff) as f32 / 1023.0,
+              ((p >> 10) & 0x3ff) as f32 / 1023.0,
+              (p & 0x3ff) as f32 / 1023.0,
+              (p >> 30) as f32 / 3.0,
+            ])
+            .collect()
+        },
+        _ => {
+          log::error!("Unsupported {} image format \"{:?}\" for capturing.", name, image.format);
+          return Err(HalaRendererError::new("Unsupported G-Buffer image format for capturing.", None));
+        }
+      };
+      // The normal is octahedral encoded in the G-Buffer, save the decoded one.
+      let pixels = if name == "normal" {
+        pixels.iter().map(|p| Self::decode_octahedral_normal(p[0], p[1])).collect()
+      } else {
+        pixels
+      };
+      Self::save_rgba32f(&dir.join(format!("{}_{}.exr", prefix, name)), width, height, pixels.concat())?;
+    }
+
+    // Pack the 32bits IDs into RGBA8 to keep them lossless.
+    let visibility = self.download_image::<u32>(
+      self.visibility_image.as_ref(),
+      hala_gfx::HalaImageLayout::SHADER_READ_ONLY_OPTIMAL,
+      width,
+      height,
+    )?;
+    let bytes = visibility.iter().flat_map(|id| id.to_le_bytes()).collect::<Vec<_>>();
+    Self::save_rgba8(&dir.join(format!("{}_visibility.png", prefix)), width, height, bytes)?;
+
+    // Only the first mip level of the Hi-Z buffer.
+    let (hiz_width, hiz_height) = (width / 2, height / 2);
+    let hiz = self.download_image::<f32>(
+      self.hiz_image.as_ref(),
+      hala_gfx::HalaImageLayout::SHADER_READ_ONLY_OPTIMAL,
+      hiz_width,
+      hiz_height,
+    )?;
+    let pixels = hiz.iter().flat_map(|d| [*d, *d, *d, 1.0]).collect::<Vec<_>>();
+    Self::save_rgba32f(&dir.join(format!("{}_hiz.exr", prefix)), hiz_width, hiz_height, pixels)?;
+
+    Ok(())
+  }
+
+  /// Decode the octahedral encoded normal to the [0, 1] color.
+  /// param x: The first encoded component in [0, 1].
+  /// param y: The second encoded component in [0, 1].
+  /// return: The normal color.
+  fn decode_octahedral_normal(x: f32, y: f32) -> [f32; 4] {
+    let e = glam::Vec2::new(x, y) * 2.0 - 1.0;
+    let mut n = glam::Vec3::new(e.x, e.y, 1.0 - e.x.abs() - e.y.abs());
+    let t = (-n.z).clamp(0.0, 1.0);
+    n.x -= if n.x >= 0.0 { t } else { -t };
+    n.y -= if n.y >= 0.0 { t } else { -t };
+    let n = n.normalize_or_zero() * 0.5 + 0.5;
+    [n.x, n.y, n.z, 1.0]
+  }
+
+  /// Save the RGBA8 pixels to a PNG file.
+  /// param path: The file path.
+  /// param width: The width of the image.
+  /// param height: The height of the image.
+  /// param bytes: The pixels.
+  /// return: The result.
+  fn save_rgba8(path: &std::path::Path, width: u32, height: u32, bytes: Vec<u8>) -> Result<(), HalaRendererError> {
+    let image = image::RgbaImage::from_raw(width, height, bytes)
+      .ok_or(HalaRendererError::new("The size of the pixels does not match the image.", None))?;
+    image.save(path).map_err(|err| {
+      log::error!("Failed to save the image \"{}\": {}", path.display(), err);
+      HalaRendererError::new("Failed to save the image.", None)
+    })?;
+    log::info!("Saved the image \"{}\".", path.display());
+
+    Ok(())
+  }
+
+  /// Save the RGBA32F pixels to an EXR file.
+  /// param path: The file path.
+  /// param width: The width of the image.
+  /// param height: The height of the image.
+  /// param pixels: The pixels.
+  /// return: The result.
+  fn save_rgba32f(path: &std::path::Path, width: u32, height: u32, pixels: Vec<f32>) -> Result<(), HalaRendererError> {
+    let image = image::Rgba32FImage::from_raw(width, height, pixels)
+      .ok_or(HalaRendererError::new("The size of the pixels does not match the image.", None))?;
+    image.save(path).map_err(|err| {
+      log::error!("Failed to save the image \"{}\": {}", path.display(), err);
+      HalaRendererError::new("Failed to save the image.", None)
+    })?;
+    log::info!("Saved the image \"{}\".", path.display());
+
+    Ok(())
+  }
+
+}
@@ -16,6 +16,7 @@ impl VisRenderer {
     self.debug_settings = debug_view_settings;
 
     self.setup_debug()?;
+    self.update_offscreen_aliasing()?;
 
     Ok(())
   }
@@ -43,9 +44,9 @@ impl VisRenderer {
     Ok(())
   }
 
-  /// Print the culling results to the log.
-  /// return: The result.
-  pub(crate) fn debug_culling_results(&self) -> anyhow::Result<(), HalaRendererError> {
+  /// Get the culling results of the first culling pass of the two pass culling.
+  /// return: The number of culled meshlets and the number of all meshlets.
+  pub(crate) fn get_culling_results(&self) -> anyhow::Result<(u32, u32), HalaRendererError> {
     let scene = self.scene_in_gpu.as_ref()
       .ok_or(HalaRendererError::new("Failed to find the scene in the GPU.", None))?;
     let compute_command_buffers = &self.resources.compute_command_buffers;
@@ -65,11 +66,42 @@ impl VisRenderer {
         culled_count += 1;
       }
     }
-    log::info!("Culled Result: {} / {}, Culling Rate: {:.2}%", culled_count, scene.meshlet_count, culled_count as f32 / scene.meshlet_count as f32 * 100.0);
+
+    Ok((culled_count, scene.meshlet_count))
+  }
+
+  /// Print the culling results to the log.
+  /// return: The result.
+  pub(crate) fn debug_culling_results(&self) -> anyhow::Result<(), HalaRendererError> {
+    let (culled_count, meshlet_count) = self.get_culling_results()?;
+    log::info!("Culled Result: {} / {}, Culling Rate: {:.2}%", culled_count, meshlet_count, culled_count as f32 / meshlet_count as f32 * 100.0);
 
     Ok(())
   }
 
+  /// Get the number of drawn opaque meshlets accumulated over all frames by the task shaders of the visibility buffer.
+  /// The counter wraps around, use the wrapping difference between two reads.
+  /// return: The accumulated number of drawn meshlets.
+  pub(crate) fn get_drawn_meshlets_counter(&self) -> anyhow::Result<u32, HalaRendererError> {
+    let compute_command_buffers = &self.resources.compute_command_buffers;
+    let transfer_staging_buffer = &self.resources.transfer_staging_buffer;
+
+    let mut render_statistics = [0u32; 4];
+    self.render_statistics_buffer.download_gpu_memory_with_buffer(
+      &mut render_statistics,
+      transfer_staging_buffer,
+      compute_command_buffers,
+    )?;
+
+    Ok(render_statistics[0])
+  }
+
+  /// Get the number of all meshlets in the scene.
+  /// return: The number of meshlets.
+  pub(crate) fn get_num_of_meshlets(&self) -> u32 {
+    self.scene_in_gpu.as_ref().map_or(0, |scene| scene.meshlet_count)
+  }
+
   /// Print the indirect draw buffer to the log.
   /// return: The result.
   pub(crate) fn debug_indirect_draw(&self) -> anyhow::Result<(), HalaRendererError> {
@@ -110,8 +142,8 @@ impl VisRenderer {
       compute_command_buffers,
     )?;
 
-    let x = (self.info.width + GlobalConstants::CLASSIFY_TILE_WIDTH - 1) / GlobalConstants::CLASSIFY_TILE_WIDTH;
-    let y = (self.info.height + GlobalConstants::CLASSIFY_TILE_WIDTH - 1) / GlobalConstants::CLASSIFY_TILE_WIDTH;
+    let x = (self.render_width + GlobalConstants::CLASSIFY_TILE_WIDTH - 1) / GlobalConstants::CLASSIFY_TILE_WIDTH;
+    let y = (self.render_height + GlobalConstants::CLASSIFY_TILE_WIDTH - 1) / GlobalConstants::CLASSIFY_TILE_WIDTH;
     let num_of_tiles = x * y;
     let mut tile_index = vec![0u32; num_of_tiles as usize * GlobalConstants::CLASSIFY_DEPTH_RANGE as usize];
     tile_index_buffer.download_gpu_memory_with_buffer(
@@ -30,7 +30,41 @@ use hala_renderer::{
 use super::{
   DebugSettings,
   GlobalUniform,
+  GpuPass,
+  GpuProfiler,
+  HeadlessOutputSettings,
   VisRenderer,
+  render_graph::{
+    RenderGraph,
+    RGAccess,
+  },
+  transient::{
+    AliasingPlan,
+    OffscreenTarget,
+    TransientTarget,
+  },
+  async_compute::AsyncCompute,
+  dynamic_resolution::{
+    DynamicResolution,
+    DynamicResolutionSettings,
+  },
+  shadow::{
+    ShadowSettings,
+    ShadowUniform,
+  },
+  tangent,
+  light_range,
+  material_variant::{
+    self,
+    DrawMaterialFlags,
+  },
+  transparency,
+  picking,
+  environment::Environment,
+  tonemap::TonemapSettings,
+  taa::TaaSettings,
+  ssao::SsaoSettings,
+  post_process::PostProcessSettings,
 };
 
 use crate::config::GPUProgramsConfig;
@@ -99,9 +133,6 @@ impl VisRenderer {
     gpu_req: &HalaGPURequirements,
     window: &winit::window::Window,
   ) -> Result<Self, HalaRendererError> {
-    let width = gpu_req.width;
-    let height = gpu_req.height;
-
     let resources = HalaRendererResources::new(
       name,
       gpu_req,
@@ -109,6 +140,57 @@ impl VisRenderer {
       &Self::get_descriptor_sizes(),
     )?;
 
+    Self::with_resources(name, gpu_req, resources, None)
+  }
+
+  /// Create a new renderer without a window.
+  /// There is no surface and no swapchain, every frame in flight renders to its own offscreen final target,
+  /// whose format and number come from the output settings.
+  /// NOTICE: The surfaceless resources still cycle the frames in flight with their fences, the frames are submitted without presenting.
+  /// param name: The name of the renderer.
+  /// param gpu_req: The GPU requirements of the renderer.
+  /// param output: The final output settings.
+  /// return: The renderer.
+  pub fn new_headless(
+    name: &str,
+    gpu_req: &HalaGPURequirements,
+    output: HeadlessOutputSettings,
+  ) -> Result<Self, HalaRendererError> {
+    let resources = HalaRendererResources::new_headless(
+      name,
+      gpu_req,
+      output.num_of_frames_in_flight,
+      &Self::get_descriptor_sizes(),
+    )?;
+
+    let mut renderer = Self::with_resources(name, gpu_req, resources, Some(output))?;
+    renderer.enable_offscreen_output()?;
+
+    Ok(renderer)
+  }
+
+  /// Create a new renderer with the created resources.
+  /// param name: The name of the renderer.
+  /// param gpu_req: The GPU requirements of the renderer.
+  /// param resources: The renderer resources.
+  /// param headless_output: The final output settings if the resources are created without a window, None to take them from the swapchain.
+  /// return: The renderer.
+  fn with_resources(
+    name: &str,
+    gpu_req: &HalaGPURequirements,
+    resources: HalaRendererResources,
+    headless_output: Option<HeadlessOutputSettings>,
+  ) -> Result<Self, HalaRendererError> {
+    let width = gpu_req.width;
+    let height = gpu_req.height;
+    let (num_of_frames, output_format, output_depth_format) = match headless_output {
+      Some(output) => (output.num_of_frames_in_flight, output.format, hala_gfx::HalaFormat::D32_SFLOAT),
+      None => {
+        let context = resources.context.borrow();
+        (context.swapchain.num_of_images, context.swapchain.format, context.swapchain.depth_stencil_format)
+      },
+    };
+
     let static_descriptor_set = hala_gfx::HalaDescriptorSet::new_static(
       Rc::clone(&resources.context.borrow().logical_device),
       Rc::clone(&resources.descriptor_pool),
@@ -155,6 +237,38 @@ impl VisRenderer {
               | hala_gfx::HalaShaderStageFlags::TASK | hala_gfx::HalaShaderStageFlags::MESH | hala_gfx::HalaShaderStageFlags::VERTEX,
             binding_flags: hala_gfx::HalaDescriptorBindingFlags::PARTIALLY_BOUND
           },
+          hala_gfx::HalaDescriptorSetLayoutBinding { // Render statistics storage buffer.
+            binding_index: 5,
+            descriptor_type: hala_gfx::HalaDescriptorType::STORAGE_BUFFER,
+            descriptor_count: 1,
+            stage_flags: hala_gfx::HalaShaderStageFlags::FRAGMENT | hala_gfx::HalaShaderStageFlags::COMPUTE
+              | hala_gfx::HalaShaderStageFlags::TASK | hala_gfx::HalaShaderStageFlags::MESH | hala_gfx::HalaShaderStageFlags::VERTEX,
+            binding_flags: hala_gfx::HalaDescriptorBindingFlags::PARTIALLY_BOUND
+          },
+          hala_gfx::HalaDescriptorSetLayoutBinding { // Draw material flags storage buffer.
+            binding_index: 6,
+            descriptor_type: hala_gfx::HalaDescriptorType::STORAGE_BUFFER,
+            descriptor_count: 1,
+            stage_flags: hala_gfx::HalaShaderStageFlags::FRAGMENT | hala_gfx::HalaShaderStageFlags::COMPUTE
+              | hala_gfx::HalaShaderStageFlags::TASK | hala_gfx::HalaShaderStageFlags::MESH | hala_gfx::HalaShaderStageFlags::VERTEX,
+            binding_flags: hala_gfx::HalaDescriptorBindingFlags::PARTIALLY_BOUND
+          },
+          hala_gfx::HalaDescriptorSetLayoutBinding { // Tangent signs storage buffer.
+            binding_index: 7,
+            descriptor_type: hala_gfx::HalaDescriptorType::STORAGE_BUFFER,
+            descriptor_count: 1,
+            stage_flags: hala_gfx::HalaShaderStageFlags::FRAGMENT | hala_gfx::HalaShaderStageFlags::COMPUTE
+              | hala_gfx::HalaShaderStageFlags::TASK | hala_gfx::HalaShaderStageFlags::MESH | hala_gfx::HalaShaderStageFlags::VERTEX,
+            binding_flags: hala_gfx::HalaDescriptorBindingFlags::PARTIALLY_BOUND
+          },
+          hala_gfx::HalaDescriptorSetLayoutBinding { // Light ranges storage buffer.
+            binding_index: 8,
+            descriptor_type: hala_gfx::HalaDescriptorType::STORAGE_BUFFER,
+            descriptor_count: 1,
+            stage_flags: hala_gfx::HalaShaderStageFlags::FRAGMENT | hala_gfx::HalaShaderStageFlags::COMPUTE
+              | hala_gfx::HalaShaderStageFlags::TASK | hala_gfx::HalaShaderStageFlags::MESH | hala_gfx::HalaShaderStageFlags::VERTEX,
+            binding_flags: hala_gfx::HalaDescriptorBindingFlags::PARTIALLY_BOUND
+          },
         ],
         "main_static.descriptor_set_layout",
       )?,
@@ -171,14 +285,33 @@ impl VisRenderer {
       "global.uniform_buffer",
     )?;
 
+    // Create shadow uniform buffer.
+    let shadow_uniform_buffer = hala_gfx::HalaBuffer::new(
+      Rc::clone(&resources.context.borrow().logical_device),
+      std::mem::size_of::<ShadowUniform>() as u64,
+      hala_gfx::HalaBufferUsageFlags::UNIFORM_BUFFER,
+      hala_gfx::HalaMemoryLocation::CpuToGpu,
+      "shadow.uniform_buffer",
+    )?;
+
     let (
       visibility_image,
       depth_image,
       material_depth_image,
       albedo_image,
       normal_image,
+      material_image,
+      emissive_image,
       hiz_image,
-    ) = Self::create_offscreen_images(&resources, width, height, false)?;
+    ) = Self::create_offscreen_images(&resources, width, height, false)?; // Recreated by the G-Buffer setting when loading the GPU programs.
+    let shadow_map_image = Self::create_shadow_map_image(&resources, &ShadowSettings::default())?;
+    let environment = Environment::new_placeholder(&resources)?;
+    let (oit_accumulation_image, oit_weight_image) = Self::create_oit_images(&resources, width, height)?;
+    let (scene_color_image, scene_depth_image) = Self::create_scene_images(&resources, width, height)?;
+    let (motion_image, taa_history_images) = Self::create_taa_images(&resources, width, height)?;
+    let (taa_descriptor_sets, taa_tonemap_descriptor_sets) = Self::create_taa_descriptor_sets(&resources)?;
+    let (ssao_raw_image, ssao_image) = Self::create_ssao_images(&resources, width, height, SsaoSettings::default().half_resolution)?;
+    let bloom_image = Self::create_bloom_image(&resources, width, height)?;
 
     // Create the point sampler.
     let point_sampler = hala_gfx::HalaSampler::new(
@@ -193,49 +326,24 @@ impl VisRenderer {
       "point.sampler",
     )?;
 
-    // Create the HiZ descriptor sets.
-    let mut hiz_descriptor_sets = Vec::new();
-    for mip_level in 0..hiz_image.mip_levels {
-      let descriptor_set = hala_gfx::HalaDescriptorSet::new_static(
-        Rc::clone(&resources.context.borrow().logical_device),
-        Rc::clone(&resources.descriptor_pool),
-        hala_gfx::HalaDescriptorSetLayout::new(
-          Rc::clone(&resources.context.borrow().logical_device),
-          &[
-            hala_gfx::HalaDescriptorSetLayoutBinding {
-              binding_index: 0,
-              descriptor_type: hala_gfx::HalaDescriptorType::SAMPLED_IMAGE,
-              descriptor_count: 1,
-              stage_flags: hala_gfx::HalaShaderStageFlags::FRAGMENT | hala_gfx::HalaShaderStageFlags::VERTEX | hala_gfx::HalaShaderStageFlags::TASK | hala_gfx::HalaShaderStageFlags::MESH,
-              binding_flags: hala_gfx::HalaDescriptorBindingFlags::PARTIALLY_BOUND
-            },
-            hala_gfx::HalaDescriptorSetLayoutBinding {
-              binding_index: 1,
-              descriptor_type: hala_gfx::HalaDescriptorType::SAMPLER,
-              descriptor_count: 1,
-              stage_flags: hala_gfx::HalaShaderStageFlags::FRAGMENT | hala_gfx::HalaShaderStageFlags::VERTEX | hala_gfx::HalaShaderStageFlags::TASK | hala_gfx::HalaShaderStageFlags::MESH,
-              binding_flags: hala_gfx::HalaDescriptorBindingFlags::PARTIALLY_BOUND
-            },
-          ],
-          &format!("hiz.descriptor_set_layout[{}]", mip_level),
-        )?,
-        0,
-        &format!("hiz.descriptor_set[{}]", mip_level),
-      )?;
+    // Create the linear sampler.
+    let linear_sampler = hala_gfx::HalaSampler::new(
+      Rc::clone(&resources.context.borrow().logical_device),
+      (hala_gfx::HalaFilter::LINEAR, hala_gfx::HalaFilter::LINEAR),
+      hala_gfx::HalaSamplerMipmapMode::NEAREST,
+      (hala_gfx::HalaSamplerAddressMode::CLAMP_TO_EDGE, hala_gfx::HalaSamplerAddressMode::CLAMP_TO_EDGE, hala_gfx::HalaSamplerAddressMode::CLAMP_TO_EDGE),
+      0.0,
+      false,
+      0.0,
+      (0.0, 0.0),
+      "linear.sampler",
+    )?;
 
-      descriptor_set.update_sampled_images_with_view(
-        0,
-        0,
-        &[hiz_image.mip_views[mip_level as usize]],
-      );
-      descriptor_set.update_samplers(
-        0,
-        1,
-        &[point_sampler.as_ref()],
-      );
+    // Create the HiZ descriptor sets.
+    let hiz_descriptor_sets = Self::create_hiz_descriptor_sets(&resources, &hiz_image, &point_sampler)?;
 
-      hiz_descriptor_sets.push(descriptor_set);
-    }
+    // Create the bloom descriptor sets.
+    let (bloom_source_descriptor_sets, bloom_descriptor_sets) = Self::create_bloom_descriptor_sets(&resources, &bloom_image, &linear_sampler)?;
 
     // Create indirect draw buffer.
     let indirect_draw_buffer = hala_gfx::HalaBuffer::new(
@@ -245,18 +353,30 @@ impl VisRenderer {
       hala_gfx::HalaMemoryLocation::GpuOnly,
       "indirect_draw.buffer",
     )?;
-    let tile_index_buffer = {
-      let x = (width + GlobalConstants::CLASSIFY_TILE_WIDTH - 1) / GlobalConstants::CLASSIFY_TILE_WIDTH;
-      let y = (height + GlobalConstants::CLASSIFY_TILE_WIDTH - 1) / GlobalConstants::CLASSIFY_TILE_WIDTH;
-      let num_of_tiles = x * y;
-      hala_gfx::HalaBuffer::new(
-        Rc::clone(&resources.context.borrow().logical_device),
-        std::mem::size_of::<u32>() as u64 * num_of_tiles as u64 * GlobalConstants::CLASSIFY_DEPTH_RANGE as u64,
-        hala_gfx::HalaBufferUsageFlags::STORAGE_BUFFER | hala_gfx::HalaBufferUsageFlags::TRANSFER_SRC,
-        hala_gfx::HalaMemoryLocation::GpuOnly,
-        "tile_index.buffer",
-      )?
-    };
+    let tile_index_buffer = Self::create_tile_index_buffer(&resources, width, height)?;
+    let light_cluster_buffer = Self::create_light_cluster_buffer(&resources, width, height)?;
+
+    // Create render statistics buffer.
+    // The counters are accumulated over all frames, read the differences between two frames.
+    let render_statistics_buffer = hala_gfx::HalaBuffer::new(
+      Rc::clone(&resources.context.borrow().logical_device),
+      std::mem::size_of::<u32>() as u64 * 4,
+      hala_gfx::HalaBufferUsageFlags::STORAGE_BUFFER | hala_gfx::HalaBufferUsageFlags::TRANSFER_SRC,
+      hala_gfx::HalaMemoryLocation::GpuOnly,
+      "render_statistics.buffer",
+    )?;
+
+    // Create the luminance histogram and the exposure buffers.
+    let (luminance_histogram_buffer, exposure_buffer) = Self::create_exposure_buffers(&resources)?;
+
+    // Create the pick buffer.
+    let pick_buffer = Self::create_pick_buffer(&resources)?;
+
+    // Create GPU profiler.
+    let gpu_profiler = GpuProfiler::new(&resources.context.borrow(), num_of_frames)?;
+
+    // Create the command buffers and the semaphores of the async compute.
+    let async_compute = AsyncCompute::new(&resources.context.borrow(), num_of_frames)?;
 
     // Return the renderer.
     log::debug!("A HalaRenderer \"{}\"[{} x {}] is created.", name, width, height);
@@ -267,12 +387,47 @@ impl VisRenderer {
       statistics: HalaRendererStatistics::new(),
 
       debug_settings: DebugSettings::default(),
+      use_small_gbuffer: false,
+      use_async_compute: false,
+      use_weighted_blended_oit: false,
+      is_hiz_valid: false,
+
+      render_width: width,
+      render_height: height,
+      target_width: width,
+      target_height: height,
+      prev_render_size: (width, height),
+      dynamic_resolution: DynamicResolution::new(DynamicResolutionSettings::default()),
+
+      shadow_settings: ShadowSettings::default(),
+      shadow_light: None,
+
+      environment_intensity: 1.0,
+      tonemap_settings: TonemapSettings::default(),
+      exposure_adaptation: 1.0,
+      is_exposure_valid: false,
+
+      taa_settings: TaaSettings::default(),
+      taa_frame_index: 0,
+      is_taa_history_valid: false,
+      prev_vp_mtx: glam::Mat4::IDENTITY,
+      prev_mvp_mtxs: Vec::new(),
+      ssao_settings: SsaoSettings::default(),
+      post_process_settings: PostProcessSettings::default(),
+      pick_request: None,
+      pick_image_index: None,
+      picked_object: None,
+
+      camera_index: 0,
+      camera_v_mtx: glam::Mat4::IDENTITY,
+      camera_p_mtx: glam::Mat4::IDENTITY,
 
       static_descriptor_set,
       dynamic_descriptor_set: None,
       textures_descriptor_set: None,
 
       global_uniform_buffer,
+      shadow_uniform_buffer,
       object_uniform_buffers: Vec::new(),
 
       scene_in_gpu: None,
@@ -282,29 +437,121 @@ impl VisRenderer {
 
       compute_programs: HashMap::new(),
       compute_descriptor_sets: HashMap::new(),
-
-      visibility_image: std::mem::ManuallyDrop::new(visibility_image),
-      depth_image: std::mem::ManuallyDrop::new(depth_image),
-      material_depth_image: std::mem::ManuallyDrop::new(material_depth_image),
-      albedo_image: std::mem::ManuallyDrop::new(albedo_image),
-      normal_image: std::mem::ManuallyDrop::new(normal_image),
+      programs_file: None,
+
+      visibility_image: std::mem::ManuallyDrop::new(Rc::new(visibility_image)),
+      depth_image: std::mem::ManuallyDrop::new(Rc::new(depth_image)),
+      material_depth_image: std::mem::ManuallyDrop::new(Rc::new(material_depth_image)),
+      albedo_image: std::mem::ManuallyDrop::new(Rc::new(albedo_image)),
+      normal_image: std::mem::ManuallyDrop::new(Rc::new(normal_image)),
+      material_image: std::mem::ManuallyDrop::new(Rc::new(material_image)),
+      emissive_image: std::mem::ManuallyDrop::new(Rc::new(emissive_image)),
       hiz_image: std::mem::ManuallyDrop::new(hiz_image),
+      shadow_map_image: std::mem::ManuallyDrop::new(shadow_map_image),
+      environment: std::mem::ManuallyDrop::new(environment),
 
       pre_culling_flags: None,
+      draw_material_flags_buffer: None,
+      tangent_signs_buffer: None,
+      light_ranges_buffer: None,
+      material_variants: 0,
+      transparent_draws: Vec::new(),
+      draw_infos: Vec::new(),
 
       hiz_descriptor_sets,
 
       point_sampler,
+      linear_sampler,
 
       indirect_draw_buffer,
       tile_index_buffer: std::mem::ManuallyDrop::new(tile_index_buffer),
+      light_cluster_buffer: std::mem::ManuallyDrop::new(light_cluster_buffer),
+      render_statistics_buffer,
+      luminance_histogram_buffer,
+      exposure_buffer,
+      pick_buffer,
+
+      is_headless: headless_output.is_some(),
+      num_of_frames,
+      output_format,
+      output_depth_format,
+      final_images: Vec::new(),
+      final_depth_images: Vec::new(),
+      last_final_image_index: 0,
+
+      scene_color_image: Some(scene_color_image),
+      scene_depth_image: Some(scene_depth_image),
+
+      motion_image: Some(motion_image),
+      taa_history_images,
+      taa_descriptor_sets,
+      taa_tonemap_descriptor_sets,
+
+      ssao_raw_image: Some(ssao_raw_image),
+      ssao_image: Some(ssao_image),
+
+      bloom_image: Some(bloom_image),
+      bloom_source_descriptor_sets,
+      bloom_descriptor_sets,
+
+      oit_accumulation_image: Some(oit_accumulation_image),
+      oit_weight_image: Some(oit_weight_image),
+
+      gpu_profiler,
+
+      async_compute,
+
+      aliasing_plan: AliasingPlan::default(),
+      transient_memory: None,
     })
   }
 
+  /// Render the final result to the offscreen images instead of the swapchain.
+  /// Each frame in flight has its own final images, so a frame never overwrites the final image another frame is still reading.
+  /// The offscreen images use the output formats, which are the swapchain formats if there is a window.
+  /// return: The result.
+  pub fn enable_offscreen_output(&mut self) -> Result<(), HalaRendererError> {
+    let context = self.resources.context.borrow();
+
+    let mut final_images = Vec::with_capacity(self.num_of_frames);
+    let mut final_depth_images = Vec::with_capacity(self.num_of_frames);
+    for index in 0..self.num_of_frames {
+      final_images.push(hala_gfx::HalaImage::new_2d(
+        Rc::clone(&context.logical_device),
+        hala_gfx::HalaImageUsageFlags::COLOR_ATTACHMENT | hala_gfx::HalaImageUsageFlags::SAMPLED | hala_gfx::HalaImageUsageFlags::TRANSFER_SRC,
+        self.output_format,
+        self.info.width,
+        self.info.height,
+        1,
+        1,
+        hala_gfx::HalaMemoryLocation::GpuOnly,
+        &format!("final_{}.image", index),
+      )?);
+
+      final_depth_images.push(hala_gfx::HalaImage::new_2d(
+        Rc::clone(&context.logical_device),
+        hala_gfx::HalaImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+        self.output_depth_format,
+        self.info.width,
+        self.info.height,
+        1,
+        1,
+        hala_gfx::HalaMemoryLocation::GpuOnly,
+        &format!("final_depth_{}.image", index),
+      )?);
+    }
+
+    drop(context);
+    self.final_images = final_images;
+    self.final_depth_images = final_depth_images;
+
+    Ok(())
+  }
+
   /// Create the offscreen images.
   /// param width: The width of the images.
   /// param height: The height of the images.
-  /// return: The visibility image, depth image, material depth image, albedo image and normal image.
+  /// return: The visibility image, depth image, material depth image, albedo image, normal image, material image, emissive image and Hi-Z image.
   pub fn create_offscreen_images(
     resources: &HalaRendererResources,
     width: u32,
@@ -317,104 +564,692 @@ impl VisRenderer {
     hala_gfx::HalaImage,
     hala_gfx::HalaImage,
     hala_gfx::HalaImage,
+    hala_gfx::HalaImage,
+    hala_gfx::HalaImage,
   ), HalaRendererError> {
-    // Create visibility render target.
-    let visibility_image = hala_gfx::HalaImage::new_2d(
+    let visibility_image = Self::create_offscreen_image(resources, OffscreenTarget::Visibility, width, height, use_small_gbuffer)?;
+    let depth_image = Self::create_offscreen_image(resources, OffscreenTarget::Depth, width, height, use_small_gbuffer)?;
+    let material_depth_image = Self::create_offscreen_image(resources, OffscreenTarget::MaterialDepth, width, height, use_small_gbuffer)?;
+    let albedo_image = Self::create_offscreen_image(resources, OffscreenTarget::Albedo, width, height, use_small_gbuffer)?;
+    let normal_image = Self::create_offscreen_image(resources, OffscreenTarget::Normal, width, height, use_small_gbuffer)?;
+    let material_image = Self::create_offscreen_image(resources, OffscreenTarget::Material, width, height, use_small_gbuffer)?;
+    let emissive_image = Self::create_offscreen_image(resources, OffscreenTarget::Emissive, width, height, use_small_gbuffer)?;
+
+    // Create Hi-Z render target.
+    let (hiz_width, hiz_height) = ((width / 2).max(1), (height / 2).max(1));
+    let hiz_image = hala_gfx::HalaImage::new_2d_with_seperate_views(
+      Rc::clone(&resources.context.borrow().logical_device),
+      hala_gfx::HalaImageUsageFlags::COLOR_ATTACHMENT | hala_gfx::HalaImageUsageFlags::SAMPLED | hala_gfx::HalaImageUsageFlags::TRANSFER_SRC,
+      hala_gfx::HalaFormat::R32_SFLOAT,
+      hiz_width,
+      hiz_height,
+      Self::get_hiz_mip_levels(hiz_width, hiz_height),
+      1,
+      hala_gfx::HalaMemoryLocation::GpuOnly,
+      "hiz.image",
+    )?;
+
+    Ok((
+      visibility_image,
+      depth_image,
+      material_depth_image,
+      albedo_image,
+      normal_image,
+      material_image,
+      emissive_image,
+      hiz_image,
+    ))
+  }
+
+  /// Get the number of the Hi-Z mip levels.
+  /// The mip chain stops before any side of the mip level becomes zero.
+  /// param width: The width of the Hi-Z image.
+  /// param height: The height of the Hi-Z image.
+  /// return: The number of the mip levels.
+  fn get_hiz_mip_levels(width: u32, height: u32) -> u32 {
+    (u32::BITS - width.min(height).max(1).leading_zeros()).min(GlobalConstants::MAX_HIZ_MIP_LEVELS)
+  }
+
+  /// Create the shadow map image, the cascades are placed side by side.
+  /// param resources: The renderer resources.
+  /// param settings: The shadow settings.
+  /// return: The image.
+  fn create_shadow_map_image(
+    resources: &HalaRendererResources,
+    settings: &ShadowSettings,
+  ) -> Result<hala_gfx::HalaImage, HalaRendererError> {
+    let image = hala_gfx::HalaImage::new_2d(
       Rc::clone(&resources.context.borrow().logical_device),
-      hala_gfx::HalaImageUsageFlags::COLOR_ATTACHMENT | hala_gfx::HalaImageUsageFlags::INPUT_ATTACHMENT | hala_gfx::HalaImageUsageFlags::SAMPLED,
-      hala_gfx::HalaFormat::R32_UINT,
+      hala_gfx::HalaImageUsageFlags::DEPTH_STENCIL_ATTACHMENT | hala_gfx::HalaImageUsageFlags::SAMPLED,
+      hala_gfx::HalaFormat::D32_SFLOAT,
+      settings.resolution * settings.num_of_cascades,
+      settings.resolution,
+      1,
+      1,
+      hala_gfx::HalaMemoryLocation::GpuOnly,
+      "shadow_map.image",
+    )?;
+
+    Ok(image)
+  }
+
+  /// Create the descriptor sets to sample each mip level of the Hi-Z image.
+  /// param resources: The renderer resources.
+  /// param hiz_image: The Hi-Z image.
+  /// param point_sampler: The point sampler.
+  /// return: The descriptor sets.
+  fn create_hiz_descriptor_sets(
+    resources: &HalaRendererResources,
+    hiz_image: &hala_gfx::HalaImage,
+    point_sampler: &hala_gfx::HalaSampler,
+  ) -> Result<Vec<hala_gfx::HalaDescriptorSet>, HalaRendererError> {
+    let mut hiz_descriptor_sets = Vec::new();
+    for mip_level in 0..hiz_image.mip_levels {
+      let descriptor_set = hala_gfx::HalaDescriptorSet::new_static(
+        Rc::clone(&resources.context.borrow().logical_device),
+        Rc::clone(&resources.descriptor_pool),
+        hala_gfx::HalaDescriptorSetLayout::new(
+          Rc::clone(&resources.context.borrow().logical_device),
+          &[
+            hala_gfx::HalaDescriptorSetLayoutBinding {
+              binding_index: 0,
+              descriptor_type: hala_gfx::HalaDescriptorType::SAMPLED_IMAGE,
+              descriptor_count: 1,
+              stage_flags: hala_gfx::HalaShaderStageFlags::FRAGMENT | hala_gfx::HalaShaderStageFlags::VERTEX | hala_gfx::HalaShaderStageFlags::TASK | hala_gfx::HalaShaderStageFlags::MESH,
+              binding_flags: hala_gfx::HalaDescriptorBindingFlags::PARTIALLY_BOUND
+            },
+            hala_gfx::HalaDescriptorSetLayoutBinding {
+              binding_index: 1,
+              descriptor_type: hala_gfx::HalaDescriptorType::SAMPLER,
+              descriptor_count: 1,
+              stage_flags: hala_gfx::HalaShaderStageFlags::FRAGMENT | hala_gfx::HalaShaderStageFlags::VERTEX | hala_gfx::HalaShaderStageFlags::TASK | hala_gfx::HalaShaderStageFlags::MESH,
+              binding_flags: hala_gfx::HalaDescriptorBindingFlags::PARTIALLY_BOUND
+            },
+          ],
+          &format!("hiz.descriptor_set_layout[{}]", mip_level),
+        )?,
+        0,
+        &format!("hiz.descriptor_set[{}]", mip_level),
+      )?;
+
+      descriptor_set.update_sampled_images_with_view(
+        0,
+        0,
+        &[hiz_image.mip_views[mip_level as usize]],
+      );
+      descriptor_set.update_samplers(
+        0,
+        1,
+        &[point_sampler],
+      );
+
+      hiz_descriptor_sets.push(descriptor_set);
+    }
+
+    Ok(hiz_descriptor_sets)
+  }
+
+  /// Create the tile index buffer.
+  /// param resources: The renderer resources.
+  /// param width: The width of the screen.
+  /// param height: The height of the screen.
+  /// return: The buffer.
+  pub(crate) fn create_tile_index_buffer(
+    resources: &HalaRendererResources,
+    width: u32,
+    height: u32,
+  ) -> Result<hala_gfx::HalaBuffer, HalaRendererError> {
+    let x = (width + GlobalConstants::CLASSIFY_TILE_WIDTH - 1) / GlobalConstants::CLASSIFY_TILE_WIDTH;
+    let y = (height + GlobalConstants::CLASSIFY_TILE_WIDTH - 1) / GlobalConstants::CLASSIFY_TILE_WIDTH;
+    let num_of_tiles = x * y;
+    let buffer = hala_gfx::HalaBuffer::new(
+      Rc::clone(&resources.context.borrow().logical_device),
+      std::mem::size_of::<u32>() as u64 * num_of_tiles as u64 * GlobalConstants::CLASSIFY_DEPTH_RANGE as u64,
+      hala_gfx::HalaBufferUsageFlags::STORAGE_BUFFER | hala_gfx::HalaBufferUsageFlags::TRANSFER_SRC,
+      hala_gfx::HalaMemoryLocation::GpuOnly,
+      "tile_index.buffer",
+    )?;
+
+    Ok(buffer)
+  }
+
+  /// Get the number of the light culling tiles.
+  /// param width: The width of the screen.
+  /// param height: The height of the screen.
+  /// return: The number of the tiles in X and Y.
+  pub(crate) fn get_num_of_light_tiles(width: u32, height: u32) -> (u32, u32) {
+    (
+      (width + GlobalConstants::LIGHT_TILE_SIZE - 1) / GlobalConstants::LIGHT_TILE_SIZE,
+      (height + GlobalConstants::LIGHT_TILE_SIZE - 1) / GlobalConstants::LIGHT_TILE_SIZE,
+    )
+  }
+
+  /// Create the light cluster buffer.
+  /// param resources: The renderer resources.
+  /// param width: The width of the screen.
+  /// param height: The height of the screen.
+  /// return: The buffer.
+  pub(crate) fn create_light_cluster_buffer(
+    resources: &HalaRendererResources,
+    width: u32,
+    height: u32,
+  ) -> Result<hala_gfx::HalaBuffer, HalaRendererError> {
+    let (x, y) = Self::get_num_of_light_tiles(width, height);
+    let num_of_tiles = x * y;
+    // The light mask of each cluster, then the farthest view distance of each tile.
+    let buffer = hala_gfx::HalaBuffer::new(
+      Rc::clone(&resources.context.borrow().logical_device),
+      std::mem::size_of::<u32>() as u64 * num_of_tiles as u64 * (GlobalConstants::LIGHT_NUM_OF_SLICES + 1) as u64,
+      hala_gfx::HalaBufferUsageFlags::STORAGE_BUFFER | hala_gfx::HalaBufferUsageFlags::TRANSFER_SRC,
+      hala_gfx::HalaMemoryLocation::GpuOnly,
+      "light_cluster.buffer",
+    )?;
+
+    Ok(buffer)
+  }
+
+  /// Recreate all resources depending on the size of the render targets.
+  /// The caller makes sure the old resources are not used by the GPU.
+  /// param width: The new target width.
+  /// param height: The new target height.
+  /// return: The result.
+  pub(crate) fn recreate_size_dependent_resources(&mut self, width: u32, height: u32) -> Result<(), HalaRendererError> {
+    self.target_width = width;
+    self.target_height = height;
+    // The render size never exceeds the targets, the caller updates it to the current render scale.
+    self.render_width = self.render_width.min(width);
+    self.render_height = self.render_height.min(height);
+    self.prev_render_size = (self.render_width, self.render_height);
+
+    self.hiz_descriptor_sets.clear();
+    unsafe {
+      std::mem::ManuallyDrop::drop(&mut self.light_cluster_buffer);
+      std::mem::ManuallyDrop::drop(&mut self.tile_index_buffer);
+      std::mem::ManuallyDrop::drop(&mut self.hiz_image);
+      std::mem::ManuallyDrop::drop(&mut self.emissive_image);
+      std::mem::ManuallyDrop::drop(&mut self.material_image);
+      std::mem::ManuallyDrop::drop(&mut self.normal_image);
+      std::mem::ManuallyDrop::drop(&mut self.albedo_image);
+      std::mem::ManuallyDrop::drop(&mut self.material_depth_image);
+      std::mem::ManuallyDrop::drop(&mut self.visibility_image);
+      std::mem::ManuallyDrop::drop(&mut self.depth_image);
+    }
+    self.transient_memory = None;
+    let (
+      visibility_image,
+      depth_image,
+      material_depth_image,
+      albedo_image,
+      normal_image,
+      material_image,
+      emissive_image,
+      hiz_image,
+    ) = Self::create_offscreen_images(&self.resources, width, height, self.use_small_gbuffer)?;
+    self.visibility_image = std::mem::ManuallyDrop::new(Rc::new(visibility_image));
+    self.depth_image = std::mem::ManuallyDrop::new(Rc::new(depth_image));
+    self.material_depth_image = std::mem::ManuallyDrop::new(Rc::new(material_depth_image));
+    self.albedo_image = std::mem::ManuallyDrop::new(Rc::new(albedo_image));
+    self.normal_image = std::mem::ManuallyDrop::new(Rc::new(normal_image));
+    self.material_image = std::mem::ManuallyDrop::new(Rc::new(material_image));
+    self.emissive_image = std::mem::ManuallyDrop::new(Rc::new(emissive_image));
+    self.hiz_image = std::mem::ManuallyDrop::new(hiz_image);
+    self.hiz_descriptor_sets = Self::create_hiz_descriptor_sets(&self.resources, &self.hiz_image, &self.point_sampler)?;
+    self.tile_index_buffer = std::mem::ManuallyDrop::new(Self::create_tile_index_buffer(&self.resources, width, height)?);
+    self.light_cluster_buffer = std::mem::ManuallyDrop::new(Self::create_light_cluster_buffer(&self.resources, width, height)?);
+
+    self.scene_depth_image = None;
+    self.scene_color_image = None;
+    let (scene_color_image, scene_depth_image) = Self::create_scene_images(&self.resources, width, height)?;
+    self.scene_color_image = Some(scene_color_image);
+    self.scene_depth_image = Some(scene_depth_image);
+
+    self.taa_history_images.clear();
+    self.motion_image = None;
+    let (motion_image, taa_history_images) = Self::create_taa_images(&self.resources, width, height)?;
+    self.motion_image = Some(motion_image);
+    self.taa_history_images = taa_history_images;
+
+    self.ssao_image = None;
+    self.ssao_raw_image = None;
+    let (ssao_raw_image, ssao_image) = Self::create_ssao_images(&self.resources, width, height, self.ssao_settings.half_resolution)?;
+    self.ssao_raw_image = Some(ssao_raw_image);
+    self.ssao_image = Some(ssao_image);
+
+    self.bloom_descriptor_sets.clear();
+    self.bloom_source_descriptor_sets.clear();
+    self.bloom_image = None;
+    let bloom_image = Self::create_bloom_image(&self.resources, width, height)?;
+    let (bloom_source_descriptor_sets, bloom_descriptor_sets) = Self::create_bloom_descriptor_sets(&self.resources, &bloom_image, &self.linear_sampler)?;
+    self.bloom_image = Some(bloom_image);
+    self.bloom_source_descriptor_sets = bloom_source_descriptor_sets;
+    self.bloom_descriptor_sets = bloom_descriptor_sets;
+
+    self.oit_weight_image = None;
+    self.oit_accumulation_image = None;
+    let (oit_accumulation_image, oit_weight_image) = Self::create_oit_images(&self.resources, width, height)?;
+    self.oit_accumulation_image = Some(oit_accumulation_image);
+    self.oit_weight_image = Some(oit_weight_image);
+
+    // The Hi-Z buffer of the last frame does not match the new depth, clear it before culling with it.
+    self.is_hiz_valid = false;
+    self.is_taa_history_valid = false;
+
+    self.setup_visibility()?;
+
+    // The new images have their own memory, place them in the heap again.
+    self.aliasing_plan = AliasingPlan::default();
+    self.update_offscreen_aliasing()?;
+
+    Ok(())
+  }
+
+  /// Create the HDR targets of the final view at the render size.
+  /// NOTICE: Keep the formats same as the final view programs in programs.toml.
+  /// param resources: The renderer resources.
+  /// param width: The render width.
+  /// param height: The render height.
+  /// return: The scene color image and the scene depth image.
+  fn create_scene_images(
+    resources: &HalaRendererResources,
+    width: u32,
+    height: u32,
+  ) -> Result<(hala_gfx::HalaImage, hala_gfx::HalaImage), HalaRendererError> {
+    let context = resources.context.borrow();
+
+    let scene_color_image = hala_gfx::HalaImage::new_2d(
+      Rc::clone(&context.logical_device),
+      hala_gfx::HalaImageUsageFlags::COLOR_ATTACHMENT | hala_gfx::HalaImageUsageFlags::SAMPLED,
+      hala_gfx::HalaFormat::R16G16B16A16_SFLOAT,
       width,
       height,
       1,
       1,
       hala_gfx::HalaMemoryLocation::GpuOnly,
-      "visibility.image",
+      "scene_color.image",
     )?;
 
-    // Create depth render target.
-    let depth_image = hala_gfx::HalaImage::new_2d(
-      Rc::clone(&resources.context.borrow().logical_device),
-      hala_gfx::HalaImageUsageFlags::DEPTH_STENCIL_ATTACHMENT | hala_gfx::HalaImageUsageFlags::INPUT_ATTACHMENT | hala_gfx::HalaImageUsageFlags::SAMPLED,
+    let scene_depth_image = hala_gfx::HalaImage::new_2d(
+      Rc::clone(&context.logical_device),
+      hala_gfx::HalaImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
       hala_gfx::HalaFormat::D32_SFLOAT,
       width,
       height,
       1,
       1,
       hala_gfx::HalaMemoryLocation::GpuOnly,
-      "offscreen_depth.image",
+      "scene_depth.image",
     )?;
 
-    // Create material depth render target.
-    let material_depth_image = hala_gfx::HalaImage::new_2d(
-      Rc::clone(&resources.context.borrow().logical_device),
-      hala_gfx::HalaImageUsageFlags::DEPTH_STENCIL_ATTACHMENT | hala_gfx::HalaImageUsageFlags::INPUT_ATTACHMENT | hala_gfx::HalaImageUsageFlags::SAMPLED,
-      hala_gfx::HalaFormat::D32_SFLOAT,
+    Ok((scene_color_image, scene_depth_image))
+  }
+
+  /// Create the render targets of the weighted blended OIT.
+  /// param resources: The renderer resources.
+  /// param width: The render width.
+  /// param height: The render height.
+  /// return: The accumulation image and the weight image.
+  fn create_oit_images(
+    resources: &HalaRendererResources,
+    width: u32,
+    height: u32,
+  ) -> Result<(hala_gfx::HalaImage, hala_gfx::HalaImage), HalaRendererError> {
+    let context = resources.context.borrow();
+
+    let oit_accumulation_image = hala_gfx::HalaImage::new_2d(
+      Rc::clone(&context.logical_device),
+      hala_gfx::HalaImageUsageFlags::COLOR_ATTACHMENT | hala_gfx::HalaImageUsageFlags::SAMPLED,
+      hala_gfx::HalaFormat::R16G16B16A16_SFLOAT,
       width,
       height,
       1,
       1,
       hala_gfx::HalaMemoryLocation::GpuOnly,
-      "material_depth.image",
+      "oit_accumulation.image",
     )?;
 
-    // Create albedo render target.
-    let albedo_image = hala_gfx::HalaImage::new_2d(
+    let oit_weight_image = hala_gfx::HalaImage::new_2d(
+      Rc::clone(&context.logical_device),
+      hala_gfx::HalaImageUsageFlags::COLOR_ATTACHMENT | hala_gfx::HalaImageUsageFlags::SAMPLED,
+      hala_gfx::HalaFormat::R16_SFLOAT,
+      width,
+      height,
+      1,
+      1,
+      hala_gfx::HalaMemoryLocation::GpuOnly,
+      "oit_weight.image",
+    )?;
+
+    Ok((oit_accumulation_image, oit_weight_image))
+  }
+
+  /// Scale the window size by the render scale.
+  /// param width: The width of the window.
+  /// param height: The height of the window.
+  /// param scale: The render scale.
+  /// return: The scaled width and height.
+  fn get_scaled_size(width: u32, height: u32, scale: f32) -> (u32, u32) {
+    (
+      ((width as f32 * scale).round() as u32).max(1),
+      ((height as f32 * scale).round() as u32).max(1),
+    )
+  }
+
+  /// Get the rendered part of an offscreen target or its mip level, the targets are allocated at the maximum render scale.
+  /// param width: The width of the target.
+  /// param height: The height of the target.
+  /// return: The rendered width and height.
+  pub(crate) fn get_rendered_size(&self, width: u32, height: u32) -> (u32, u32) {
+    (
+      (width as u64 * self.render_width as u64).div_ceil(self.target_width.max(1) as u64).max(1) as u32,
+      (height as u64 * self.render_height as u64).div_ceil(self.target_height.max(1) as u64).max(1) as u32,
+    )
+  }
+
+  /// Update the render size by the window size and the render scale.
+  /// The offscreen targets are only recreated if the window size or the maximum render scale changed,
+  /// a change of the render scale only changes the viewport rendered into them.
+  /// param width: The width of the window.
+  /// param height: The height of the window.
+  /// return: The result.
+  pub(crate) fn update_render_size(&mut self, width: u32, height: u32) -> Result<(), HalaRendererError> {
+    // The minimized window keeps the old resources.
+    if width == 0 || height == 0 {
+      return Ok(());
+    }
+    self.info.width = width;
+    self.info.height = height;
+
+    let (target_width, target_height) = Self::get_scaled_size(width, height, self.dynamic_resolution.get_max_scale());
+    let is_target_resized = target_width != self.target_width || target_height != self.target_height;
+    let is_output_resized = self.final_images.first()
+      .is_some_and(|final_image| final_image.extent.width != width || final_image.extent.height != height);
+    if is_target_resized || is_output_resized {
+      // The old resources may be still used by the frames in flight.
+      self.wait_idle()?;
+      if is_output_resized {
+        self.final_depth_images.clear();
+        self.final_images.clear();
+        self.enable_offscreen_output()?;
+      }
+      if is_target_resized {
+        self.recreate_size_dependent_resources(target_width, target_height)?;
+        log::info!("The offscreen targets are resized to {} x {} for the window {} x {}.", target_width, target_height, width, height);
+      }
+    }
+
+    let (render_width, render_height) = Self::get_scaled_size(width, height, self.dynamic_resolution.get_scale());
+    let (render_width, render_height) = (render_width.min(self.target_width), render_height.min(self.target_height));
+    if render_width != self.render_width || render_height != self.render_height {
+      self.render_width = render_width;
+      self.render_height = render_height;
+      log::debug!("The render size is changed to {} x {} in the {} x {} offscreen targets.", render_width, render_height, self.target_width, self.target_height);
+    }
+
+    Ok(())
+  }
+
+  /// Set the dynamic resolution settings.
+  /// The render targets are recreated by the next update if the maximum render scale changes.
+  /// param settings: The settings.
+  pub fn set_dynamic_resolution_settings(&mut self, settings: DynamicResolutionSettings) {
+    if self.dynamic_resolution.settings != settings {
+      self.dynamic_resolution.set_settings(settings);
+    }
+  }
+
+  /// Get the dynamic resolution settings.
+  /// return: The settings.
+  pub fn get_dynamic_resolution_settings(&self) -> DynamicResolutionSettings {
+    self.dynamic_resolution.settings
+  }
+
+  /// Get the current render scale.
+  /// return: The render scale relative to the window size.
+  pub fn get_render_scale(&self) -> f32 {
+    self.dynamic_resolution.get_scale()
+  }
+
+  /// Set the shadow settings.
+  /// The shadow map is recreated if the number of the cascades or the resolution changes.
+  /// param settings: The settings.
+  /// return: The result.
+  pub fn set_shadow_settings(&mut self, settings: ShadowSettings) -> Result<(), HalaRendererError> {
+    let is_resized = settings.num_of_cascades != self.shadow_settings.num_of_cascades
+      || settings.resolution != self.shadow_settings.resolution;
+    self.shadow_settings = settings;
+    if !is_resized {
+      return Ok(());
+    }
+
+    // The old shadow map may be still used by the frames in flight.
+    self.wait_idle()?;
+    unsafe {
+      std::mem::ManuallyDrop::drop(&mut self.shadow_map_image);
+    }
+    self.shadow_map_image = std::mem::ManuallyDrop::new(Self::create_shadow_map_image(&self.resources, &settings)?);
+    if !self.graphics_descriptor_sets.is_empty() {
+      self.setup_visibility()?;
+    }
+
+    Ok(())
+  }
+
+  /// Get the shadow settings.
+  /// return: The settings.
+  pub fn get_shadow_settings(&self) -> ShadowSettings {
+    self.shadow_settings
+  }
+
+  /// Get the number of the Hi-Z mip levels.
+  /// return: The number of the mip levels.
+  pub fn get_num_of_hiz_levels(&self) -> u32 {
+    self.hiz_image.mip_levels
+  }
+
+  /// Get the formats of the G-Buffer images.
+  /// param use_small_gbuffer: Whether use the small G-Buffer formats.
+  /// return: The albedo format, the normal format, the material format and the emissive format.
+  pub fn get_gbuffer_formats(use_small_gbuffer: bool) -> (hala_gfx::HalaFormat, hala_gfx::HalaFormat, hala_gfx::HalaFormat, hala_gfx::HalaFormat) {
+    if use_small_gbuffer {
+      (
+        hala_gfx::HalaFormat::R8G8B8A8_UNORM,
+        hala_gfx::HalaFormat::A2R10G10B10_UNORM_PACK32,
+        hala_gfx::HalaFormat::R8G8B8A8_UNORM,
+        // The emissive is not limited to [0, 1].
+        hala_gfx::HalaFormat::R16G16B16A16_SFLOAT,
+      )
+    } else {
+      (
+        hala_gfx::HalaFormat::R32G32B32A32_SFLOAT,
+        hala_gfx::HalaFormat::R32G32B32A32_SFLOAT,
+        hala_gfx::HalaFormat::R32G32B32A32_SFLOAT,
+        hala_gfx::HalaFormat::R32G32B32A32_SFLOAT,
+      )
+    }
+  }
+
+  /// Get the usage and the format of the image of the transient offscreen target.
+  /// param target: The target.
+  /// param use_small_gbuffer: Whether use the small G-Buffer formats.
+  /// return: The usage and the format.
+  fn get_offscreen_image_desc(
+    target: OffscreenTarget,
+    use_small_gbuffer: bool,
+  ) -> (hala_gfx::HalaImageUsageFlags, hala_gfx::HalaFormat) {
+    let gbuffer_usage = hala_gfx::HalaImageUsageFlags::COLOR_ATTACHMENT | hala_gfx::HalaImageUsageFlags::INPUT_ATTACHMENT | hala_gfx::HalaImageUsageFlags::TRANSFER_SRC;
+    let depth_usage = hala_gfx::HalaImageUsageFlags::DEPTH_STENCIL_ATTACHMENT | hala_gfx::HalaImageUsageFlags::INPUT_ATTACHMENT | hala_gfx::HalaImageUsageFlags::SAMPLED;
+    let gbuffer_formats = Self::get_gbuffer_formats(use_small_gbuffer);
+    match target {
+      OffscreenTarget::Visibility => (
+        hala_gfx::HalaImageUsageFlags::COLOR_ATTACHMENT | hala_gfx::HalaImageUsageFlags::INPUT_ATTACHMENT | hala_gfx::HalaImageUsageFlags::SAMPLED | hala_gfx::HalaImageUsageFlags::TRANSFER_SRC,
+        hala_gfx::HalaFormat::R32_UINT,
+      ),
+      OffscreenTarget::Depth | OffscreenTarget::MaterialDepth => (depth_usage, hala_gfx::HalaFormat::D32_SFLOAT),
+      OffscreenTarget::Albedo => (gbuffer_usage, gbuffer_formats.0),
+      OffscreenTarget::Normal => (gbuffer_usage, gbuffer_formats.1),
+      OffscreenTarget::Material => (gbuffer_usage, gbuffer_formats.2),
+      OffscreenTarget::Emissive => (gbuffer_usage, gbuffer_formats.3),
+    }
+  }
+
+  /// Create the transient offscreen image with its own memory.
+  /// param target: The target.
+  /// param width: The width of the image.
+  /// param height: The height of the image.
+  /// param use_small_gbuffer: Whether use the small G-Buffer formats.
+  /// return: The image.
+  pub fn create_offscreen_image(
+    resources: &HalaRendererResources,
+    target: OffscreenTarget,
+    width: u32,
+    height: u32,
+    use_small_gbuffer: bool,
+  ) -> Result<hala_gfx::HalaImage, HalaRendererError> {
+    let (usage, format) = Self::get_offscreen_image_desc(target, use_small_gbuffer);
+    let image = hala_gfx::HalaImage::new_2d(
       Rc::clone(&resources.context.borrow().logical_device),
-      hala_gfx::HalaImageUsageFlags::COLOR_ATTACHMENT | hala_gfx::HalaImageUsageFlags::INPUT_ATTACHMENT,
-      if use_small_gbuffer { hala_gfx::HalaFormat::R8G8B8A8_UNORM } else { hala_gfx::HalaFormat::R32G32B32A32_SFLOAT },
+      usage,
+      format,
       width,
       height,
       1,
       1,
       hala_gfx::HalaMemoryLocation::GpuOnly,
-      "albedo.image",
+      target.debug_name(),
     )?;
 
-    // Create normal render target.
-    let normal_image = hala_gfx::HalaImage::new_2d(
+    Ok(image)
+  }
+
+  /// Create the transient offscreen image without memory, it is bound to the transient heap later.
+  /// param target: The target.
+  /// param width: The width of the image.
+  /// param height: The height of the image.
+  /// param use_small_gbuffer: Whether use the small G-Buffer formats.
+  /// return: The image.
+  fn create_placed_offscreen_image(
+    resources: &HalaRendererResources,
+    target: OffscreenTarget,
+    width: u32,
+    height: u32,
+    use_small_gbuffer: bool,
+  ) -> Result<hala_gfx::HalaImage, HalaRendererError> {
+    let (usage, format) = Self::get_offscreen_image_desc(target, use_small_gbuffer);
+    let image = hala_gfx::HalaImage::new_2d_without_memory(
       Rc::clone(&resources.context.borrow().logical_device),
-      hala_gfx::HalaImageUsageFlags::COLOR_ATTACHMENT | hala_gfx::HalaImageUsageFlags::INPUT_ATTACHMENT,
-      if use_small_gbuffer { hala_gfx::HalaFormat::A2R10G10B10_UNORM_PACK32 } else { hala_gfx::HalaFormat::R32G32B32A32_SFLOAT },
+      usage,
+      format,
       width,
       height,
       1,
       1,
-      hala_gfx::HalaMemoryLocation::GpuOnly,
-      "normal.image",
+      target.debug_name(),
     )?;
 
-    // Create Hi-Z render target.
-    let hiz_image = hala_gfx::HalaImage::new_2d_with_seperate_views(
-      Rc::clone(&resources.context.borrow().logical_device),
-      hala_gfx::HalaImageUsageFlags::COLOR_ATTACHMENT | hala_gfx::HalaImageUsageFlags::SAMPLED,
-      hala_gfx::HalaFormat::R32_SFLOAT,
-      width / 2,
-      height / 2,
-      5,
-      1,
+    Ok(image)
+  }
+
+  /// Place the transient offscreen targets in one memory heap, the targets whose lifetimes do not overlap in the frame graph share the memory.
+  /// The plan is made by the current debug settings, so it must be updated when they change.
+  /// return: The result.
+  pub(crate) fn update_offscreen_aliasing(&mut self) -> Result<(), HalaRendererError> {
+    let lifetimes = {
+      let context = self.resources.context.borrow();
+      let graph = self.build_frame_graph(&context, |_, _| Ok(()))?;
+      graph.get_lifetimes(self.use_async_compute)?
+    };
+
+    // The memory requirements are only known after creating the images.
+    let mut images = OffscreenTarget::ALL.iter()
+      .map(|target| Self::create_placed_offscreen_image(&self.resources, *target, self.target_width, self.target_height, self.use_small_gbuffer))
+      .collect::<Result<Vec<_>, _>>()?;
+    let requirements = images.iter().map(|image| image.get_memory_requirements()).collect::<Vec<_>>();
+    let targets = OffscreenTarget::ALL.iter().zip(requirements.iter())
+      .map(|(target, requirements)| TransientTarget {
+        target: *target,
+        size: requirements.size,
+        alignment: requirements.alignment,
+        lifetime: lifetimes.iter().find(|(name, _)| *name == target.name()).and_then(|(_, lifetime)| *lifetime),
+      })
+      .collect::<Vec<_>>();
+
+    let plan = AliasingPlan::new(&targets);
+    if plan == self.aliasing_plan {
+      return Ok(());
+    }
+    plan.report(&targets);
+
+    let memory_type_bits = requirements.iter().fold(u32::MAX, |bits, requirements| bits & requirements.memory_type_bits);
+    if memory_type_bits == 0 {
+      log::error!("The offscreen targets have no memory type in common.");
+      return Err(HalaRendererError::new("The offscreen targets have no memory type in common.", None));
+    }
+    let memory = hala_gfx::HalaMemory::new(
+      Rc::clone(&self.resources.context.borrow().logical_device),
+      plan.heap_size,
+      memory_type_bits,
       hala_gfx::HalaMemoryLocation::GpuOnly,
-      "hiz.image",
+      "offscreen_targets.memory",
     )?;
+    for (image, offset) in images.iter_mut().zip(plan.offsets.iter()) {
+      image.bind_memory(&memory, *offset)?;
+    }
 
-    Ok((
-      visibility_image,
-      depth_image,
-      material_depth_image,
-      albedo_image,
-      normal_image,
-      hiz_image,
-    ))
+    // The old images may be still used by the frames in flight.
+    self.wait_idle()?;
+    unsafe {
+      std::mem::ManuallyDrop::drop(&mut self.emissive_image);
+      std::mem::ManuallyDrop::drop(&mut self.material_image);
+      std::mem::ManuallyDrop::drop(&mut self.normal_image);
+      std::mem::ManuallyDrop::drop(&mut self.albedo_image);
+      std::mem::ManuallyDrop::drop(&mut self.material_depth_image);
+      std::mem::ManuallyDrop::drop(&mut self.depth_image);
+      std::mem::ManuallyDrop::drop(&mut self.visibility_image);
+    }
+    // The old heap is released after the images placed in it.
+    self.transient_memory = Some(memory);
+    let mut images = images.into_iter().map(Rc::new);
+    self.visibility_image = std::mem::ManuallyDrop::new(images.next().unwrap());
+    self.depth_image = std::mem::ManuallyDrop::new(images.next().unwrap());
+    self.material_depth_image = std::mem::ManuallyDrop::new(images.next().unwrap());
+    self.albedo_image = std::mem::ManuallyDrop::new(images.next().unwrap());
+    self.normal_image = std::mem::ManuallyDrop::new(images.next().unwrap());
+    self.material_image = std::mem::ManuallyDrop::new(images.next().unwrap());
+    self.emissive_image = std::mem::ManuallyDrop::new(images.next().unwrap());
+    self.aliasing_plan = plan;
+
+    self.setup_debug()?;
+    self.setup_visibility()?;
+
+    Ok(())
   }
 
   /// Set the scene to be rendered.
   /// param scene_in_cpu: The scene in the CPU.
+  /// param light_ranges: The glTF range of each light node, none if the light has no range.
   /// return: The result.
-  pub fn set_scene(&mut self, scene_in_cpu: &mut cpu::HalaScene) -> Result<(), HalaRendererError> {
+  pub fn set_scene(&mut self, scene_in_cpu: &mut cpu::HalaScene, light_ranges: &[Option<f32>]) -> Result<(), HalaRendererError> {
     let context = self.resources.context.borrow();
 
     // Release the old scene in the GPU.
     self.scene_in_gpu = None;
 
+    // The normal maps need the tangents, generate them for the primitives without.
+    let num_of_generated = tangent::generate_missing_tangents(scene_in_cpu);
+    if num_of_generated > 0 {
+      log::info!("Generated the tangents of {} primitives.", num_of_generated);
+    }
+    let tangent_signs = tangent::collect_tangent_signs(scene_in_cpu);
+
+    // The alpha mask and double-sided flags are indexed by the draw index, which follows the primitive order.
+    let draw_material_flags = material_variant::collect_draw_material_flags(scene_in_cpu);
+    self.transparent_draws = transparency::collect_transparent_draws(scene_in_cpu, &draw_material_flags);
+    // The selection of the old scene is dropped.
+    self.draw_infos = picking::collect_draw_infos(scene_in_cpu, &draw_material_flags);
+    self.pick_request = None;
+    self.pick_image_index = None;
+    self.picked_object = None;
+
     // Upload the new scene to the GPU.
     let scene_in_gpu = loader::HalaSceneGPUUploader::upload(
       &context,
@@ -429,24 +1264,164 @@ impl VisRenderer {
       log::error!("The materials count \"{}\" is too large than the limit \"{}\".", scene_in_gpu.materials.len(), 256 * 32);
       return Err(HalaRendererError::new("The materials count is too large than the limit.", None));
     }
+    if scene_in_gpu.light_data.len() > GlobalConstants::MAX_LIGHTS as usize {
+      log::warn!("The lights count \"{}\" is larger than the limit \"{}\", the rest are ignored.", scene_in_gpu.light_data.len(), GlobalConstants::MAX_LIGHTS);
+    }
+
+    // The first directional light casts the cascaded shadow.
+    self.shadow_light = scene_in_gpu.light_data.iter()
+      .take(GlobalConstants::MAX_LIGHTS as usize)
+      .position(|light| light._type == 1)
+      .map(|light_index| (light_index as u32, glam::Vec3::from(scene_in_gpu.light_data[light_index].u)));
+
+    let draw_material_flags_buffer = hala_gfx::HalaBuffer::new(
+      Rc::clone(&context.logical_device),
+      (std::mem::size_of::<DrawMaterialFlags>() * draw_material_flags.len().max(1)) as u64,
+      hala_gfx::HalaBufferUsageFlags::STORAGE_BUFFER,
+      hala_gfx::HalaMemoryLocation::CpuToGpu,
+      "draw_material_flags.buffer",
+    )?;
+    if !draw_material_flags.is_empty() {
+      draw_material_flags_buffer.update_memory(0, draw_material_flags.as_slice())?;
+    }
+    self.draw_material_flags_buffer = Some(draw_material_flags_buffer);
+    let tangent_signs_buffer = hala_gfx::HalaBuffer::new(
+      Rc::clone(&context.logical_device),
+      (std::mem::size_of::<u32>() * tangent_signs.len()) as u64,
+      hala_gfx::HalaBufferUsageFlags::STORAGE_BUFFER,
+      hala_gfx::HalaMemoryLocation::CpuToGpu,
+      "tangent_signs.buffer",
+    )?;
+    tangent_signs_buffer.update_memory(0, tangent_signs.as_slice())?;
+    self.tangent_signs_buffer = Some(tangent_signs_buffer);
+    let light_ranges = light_range::collect_light_ranges(light_ranges, scene_in_gpu.light_data.len());
+    let light_ranges_buffer = hala_gfx::HalaBuffer::new(
+      Rc::clone(&context.logical_device),
+      (std::mem::size_of::<f32>() * light_ranges.len()) as u64,
+      hala_gfx::HalaBufferUsageFlags::STORAGE_BUFFER,
+      hala_gfx::HalaMemoryLocation::CpuToGpu,
+      "light_ranges.buffer",
+    )?;
+    light_ranges_buffer.update_memory(0, light_ranges.as_slice())?;
+    self.light_ranges_buffer = Some(light_ranges_buffer);
+    self.material_variants = material_variant::get_used_material_variants(&draw_material_flags);
+    if !self.transparent_draws.is_empty() {
+      // The sorted transparent draws only dispatch their own meshlets.
+      let meshlets = scene_in_gpu.meshlets.as_ref().ok_or(HalaRendererError::new("The global meshlet buffer is none!", None))?;
+      let mut meshlet_words = vec![0u32; scene_in_gpu.meshlet_count as usize * transparency::MESHLET_SIZE_IN_WORDS];
+      meshlets.download_gpu_memory_with_buffer(
+        &mut meshlet_words,
+        &self.resources.transfer_staging_buffer,
+        &self.resources.compute_command_buffers,
+      )?;
+      transparency::set_meshlet_ranges(&mut self.transparent_draws, &meshlet_words);
+      log::info!("The scene has {} transparent draws.", self.transparent_draws.len());
+    }
 
     self.scene_in_gpu = Some(scene_in_gpu);
 
+    // The meshes of the last frame belong to the old scene.
+    self.prev_mvp_mtxs.clear();
+    self.is_taa_history_valid = false;
+
+    // Use the No.1 camera of the scene as the initial camera.
+    if self.get_num_of_scene_cameras() > 0 {
+      self.set_camera_index(0)?;
+    }
+
+    Ok(())
+  }
+
+  /// Get the number of the cameras in the scene.
+  /// return: The number of the cameras.
+  pub fn get_num_of_scene_cameras(&self) -> usize {
+    match self.scene_in_gpu.as_ref() {
+      Some(scene) => scene.camera_view_matrices.len().min(scene.camera_proj_matrices.len()),
+      None => 0,
+    }
+  }
+
+  /// Get the camera in the scene.
+  /// param index: The index of the camera.
+  /// return: The view matrix and the projection matrix.
+  pub fn get_scene_camera(&self, index: usize) -> Option<(glam::Mat4, glam::Mat4)> {
+    let scene = self.scene_in_gpu.as_ref()?;
+    match (scene.camera_view_matrices.get(index), scene.camera_proj_matrices.get(index)) {
+      (Some(v_mtx), Some(p_mtx)) => Some((*v_mtx, *p_mtx)),
+      _ => None,
+    }
+  }
+
+  /// Get the GPU profiler.
+  /// return: The GPU profiler.
+  pub fn get_gpu_profiler(&self) -> &GpuProfiler {
+    &self.gpu_profiler
+  }
+
+  /// Get the mutable GPU profiler.
+  /// return: The mutable GPU profiler.
+  pub fn get_gpu_profiler_mut(&mut self) -> &mut GpuProfiler {
+    &mut self.gpu_profiler
+  }
+
+  /// Get the index of the active scene camera.
+  /// return: The index of the camera.
+  pub fn get_camera_index(&self) -> usize {
+    self.camera_index
+  }
+
+  /// Set the active scene camera and use its matrices to render the scene.
+  /// param index: The index of the camera.
+  /// return: The result.
+  pub fn set_camera_index(&mut self, index: usize) -> Result<(), HalaRendererError> {
+    let (v_mtx, p_mtx) = match self.get_scene_camera(index) {
+      Some(matrices) => matrices,
+      None => {
+        log::error!("The camera index \"{}\" is out of range, the scene has {} camera(s).", index, self.get_num_of_scene_cameras());
+        return Err(HalaRendererError::new("The camera index is out of range.", None));
+      }
+    };
+
+    self.camera_index = index;
+    self.set_camera(v_mtx, p_mtx);
+    // The view jumps to the other camera, the history does not match it.
+    self.is_taa_history_valid = false;
+
     Ok(())
   }
 
+  /// Set the camera used to render the scene.
+  /// param v_mtx: The view matrix.
+  /// param p_mtx: The projection matrix.
+  pub fn set_camera(&mut self, v_mtx: glam::Mat4, p_mtx: glam::Mat4) {
+    self.camera_v_mtx = v_mtx;
+    self.camera_p_mtx = p_mtx;
+  }
+
+  /// Get the camera used to render the scene.
+  /// return: The view matrix and the projection matrix.
+  pub fn get_camera(&self) -> (glam::Mat4, glam::Mat4) {
+    (self.camera_v_mtx, self.camera_p_mtx)
+  }
+
+  /// Drop the state carried from the last frames, the next frame renders as the first one.
+  /// The Hi-Z buffer, the eye adaptation, the TAA jitter and history and the render scale restart from scratch.
+  pub fn reset_temporal_state(&mut self) {
+    self.is_hiz_valid = false;
+    self.is_exposure_valid = false;
+    self.taa_frame_index = 0;
+    self.is_taa_history_valid = false;
+    self.prev_mvp_mtxs.clear();
+    self.dynamic_resolution.set_settings(self.dynamic_resolution.settings);
+  }
+
   /// Load all GPU programs.
   /// param path: The path to the GPU programs configure.
   /// return: The result.
   pub fn load_gpu_programs<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), HalaRendererError> {
     let path = path.as_ref();
-    let config = match GPUProgramsConfig::load(path) {
-      Ok(config) => config,
-      Err(err) => {
-        log::error!("Failed to load the GPU programs configure: {:?}", err);
-        return Err(HalaRendererError::new("Failed to load the GPU programs configure.", None));
-      }
-    };
+    let config = self.load_gpu_programs_config(path)?;
+    self.programs_file = Some(path.to_path_buf());
 
     if cfg!(debug_assertions) {
       for (name, _) in config.graphics_programs.iter() {
@@ -494,12 +1469,117 @@ impl VisRenderer {
     self.setup_debug()?;
     self.setup_once_visibility()?;
     self.setup_visibility()?;
+    self.update_offscreen_aliasing()?;
 
     pipeline_cache.save("./out/pipeline_cache.bin")?;
 
     Ok(())
   }
 
+  /// Load the GPU programs configure, match the material tile program to the G-Buffer formats and derive the material variants.
+  /// param path: The path to the GPU programs configure.
+  /// return: The GPU programs configure.
+  fn load_gpu_programs_config(&self, path: &std::path::Path) -> Result<GPUProgramsConfig, HalaRendererError> {
+    let mut config = match GPUProgramsConfig::load(path) {
+      Ok(config) => config,
+      Err(err) => {
+        log::error!("Failed to load the GPU programs configure: {:?}", err);
+        return Err(HalaRendererError::new("Failed to load the GPU programs configure.", None));
+      }
+    };
+
+    let material_tile_desc = config.graphics_programs.get_mut("material_tile")
+      .ok_or(HalaRendererError::new("Failed to find the material tile program configure.", None))?;
+    let (albedo_format, normal_format, material_format, emissive_format) = Self::get_gbuffer_formats(self.use_small_gbuffer);
+    // The motion is not a part of the G-Buffer, its format is fixed.
+    material_tile_desc.color_formats = vec![albedo_format, normal_format, material_format, emissive_format, hala_gfx::HalaFormat::R16G16_SFLOAT];
+
+    material_variant::add_material_variant_programs(&mut config)?;
+
+    Ok(config)
+  }
+
+  /// Switch between the small and the full G-Buffer formats.
+  /// The G-Buffer images and the material tile program are recreated if the GPU programs have been loaded.
+  /// param use_small_gbuffer: Whether use the small G-Buffer formats.
+  /// return: The result.
+  pub fn set_use_small_gbuffer(&mut self, use_small_gbuffer: bool) -> Result<(), HalaRendererError> {
+    if self.use_small_gbuffer == use_small_gbuffer {
+      return Ok(());
+    }
+    self.use_small_gbuffer = use_small_gbuffer;
+
+    let programs_file = match self.programs_file.clone() {
+      Some(programs_file) => programs_file,
+      None => return Ok(()),
+    };
+
+    // The program may be still used by the frames in flight.
+    self.wait_idle()?;
+
+    let mut config = self.load_gpu_programs_config(&programs_file)?;
+    let mut material_tile_config = HashMap::new();
+    if let Some(desc) = config.graphics_programs.remove("material_tile") {
+      material_tile_config.insert("material_tile".to_string(), desc);
+    }
+    let pipeline_cache = hala_gfx::HalaPipelineCache::new(
+      Rc::clone(&self.resources.context.borrow().logical_device),
+    )?;
+    let (
+      graphics_programs,
+      graphics_descriptor_sets
+    ) = self.create_graphics_program(
+      &material_tile_config,
+      &pipeline_cache,
+    )?;
+    self.graphics_programs.extend(graphics_programs);
+    self.graphics_descriptor_sets.extend(graphics_descriptor_sets);
+
+    // Recreate the G-Buffer images, this also updates the descriptor sets.
+    self.aliasing_plan = AliasingPlan::default();
+    self.update_offscreen_aliasing()?;
+
+    log::info!("Switched to the {} G-Buffer.", if use_small_gbuffer { "small" } else { "full" });
+
+    Ok(())
+  }
+
+  /// Switch between running the material classification on the async compute queue and on the graphics queue.
+  /// The switch takes effect from the next recorded frame.
+  /// param use_async_compute: Whether use the async compute queue.
+  /// return: The result.
+  pub fn set_use_async_compute(&mut self, use_async_compute: bool) -> Result<(), HalaRendererError> {
+    if self.use_async_compute == use_async_compute {
+      return Ok(());
+    }
+    log::info!("Switched to the {} material classification.", if use_async_compute { "async compute" } else { "serial" });
+    self.use_async_compute = use_async_compute;
+    // The async compute passes run alongside the graphics passes, which changes the lifetimes of the offscreen targets.
+    if self.programs_file.is_some() {
+      self.update_offscreen_aliasing()?;
+    }
+
+    Ok(())
+  }
+
+  /// Switch between the sorted forward transparency and the weighted blended OIT.
+  /// The switch takes effect from the next recorded frame.
+  /// param use_weighted_blended_oit: Whether use the weighted blended OIT.
+  /// return: The result.
+  pub fn set_use_weighted_blended_oit(&mut self, use_weighted_blended_oit: bool) -> Result<(), HalaRendererError> {
+    if self.use_weighted_blended_oit == use_weighted_blended_oit {
+      return Ok(());
+    }
+    log::info!("Switched to the {} transparency.", if use_weighted_blended_oit { "weighted blended OIT" } else { "sorted" });
+    self.use_weighted_blended_oit = use_weighted_blended_oit;
+    // The transparent passes read the depth differently, which changes the lifetimes of the offscreen targets.
+    if self.programs_file.is_some() {
+      self.update_offscreen_aliasing()?;
+    }
+
+    Ok(())
+  }
+
   /// Create the graphics program.
   /// param program_config: The program configure.
   /// param pipeline_cache: The pipeline cache.
@@ -564,6 +1644,22 @@ impl VisRenderer {
           Some(pipeline_cache),
           name,
         )?
+      } else if self.is_headless {
+        // The programs rendering to the final target use the headless output formats, there is no swapchain.
+        let mut desc = desc.clone();
+        desc.color_formats = vec![self.output_format];
+        desc.depth_format = Some(self.output_depth_format);
+        HalaGraphicsProgram::new(
+          Rc::clone(&self.resources.context.borrow().logical_device),
+          descriptor_set_layouts.as_slice(),
+          hala_gfx::HalaPipelineCreateFlags::default(),
+          &[] as &[hala_gfx::HalaVertexInputAttributeDescription],
+          &[] as &[hala_gfx::HalaVertexInputBindingDescription],
+          &[hala_gfx::HalaDynamicState::VIEWPORT, hala_gfx::HalaDynamicState::SCISSOR],
+          &desc,
+          Some(pipeline_cache),
+          name,
+        )?
       } else {
         HalaGraphicsProgram::with_swapchain(
           Rc::clone(&self.resources.context.borrow().logical_device),
@@ -645,236 +1741,433 @@ impl VisRenderer {
     Ok((programs, descriptor_sets))
   }
 
-  /// Record the rendering command buffer.
-  /// param index: The index of the current image.
-  /// param graphics_command_buffers: The graphics command buffers.
-  /// param compute_command_buffers: The compute command buffers.
+  /// Build the render graph of the frame by the debug settings.
+  /// param context: The context.
   /// param ui_fn: The draw UI function.
-  /// return: The result.
-  pub(crate) fn record_command_buffer<F>(
-    &self,
-    index: usize,
-    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
-    compute_command_buffers: &hala_gfx::HalaCommandBufferSet,
+  /// return: The render graph.
+  fn build_frame_graph<'a, F>(
+    &'a self,
+    context: &'a hala_gfx::HalaContext,
     ui_fn: F,
-  ) -> Result<(), HalaRendererError>
-    where F: FnOnce(usize, &hala_gfx::HalaCommandBufferSet) -> Result<(), hala_gfx::HalaGfxError>
+  ) -> Result<RenderGraph<'a>, HalaRendererError>
+    where F: FnOnce(usize, &hala_gfx::HalaCommandBufferSet) -> Result<(), hala_gfx::HalaGfxError> + 'a
   {
-    let context = self.resources.context.borrow();
-    let scene = self.scene_in_gpu.as_ref()
-      .ok_or(HalaRendererError::new("Failed to find the scene in the GPU.", None))?;
-
-    // Prepare the command buffer and timestamp.
-    graphics_command_buffers.reset(index, false)?;
-    graphics_command_buffers.begin(index, hala_gfx::HalaCommandBufferUsageFlags::empty())?;
-    graphics_command_buffers.reset_query_pool(index, &context.timestamp_query_pool, (index * 2) as u32, 2);
-    graphics_command_buffers.write_timestamp(index, hala_gfx::HalaPipelineStageFlags2::NONE, &context.timestamp_query_pool, (index * 2) as u32);
-
-    if cfg!(debug_assertions) {
-      graphics_command_buffers.begin_debug_label(index, "Draw", [1.0, 1.0, 1.0, 1.0]);
-    }
-
-    // If there is no frame, we need to clear the depth image.
-    if self.statistics.total_gpu_frames == 0 {
-      self.clear_depth_pass(index, graphics_command_buffers)?;
-      self.depth_reduction_pass(index, graphics_command_buffers)?;
+    let is_first_frame = !self.is_hiz_valid;
+    let mut graph = RenderGraph::new();
+    let visibility = graph.import_image(OffscreenTarget::Visibility.name(), &self.visibility_image, None);
+    let depth = graph.import_image(OffscreenTarget::Depth.name(), &self.depth_image, None);
+    let material_depth = graph.import_image(OffscreenTarget::MaterialDepth.name(), &self.material_depth_image, None);
+    let albedo = graph.import_image(OffscreenTarget::Albedo.name(), &self.albedo_image, None);
+    let normal = graph.import_image(OffscreenTarget::Normal.name(), &self.normal_image, None);
+    let material = graph.import_image(OffscreenTarget::Material.name(), &self.material_image, None);
+    let emissive = graph.import_image(OffscreenTarget::Emissive.name(), &self.emissive_image, None);
+    // The targets placed in overlapping memory wait for each other.
+    let transients = [visibility, depth, material_depth, albedo, normal, material, emissive];
+    for (handle, aliases) in transients.iter().zip(self.aliasing_plan.aliases.iter()) {
+      for alias in aliases.iter() {
+        graph.alias_memory(*handle, transients[*alias]);
+      }
     }
-
-    graphics_command_buffers.set_viewport(
-      index,
-      0,
-      &[
-        (
-          0.,
-          self.info.height as f32,
-          self.info.width as f32,
-          -(self.info.height as f32), // For vulkan y is down.
-          0.,
-          1.
-        ),
-      ],
+    // The Hi-Z buffer is read by the culling of the next frame.
+    let hiz = graph.import_image("hiz", &self.hiz_image, if is_first_frame { None } else { Some(RGAccess::ShaderRead) });
+    graph.export(hiz);
+    let indirect_draw = graph.import_buffer("indirect_draw", &self.indirect_draw_buffer, None);
+    let tile_index = graph.import_buffer("tile_index", &self.tile_index_buffer, None);
+    let light_clusters = graph.import_buffer("light_clusters", &self.light_cluster_buffer, None);
+    let shadow_map = graph.import_image("shadow_map", &self.shadow_map_image, None);
+    // The final view is rendered to the HDR targets at the render size, then tonemapped to the final target.
+    let scene_color = graph.import_image(
+      "scene_color",
+      self.scene_color_image.as_ref()
+        .ok_or(HalaRendererError::new("Failed to find the scene color image.", None))?,
+      None,
     );
-    graphics_command_buffers.set_scissor(
-      index,
-      0,
-      &[
-        (0, 0, self.info.width, self.info.height),
-      ],
+    let scene_depth = graph.import_image(
+      "scene_depth",
+      self.scene_depth_image.as_ref()
+        .ok_or(HalaRendererError::new("Failed to find the scene depth image.", None))?,
+      None,
+    );
+    let motion = graph.import_image(
+      "motion",
+      self.motion_image.as_ref()
+        .ok_or(HalaRendererError::new("Failed to find the motion image.", None))?,
+      None,
+    );
+    // The history written by the last frame is read by this frame, then the other history image is written.
+    let (history_read_index, history_write_index) = self.get_taa_history_indices();
+    let taa_history_images = match (self.taa_history_images.get(history_read_index), self.taa_history_images.get(history_write_index)) {
+      (Some(history_read_image), Some(history_write_image)) => (history_read_image, history_write_image),
+      _ => return Err(HalaRendererError::new("Failed to find the TAA history images.", None)),
+    };
+    let taa_history = graph.import_image(
+      "taa_history",
+      taa_history_images.0,
+      if self.is_taa_history_valid { Some(RGAccess::ShaderRead) } else { None },
+    );
+    let taa_output = graph.import_image("taa_output", taa_history_images.1, None);
+    let bloom = graph.import_image(
+      "bloom",
+      self.bloom_image.as_ref()
+        .ok_or(HalaRendererError::new("Failed to find the bloom image.", None))?,
+      None,
+    );
+    // The histogram is cleared and the adapted luminance is read by the next frame.
+    let luminance_histogram = graph.import_buffer("luminance_histogram", &self.luminance_histogram_buffer, Some(RGAccess::StorageWrite));
+    graph.export(luminance_histogram);
+    let exposure = graph.import_buffer("exposure", &self.exposure_buffer, Some(RGAccess::StorageWrite));
+    graph.export(exposure);
+    // The picked IDs are read back when the frame is finished.
+    let pick = graph.import_buffer("pick", &self.pick_buffer, Some(RGAccess::StorageWrite));
+    graph.export(pick);
+    let oit_images = match (self.oit_accumulation_image.as_ref(), self.oit_weight_image.as_ref()) {
+      (Some(oit_accumulation_image), Some(oit_weight_image)) => Some((
+        graph.import_image("oit_accumulation", oit_accumulation_image, None),
+        graph.import_image("oit_weight", oit_weight_image, None),
+      )),
+      _ => None,
+    };
+    let ssao_images = match (self.ssao_raw_image.as_ref(), self.ssao_image.as_ref()) {
+      (Some(ssao_raw_image), Some(ssao_image)) => Some((
+        graph.import_image("ssao_raw", ssao_raw_image, None),
+        graph.import_image("ssao", ssao_image, None),
+      )),
+      _ => None,
+    };
+    let pre_culling_flags = graph.import_buffer(
+      "pre_culling_flags",
+      self.pre_culling_flags.as_ref()
+        .ok_or(HalaRendererError::new("Failed to find the pre culling flags buffer.", None))?,
+      None,
     );
 
-    let is_debug_view = self.debug_settings.show_triangle || self.debug_settings.show_meshlet;
-    if is_debug_view {
-      self.setup_swapchain_begin_barriers(&context, index, graphics_command_buffers)?;
-
-      graphics_command_buffers.begin_rendering_with_swapchain(
-        index,
-        &context.swapchain,
-        (0, 0, context.gpu_req.width, context.gpu_req.height),
-        Some([25.0 / 255.0, 118.0 / 255.0, 210.0 / 255.0, 1.0]),
-        Some(0.0),
-        Some(0),
-      );
+    // If there is no valid Hi-Z buffer, e.g. the first frame or after resizing, we need to clear the depth image.
+    if is_first_frame {
+      graph.add_pass("clear_depth", None)
+        .write(depth, RGAccess::DepthAttachment)
+        .execute(move |index, command_buffers| self.clear_depth_pass(index, command_buffers));
+      graph.add_pass("clear_hiz", None)
+        .read(depth, RGAccess::DepthRead)
+        .write_and_transition(hiz, RGAccess::ColorAttachment, RGAccess::ShaderRead)
+        .execute(move |index, command_buffers| self.depth_reduction_pass(index, command_buffers));
+    }
 
-      if self.debug_settings.show_triangle {
-        let triangle_visualization_program = self.graphics_programs.get("triangle_visualization")
-          .ok_or(HalaRendererError::new("Failed to find the triangle visualization program.", None))?;
-        let triangle_visualization_descriptor_set = self.graphics_descriptor_sets.get("triangle_visualization");
-        self.draw_scene(
-          index,
-          graphics_command_buffers,
-          true,
-          triangle_visualization_program,
-          triangle_visualization_descriptor_set,
-        )?;
-      } else if self.debug_settings.show_meshlet {
-        let meshlet_visualization_program = self.graphics_programs.get("meshlet_visualization")
-          .ok_or(HalaRendererError::new("Failed to find the meshlet visualization program.", None))?;
-        let meshlet_visualization_descriptor_set = self.graphics_descriptor_sets.get("meshlet_visualization");
-        self.draw_scene(
-          index,
-          graphics_command_buffers,
-          true,
-          meshlet_visualization_program,
-          meshlet_visualization_descriptor_set,
-        )?;
-      }
+    let is_debug_view = self.debug_settings.show_triangle || self.debug_settings.show_meshlet;
+    let is_lighting_view = !is_debug_view
+      && !self.debug_settings.show_hiz
+      && !self.debug_settings.show_visibility
+      && !self.debug_settings.show_albedo
+      && !self.debug_settings.show_normal
+      && !self.debug_settings.show_material_depth;
+    // The transparent draws are only lit in the lighting view.
+    let oit_images = if is_lighting_view && self.use_weighted_blended_oit && !self.transparent_draws.is_empty() {
+      oit_images
+    } else {
+      None
+    };
+    // The ambient occlusion is only applied in the lighting view.
+    let ssao_images = if is_lighting_view && self.ssao_settings.enabled {
+      ssao_images
     } else {
+      None
+    };
+    if !is_debug_view {
       if self.debug_settings.disable_culling {
         // Write the visibility to the visibility buffer without culling.
-        self.no_culling_visibility_buffer_pass(index, graphics_command_buffers)?;
+        graph.add_pass("no_culling_visibility_buffer", Some(GpuPass::VisibilityBuffer))
+          .write(visibility, RGAccess::ColorAttachment)
+          .write(depth, RGAccess::DepthAttachment)
+          .execute(move |index, command_buffers| self.no_culling_visibility_buffer_pass(index, command_buffers));
       } else if self.debug_settings.one_pass_culling {
         // Culling the invisible meshlets by the last frame's Hi-Z buffer.
-        let one_pass_culling_program = self.graphics_programs.get("one_pass_culling")
-          .ok_or(HalaRendererError::new("Failed to find the one pass culling program.", None))?;
-        let one_pass_culling_descriptor_set = self.graphics_descriptor_sets.get("one_pass_culling");
-        self.culling_pass(index, graphics_command_buffers, one_pass_culling_program, one_pass_culling_descriptor_set)?;
-        self.depth_reduction_pass(index, graphics_command_buffers)?;
+        graph.add_pass("one_pass_culling", Some(GpuPass::Culling))
+          .read(hiz, RGAccess::ShaderRead)
+          .write(visibility, RGAccess::ColorAttachment)
+          .write(depth, RGAccess::DepthAttachment)
+          .execute(move |index, command_buffers| self.culling_pass(index, command_buffers, "one_pass_culling"));
       } else {
         // Culling the invisible meshlets by the last frame's Hi-Z buffer.
-        let pre_culling_program = self.graphics_programs.get("pre_culling")
-          .ok_or(HalaRendererError::new("Failed to find the pre culling program.", None))?;
-        let pre_culling_descriptor_set = self.graphics_descriptor_sets.get("pre_culling");
-        self.culling_pass(index, graphics_command_buffers, pre_culling_program, pre_culling_descriptor_set)?;
-        self.depth_reduction_pass(index, graphics_command_buffers)?;
+        graph.add_pass("pre_culling", Some(GpuPass::Culling))
+          .read(hiz, RGAccess::ShaderRead)
+          .write(visibility, RGAccess::ColorAttachment)
+          .write(depth, RGAccess::DepthAttachment)
+          .write(pre_culling_flags, RGAccess::StorageWrite)
+          .execute(move |index, command_buffers| self.culling_pass(index, command_buffers, "pre_culling"));
+        graph.add_pass("pre_depth_reduction", Some(GpuPass::DepthReduction))
+          .read(depth, RGAccess::DepthRead)
+          .write_and_transition(hiz, RGAccess::ColorAttachment, RGAccess::ShaderRead)
+          .execute(move |index, command_buffers| self.depth_reduction_pass(index, command_buffers));
         // Culling the truely invisible meshlets by the current frame's Hi-Z buffer.
         // Write the visibility to the visibility buffer.
-        self.visibility_buffer_pass(index, graphics_command_buffers)?;
-        self.depth_reduction_pass(index, graphics_command_buffers)?;
+        graph.add_pass("visibility_buffer", Some(GpuPass::VisibilityBuffer))
+          .read(hiz, RGAccess::ShaderRead)
+          .read(pre_culling_flags, RGAccess::StorageRead)
+          .modify(visibility, RGAccess::ColorAttachment)
+          .modify(depth, RGAccess::DepthAttachment)
+          .execute(move |index, command_buffers| self.visibility_buffer_pass(index, command_buffers));
       }
       // Write the material type to the depth buffer.
-      self.material_depth_pass(index, graphics_command_buffers)?;
+      graph.add_pass("material_depth", Some(GpuPass::MaterialDepth))
+        .read(visibility, RGAccess::ShaderRead)
+        .read(depth, RGAccess::DepthRead)
+        .write(material_depth, RGAccess::DepthAttachment)
+        .execute(move |index, command_buffers| self.material_depth_pass(index, command_buffers));
       // Clear the indirect draw buffer.
-      self.clear_indirect_buffer(index, graphics_command_buffers)?;
+      graph.add_pass("clear_indirect_buffer", Some(GpuPass::ClearIndirect))
+        .on_async_compute()
+        .write(indirect_draw, RGAccess::StorageWrite)
+        .execute(move |index, command_buffers| self.clear_indirect_buffer(index, command_buffers));
       // Classify the screen tiles by the material type.
-      self.material_classification_pass(index, graphics_command_buffers, compute_command_buffers)?;
+      // Only the material depth is read, so the depth reduction can run alongside it on the graphics queue.
+      graph.add_pass("material_classification", Some(GpuPass::Classification))
+        .on_async_compute()
+        .read(material_depth, RGAccess::DepthRead)
+        .modify(indirect_draw, RGAccess::StorageWrite)
+        .write(tile_index, RGAccess::StorageWrite)
+        .execute(move |index, command_buffers| self.material_classification_pass(index, command_buffers));
+      // Build the Hi-Z buffer for the next frame, the light culling also bounds the clusters by it.
+      if !self.debug_settings.disable_culling || is_lighting_view {
+        graph.add_pass("depth_reduction", Some(GpuPass::DepthReduction))
+          .read(depth, RGAccess::DepthRead)
+          .write_and_transition(hiz, RGAccess::ColorAttachment, RGAccess::ShaderRead)
+          .execute(move |index, command_buffers| self.depth_reduction_pass(index, command_buffers));
+      }
       // Write G-Buffer by tiles.
-      self.material_tile_pass(index, graphics_command_buffers)?;
+      graph.add_pass("material_tile", Some(GpuPass::MaterialTile))
+        .read(indirect_draw, RGAccess::IndirectRead)
+        .read(tile_index, RGAccess::StorageRead)
+        .read(visibility, RGAccess::ShaderRead)
+        .modify(material_depth, RGAccess::DepthAttachment)
+        .write(albedo, RGAccess::ColorAttachment)
+        .write(normal, RGAccess::ColorAttachment)
+        .write(material, RGAccess::ColorAttachment)
+        .write(emissive, RGAccess::ColorAttachment)
+        .write(motion, RGAccess::ColorAttachment)
+        .execute(move |index, command_buffers| self.material_tile_pass(index, command_buffers));
+      // Bin the lights into the clusters between the near plane and the farthest depth of each tile.
+      if is_lighting_view {
+        graph.add_pass("light_culling", Some(GpuPass::LightCulling))
+          .read(hiz, RGAccess::ShaderRead)
+          .write(light_clusters, RGAccess::StorageWrite)
+          .execute(move |index, command_buffers| self.light_culling_pass(index, command_buffers));
+        // Draw the cascades of the directional light by the meshlet culling pipeline.
+        graph.add_pass("shadow_map", Some(GpuPass::Shadow))
+          .write(shadow_map, RGAccess::DepthAttachment)
+          .execute(move |index, command_buffers| self.shadow_map_pass(index, command_buffers));
+        // Compute the ambient occlusion from the depth and the normal, then blur it without crossing the edges.
+        if let Some((ssao_raw, ssao)) = ssao_images {
+          let is_blur = self.ssao_settings.blur;
+          graph.add_pass("ssao", Some(GpuPass::SSAO))
+            .read(depth, RGAccess::DepthRead)
+            .read(normal, RGAccess::ShaderRead)
+            .write(if is_blur { ssao_raw } else { ssao }, RGAccess::StorageWrite)
+            .execute(move |index, command_buffers| self.ssao_pass(index, command_buffers));
+          if is_blur {
+            graph.add_pass("ssao_blur", Some(GpuPass::SSAO))
+              .read(ssao_raw, RGAccess::ShaderRead)
+              .read(depth, RGAccess::DepthRead)
+              .write(ssao, RGAccess::StorageWrite)
+              .execute(move |index, command_buffers| self.ssao_blur_pass(index, command_buffers));
+          }
+        }
+        // Accumulate the transparent draws in any order, the final pass composites them over the lighting result.
+        if let Some((oit_accumulation, oit_weight)) = oit_images {
+          graph.add_pass("transparent_oit", Some(GpuPass::Transparency))
+            .read(depth, RGAccess::DepthRead)
+            .read(light_clusters, RGAccess::StorageRead)
+            .read(shadow_map, RGAccess::DepthRead)
+            .write(oit_accumulation, RGAccess::ColorAttachment)
+            .write(oit_weight, RGAccess::ColorAttachment)
+            .execute(move |index, command_buffers| self.transparent_oit_pass(index, command_buffers));
+        }
+      }
+    }
 
-      self.setup_swapchain_begin_barriers(&context, index, graphics_command_buffers)?;
+    // Draw the lighting result or the debug view.
+    // The triangle and meshlet views draw the scene directly, so they are not timed as the lighting.
+    let final_pass = graph.add_pass("final_view", Some(if is_debug_view { GpuPass::DebugDraw } else { GpuPass::Lighting }))
+      .write(scene_color, RGAccess::ColorAttachment)
+      .write(scene_depth, RGAccess::DepthAttachment);
+    let final_pass = if is_debug_view {
+      final_pass
+    } else if self.debug_settings.show_hiz {
+      final_pass.read(hiz, RGAccess::ShaderRead)
+    } else if self.debug_settings.show_visibility {
+      final_pass.read(visibility, RGAccess::ShaderRead)
+    } else if self.debug_settings.show_albedo {
+      final_pass.read(albedo, RGAccess::ShaderRead)
+    } else if self.debug_settings.show_normal {
+      final_pass.read(normal, RGAccess::ShaderRead)
+    } else if self.debug_settings.show_material_depth {
+      final_pass.read(material_depth, RGAccess::DepthRead)
+    } else {
+      let final_pass = final_pass
+        .read(albedo, RGAccess::ShaderRead)
+        .read(normal, RGAccess::ShaderRead)
+        .read(material, RGAccess::ShaderRead)
+        .read(emissive, RGAccess::ShaderRead)
+        .read(depth, RGAccess::DepthRead)
+        .read(light_clusters, RGAccess::StorageRead)
+        .read(shadow_map, RGAccess::DepthRead);
+      let final_pass = match ssao_images {
+        Some((_, ssao)) => final_pass.read(ssao, RGAccess::ShaderRead),
+        None => final_pass,
+      };
+      match oit_images {
+        Some((oit_accumulation, oit_weight)) => final_pass
+          .read(oit_accumulation, RGAccess::ShaderRead)
+          .read(oit_weight, RGAccess::ShaderRead),
+        None => final_pass,
+      }
+    };
+    final_pass.execute(move |index, command_buffers| {
+      self.begin_scene_rendering(index, command_buffers)?;
+      self.final_view_pass(index, command_buffers)?;
+      command_buffers.end_rendering(index);
+      Ok(())
+    });
+
+    // Read the IDs under the cursor after the visibility and the depth of the opaque draws are complete.
+    if self.get_pick_texel().is_some() {
+      graph.add_pass("pick", None)
+        .read(visibility, RGAccess::ShaderRead)
+        .read(depth, RGAccess::DepthRead)
+        .write(pick, RGAccess::StorageWrite)
+        .execute(move |index, command_buffers| self.pick_pass(index, command_buffers));
+    }
 
-      graphics_command_buffers.begin_rendering_with_swapchain(
-        index,
-        &context.swapchain,
-        (0, 0, context.gpu_req.width, context.gpu_req.height),
-        Some([25.0 / 255.0, 118.0 / 255.0, 210.0 / 255.0, 1.0]),
-        Some(0.0),
-        Some(0),
-      );
+    // Blend the lighting result with the reprojected history, the tonemap pass reads the resolved color.
+    let is_taa = self.is_taa_active();
+    if is_taa {
+      graph.add_pass("taa", Some(GpuPass::TAA))
+        .read(scene_color, RGAccess::ShaderRead)
+        .read(taa_history, RGAccess::ShaderRead)
+        .read(motion, RGAccess::ShaderRead)
+        .read(depth, RGAccess::DepthRead)
+        .write(taa_output, RGAccess::ColorAttachment)
+        .execute(move |index, command_buffers| self.taa_pass(index, command_buffers));
+    }
 
-      if self.debug_settings.show_hiz {
-        let hiz_visualization_program = self.graphics_programs.get("hiz_visualization")
-          .ok_or(HalaRendererError::new("Failed to find the Hi-Z visualization program.", None))?;
-        hiz_visualization_program.push_constants_f32(
-          index,
-          graphics_command_buffers,
-          0,
-          &[100f32]
-        );
-        self.draw_screen_quad(
-          index,
-          graphics_command_buffers,
-          hiz_visualization_program,
-          Some(self.hiz_descriptor_sets[self.debug_settings.hiz_level as usize].as_ref()),
-        )?;
-      } else if self.debug_settings.show_visibility {
-        let id_buffer_visualization_program = self.graphics_programs.get("id_buffer_visualization")
-          .ok_or(HalaRendererError::new("Failed to find the id buffer visualization program.", None))?;
-        let id_buffer_visualization_descriptor_set = self.graphics_descriptor_sets.get("id_buffer_visualization");
-        self.draw_screen_quad(
-          index,
-          graphics_command_buffers,
-          id_buffer_visualization_program,
-          id_buffer_visualization_descriptor_set,
-        )?;
-      } else if self.debug_settings.show_material_depth || self.debug_settings.show_albedo || self.debug_settings.show_normal {
-        if self.debug_settings.show_albedo {
-          graphics_command_buffers.set_image_barriers(
-            index,
-            &[
-              hala_gfx::HalaImageBarrierInfo {
-                old_layout: hala_gfx::HalaImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-                new_layout: hala_gfx::HalaImageLayout::SHADER_READ_ONLY_OPTIMAL,
-                src_access_mask: hala_gfx::HalaAccessFlags2::COLOR_ATTACHMENT_WRITE,
-                dst_access_mask: hala_gfx::HalaAccessFlags2::INPUT_ATTACHMENT_READ,
-                src_stage_mask: hala_gfx::HalaPipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
-                dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::FRAGMENT_SHADER,
-                aspect_mask: hala_gfx::HalaImageAspectFlags::COLOR,
-                image: self.albedo_image.raw,
-                ..Default::default()
-              },
-            ],
-          );
-        } else if self.debug_settings.show_normal {
-          graphics_command_buffers.set_image_barriers(
-            index,
-            &[
-              hala_gfx::HalaImageBarrierInfo {
-                old_layout: hala_gfx::HalaImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-                new_layout: hala_gfx::HalaImageLayout::SHADER_READ_ONLY_OPTIMAL,
-                src_access_mask: hala_gfx::HalaAccessFlags2::COLOR_ATTACHMENT_WRITE,
-                dst_access_mask: hala_gfx::HalaAccessFlags2::INPUT_ATTACHMENT_READ,
-                src_stage_mask: hala_gfx::HalaPipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
-                dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::FRAGMENT_SHADER,
-                aspect_mask: hala_gfx::HalaImageAspectFlags::COLOR,
-                image: self.normal_image.raw,
-                ..Default::default()
-              },
-            ],
-          );
-        }
+    // Blur the color to be tonemapped through the mip chain, the tonemap pass blends it back.
+    let tonemap_source = if is_taa { taa_output } else { scene_color };
+    let is_bloom = self.is_bloom_active();
+    if is_bloom {
+      graph.add_pass("bloom", Some(GpuPass::Bloom))
+        .read(tonemap_source, RGAccess::ShaderRead)
+        .write_and_transition(bloom, RGAccess::ColorAttachment, RGAccess::ShaderRead)
+        .execute(move |index, command_buffers| self.bloom_pass(index, command_buffers));
+    }
 
-        let attachment_to_screen_program = self.graphics_programs.get("attachment_to_screen")
-          .ok_or(HalaRendererError::new("Failed to find the attachment to screen program.", None))?;
-        let attachment_to_screen_descriptor_set = self.graphics_descriptor_sets.get("attachment_to_screen");
-        // Scale the material depth value to visualize.
-        let scale = GlobalConstants::CLASSIFY_DEPTH_RANGE as f32 / scene.materials.len() as f32;
-        let scales = if self.debug_settings.show_material_depth {
-          [scale, scale, scale, 1.0]
-        } else {
-          [1.0, 1.0, 1.0, 1.0]
-        };
-        attachment_to_screen_program.push_constants_f32(
-          index,
-          graphics_command_buffers,
-          0,
-          &scales,
-        );
-        self.draw_screen_quad(
-          index,
-          graphics_command_buffers,
-          attachment_to_screen_program,
-          attachment_to_screen_descriptor_set,
-        )?;
-      } else {
-        self.lighting_pass(index, graphics_command_buffers)?;
+    // Average the luminance of the lighting result and adapt the exposure to it.
+    let is_auto_exposure = self.is_tonemapped_view() && self.tonemap_settings.auto_exposure;
+    if is_auto_exposure {
+      graph.add_pass("luminance_histogram", Some(GpuPass::Exposure))
+        .read(scene_color, RGAccess::ShaderRead)
+        .modify(luminance_histogram, RGAccess::StorageWrite)
+        .execute(move |index, command_buffers| self.luminance_histogram_pass(index, command_buffers));
+      graph.add_pass("exposure", Some(GpuPass::Exposure))
+        .modify(luminance_histogram, RGAccess::StorageWrite)
+        .modify(exposure, RGAccess::StorageWrite)
+        .execute(move |index, command_buffers| self.exposure_pass(index, command_buffers));
+    }
+
+    // Tonemap the scene color onto the final target at the window size, outline the selected object, then draw the UI.
+    let output_pass = graph.add_pass("tonemap", None)
+      .as_output()
+      .read(tonemap_source, RGAccess::ShaderRead);
+    let output_pass = if is_auto_exposure {
+      output_pass.read(exposure, RGAccess::StorageRead)
+    } else {
+      output_pass
+    };
+    let output_pass = if is_bloom {
+      output_pass.read(bloom, RGAccess::ShaderRead)
+    } else {
+      output_pass
+    };
+    let is_selection_outline = self.is_selection_outline_active();
+    let output_pass = if is_selection_outline {
+      output_pass
+        .read(visibility, RGAccess::ShaderRead)
+        .read(depth, RGAccess::DepthRead)
+    } else {
+      output_pass
+    };
+    output_pass.execute(move |index, command_buffers| {
+      self.begin_final_rendering(context, index, command_buffers)?;
+
+      self.gpu_profiler.begin_scope(index, command_buffers, GpuPass::Tonemap);
+      self.tonemap_pass(index, command_buffers)?;
+      if is_selection_outline {
+        self.selection_outline_pass(index, command_buffers)?;
       }
+      self.gpu_profiler.end_scope(index, command_buffers, GpuPass::Tonemap);
+
+      self.gpu_profiler.begin_scope(index, command_buffers, GpuPass::UI);
+      ui_fn(index, command_buffers)?;
+      self.gpu_profiler.end_scope(index, command_buffers, GpuPass::UI);
+
+      self.end_final_rendering(context, index, command_buffers)
+    });
+
+    Ok(graph)
+  }
+
+  /// Record the rendering command buffer.
+  /// param index: The index of the current image.
+  /// param graphics_command_buffers: The graphics command buffers.
+  /// param compute_command_buffers: The compute command buffers.
+  /// param ui_fn: The draw UI function.
+  /// return: The result.
+  pub(crate) fn record_command_buffer<F>(
+    &self,
+    index: usize,
+    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
+    compute_command_buffers: &hala_gfx::HalaCommandBufferSet,
+    ui_fn: F,
+  ) -> Result<(), HalaRendererError>
+    where F: FnOnce(usize, &hala_gfx::HalaCommandBufferSet) -> Result<(), hala_gfx::HalaGfxError>
+  {
+    let context = self.resources.context.borrow();
+    if self.scene_in_gpu.is_none() {
+      return Err(HalaRendererError::new("Failed to find the scene in the GPU.", None));
+    }
+
+    // With the async compute, the frame starts in the pre graphics command buffers.
+    let async_compute = if self.use_async_compute {
+      Some(self.async_compute.get_graph_command_buffers(compute_command_buffers))
+    } else {
+      None
+    };
+    let first_command_buffers = async_compute.as_ref().map_or(graphics_command_buffers, |async_compute| async_compute.pre_graphics);
+
+    // Prepare the command buffer and timestamp.
+    graphics_command_buffers.reset(index, false)?;
+    graphics_command_buffers.begin(index, hala_gfx::HalaCommandBufferUsageFlags::empty())?;
+    if async_compute.is_some() {
+      self.async_compute.begin(index, compute_command_buffers)?;
     }
+    first_command_buffers.reset_query_pool(index, &context.timestamp_query_pool, (index * 2) as u32, 2);
+    first_command_buffers.write_timestamp(index, hala_gfx::HalaPipelineStageFlags2::NONE, &context.timestamp_query_pool, (index * 2) as u32);
+    self.gpu_profiler.begin_frame(index, first_command_buffers);
 
-    ui_fn(index, graphics_command_buffers)?;
+    if cfg!(debug_assertions) {
+      graphics_command_buffers.begin_debug_label(index, "Draw", [1.0, 1.0, 1.0, 1.0]);
+    }
 
-    graphics_command_buffers.end_rendering(index);
+    // All graphics command buffers may draw, the offscreen passes use the render size.
+    let mut draw_command_buffers = vec![graphics_command_buffers];
+    if let Some(async_compute) = async_compute.as_ref() {
+      draw_command_buffers.push(async_compute.pre_graphics);
+      draw_command_buffers.push(async_compute.overlap_graphics);
+    }
+    for command_buffers in draw_command_buffers {
+      self.set_screen_viewport(index, command_buffers, self.render_width, self.render_height);
+    }
 
-    self.setup_swapchain_end_barriers(&context, index, graphics_command_buffers)?;
+    // The barriers between the passes are derived from the declared accesses.
+    let graph = self.build_frame_graph(&context, ui_fn)?;
+    graph.execute(index, graphics_command_buffers, async_compute.as_ref(), &self.gpu_profiler)?;
 
     if cfg!(debug_assertions) {
       graphics_command_buffers.end_debug_label(index);
@@ -885,6 +2178,11 @@ impl VisRenderer {
       hala_gfx::HalaPipelineStageFlags2::ALL_COMMANDS,
       &context.timestamp_query_pool,
       (index * 2 + 1) as u32);
+    // The graphics command buffer is submitted last and waits for the async compute, so the frame ends here.
+    self.gpu_profiler.end_frame(index, graphics_command_buffers);
+    if async_compute.is_some() {
+      self.async_compute.end(index, compute_command_buffers)?;
+    }
     graphics_command_buffers.end(index)?;
 
     Ok(())
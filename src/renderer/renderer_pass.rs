@@ -6,6 +6,18 @@ use hala_renderer::{
 use crate::renderer::{
   GlobalConstants,
   VisRenderer,
+  render_graph::RGAccess,
+  material_variant::{
+    DRAW_FLAG_DOUBLE_SIDED,
+    NUM_OF_MATERIAL_VARIANTS,
+    get_variant_program_name,
+  },
+  transparency::{
+    TRANSPARENT_ALL_DRAWS,
+    sort_transparent_draws,
+  },
+  tonemap::LUMINANCE_HISTOGRAM_THREAD_WIDTH,
+  ssao::SSAO_THREAD_WIDTH,
 };
 
 /// The debug implementation of the visibility renderer.
@@ -79,6 +91,202 @@ impl VisRenderer {
     Ok(())
   }
 
+  /// Whether the depth format of the final target has the stencil aspect.
+  /// return: The result.
+  fn has_output_stencil(&self) -> bool {
+    matches!(
+      self.output_depth_format,
+      hala_gfx::HalaFormat::D16_UNORM_S8_UINT | hala_gfx::HalaFormat::D24_UNORM_S8_UINT | hala_gfx::HalaFormat::D32_SFLOAT_S8_UINT
+    )
+  }
+
+  /// Begin rendering to the final target.
+  /// If the offscreen output is enabled, render to the final image of the frame in flight instead of the swapchain.
+  /// param context: The context.
+  /// param index: The index of the current image.
+  /// param graphics_command_buffers: The graphics command buffers.
+  /// return: The result.
+  pub(crate) fn begin_final_rendering(
+    &self,
+    context: &hala_gfx::HalaContext,
+    index: usize,
+    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
+  ) -> Result<(), HalaRendererError> {
+    match (self.final_images.get(index), self.final_depth_images.get(index)) {
+      (Some(final_image), Some(final_depth_image)) => {
+        graphics_command_buffers.set_image_barriers(
+          index,
+          &[
+            hala_gfx::HalaImageBarrierInfo {
+              old_layout: hala_gfx::HalaImageLayout::UNDEFINED,
+              new_layout: hala_gfx::HalaImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+              src_access_mask: hala_gfx::HalaAccessFlags2::NONE,
+              dst_access_mask: hala_gfx::HalaAccessFlags2::COLOR_ATTACHMENT_WRITE,
+              src_stage_mask: hala_gfx::HalaPipelineStageFlags2::TOP_OF_PIPE,
+              dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+              aspect_mask: hala_gfx::HalaImageAspectFlags::COLOR,
+              image: final_image.raw,
+              ..Default::default()
+            },
+            hala_gfx::HalaImageBarrierInfo {
+              old_layout: hala_gfx::HalaImageLayout::UNDEFINED,
+              new_layout: hala_gfx::HalaImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+              src_access_mask: hala_gfx::HalaAccessFlags2::NONE,
+              dst_access_mask: hala_gfx::HalaAccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE,
+              src_stage_mask: hala_gfx::HalaPipelineStageFlags2::TOP_OF_PIPE,
+              dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::EARLY_FRAGMENT_TESTS | hala_gfx::HalaPipelineStageFlags2::LATE_FRAGMENT_TESTS,
+              aspect_mask: hala_gfx::HalaImageAspectFlags::DEPTH | if self.has_output_stencil() { hala_gfx::HalaImageAspectFlags::STENCIL } else { hala_gfx::HalaImageAspectFlags::empty() },
+              image: final_depth_image.raw,
+              ..Default::default()
+            },
+          ],
+        );
+
+        graphics_command_buffers.begin_rendering_with(
+          index,
+          &[final_image],
+          Some(final_depth_image),
+          (0, 0, self.info.width, self.info.height),
+          &[Some([25.0 / 255.0, 118.0 / 255.0, 210.0 / 255.0, 1.0])],
+          Some(0.0),
+          Some(0),
+          hala_gfx::HalaAttachmentStoreOp::STORE,
+          hala_gfx::HalaAttachmentStoreOp::DONT_CARE,
+          hala_gfx::HalaAttachmentStoreOp::DONT_CARE,
+        );
+      },
+      _ => {
+        self.setup_swapchain_begin_barriers(context, index, graphics_command_buffers)?;
+
+        graphics_command_buffers.begin_rendering_with_swapchain(
+          index,
+          &context.swapchain,
+          (0, 0, context.gpu_req.width, context.gpu_req.height),
+          Some([25.0 / 255.0, 118.0 / 255.0, 210.0 / 255.0, 1.0]),
+          Some(0.0),
+          Some(0),
+        );
+      },
+    }
+
+    Ok(())
+  }
+
+  /// End rendering to the final target.
+  /// If the offscreen output is enabled, the swapchain image is only transitioned to the present layout.
+  /// param context: The context.
+  /// param index: The index of the current image.
+  /// param graphics_command_buffers: The graphics command buffers.
+  /// return: The result.
+  pub(crate) fn end_final_rendering(
+    &self,
+    context: &hala_gfx::HalaContext,
+    index: usize,
+    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
+  ) -> Result<(), HalaRendererError> {
+    graphics_command_buffers.end_rendering(index);
+
+    if let Some(final_image) = self.final_images.get(index) {
+      let mut barriers = vec![
+        hala_gfx::HalaImageBarrierInfo {
+          old_layout: hala_gfx::HalaImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+          new_layout: hala_gfx::HalaImageLayout::SHADER_READ_ONLY_OPTIMAL,
+          src_access_mask: hala_gfx::HalaAccessFlags2::COLOR_ATTACHMENT_WRITE,
+          dst_access_mask: hala_gfx::HalaAccessFlags2::SHADER_READ | hala_gfx::HalaAccessFlags2::TRANSFER_READ,
+          src_stage_mask: hala_gfx::HalaPipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+          dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::FRAGMENT_SHADER | hala_gfx::HalaPipelineStageFlags2::TRANSFER,
+          aspect_mask: hala_gfx::HalaImageAspectFlags::COLOR,
+          image: final_image.raw,
+          ..Default::default()
+        },
+      ];
+      // The swapchain image is not rendered, but it still needs to be in the present layout.
+      if !self.is_headless {
+        barriers.push(hala_gfx::HalaImageBarrierInfo {
+          old_layout: hala_gfx::HalaImageLayout::UNDEFINED,
+          new_layout: hala_gfx::HalaImageLayout::PRESENT_SRC,
+          src_access_mask: hala_gfx::HalaAccessFlags2::NONE,
+          dst_access_mask: hala_gfx::HalaAccessFlags2::NONE,
+          src_stage_mask: hala_gfx::HalaPipelineStageFlags2::TOP_OF_PIPE,
+          dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::BOTTOM_OF_PIPE,
+          aspect_mask: hala_gfx::HalaImageAspectFlags::COLOR,
+          image: context.swapchain.images[index],
+          ..Default::default()
+        });
+      }
+      graphics_command_buffers.set_image_barriers(index, &barriers);
+    } else {
+      self.setup_swapchain_end_barriers(context, index, graphics_command_buffers)?;
+    }
+
+    Ok(())
+  }
+
+  /// Set the flipped viewport and the scissor covering the screen.
+  /// param index: The index of the current image.
+  /// param graphics_command_buffers: The graphics command buffers.
+  /// param width: The width of the screen.
+  /// param height: The height of the screen.
+  pub(crate) fn set_screen_viewport(
+    &self,
+    index: usize,
+    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
+    width: u32,
+    height: u32,
+  ) {
+    graphics_command_buffers.set_viewport(
+      index,
+      0,
+      &[
+        (
+          0.,
+          height as f32,
+          width as f32,
+          -(height as f32), // For vulkan y is down.
+          0.,
+          1.
+        ),
+      ],
+    );
+    graphics_command_buffers.set_scissor(
+      index,
+      0,
+      &[
+        (0, 0, width, height),
+      ],
+    );
+  }
+
+  /// Begin rendering the final view to the scene images at the render size.
+  /// param index: The index of the current image.
+  /// param graphics_command_buffers: The graphics command buffers.
+  /// return: The result.
+  pub(crate) fn begin_scene_rendering(
+    &self,
+    index: usize,
+    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
+  ) -> Result<(), HalaRendererError> {
+    let scene_color_image = self.scene_color_image.as_ref()
+      .ok_or(HalaRendererError::new("Failed to find the scene color image.", None))?;
+    let scene_depth_image = self.scene_depth_image.as_ref()
+      .ok_or(HalaRendererError::new("Failed to find the scene depth image.", None))?;
+
+    graphics_command_buffers.begin_rendering_with(
+      index,
+      &[scene_color_image],
+      Some(scene_depth_image),
+      (0, 0, self.render_width, self.render_height),
+      &[Some([25.0 / 255.0, 118.0 / 255.0, 210.0 / 255.0, 1.0])],
+      Some(0.0),
+      Some(0),
+      hala_gfx::HalaAttachmentStoreOp::STORE,
+      hala_gfx::HalaAttachmentStoreOp::DONT_CARE,
+      hala_gfx::HalaAttachmentStoreOp::DONT_CARE,
+    );
+
+    Ok(())
+  }
+
   /// Draw the scene.
   /// param index: The index of the current image.
   /// param graphics_command_buffers: The graphics command buffers.
@@ -93,6 +301,44 @@ impl VisRenderer {
     require_push_constants: bool,
     graphics_program: &HalaGraphicsProgram,
     descriptor_set: Option<&hala_gfx::HalaDescriptorSet>,
+  ) -> Result<(), HalaRendererError> {
+    let scene = self.scene_in_gpu.as_ref().ok_or(hala_gfx::HalaGfxError::new("The scene in GPU is none!", None))?;
+
+    if require_push_constants {
+      let mut push_constants = Vec::new();
+      push_constants.extend_from_slice(&scene.meshlet_count.to_le_bytes());
+
+      graphics_program.push_constants(
+        index,
+        graphics_command_buffers,
+        0,
+        push_constants.as_slice(),
+      );
+    }
+
+    self.draw_meshlets(
+      index,
+      graphics_command_buffers,
+      scene.meshlet_count,
+      graphics_program,
+      descriptor_set,
+    )
+  }
+
+  /// Draw the meshlets, one task shader thread per meshlet.
+  /// param index: The index of the current image.
+  /// param graphics_command_buffers: The graphics command buffers.
+  /// param meshlet_count: The number of the meshlets to dispatch.
+  /// param graphics_program: The graphics program.
+  /// param descriptor_set: The descriptor set.
+  /// return: The result.
+  pub(crate) fn draw_meshlets(
+    &self,
+    index: usize,
+    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
+    meshlet_count: u32,
+    graphics_program: &HalaGraphicsProgram,
+    descriptor_set: Option<&hala_gfx::HalaDescriptorSet>,
   ) -> Result<(), HalaRendererError> {
     let dynamic_descriptor_set = self.dynamic_descriptor_set.as_ref()
       .ok_or(HalaRendererError::new("Failed to find the dynamic descriptor set.", None))?;
@@ -122,28 +368,61 @@ impl VisRenderer {
       );
     };
 
-    // Render the scene.
-    let scene = self.scene_in_gpu.as_ref().ok_or(hala_gfx::HalaGfxError::new("The scene in GPU is none!", None))?;
+    let dispatch_size_x = (meshlet_count + 32 - 1) / 32;  // 32 threads per task group.
+    graphics_command_buffers.draw_mesh_tasks(
+      index,
+      dispatch_size_x,
+      1,
+      1,
+    );
 
-    if require_push_constants {
-      let mut push_constants = Vec::new();
-      push_constants.extend_from_slice(&scene.meshlet_count.to_le_bytes());
+    Ok(())
+  }
+
+  /// Draw the scene once for each material variant used by the scene.
+  /// Each draw only dispatches the meshlets of its variant, the variant is appended to the push constants.
+  /// param index: The index of the current image.
+  /// param graphics_command_buffers: The graphics command buffers.
+  /// param program_name: The name of the opaque single-sided program.
+  /// param push_constants: The push constants before the material variant.
+  /// return: The result.
+  pub(crate) fn draw_scene_material_variants(
+    &self,
+    index: usize,
+    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
+    program_name: &str,
+    push_constants: &[u8],
+  ) -> Result<(), HalaRendererError> {
+    for material_variant in 0..NUM_OF_MATERIAL_VARIANTS {
+      if self.material_variants & (1 << material_variant) == 0 {
+        continue;
+      }
+
+      let variant_program_name = get_variant_program_name(program_name, material_variant);
+      let graphics_program = self.graphics_programs.get(&variant_program_name)
+        .ok_or_else(|| {
+          log::error!("Failed to find the \"{}\" program.", variant_program_name);
+          HalaRendererError::new("Failed to find the material variant program.", None)
+        })?;
+      let descriptor_set = self.graphics_descriptor_sets.get(&variant_program_name);
 
+      let mut variant_push_constants = push_constants.to_vec();
+      variant_push_constants.extend_from_slice(&material_variant.to_le_bytes());
       graphics_program.push_constants(
         index,
         graphics_command_buffers,
         0,
-        push_constants.as_slice(),
+        variant_push_constants.as_slice(),
       );
-    }
 
-    let dispatch_size_x = (scene.meshlet_count + 32 - 1) / 32;  // 32 threads per task group.
-    graphics_command_buffers.draw_mesh_tasks(
-      index,
-      dispatch_size_x,
-      1,
-      1,
-    );
+      self.draw_scene(
+        index,
+        graphics_command_buffers,
+        false,
+        graphics_program,
+        descriptor_set,
+      )?;
+    }
 
     Ok(())
   }
@@ -212,28 +491,11 @@ impl VisRenderer {
     index: usize,
     graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
   ) -> Result<(), HalaRendererError> {
-    graphics_command_buffers.set_image_barriers(
-      index,
-      &[
-        hala_gfx::HalaImageBarrierInfo {
-          old_layout: hala_gfx::HalaImageLayout::UNDEFINED,
-          new_layout: hala_gfx::HalaImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
-          src_access_mask: hala_gfx::HalaAccessFlags2::NONE,
-          dst_access_mask: hala_gfx::HalaAccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE,
-          src_stage_mask: hala_gfx::HalaPipelineStageFlags2::TOP_OF_PIPE,
-          dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::EARLY_FRAGMENT_TESTS | hala_gfx::HalaPipelineStageFlags2::LATE_FRAGMENT_TESTS,
-          aspect_mask: hala_gfx::HalaImageAspectFlags::DEPTH,
-          image: self.depth_image.raw,
-          ..Default::default()
-        },
-      ],
-    );
-
     graphics_command_buffers.begin_rendering_with(
       index,
       &[],
       Some(self.depth_image.as_ref()),
-      (0, 0, self.info.width, self.info.height),
+      (0, 0, self.render_width, self.render_height),
       &[],
       Some(0.0),
       None,
@@ -256,40 +518,11 @@ impl VisRenderer {
     index: usize,
     graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
   ) -> Result<(), HalaRendererError> {
-    // Setup barriers.
-    graphics_command_buffers.set_image_barriers(
-      index,
-      &[
-        hala_gfx::HalaImageBarrierInfo {
-          old_layout: hala_gfx::HalaImageLayout::UNDEFINED,
-          new_layout: hala_gfx::HalaImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-          src_access_mask: hala_gfx::HalaAccessFlags2::NONE,
-          dst_access_mask: hala_gfx::HalaAccessFlags2::COLOR_ATTACHMENT_WRITE,
-          src_stage_mask: hala_gfx::HalaPipelineStageFlags2::TOP_OF_PIPE,
-          dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
-          aspect_mask: hala_gfx::HalaImageAspectFlags::COLOR,
-          image: self.visibility_image.raw,
-          ..Default::default()
-        },
-        hala_gfx::HalaImageBarrierInfo {
-          old_layout: hala_gfx::HalaImageLayout::UNDEFINED,
-          new_layout: hala_gfx::HalaImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
-          src_access_mask: hala_gfx::HalaAccessFlags2::NONE,
-          dst_access_mask: hala_gfx::HalaAccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE,
-          src_stage_mask: hala_gfx::HalaPipelineStageFlags2::TOP_OF_PIPE,
-          dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::EARLY_FRAGMENT_TESTS | hala_gfx::HalaPipelineStageFlags2::LATE_FRAGMENT_TESTS,
-          aspect_mask: hala_gfx::HalaImageAspectFlags::DEPTH,
-          image: self.depth_image.raw,
-          ..Default::default()
-        },
-      ],
-    );
-
     graphics_command_buffers.begin_rendering_with(
       index,
       &[self.visibility_image.as_ref()],
       Some(self.depth_image.as_ref()),
-      (0, 0, self.info.width, self.info.height),
+      (0, 0, self.render_width, self.render_height),
       &[Some([0.0, 0.0, 0.0, 1.0])],
       Some(0.0),
       None,
@@ -298,86 +531,38 @@ impl VisRenderer {
       hala_gfx::HalaAttachmentStoreOp::DONT_CARE,
     );
 
-    let no_culling_visibility_buffer_program = self.graphics_programs.get("no_culling_visibility_buffer")
-      .ok_or(HalaRendererError::new("Failed to find the no culling visibility buffer program.", None))?;
-    let no_culling_visibility_buffer_descriptor_set = self.graphics_descriptor_sets.get("no_culling_visibility_buffer");
+    let scene = self.scene_in_gpu.as_ref().ok_or(hala_gfx::HalaGfxError::new("The scene in GPU is none!", None))?;
 
-    self.draw_scene(
+    let mut push_constants = Vec::new();
+    push_constants.extend_from_slice(&scene.meshlet_count.to_le_bytes());
+    self.draw_scene_material_variants(
       index,
       graphics_command_buffers,
-      true,
-      no_culling_visibility_buffer_program,
-      no_culling_visibility_buffer_descriptor_set,
+      "no_culling_visibility_buffer",
+      push_constants.as_slice(),
     )?;
 
     graphics_command_buffers.end_rendering(index);
 
-    // Because we will skip the culling pass, we need to setup the depth barrier for lighting pass.
-    graphics_command_buffers.set_image_barriers(
-      index,
-      &[
-        hala_gfx::HalaImageBarrierInfo {
-          old_layout: hala_gfx::HalaImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
-          new_layout: hala_gfx::HalaImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
-          src_access_mask: hala_gfx::HalaAccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE,
-          dst_access_mask: hala_gfx::HalaAccessFlags2::INPUT_ATTACHMENT_READ,
-          src_stage_mask: hala_gfx::HalaPipelineStageFlags2::EARLY_FRAGMENT_TESTS | hala_gfx::HalaPipelineStageFlags2::LATE_FRAGMENT_TESTS,
-          dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::FRAGMENT_SHADER,
-          aspect_mask: hala_gfx::HalaImageAspectFlags::DEPTH,
-          image: self.depth_image.raw,
-          ..Default::default()
-        },
-      ],
-    );
-
     Ok(())
   }
 
   /// The culling pass.
   /// param index: The index of the current image.
   /// param graphics_command_buffers: The graphics command buffers.
+  /// param program_name: The name of the culling program.
   /// return: The result.
   pub(crate) fn culling_pass(
     &self,
     index: usize,
     graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
-    graphics_program: &HalaGraphicsProgram,
-    descriptor_set: Option<&hala_gfx::HalaDescriptorSet>,
+    program_name: &str,
   ) -> Result<(), HalaRendererError> {
-    // Setup barriers.
-    graphics_command_buffers.set_image_barriers(
-      index,
-      &[
-        hala_gfx::HalaImageBarrierInfo {
-          old_layout: hala_gfx::HalaImageLayout::UNDEFINED,
-          new_layout: hala_gfx::HalaImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-          src_access_mask: hala_gfx::HalaAccessFlags2::NONE,
-          dst_access_mask: hala_gfx::HalaAccessFlags2::COLOR_ATTACHMENT_WRITE,
-          src_stage_mask: hala_gfx::HalaPipelineStageFlags2::TOP_OF_PIPE,
-          dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
-          aspect_mask: hala_gfx::HalaImageAspectFlags::COLOR,
-          image: self.visibility_image.raw,
-          ..Default::default()
-        },
-        hala_gfx::HalaImageBarrierInfo {
-          old_layout: hala_gfx::HalaImageLayout::UNDEFINED,
-          new_layout: hala_gfx::HalaImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
-          src_access_mask: hala_gfx::HalaAccessFlags2::NONE,
-          dst_access_mask: hala_gfx::HalaAccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE,
-          src_stage_mask: hala_gfx::HalaPipelineStageFlags2::TOP_OF_PIPE,
-          dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::EARLY_FRAGMENT_TESTS | hala_gfx::HalaPipelineStageFlags2::LATE_FRAGMENT_TESTS,
-          aspect_mask: hala_gfx::HalaImageAspectFlags::DEPTH,
-          image: self.depth_image.raw,
-          ..Default::default()
-        },
-      ],
-    );
-
     graphics_command_buffers.begin_rendering_with(
       index,
       &[self.visibility_image.as_ref()],
       Some(self.depth_image.as_ref()),
-      (0, 0, self.info.width, self.info.height),
+      (0, 0, self.render_width, self.render_height),
       &[Some([0.0, 0.0, 0.0, 1.0])],
       Some(0.0),
       None,
@@ -390,22 +575,16 @@ impl VisRenderer {
 
     let mut push_constants = Vec::new();
     push_constants.extend_from_slice(&scene.meshlet_count.to_le_bytes());
+    // The Hi-Z buffer is reduced from the depth of the last frame, at its render size.
+    let (hiz_width, hiz_height) = Self::get_hiz_rendered_size(self.prev_render_size);
     push_constants.extend_from_slice(&self.hiz_image.mip_levels.to_le_bytes());
-    push_constants.extend_from_slice(&self.hiz_image.extent.width.to_le_bytes());
-    push_constants.extend_from_slice(&self.hiz_image.extent.height.to_le_bytes());
-    graphics_program.push_constants(
+    push_constants.extend_from_slice(&hiz_width.to_le_bytes());
+    push_constants.extend_from_slice(&hiz_height.to_le_bytes());
+    self.draw_scene_material_variants(
       index,
       graphics_command_buffers,
-      0,
+      program_name,
       push_constants.as_slice(),
-    );
-
-    self.draw_scene(
-      index,
-      graphics_command_buffers,
-      false,
-      graphics_program,
-      descriptor_set,
     )?;
 
     graphics_command_buffers.end_rendering(index);
@@ -422,41 +601,12 @@ impl VisRenderer {
     index: usize,
     graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
   ) -> Result<(), HalaRendererError> {
-    // Setup visibility buffer barriers.
-    graphics_command_buffers.set_image_barriers(
-      index,
-      &[
-        hala_gfx::HalaImageBarrierInfo {
-          old_layout: hala_gfx::HalaImageLayout::UNDEFINED,
-          new_layout: hala_gfx::HalaImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-          src_access_mask: hala_gfx::HalaAccessFlags2::NONE,
-          dst_access_mask: hala_gfx::HalaAccessFlags2::COLOR_ATTACHMENT_WRITE,
-          src_stage_mask: hala_gfx::HalaPipelineStageFlags2::TOP_OF_PIPE,
-          dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
-          aspect_mask: hala_gfx::HalaImageAspectFlags::COLOR,
-          image: self.visibility_image.raw,
-          ..Default::default()
-        },
-        hala_gfx::HalaImageBarrierInfo {
-          old_layout: hala_gfx::HalaImageLayout::UNDEFINED,
-          new_layout: hala_gfx::HalaImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
-          src_access_mask: hala_gfx::HalaAccessFlags2::NONE,
-          dst_access_mask: hala_gfx::HalaAccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE,
-          src_stage_mask: hala_gfx::HalaPipelineStageFlags2::TOP_OF_PIPE,
-          dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::EARLY_FRAGMENT_TESTS | hala_gfx::HalaPipelineStageFlags2::LATE_FRAGMENT_TESTS,
-          aspect_mask: hala_gfx::HalaImageAspectFlags::DEPTH,
-          image: self.depth_image.raw,
-          ..Default::default()
-        },
-      ],
-    );
-
     // Draw the visibility buffer.
     graphics_command_buffers.begin_rendering_with_ex(
       index,
       &[self.visibility_image.as_ref()],
       Some(self.depth_image.as_ref()),
-      (0, 0, self.info.width, self.info.height),
+      (0, 0, self.render_width, self.render_height),
       &[None],
       None,
       None,
@@ -469,28 +619,19 @@ impl VisRenderer {
     );
 
     let scene = self.scene_in_gpu.as_ref().ok_or(hala_gfx::HalaGfxError::new("The scene in GPU is none!", None))?;
-    let visibility_buffer_program = self.graphics_programs.get("visibility_buffer")
-      .ok_or(HalaRendererError::new("Failed to find the visibility buffer program.", None))?;
-    let visibility_buffer_descriptor_set = self.graphics_descriptor_sets.get("visibility_buffer");
 
     let mut push_constants = Vec::new();
     push_constants.extend_from_slice(&scene.meshlet_count.to_le_bytes());
+    // The Hi-Z buffer is reduced from the depth of this frame.
+    let (hiz_width, hiz_height) = Self::get_hiz_rendered_size((self.render_width, self.render_height));
     push_constants.extend_from_slice(&self.hiz_image.mip_levels.to_le_bytes());
-    push_constants.extend_from_slice(&self.hiz_image.extent.width.to_le_bytes());
-    push_constants.extend_from_slice(&self.hiz_image.extent.height.to_le_bytes());
-    visibility_buffer_program.push_constants(
+    push_constants.extend_from_slice(&hiz_width.to_le_bytes());
+    push_constants.extend_from_slice(&hiz_height.to_le_bytes());
+    self.draw_scene_material_variants(
       index,
       graphics_command_buffers,
-      0,
+      "visibility_buffer",
       push_constants.as_slice(),
-    );
-
-    self.draw_scene(
-      index,
-      graphics_command_buffers,
-      false,
-      visibility_buffer_program,
-      visibility_buffer_descriptor_set,
     )?;
 
     graphics_command_buffers.end_rendering(index);
@@ -498,7 +639,16 @@ impl VisRenderer {
     Ok(())
   }
 
+  /// Get the rendered size of the first mip level of the Hi-Z buffer.
+  /// param render_size: The render size of the depth the Hi-Z buffer is reduced from.
+  /// return: The width and height.
+  fn get_hiz_rendered_size(render_size: (u32, u32)) -> (u32, u32) {
+    ((render_size.0 / 2).max(1), (render_size.1 / 2).max(1))
+  }
+
   /// The depth reduction pass.
+  /// Only the part of each mip level covering the render size is reduced, the rest is left by the larger render scales.
+  /// The Hi-Z image is expected in the color attachment layout, each mip level is left in the shader read layout.
   /// param index: The index of the current image.
   /// param graphics_command_buffers: The graphics command buffers.
   /// return: The result.
@@ -507,48 +657,13 @@ impl VisRenderer {
     index: usize,
     graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
   ) -> Result<(), HalaRendererError> {
-    // Setup depth reduction barriers.
-    graphics_command_buffers.set_image_barriers(
-      index,
-      &[
-        hala_gfx::HalaImageBarrierInfo {
-          old_layout: hala_gfx::HalaImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
-          new_layout: hala_gfx::HalaImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
-          src_access_mask: hala_gfx::HalaAccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE,
-          dst_access_mask: hala_gfx::HalaAccessFlags2::INPUT_ATTACHMENT_READ | hala_gfx::HalaAccessFlags2::SHADER_READ,
-          src_stage_mask: hala_gfx::HalaPipelineStageFlags2::EARLY_FRAGMENT_TESTS | hala_gfx::HalaPipelineStageFlags2::LATE_FRAGMENT_TESTS,
-          dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::FRAGMENT_SHADER | hala_gfx::HalaPipelineStageFlags2::COMPUTE_SHADER,
-          aspect_mask: hala_gfx::HalaImageAspectFlags::DEPTH,
-          image: self.depth_image.raw,
-          ..Default::default()
-        },
-      ],
-    );
-
     let depth_reduction_program = self.graphics_programs.get("depth_reduction")
       .ok_or(HalaRendererError::new("Failed to find the depth reduction program.", None))?;
     let depth_reduction_descriptor_set = self.graphics_descriptor_sets.get("depth_reduction");
 
-    let mut width = self.hiz_image.extent.width;
-    let mut height = self.hiz_image.extent.height;
+    let (mut source_width, mut source_height) = (self.render_width, self.render_height);
+    let (mut width, mut height) = Self::get_hiz_rendered_size((self.render_width, self.render_height));
     for mip_level in 0..self.hiz_image.mip_levels {
-      // Setup the write barrier.
-      graphics_command_buffers.set_image_barriers(
-        index,
-        &[hala_gfx::HalaImageBarrierInfo {
-          old_layout: hala_gfx::HalaImageLayout::UNDEFINED,
-          new_layout: hala_gfx::HalaImageLayout::GENERAL,
-          src_access_mask: hala_gfx::HalaAccessFlags2::NONE,
-          dst_access_mask: hala_gfx::HalaAccessFlags2::SHADER_WRITE,
-          src_stage_mask: hala_gfx::HalaPipelineStageFlags2::TOP_OF_PIPE,
-          dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::FRAGMENT_SHADER,
-          aspect_mask: hala_gfx::HalaImageAspectFlags::COLOR,
-          image: self.hiz_image.raw,
-          base_mip_level: mip_level,
-          ..Default::default()
-        }],
-      );
-
       // Set mip level viewport and scissor.
       graphics_command_buffers.set_viewport(
         index,
@@ -579,6 +694,15 @@ impl VisRenderer {
         hala_gfx::HalaAttachmentStoreOp::DONT_CARE,
       );
 
+      let mut push_constants = Vec::new();
+      push_constants.extend_from_slice(&source_width.to_le_bytes());
+      push_constants.extend_from_slice(&source_height.to_le_bytes());
+      depth_reduction_program.push_constants(
+        index,
+        graphics_command_buffers,
+        0,
+        push_constants.as_slice(),
+      );
       self.draw_screen_quad(
         index,
         graphics_command_buffers,
@@ -589,38 +713,18 @@ impl VisRenderer {
       graphics_command_buffers.end_rendering(index);
 
       // Set screen viewport and scissor.
-      graphics_command_buffers.set_viewport(
-        index,
-        0,
-        &[
-          (
-            0.,
-            self.info.height as f32,
-            self.info.width as f32,
-            -(self.info.height as f32), // For vulkan y is down.
-            0.,
-            1.
-          ),
-        ],
-      );
-      graphics_command_buffers.set_scissor(
-        index,
-        0,
-        &[
-          (0, 0, self.info.width, self.info.height),
-        ],
-      );
+      self.set_screen_viewport(index, graphics_command_buffers, self.render_width, self.render_height);
 
-      // Setup the read barrier.
+      // Setup the read barrier, the next mip level and the later passes read this one.
       graphics_command_buffers.set_image_barriers(
         index,
         &[hala_gfx::HalaImageBarrierInfo {
-          old_layout: hala_gfx::HalaImageLayout::GENERAL,
-          new_layout: hala_gfx::HalaImageLayout::SHADER_READ_ONLY_OPTIMAL,
-          src_access_mask: hala_gfx::HalaAccessFlags2::SHADER_WRITE,
-          dst_access_mask: hala_gfx::HalaAccessFlags2::SHADER_READ,
-          src_stage_mask: hala_gfx::HalaPipelineStageFlags2::FRAGMENT_SHADER,
-          dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::FRAGMENT_SHADER,
+          old_layout: hala_gfx::HalaImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+          new_layout: RGAccess::ShaderRead.layout(),
+          src_access_mask: hala_gfx::HalaAccessFlags2::COLOR_ATTACHMENT_WRITE,
+          dst_access_mask: RGAccess::ShaderRead.access_mask(),
+          src_stage_mask: hala_gfx::HalaPipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+          dst_stage_mask: RGAccess::ShaderRead.stage_mask(),
           aspect_mask: hala_gfx::HalaImageAspectFlags::COLOR,
           image: self.hiz_image.raw,
           base_mip_level: mip_level,
@@ -628,8 +732,8 @@ impl VisRenderer {
         }],
       );
 
-      width >>= 1;
-      height >>= 1;
+      (source_width, source_height) = (width, height);
+      (width, height) = ((width / 2).max(1), (height / 2).max(1));
     }
 
     Ok(())
@@ -644,42 +748,13 @@ impl VisRenderer {
     index: usize,
     graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
   ) -> Result<(), HalaRendererError> {
-    // Setup material depth barriers.
-    graphics_command_buffers.set_image_barriers(
-      index,
-      &[
-        hala_gfx::HalaImageBarrierInfo {
-          old_layout: hala_gfx::HalaImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-          new_layout: hala_gfx::HalaImageLayout::SHADER_READ_ONLY_OPTIMAL,
-          src_access_mask: hala_gfx::HalaAccessFlags2::COLOR_ATTACHMENT_WRITE,
-          dst_access_mask: hala_gfx::HalaAccessFlags2::INPUT_ATTACHMENT_READ | hala_gfx::HalaAccessFlags2::SHADER_READ,
-          src_stage_mask: hala_gfx::HalaPipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
-          dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::FRAGMENT_SHADER | hala_gfx::HalaPipelineStageFlags2::COMPUTE_SHADER,
-          aspect_mask: hala_gfx::HalaImageAspectFlags::COLOR,
-          image: self.visibility_image.raw,
-          ..Default::default()
-        },
-        hala_gfx::HalaImageBarrierInfo {
-          old_layout: hala_gfx::HalaImageLayout::UNDEFINED,
-          new_layout: hala_gfx::HalaImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
-          src_access_mask: hala_gfx::HalaAccessFlags2::NONE,
-          dst_access_mask: hala_gfx::HalaAccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE,
-          src_stage_mask: hala_gfx::HalaPipelineStageFlags2::TOP_OF_PIPE,
-          dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::EARLY_FRAGMENT_TESTS | hala_gfx::HalaPipelineStageFlags2::LATE_FRAGMENT_TESTS,
-          aspect_mask: hala_gfx::HalaImageAspectFlags::DEPTH,
-          image: self.material_depth_image.raw,
-          ..Default::default()
-        },
-      ],
-    );
-
     // Draw material depth buffer.
     {
       graphics_command_buffers.begin_rendering_with(
         index,
         &[],
         Some(self.material_depth_image.as_ref()),
-        (0, 0, self.info.width, self.info.height),
+        (0, 0, self.render_width, self.render_height),
         &[],
         None,
         None,
@@ -706,27 +781,13 @@ impl VisRenderer {
 
   /// Clear the indirect buffer.
   /// param index: The index of the current image.
-  /// param graphics_command_buffers: The graphics command buffers.
+  /// param command_buffers: The graphics or compute command buffers.
   /// return: The result.
   pub(crate) fn clear_indirect_buffer(
     &self,
     index: usize,
-    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
+    command_buffers: &hala_gfx::HalaCommandBufferSet,
   ) -> Result<(), HalaRendererError> {
-    // Setup indirect buffer barriers.
-    graphics_command_buffers.set_buffer_barriers(
-      index,
-      &[
-        hala_gfx::HalaBufferBarrierInfo {
-          src_stage_mask: hala_gfx::HalaPipelineStageFlags2::TOP_OF_PIPE,
-          dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::COMPUTE_SHADER,
-          buffer: self.indirect_draw_buffer.raw,
-          size: self.indirect_draw_buffer.size,
-          ..Default::default()
-        },
-      ],
-    );
-
     let scene = self.scene_in_gpu.as_ref().ok_or(hala_gfx::HalaGfxError::new("The scene in GPU is none!", None))?;
     let dynamic_descriptor_set = self.dynamic_descriptor_set.as_ref()
       .ok_or(HalaRendererError::new("Failed to find the dynamic descriptor set.", None))?;
@@ -743,7 +804,7 @@ impl VisRenderer {
 
     clear_indirect_buffer_program.bind(
       index,
-      graphics_command_buffers,
+      command_buffers,
       &[
         self.static_descriptor_set.as_ref(),
         dynamic_descriptor_set,
@@ -753,12 +814,12 @@ impl VisRenderer {
     );
     clear_indirect_buffer_program.push_constants(
       index,
-      graphics_command_buffers,
+      command_buffers,
       0,
       push_constants.as_slice(),
     );
 
-    graphics_command_buffers.dispatch(
+    command_buffers.dispatch(
       index,
       (scene.materials.len() as u32 + 32 - 1) / 32,
       1,
@@ -770,38 +831,13 @@ impl VisRenderer {
 
   /// The material classification pass.
   /// param index: The index of the current image.
-  /// param graphics_command_buffers: The graphics command buffers.
-  /// param compute_command_buffers: The compute command buffers.
+  /// param command_buffers: The graphics or compute command buffers.
   /// return: The result.
   pub(crate) fn material_classification_pass(
     &self,
     index: usize,
-    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
-    _compute_command_buffers: &hala_gfx::HalaCommandBufferSet,
+    command_buffers: &hala_gfx::HalaCommandBufferSet,
   ) -> Result<(), HalaRendererError> {
-    // Setup material classification barriers.
-    graphics_command_buffers.set_buffer_barriers(
-      index,
-      &[
-        hala_gfx::HalaBufferBarrierInfo {
-          src_stage_mask: hala_gfx::HalaPipelineStageFlags2::COMPUTE_SHADER,
-          dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::COMPUTE_SHADER,
-          src_access_mask: hala_gfx::HalaAccessFlags2::SHADER_WRITE,
-          dst_access_mask: hala_gfx::HalaAccessFlags2::SHADER_READ,
-          buffer: self.indirect_draw_buffer.raw,
-          size: self.indirect_draw_buffer.size,
-          ..Default::default()
-        },
-        hala_gfx::HalaBufferBarrierInfo {
-          src_stage_mask: hala_gfx::HalaPipelineStageFlags2::COMPUTE_SHADER,
-          dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::COMPUTE_SHADER,
-          buffer: self.tile_index_buffer.raw,
-          size: self.tile_index_buffer.size,
-          ..Default::default()
-        },
-      ],
-    );
-
     let material_classification_program = self.compute_programs.get("material_classification")
       .ok_or(HalaRendererError::new("Failed to find the material classification program.", None))?;
     let material_classification_descriptor_set = self.compute_descriptor_sets.get("material_classification")
@@ -811,18 +847,18 @@ impl VisRenderer {
     let texture_descriptor_set = self.textures_descriptor_set.as_ref()
       .ok_or(HalaRendererError::new("Failed to find the textures descriptor set.", None))?;
 
-    let x = (self.info.width + GlobalConstants::CLASSIFY_TILE_WIDTH - 1) / GlobalConstants::CLASSIFY_TILE_WIDTH;
-    let y = (self.info.height + GlobalConstants::CLASSIFY_TILE_WIDTH - 1) / GlobalConstants::CLASSIFY_TILE_WIDTH;
+    let x = (self.render_width + GlobalConstants::CLASSIFY_TILE_WIDTH - 1) / GlobalConstants::CLASSIFY_TILE_WIDTH;
+    let y = (self.render_height + GlobalConstants::CLASSIFY_TILE_WIDTH - 1) / GlobalConstants::CLASSIFY_TILE_WIDTH;
     let num_of_tiles = x * y;
     let mut push_constants = Vec::new();
-    push_constants.extend_from_slice(&self.info.width.to_le_bytes());
-    push_constants.extend_from_slice(&self.info.height.to_le_bytes());
+    push_constants.extend_from_slice(&self.render_width.to_le_bytes());
+    push_constants.extend_from_slice(&self.render_height.to_le_bytes());
     push_constants.extend_from_slice(&x.to_le_bytes());
     push_constants.extend_from_slice(&num_of_tiles.to_le_bytes());
 
     material_classification_program.bind(
       index,
-      graphics_command_buffers,
+      command_buffers,
       &[
         self.static_descriptor_set.as_ref(),
         dynamic_descriptor_set,
@@ -832,12 +868,12 @@ impl VisRenderer {
     );
     material_classification_program.push_constants(
       index,
-      graphics_command_buffers,
+      command_buffers,
       0,
       push_constants.as_slice(),
     );
 
-    graphics_command_buffers.dispatch(
+    command_buffers.dispatch(
       index,
       x,
       y,
@@ -866,71 +902,33 @@ impl VisRenderer {
       .ok_or(HalaRendererError::new("Failed to find the dynamic descriptor set.", None))?;
     let texture_descriptor_set = self.textures_descriptor_set.as_ref()
       .ok_or(HalaRendererError::new("Failed to find the textures descriptor set.", None))?;
-
-    // Setup material classification barriers.
-    graphics_command_buffers.set_buffer_barriers(
-      index,
-      &[
-        hala_gfx::HalaBufferBarrierInfo {
-          src_stage_mask: hala_gfx::HalaPipelineStageFlags2::COMPUTE_SHADER,
-          dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::DRAW_INDIRECT,
-          src_access_mask: hala_gfx::HalaAccessFlags2::SHADER_WRITE,
-          dst_access_mask: hala_gfx::HalaAccessFlags2::INDIRECT_COMMAND_READ,
-          buffer: self.indirect_draw_buffer.raw,
-          size: self.indirect_draw_buffer.size,
-          ..Default::default()
-        },
-        hala_gfx::HalaBufferBarrierInfo {
-          src_stage_mask: hala_gfx::HalaPipelineStageFlags2::COMPUTE_SHADER,
-          dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::VERTEX_SHADER | hala_gfx::HalaPipelineStageFlags2::FRAGMENT_SHADER,
-          src_access_mask: hala_gfx::HalaAccessFlags2::SHADER_WRITE,
-          dst_access_mask: hala_gfx::HalaAccessFlags2::SHADER_READ,
-          buffer: self.tile_index_buffer.raw,
-          size: self.tile_index_buffer.size,
-          ..Default::default()
-        },
-      ],
-    );
-    graphics_command_buffers.set_image_barriers(
-      index,
-      &[
-        hala_gfx::HalaImageBarrierInfo {
-          old_layout: hala_gfx::HalaImageLayout::UNDEFINED,
-          new_layout: hala_gfx::HalaImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-          src_access_mask: hala_gfx::HalaAccessFlags2::NONE,
-          dst_access_mask: hala_gfx::HalaAccessFlags2::COLOR_ATTACHMENT_WRITE,
-          src_stage_mask: hala_gfx::HalaPipelineStageFlags2::TOP_OF_PIPE,
-          dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
-          aspect_mask: hala_gfx::HalaImageAspectFlags::COLOR,
-          image: self.albedo_image.raw,
-          ..Default::default()
-        },
-        hala_gfx::HalaImageBarrierInfo {
-          old_layout: hala_gfx::HalaImageLayout::UNDEFINED,
-          new_layout: hala_gfx::HalaImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-          src_access_mask: hala_gfx::HalaAccessFlags2::NONE,
-          dst_access_mask: hala_gfx::HalaAccessFlags2::COLOR_ATTACHMENT_WRITE,
-          src_stage_mask: hala_gfx::HalaPipelineStageFlags2::TOP_OF_PIPE,
-          dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
-          aspect_mask: hala_gfx::HalaImageAspectFlags::COLOR,
-          image: self.normal_image.raw,
-          ..Default::default()
-        },
-      ],
-    );
+    let motion_image = self.motion_image.as_ref()
+      .ok_or(HalaRendererError::new("Failed to find the motion image.", None))?;
 
     graphics_command_buffers.begin_rendering_with_ex(
       index,
-      &[self.albedo_image.as_ref(), self.normal_image.as_ref()],
+      &[self.albedo_image.as_ref(), self.normal_image.as_ref(), self.material_image.as_ref(), self.emissive_image.as_ref(), motion_image],
       Some(self.material_depth_image.as_ref()),
-      (0, 0, self.info.width, self.info.height),
-      &[Some([0.0, 0.0, 0.0, 1.0]), Some([0.0, 0.0, 0.0, 1.0])],
+      (0, 0, self.render_width, self.render_height),
+      &[Some([0.0, 0.0, 0.0, 1.0]), Some([0.0, 0.0, 0.0, 1.0]), Some([0.0, 1.0, 1.0, 1.0]), Some([0.0, 0.0, 0.0, 1.0]), Some([0.0, 0.0, 0.0, 0.0])],
       None,
       None,
-      &[hala_gfx::HalaAttachmentLoadOp::CLEAR, hala_gfx::HalaAttachmentLoadOp::CLEAR],
+      &[
+        hala_gfx::HalaAttachmentLoadOp::CLEAR,
+        hala_gfx::HalaAttachmentLoadOp::CLEAR,
+        hala_gfx::HalaAttachmentLoadOp::CLEAR,
+        hala_gfx::HalaAttachmentLoadOp::CLEAR,
+        hala_gfx::HalaAttachmentLoadOp::CLEAR,
+      ],
       hala_gfx::HalaAttachmentLoadOp::LOAD,
       hala_gfx::HalaAttachmentLoadOp::DONT_CARE,
-      &[hala_gfx::HalaAttachmentStoreOp::STORE, hala_gfx::HalaAttachmentStoreOp::STORE],
+      &[
+        hala_gfx::HalaAttachmentStoreOp::STORE,
+        hala_gfx::HalaAttachmentStoreOp::STORE,
+        hala_gfx::HalaAttachmentStoreOp::STORE,
+        hala_gfx::HalaAttachmentStoreOp::STORE,
+        hala_gfx::HalaAttachmentStoreOp::STORE,
+      ],
       hala_gfx::HalaAttachmentStoreOp::DONT_CARE,
       hala_gfx::HalaAttachmentStoreOp::DONT_CARE,
     );
@@ -946,14 +944,14 @@ impl VisRenderer {
       ],
     );
 
-    let x = (self.info.width + GlobalConstants::CLASSIFY_TILE_WIDTH - 1) / GlobalConstants::CLASSIFY_TILE_WIDTH;
-    let y = (self.info.height + GlobalConstants::CLASSIFY_TILE_WIDTH - 1) / GlobalConstants::CLASSIFY_TILE_WIDTH;
+    let x = (self.render_width + GlobalConstants::CLASSIFY_TILE_WIDTH - 1) / GlobalConstants::CLASSIFY_TILE_WIDTH;
+    let y = (self.render_height + GlobalConstants::CLASSIFY_TILE_WIDTH - 1) / GlobalConstants::CLASSIFY_TILE_WIDTH;
     let num_of_tiles = x * y;
     let num_of_materials = scene.materials.len();
     for material_index in 0..num_of_materials {
       let mut push_constants = Vec::new();
-      push_constants.extend_from_slice(&self.info.width.to_le_bytes());
-      push_constants.extend_from_slice(&self.info.height.to_le_bytes());
+      push_constants.extend_from_slice(&self.render_width.to_le_bytes());
+      push_constants.extend_from_slice(&self.render_height.to_le_bytes());
       push_constants.extend_from_slice(&x.to_le_bytes());
       push_constants.extend_from_slice(&num_of_tiles.to_le_bytes());
       push_constants.extend_from_slice(&(material_index as u32).to_le_bytes());
@@ -981,56 +979,1059 @@ impl VisRenderer {
     Ok(())
   }
 
-  /// The lighting pass.
+  /// The shadow map pass, draws the scene into each cascade of the directional light.
+  /// Without the directional light, the shadow map is only cleared.
   /// param index: The index of the current image.
   /// param graphics_command_buffers: The graphics command buffers.
   /// return: The result.
-  pub(crate) fn lighting_pass(
+  pub(crate) fn shadow_map_pass(
     &self,
     index: usize,
     graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
   ) -> Result<(), HalaRendererError> {
-    let lighting_program = self.graphics_programs.get("lighting")
-      .ok_or(HalaRendererError::new("Failed to find the lighting program.", None))?;
-    let lighting_descriptor_set = self.graphics_descriptor_sets.get("lighting");
+    graphics_command_buffers.begin_rendering_with(
+      index,
+      &[],
+      Some(self.shadow_map_image.as_ref()),
+      (0, 0, self.shadow_map_image.extent.width, self.shadow_map_image.extent.height),
+      &[],
+      Some(0.0),
+      None,
+      hala_gfx::HalaAttachmentStoreOp::DONT_CARE,
+      hala_gfx::HalaAttachmentStoreOp::STORE,
+      hala_gfx::HalaAttachmentStoreOp::DONT_CARE,
+    );
 
-    // Setup material tile read barriers.
-    graphics_command_buffers.set_image_barriers(
+    if self.shadow_light.is_some() {
+      let scene = self.scene_in_gpu.as_ref().ok_or(hala_gfx::HalaGfxError::new("The scene in GPU is none!", None))?;
+      let shadow_map_program = self.graphics_programs.get("shadow_map")
+        .ok_or(HalaRendererError::new("Failed to find the shadow map program.", None))?;
+      let shadow_map_descriptor_set = self.graphics_descriptor_sets.get("shadow_map");
+
+      let resolution = self.shadow_settings.resolution;
+      for cascade in 0..self.shadow_settings.num_of_cascades {
+        // Each cascade is drawn to its own part of the shadow map.
+        graphics_command_buffers.set_viewport(
+          index,
+          0,
+          &[
+            ((cascade * resolution) as f32, 0., resolution as f32, resolution as f32, 0., 1.),
+          ],
+        );
+        graphics_command_buffers.set_scissor(
+          index,
+          0,
+          &[
+            ((cascade * resolution) as i32, 0, resolution, resolution),
+          ],
+        );
+
+        let mut push_constants = Vec::new();
+        push_constants.extend_from_slice(&scene.meshlet_count.to_le_bytes());
+        push_constants.extend_from_slice(&cascade.to_le_bytes());
+        shadow_map_program.push_constants(
+          index,
+          graphics_command_buffers,
+          0,
+          push_constants.as_slice(),
+        );
+
+        self.draw_scene(
+          index,
+          graphics_command_buffers,
+          false,
+          shadow_map_program,
+          shadow_map_descriptor_set,
+        )?;
+      }
+    }
+
+    graphics_command_buffers.end_rendering(index);
+
+    // Set screen viewport and scissor.
+    self.set_screen_viewport(index, graphics_command_buffers, self.render_width, self.render_height);
+
+    Ok(())
+  }
+
+  /// The light culling pass.
+  /// param index: The index of the current image.
+  /// param graphics_command_buffers: The graphics command buffers.
+  /// return: The result.
+  pub(crate) fn light_culling_pass(
+    &self,
+    index: usize,
+    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
+  ) -> Result<(), HalaRendererError> {
+    let scene = self.scene_in_gpu.as_ref().ok_or(hala_gfx::HalaGfxError::new("The scene in GPU is none!", None))?;
+
+    let light_culling_program = self.compute_programs.get("light_culling")
+      .ok_or(HalaRendererError::new("Failed to find the light culling program.", None))?;
+    let light_culling_descriptor_set = self.compute_descriptor_sets.get("light_culling")
+      .ok_or(HalaRendererError::new("Failed to find the light culling descriptor set.", None))?;
+    let dynamic_descriptor_set = self.dynamic_descriptor_set.as_ref()
+      .ok_or(HalaRendererError::new("Failed to find the dynamic descriptor set.", None))?;
+    let texture_descriptor_set = self.textures_descriptor_set.as_ref()
+      .ok_or(HalaRendererError::new("Failed to find the textures descriptor set.", None))?;
+
+    let (x, y) = Self::get_num_of_light_tiles(self.render_width, self.render_height);
+    let num_of_tiles = x * y;
+    let num_of_lights = scene.light_data.len().min(GlobalConstants::MAX_LIGHTS as usize) as u32;
+    // The first Hi-Z level is in the half resolution, so the texel of this level covers a tile.
+    let hiz_level = (GlobalConstants::LIGHT_TILE_SIZE.trailing_zeros() - 1).min(self.hiz_image.mip_levels - 1);
+    let mut push_constants = Vec::new();
+    push_constants.extend_from_slice(&self.render_width.to_le_bytes());
+    push_constants.extend_from_slice(&self.render_height.to_le_bytes());
+    push_constants.extend_from_slice(&self.camera_p_mtx.x_axis.x.to_le_bytes());
+    push_constants.extend_from_slice(&self.camera_p_mtx.y_axis.y.to_le_bytes());
+    push_constants.extend_from_slice(&x.to_le_bytes());
+    push_constants.extend_from_slice(&num_of_tiles.to_le_bytes());
+    push_constants.extend_from_slice(&num_of_lights.to_le_bytes());
+    push_constants.extend_from_slice(&hiz_level.to_le_bytes());
+    // For the reversed-Z infinite perspective matrix, w_axis.z = z_near.
+    push_constants.extend_from_slice(&self.camera_p_mtx.w_axis.z.to_le_bytes());
+
+    light_culling_program.bind(
       index,
+      graphics_command_buffers,
       &[
-        hala_gfx::HalaImageBarrierInfo {
-          old_layout: hala_gfx::HalaImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-          new_layout: hala_gfx::HalaImageLayout::SHADER_READ_ONLY_OPTIMAL,
-          src_access_mask: hala_gfx::HalaAccessFlags2::COLOR_ATTACHMENT_WRITE,
-          dst_access_mask: hala_gfx::HalaAccessFlags2::INPUT_ATTACHMENT_READ,
-          src_stage_mask: hala_gfx::HalaPipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
-          dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::FRAGMENT_SHADER,
-          aspect_mask: hala_gfx::HalaImageAspectFlags::COLOR,
-          image: self.albedo_image.raw,
-          ..Default::default()
-        },
-        hala_gfx::HalaImageBarrierInfo {
-          old_layout: hala_gfx::HalaImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-          new_layout: hala_gfx::HalaImageLayout::SHADER_READ_ONLY_OPTIMAL,
-          src_access_mask: hala_gfx::HalaAccessFlags2::COLOR_ATTACHMENT_WRITE,
-          dst_access_mask: hala_gfx::HalaAccessFlags2::INPUT_ATTACHMENT_READ,
-          src_stage_mask: hala_gfx::HalaPipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
-          dst_stage_mask: hala_gfx::HalaPipelineStageFlags2::FRAGMENT_SHADER,
-          aspect_mask: hala_gfx::HalaImageAspectFlags::COLOR,
-          image: self.normal_image.raw,
-          ..Default::default()
-        },
+        self.static_descriptor_set.as_ref(),
+        dynamic_descriptor_set,
+        texture_descriptor_set,
+        light_culling_descriptor_set,
       ],
     );
+    light_culling_program.push_constants(
+      index,
+      graphics_command_buffers,
+      0,
+      push_constants.as_slice(),
+    );
 
-    self.draw_screen_quad(
+    graphics_command_buffers.dispatch(
+      index,
+      x,
+      y,
+      1,
+    );
+
+    Ok(())
+  }
+
+  /// The SSAO pass, computes the ambient occlusion from the depth and the normal.
+  /// param index: The index of the current image.
+  /// param graphics_command_buffers: The graphics command buffers.
+  /// return: The result.
+  pub(crate) fn ssao_pass(
+    &self,
+    index: usize,
+    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
+  ) -> Result<(), HalaRendererError> {
+    let ssao_program = self.compute_programs.get("ssao")
+      .ok_or(HalaRendererError::new("Failed to find the SSAO program.", None))?;
+    let ssao_descriptor_set = self.compute_descriptor_sets.get("ssao")
+      .ok_or(HalaRendererError::new("Failed to find the SSAO descriptor set.", None))?;
+    let dynamic_descriptor_set = self.dynamic_descriptor_set.as_ref()
+      .ok_or(HalaRendererError::new("Failed to find the dynamic descriptor set.", None))?;
+    let texture_descriptor_set = self.textures_descriptor_set.as_ref()
+      .ok_or(HalaRendererError::new("Failed to find the textures descriptor set.", None))?;
+
+    let scale = self.get_ssao_resolution_scale();
+    let settings = &self.ssao_settings;
+    // The pixels of an unit at the view distance 1 along the screen height.
+    let projection_scale = self.camera_p_mtx.y_axis.y * self.render_height as f32 * 0.5;
+    let mut push_constants = Vec::new();
+    push_constants.extend_from_slice(&self.render_width.to_le_bytes());
+    push_constants.extend_from_slice(&self.render_height.to_le_bytes());
+    push_constants.extend_from_slice(&scale.to_le_bytes());
+    push_constants.extend_from_slice(&settings.radius.to_le_bytes());
+    push_constants.extend_from_slice(&settings.intensity.to_le_bytes());
+    push_constants.extend_from_slice(&projection_scale.to_le_bytes());
+    // For the reversed-Z infinite perspective matrix, w_axis.z = z_near.
+    push_constants.extend_from_slice(&self.camera_p_mtx.w_axis.z.to_le_bytes());
+
+    ssao_program.bind(
       index,
       graphics_command_buffers,
-      lighting_program,
-      lighting_descriptor_set,
-    )?;
+      &[
+        self.static_descriptor_set.as_ref(),
+        dynamic_descriptor_set,
+        texture_descriptor_set,
+        ssao_descriptor_set,
+      ],
+    );
+    ssao_program.push_constants(
+      index,
+      graphics_command_buffers,
+      0,
+      push_constants.as_slice(),
+    );
+
+    let width = (self.render_width + scale - 1) / scale;
+    let height = (self.render_height + scale - 1) / scale;
+    graphics_command_buffers.dispatch(
+      index,
+      (width + SSAO_THREAD_WIDTH - 1) / SSAO_THREAD_WIDTH,
+      (height + SSAO_THREAD_WIDTH - 1) / SSAO_THREAD_WIDTH,
+      1,
+    );
 
     Ok(())
   }
 
+  /// The SSAO blur pass, filters the noise of the ambient occlusion by the depth aware bilateral filter.
+  /// param index: The index of the current image.
+  /// param graphics_command_buffers: The graphics command buffers.
+  /// return: The result.
+  pub(crate) fn ssao_blur_pass(
+    &self,
+    index: usize,
+    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
+  ) -> Result<(), HalaRendererError> {
+    let ssao_blur_program = self.compute_programs.get("ssao_blur")
+      .ok_or(HalaRendererError::new("Failed to find the SSAO blur program.", None))?;
+    let ssao_blur_descriptor_set = self.compute_descriptor_sets.get("ssao_blur")
+      .ok_or(HalaRendererError::new("Failed to find the SSAO blur descriptor set.", None))?;
+    let dynamic_descriptor_set = self.dynamic_descriptor_set.as_ref()
+      .ok_or(HalaRendererError::new("Failed to find the dynamic descriptor set.", None))?;
+    let texture_descriptor_set = self.textures_descriptor_set.as_ref()
+      .ok_or(HalaRendererError::new("Failed to find the textures descriptor set.", None))?;
+
+    let scale = self.get_ssao_resolution_scale();
+    let mut push_constants = Vec::new();
+    push_constants.extend_from_slice(&self.render_width.to_le_bytes());
+    push_constants.extend_from_slice(&self.render_height.to_le_bytes());
+    push_constants.extend_from_slice(&scale.to_le_bytes());
+
+    ssao_blur_program.bind(
+      index,
+      graphics_command_buffers,
+      &[
+        self.static_descriptor_set.as_ref(),
+        dynamic_descriptor_set,
+        texture_descriptor_set,
+        ssao_blur_descriptor_set,
+      ],
+    );
+    ssao_blur_program.push_constants(
+      index,
+      graphics_command_buffers,
+      0,
+      push_constants.as_slice(),
+    );
+
+    let width = (self.render_width + scale - 1) / scale;
+    let height = (self.render_height + scale - 1) / scale;
+    graphics_command_buffers.dispatch(
+      index,
+      (width + SSAO_THREAD_WIDTH - 1) / SSAO_THREAD_WIDTH,
+      (height + SSAO_THREAD_WIDTH - 1) / SSAO_THREAD_WIDTH,
+      1,
+    );
+
+    Ok(())
+  }
+
+  /// The lighting pass.
+  /// param index: The index of the current image.
+  /// param graphics_command_buffers: The graphics command buffers.
+  /// return: The result.
+  pub(crate) fn lighting_pass(
+    &self,
+    index: usize,
+    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
+  ) -> Result<(), HalaRendererError> {
+    let lighting_program = self.graphics_programs.get("lighting")
+      .ok_or(HalaRendererError::new("Failed to find the lighting program.", None))?;
+    let lighting_descriptor_set = self.graphics_descriptor_sets.get("lighting");
+
+    // Only the lights in the cluster of the pixel are evaluated.
+    let (x, y) = Self::get_num_of_light_tiles(self.render_width, self.render_height);
+    let num_of_tiles = x * y;
+    let mut push_constants = Vec::new();
+    push_constants.extend_from_slice(&x.to_le_bytes());
+    push_constants.extend_from_slice(&num_of_tiles.to_le_bytes());
+    push_constants.extend_from_slice(&self.camera_p_mtx.w_axis.z.to_le_bytes());
+    // The debug modes of the lighting shader, 1 for the light count, 2 for the shadow cascades and 3 for the ambient occlusion.
+    let debug_mode = if self.debug_settings.show_light_count {
+      1u32
+    } else if self.debug_settings.show_shadow_cascades {
+      2u32
+    } else if self.debug_settings.show_ambient_occlusion {
+      3u32
+    } else {
+      0u32
+    };
+    push_constants.extend_from_slice(&debug_mode.to_le_bytes());
+    push_constants.extend_from_slice(&self.get_shading_environment_intensity().to_le_bytes());
+    let use_ambient_occlusion = self.ssao_settings.enabled as u32;
+    push_constants.extend_from_slice(&use_ambient_occlusion.to_le_bytes());
+    lighting_program.push_constants(
+      index,
+      graphics_command_buffers,
+      0,
+      push_constants.as_slice(),
+    );
+    self.draw_screen_quad(
+      index,
+      graphics_command_buffers,
+      lighting_program,
+      lighting_descriptor_set,
+    )?;
+
+    Ok(())
+  }
+
+  /// The skybox pass, draws the environment map where the lighting pass left no scene depth.
+  /// Without an environment map, the clear color is kept as the background.
+  /// param index: The index of the current image.
+  /// param graphics_command_buffers: The graphics command buffers.
+  /// return: The result.
+  pub(crate) fn skybox_pass(
+    &self,
+    index: usize,
+    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
+  ) -> Result<(), HalaRendererError> {
+    if !self.environment.is_loaded {
+      return Ok(());
+    }
+
+    let skybox_program = self.graphics_programs.get("skybox")
+      .ok_or(HalaRendererError::new("Failed to find the skybox program.", None))?;
+    let skybox_descriptor_set = self.graphics_descriptor_sets.get("skybox");
+    skybox_program.push_constants_f32(
+      index,
+      graphics_command_buffers,
+      0,
+      &[self.environment_intensity],
+    );
+    self.draw_screen_quad(
+      index,
+      graphics_command_buffers,
+      skybox_program,
+      skybox_descriptor_set,
+    )?;
+
+    Ok(())
+  }
+
+  /// Get the push constants of the transparent programs.
+  /// param draw_index: The draw to render, TRANSPARENT_ALL_DRAWS for all transparent draws.
+  /// param meshlet_range: The offset and the count of the meshlets to dispatch.
+  /// param material_variant: The material variant, DRAW_FLAG_DOUBLE_SIDED or 0.
+  /// return: The push constants.
+  fn get_transparent_push_constants(&self, draw_index: u32, meshlet_range: (u32, u32), material_variant: u32) -> Vec<u8> {
+    // The transparent fragments are lit by the lights in their clusters as the lighting pass.
+    let (x, y) = Self::get_num_of_light_tiles(self.render_width, self.render_height);
+    let num_of_tiles = x * y;
+    let mut push_constants = Vec::new();
+    push_constants.extend_from_slice(&meshlet_range.1.to_le_bytes());
+    push_constants.extend_from_slice(&meshlet_range.0.to_le_bytes());
+    push_constants.extend_from_slice(&x.to_le_bytes());
+    push_constants.extend_from_slice(&num_of_tiles.to_le_bytes());
+    push_constants.extend_from_slice(&self.camera_p_mtx.w_axis.z.to_le_bytes());
+    push_constants.extend_from_slice(&draw_index.to_le_bytes());
+    push_constants.extend_from_slice(&material_variant.to_le_bytes());
+    push_constants.extend_from_slice(&self.get_shading_environment_intensity().to_le_bytes());
+
+    push_constants
+  }
+
+  /// The transparent pass, draws the transparent draws over the lighting result.
+  /// The sorted draws are blended from back to front, or the weighted blended OIT result is composited.
+  /// param index: The index of the current image.
+  /// param graphics_command_buffers: The graphics command buffers.
+  /// return: The result.
+  pub(crate) fn transparent_pass(
+    &self,
+    index: usize,
+    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
+  ) -> Result<(), HalaRendererError> {
+    if self.transparent_draws.is_empty() {
+      return Ok(());
+    }
+
+    if self.use_weighted_blended_oit {
+      let transparent_composite_program = self.graphics_programs.get("transparent_composite")
+        .ok_or(HalaRendererError::new("Failed to find the transparent composite program.", None))?;
+      let transparent_composite_descriptor_set = self.graphics_descriptor_sets.get("transparent_composite");
+      self.draw_screen_quad(
+        index,
+        graphics_command_buffers,
+        transparent_composite_program,
+        transparent_composite_descriptor_set,
+      )?;
+      return Ok(());
+    }
+
+    // One draw call per transparent draw, which only dispatches the meshlets of the draw.
+    for draw in sort_transparent_draws(&self.transparent_draws, &self.camera_v_mtx) {
+      if draw.meshlet_count == 0 {
+        continue;
+      }
+
+      let material_variant = draw.flags & DRAW_FLAG_DOUBLE_SIDED;
+      let program_name = get_variant_program_name("transparent", material_variant);
+      let transparent_program = self.graphics_programs.get(&program_name)
+        .ok_or(HalaRendererError::new("Failed to find the transparent program.", None))?;
+      let transparent_descriptor_set = self.graphics_descriptor_sets.get(&program_name);
+
+      let push_constants = self.get_transparent_push_constants(draw.draw_index, (draw.meshlet_offset, draw.meshlet_count), material_variant);
+      transparent_program.push_constants(
+        index,
+        graphics_command_buffers,
+        0,
+        push_constants.as_slice(),
+      );
+      self.draw_meshlets(
+        index,
+        graphics_command_buffers,
+        draw.meshlet_count,
+        transparent_program,
+        transparent_descriptor_set,
+      )?;
+    }
+
+    Ok(())
+  }
+
+  /// The weighted blended OIT pass, accumulates the transparent draws in any order.
+  /// param index: The index of the current image.
+  /// param graphics_command_buffers: The graphics command buffers.
+  /// return: The result.
+  pub(crate) fn transparent_oit_pass(
+    &self,
+    index: usize,
+    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
+  ) -> Result<(), HalaRendererError> {
+    let oit_accumulation_image = self.oit_accumulation_image.as_ref()
+      .ok_or(HalaRendererError::new("Failed to find the OIT accumulation image.", None))?;
+    let oit_weight_image = self.oit_weight_image.as_ref()
+      .ok_or(HalaRendererError::new("Failed to find the OIT weight image.", None))?;
+
+    // The revealage in the alpha of the accumulation starts from 1, nothing is covered.
+    // The scene depth is only tested, the transparent draws do not write it.
+    graphics_command_buffers.begin_rendering_with(
+      index,
+      &[oit_accumulation_image, oit_weight_image],
+      Some(self.depth_image.as_ref()),
+      (0, 0, self.render_width, self.render_height),
+      &[Some([0.0, 0.0, 0.0, 1.0]), Some([0.0, 0.0, 0.0, 0.0])],
+      None,
+      None,
+      hala_gfx::HalaAttachmentStoreOp::STORE,
+      hala_gfx::HalaAttachmentStoreOp::STORE,
+      hala_gfx::HalaAttachmentStoreOp::DONT_CARE,
+    );
+
+    // The single-sided and the double-sided draws, each dispatches all meshlets.
+    let scene = self.scene_in_gpu.as_ref().ok_or(hala_gfx::HalaGfxError::new("The scene in GPU is none!", None))?;
+    for material_variant in [0, DRAW_FLAG_DOUBLE_SIDED] {
+      if !self.transparent_draws.iter().any(|draw| draw.flags & DRAW_FLAG_DOUBLE_SIDED == material_variant) {
+        continue;
+      }
+
+      let program_name = get_variant_program_name("transparent_oit", material_variant);
+      let transparent_oit_program = self.graphics_programs.get(&program_name)
+        .ok_or(HalaRendererError::new("Failed to find the transparent OIT program.", None))?;
+      let transparent_oit_descriptor_set = self.graphics_descriptor_sets.get(&program_name);
+
+      let push_constants = self.get_transparent_push_constants(TRANSPARENT_ALL_DRAWS, (0, scene.meshlet_count), material_variant);
+      transparent_oit_program.push_constants(
+        index,
+        graphics_command_buffers,
+        0,
+        push_constants.as_slice(),
+      );
+      self.draw_scene(
+        index,
+        graphics_command_buffers,
+        false,
+        transparent_oit_program,
+        transparent_oit_descriptor_set,
+      )?;
+    }
+
+    graphics_command_buffers.end_rendering(index);
+
+    Ok(())
+  }
+
+  /// The luminance histogram pass, bins the log luminance of the scene color.
+  /// param index: The index of the current image.
+  /// param graphics_command_buffers: The graphics command buffers.
+  /// return: The result.
+  pub(crate) fn luminance_histogram_pass(
+    &self,
+    index: usize,
+    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
+  ) -> Result<(), HalaRendererError> {
+    let luminance_histogram_program = self.compute_programs.get("luminance_histogram")
+      .ok_or(HalaRendererError::new("Failed to find the luminance histogram program.", None))?;
+    let luminance_histogram_descriptor_set = self.compute_descriptor_sets.get("luminance_histogram")
+      .ok_or(HalaRendererError::new("Failed to find the luminance histogram descriptor set.", None))?;
+    let dynamic_descriptor_set = self.dynamic_descriptor_set.as_ref()
+      .ok_or(HalaRendererError::new("Failed to find the dynamic descriptor set.", None))?;
+    let texture_descriptor_set = self.textures_descriptor_set.as_ref()
+      .ok_or(HalaRendererError::new("Failed to find the textures descriptor set.", None))?;
+
+    let settings = &self.tonemap_settings;
+    let log_luminance_range = (settings.max_log_luminance - settings.min_log_luminance).max(1e-3);
+    let mut push_constants = Vec::new();
+    push_constants.extend_from_slice(&self.render_width.to_le_bytes());
+    push_constants.extend_from_slice(&self.render_height.to_le_bytes());
+    push_constants.extend_from_slice(&settings.min_log_luminance.to_le_bytes());
+    push_constants.extend_from_slice(&(1.0 / log_luminance_range).to_le_bytes());
+
+    luminance_histogram_program.bind(
+      index,
+      graphics_command_buffers,
+      &[
+        self.static_descriptor_set.as_ref(),
+        dynamic_descriptor_set,
+        texture_descriptor_set,
+        luminance_histogram_descriptor_set,
+      ],
+    );
+    luminance_histogram_program.push_constants(
+      index,
+      graphics_command_buffers,
+      0,
+      push_constants.as_slice(),
+    );
+
+    graphics_command_buffers.dispatch(
+      index,
+      (self.render_width + LUMINANCE_HISTOGRAM_THREAD_WIDTH - 1) / LUMINANCE_HISTOGRAM_THREAD_WIDTH,
+      (self.render_height + LUMINANCE_HISTOGRAM_THREAD_WIDTH - 1) / LUMINANCE_HISTOGRAM_THREAD_WIDTH,
+      1,
+    );
+
+    Ok(())
+  }
+
+  /// The exposure pass, averages the luminance histogram and adapts the exposure to it.
+  /// param index: The index of the current image.
+  /// param graphics_command_buffers: The graphics command buffers.
+  /// return: The result.
+  pub(crate) fn exposure_pass(
+    &self,
+    index: usize,
+    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
+  ) -> Result<(), HalaRendererError> {
+    let exposure_program = self.compute_programs.get("exposure")
+      .ok_or(HalaRendererError::new("Failed to find the exposure program.", None))?;
+    let exposure_descriptor_set = self.compute_descriptor_sets.get("exposure")
+      .ok_or(HalaRendererError::new("Failed to find the exposure descriptor set.", None))?;
+    let dynamic_descriptor_set = self.dynamic_descriptor_set.as_ref()
+      .ok_or(HalaRendererError::new("Failed to find the dynamic descriptor set.", None))?;
+    let texture_descriptor_set = self.textures_descriptor_set.as_ref()
+      .ok_or(HalaRendererError::new("Failed to find the textures descriptor set.", None))?;
+
+    let settings = &self.tonemap_settings;
+    let log_luminance_range = (settings.max_log_luminance - settings.min_log_luminance).max(1e-3);
+    let mut push_constants = Vec::new();
+    push_constants.extend_from_slice(&(self.render_width * self.render_height).to_le_bytes());
+    push_constants.extend_from_slice(&settings.min_log_luminance.to_le_bytes());
+    push_constants.extend_from_slice(&log_luminance_range.to_le_bytes());
+    push_constants.extend_from_slice(&self.exposure_adaptation.to_le_bytes());
+
+    exposure_program.bind(
+      index,
+      graphics_command_buffers,
+      &[
+        self.static_descriptor_set.as_ref(),
+        dynamic_descriptor_set,
+        texture_descriptor_set,
+        exposure_descriptor_set,
+      ],
+    );
+    exposure_program.push_constants(
+      index,
+      graphics_command_buffers,
+      0,
+      push_constants.as_slice(),
+    );
+
+    // A single group with a thread for each bin.
+    graphics_command_buffers.dispatch(
+      index,
+      1,
+      1,
+      1,
+    );
+
+    Ok(())
+  }
+
+  /// The pick pass, reads the visibility ID and the depth under the cursor into the pick buffer.
+  /// param index: The index of the current image.
+  /// param graphics_command_buffers: The graphics command buffers.
+  /// return: The result.
+  pub(crate) fn pick_pass(
+    &self,
+    index: usize,
+    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
+  ) -> Result<(), HalaRendererError> {
+    let pick_program = self.compute_programs.get("pick")
+      .ok_or(HalaRendererError::new("Failed to find the pick program.", None))?;
+    let pick_descriptor_set = self.compute_descriptor_sets.get("pick")
+      .ok_or(HalaRendererError::new("Failed to find the pick descriptor set.", None))?;
+    let dynamic_descriptor_set = self.dynamic_descriptor_set.as_ref()
+      .ok_or(HalaRendererError::new("Failed to find the dynamic descriptor set.", None))?;
+    let texture_descriptor_set = self.textures_descriptor_set.as_ref()
+      .ok_or(HalaRendererError::new("Failed to find the textures descriptor set.", None))?;
+    let (texel_x, texel_y) = self.get_pick_texel()
+      .ok_or(HalaRendererError::new("Failed to find the texel to pick.", None))?;
+
+    // The render size may be changed after the pick is requested.
+    let mut push_constants = Vec::new();
+    push_constants.extend_from_slice(&texel_x.min(self.render_width - 1).to_le_bytes());
+    push_constants.extend_from_slice(&texel_y.min(self.render_height - 1).to_le_bytes());
+
+    pick_program.bind(
+      index,
+      graphics_command_buffers,
+      &[
+        self.static_descriptor_set.as_ref(),
+        dynamic_descriptor_set,
+        texture_descriptor_set,
+        pick_descriptor_set,
+      ],
+    );
+    pick_program.push_constants(
+      index,
+      graphics_command_buffers,
+      0,
+      push_constants.as_slice(),
+    );
+
+    // A single thread for the texel.
+    graphics_command_buffers.dispatch(
+      index,
+      1,
+      1,
+      1,
+    );
+
+    Ok(())
+  }
+
+  /// The TAA pass, blends the lighting result with the reprojected history into the other history image.
+  /// param index: The index of the current image.
+  /// param graphics_command_buffers: The graphics command buffers.
+  /// return: The result.
+  pub(crate) fn taa_pass(
+    &self,
+    index: usize,
+    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
+  ) -> Result<(), HalaRendererError> {
+    let taa_program = self.graphics_programs.get("taa")
+      .ok_or(HalaRendererError::new("Failed to find the TAA program.", None))?;
+    let (history_read_index, history_write_index) = self.get_taa_history_indices();
+    let history_image = self.taa_history_images.get(history_write_index)
+      .ok_or(HalaRendererError::new("Failed to find the TAA history image.", None))?;
+    let taa_descriptor_set = self.taa_descriptor_sets.get(history_read_index)
+      .ok_or(HalaRendererError::new("Failed to find the TAA descriptor set.", None))?;
+
+    graphics_command_buffers.begin_rendering_with(
+      index,
+      &[history_image],
+      None,
+      (0, 0, self.render_width, self.render_height),
+      &[None],
+      None,
+      None,
+      hala_gfx::HalaAttachmentStoreOp::STORE,
+      hala_gfx::HalaAttachmentStoreOp::DONT_CARE,
+      hala_gfx::HalaAttachmentStoreOp::DONT_CARE,
+    );
+
+    // The history is dropped in the first frame after it is invalidated.
+    let history_weight = if self.is_taa_history_valid { self.taa_settings.history_weight } else { 0f32 };
+    let mut push_constants = Vec::new();
+    push_constants.extend_from_slice(&self.render_width.to_le_bytes());
+    push_constants.extend_from_slice(&self.render_height.to_le_bytes());
+    push_constants.extend_from_slice(&history_weight.to_le_bytes());
+    taa_program.push_constants(
+      index,
+      graphics_command_buffers,
+      0,
+      push_constants.as_slice(),
+    );
+
+    self.set_screen_viewport(index, graphics_command_buffers, self.render_width, self.render_height);
+    self.draw_screen_quad(
+      index,
+      graphics_command_buffers,
+      taa_program,
+      Some(taa_descriptor_set),
+    )?;
+
+    graphics_command_buffers.end_rendering(index);
+
+    Ok(())
+  }
+
+  /// Begin rendering a post-processing program onto a mip level of the image.
+  /// Only the part of the mip level covering the render size is rendered.
+  /// param index: The index of the current image.
+  /// param graphics_command_buffers: The graphics command buffers.
+  /// param image: The target image.
+  /// param mip_level: The mip level of the target image.
+  /// param is_blended: Whether the program blends onto the content of the target, otherwise the content is discarded.
+  fn begin_post_process_rendering(
+    &self,
+    index: usize,
+    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
+    image: &hala_gfx::HalaImage,
+    mip_level: u32,
+    is_blended: bool,
+  ) {
+    let (width, height) = self.get_rendered_size((image.extent.width >> mip_level).max(1), (image.extent.height >> mip_level).max(1));
+    graphics_command_buffers.set_viewport(
+      index,
+      0,
+      &[
+        (0., 0., width as f32, height as f32, 0., 1.),
+      ],
+    );
+    graphics_command_buffers.set_scissor(
+      index,
+      0,
+      &[
+        (0, 0, width, height),
+      ],
+    );
+
+    graphics_command_buffers.begin_rendering_with_view_ex(
+      index,
+      &[image.mip_views[mip_level as usize]],
+      None,
+      (0, 0, width, height),
+      &[None],
+      None,
+      None,
+      &[if is_blended { hala_gfx::HalaAttachmentLoadOp::LOAD } else { hala_gfx::HalaAttachmentLoadOp::DONT_CARE }],
+      hala_gfx::HalaAttachmentLoadOp::DONT_CARE,
+      &[hala_gfx::HalaAttachmentStoreOp::STORE],
+      hala_gfx::HalaAttachmentStoreOp::DONT_CARE,
+    );
+  }
+
+  /// The bloom pass, downsamples the color to be tonemapped through the mip chain, then upsamples and accumulates it back.
+  /// Each mip level is left in the shader read layout.
+  /// param index: The index of the current image.
+  /// param graphics_command_buffers: The graphics command buffers.
+  /// return: The result.
+  pub(crate) fn bloom_pass(
+    &self,
+    index: usize,
+    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
+  ) -> Result<(), HalaRendererError> {
+    let bloom_downsample_program = self.graphics_programs.get("bloom_downsample")
+      .ok_or(HalaRendererError::new("Failed to find the bloom downsample program.", None))?;
+    let bloom_upsample_program = self.graphics_programs.get("bloom_upsample")
+      .ok_or(HalaRendererError::new("Failed to find the bloom upsample program.", None))?;
+    let bloom_image = self.bloom_image.as_ref()
+      .ok_or(HalaRendererError::new("Failed to find the bloom image.", None))?;
+    let bloom_source_descriptor_set = self.get_bloom_source_descriptor_set()
+      .ok_or(HalaRendererError::new("Failed to find the bloom source descriptor set.", None))?;
+
+    let set_mip_level_barrier = |mip_level: u32, old_access: RGAccess, new_access: RGAccess| {
+      graphics_command_buffers.set_image_barriers(
+        index,
+        &[hala_gfx::HalaImageBarrierInfo {
+          old_layout: old_access.layout(),
+          new_layout: new_access.layout(),
+          src_access_mask: old_access.access_mask(),
+          dst_access_mask: new_access.access_mask(),
+          src_stage_mask: old_access.stage_mask(),
+          dst_stage_mask: new_access.stage_mask(),
+          aspect_mask: hala_gfx::HalaImageAspectFlags::COLOR,
+          image: bloom_image.raw,
+          base_mip_level: mip_level,
+          ..Default::default()
+        }],
+      );
+    };
+    let get_texel_size = |width: u32, height: u32| {
+      (1.0 / width.max(1) as f32, 1.0 / height.max(1) as f32)
+    };
+
+    // Each mip level is filtered from the larger one, the first one from the source color with the Karis average against the fireflies.
+    for mip_level in 0..bloom_image.mip_levels {
+      let (source_descriptor_set, (texel_width, texel_height)) = if mip_level == 0 {
+        (bloom_source_descriptor_set, get_texel_size(self.target_width, self.target_height))
+      } else {
+        let source_descriptor_set = self.bloom_descriptor_sets.get(mip_level as usize - 1)
+          .ok_or(HalaRendererError::new("Failed to find the bloom descriptor set.", None))?;
+        (source_descriptor_set, get_texel_size(bloom_image.extent.width >> (mip_level - 1), bloom_image.extent.height >> (mip_level - 1)))
+      };
+      let use_karis_average = (mip_level == 0) as u32;
+      let mut push_constants = Vec::new();
+      push_constants.extend_from_slice(&texel_width.to_le_bytes());
+      push_constants.extend_from_slice(&texel_height.to_le_bytes());
+      push_constants.extend_from_slice(&use_karis_average.to_le_bytes());
+      self.begin_post_process_rendering(index, graphics_command_buffers, bloom_image, mip_level, false);
+      bloom_downsample_program.push_constants(
+        index,
+        graphics_command_buffers,
+        0,
+        push_constants.as_slice(),
+      );
+      self.draw_screen_quad(
+        index,
+        graphics_command_buffers,
+        bloom_downsample_program,
+        Some(source_descriptor_set),
+      )?;
+      graphics_command_buffers.end_rendering(index);
+      set_mip_level_barrier(mip_level, RGAccess::ColorAttachment, RGAccess::ShaderRead);
+    }
+
+    // Each mip level is blurred by the tent filter and added onto the larger one, from the smallest to the first one.
+    for mip_level in (0..bloom_image.mip_levels.saturating_sub(1)).rev() {
+      let source_descriptor_set = self.bloom_descriptor_sets.get(mip_level as usize + 1)
+        .ok_or(HalaRendererError::new("Failed to find the bloom descriptor set.", None))?;
+      let (texel_width, texel_height) = get_texel_size(bloom_image.extent.width >> (mip_level + 1), bloom_image.extent.height >> (mip_level + 1));
+      let mut push_constants = Vec::new();
+      push_constants.extend_from_slice(&texel_width.to_le_bytes());
+      push_constants.extend_from_slice(&texel_height.to_le_bytes());
+      push_constants.extend_from_slice(&self.post_process_settings.bloom_radius.to_le_bytes());
+      set_mip_level_barrier(mip_level, RGAccess::ShaderRead, RGAccess::ColorAttachment);
+      self.begin_post_process_rendering(index, graphics_command_buffers, bloom_image, mip_level, true);
+      bloom_upsample_program.push_constants(
+        index,
+        graphics_command_buffers,
+        0,
+        push_constants.as_slice(),
+      );
+      self.draw_screen_quad(
+        index,
+        graphics_command_buffers,
+        bloom_upsample_program,
+        Some(source_descriptor_set),
+      )?;
+      graphics_command_buffers.end_rendering(index);
+      set_mip_level_barrier(mip_level, RGAccess::ColorAttachment, RGAccess::ShaderRead);
+    }
+
+    // Set screen viewport and scissor.
+    self.set_screen_viewport(index, graphics_command_buffers, self.render_width, self.render_height);
+
+    Ok(())
+  }
+
+  /// The tonemap pass, draws the exposed and tonemapped scene color onto the final target at the window size.
+  /// The bloom, the vignette and the chromatic aberration are applied to the HDR color before the tonemapping.
+  /// The debug views are drawn without the post-processing, the exposure and the tonemapping.
+  /// param index: The index of the current image.
+  /// param graphics_command_buffers: The graphics command buffers.
+  /// return: The result.
+  pub(crate) fn tonemap_pass(
+    &self,
+    index: usize,
+    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
+  ) -> Result<(), HalaRendererError> {
+    let tonemap_program = self.graphics_programs.get("tonemap")
+      .ok_or(HalaRendererError::new("Failed to find the tonemap program.", None))?;
+    // The resolved color of the TAA is in the history image written by this frame.
+    let tonemap_descriptor_set = if self.is_taa_active() {
+      self.taa_tonemap_descriptor_sets.get(self.get_taa_history_indices().1)
+    } else {
+      self.graphics_descriptor_sets.get("tonemap")
+    };
+
+    let settings = &self.tonemap_settings;
+    let (tonemap_operator, exposure, use_auto_exposure) = if !self.is_tonemapped_view() {
+      (0u32, 1f32, 0u32)
+    } else if settings.auto_exposure {
+      (settings.operator.get_shader_index(), settings.exposure_compensation.exp2(), 1u32)
+    } else {
+      (settings.operator.get_shader_index(), settings.exposure.exp2(), 0u32)
+    };
+    // The lens effects are off in the debug views, 0 bloom mip levels skips the bloom.
+    let post_process_settings = &self.post_process_settings;
+    let is_tonemapped_view = self.is_tonemapped_view();
+    let (bloom_intensity, bloom_mip_levels) = match self.bloom_image.as_ref() {
+      Some(bloom_image) if self.is_bloom_active() => (post_process_settings.bloom_intensity, bloom_image.mip_levels),
+      _ => (0f32, 0u32),
+    };
+    let vignette_intensity = if is_tonemapped_view && post_process_settings.vignette {
+      post_process_settings.vignette_intensity
+    } else {
+      0f32
+    };
+    let chromatic_aberration = if is_tonemapped_view && post_process_settings.chromatic_aberration {
+      post_process_settings.chromatic_aberration_intensity * 0.01
+    } else {
+      0f32
+    };
+    let mut push_constants = Vec::new();
+    push_constants.extend_from_slice(&tonemap_operator.to_le_bytes());
+    push_constants.extend_from_slice(&exposure.to_le_bytes());
+    push_constants.extend_from_slice(&use_auto_exposure.to_le_bytes());
+    push_constants.extend_from_slice(&bloom_intensity.to_le_bytes());
+    push_constants.extend_from_slice(&bloom_mip_levels.to_le_bytes());
+    push_constants.extend_from_slice(&vignette_intensity.to_le_bytes());
+    push_constants.extend_from_slice(&chromatic_aberration.to_le_bytes());
+    tonemap_program.push_constants(
+      index,
+      graphics_command_buffers,
+      0,
+      push_constants.as_slice(),
+    );
+
+    self.set_screen_viewport(index, graphics_command_buffers, self.info.width, self.info.height);
+    self.draw_screen_quad(
+      index,
+      graphics_command_buffers,
+      tonemap_program,
+      tonemap_descriptor_set,
+    )?;
+
+    Ok(())
+  }
+
+  /// The selection outline pass, draws the outline of the selected object onto the final target at the window size.
+  /// The outline follows the visible pixels of the selected draw, so the occluded parts are not outlined.
+  /// param index: The index of the current image.
+  /// param graphics_command_buffers: The graphics command buffers.
+  /// return: The result.
+  pub(crate) fn selection_outline_pass(
+    &self,
+    index: usize,
+    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
+  ) -> Result<(), HalaRendererError> {
+    let selection_outline_program = self.graphics_programs.get("selection_outline")
+      .ok_or(HalaRendererError::new("Failed to find the selection outline program.", None))?;
+    let selection_outline_descriptor_set = self.graphics_descriptor_sets.get("selection_outline")
+      .ok_or(HalaRendererError::new("Failed to find the selection outline descriptor set.", None))?;
+    let picked_object = self.picked_object.as_ref()
+      .ok_or(HalaRendererError::new("Failed to find the selected object.", None))?;
+
+    let outline_offset = self.get_selection_outline_offset();
+    let mut push_constants = Vec::new();
+    push_constants.extend_from_slice(&self.render_width.to_le_bytes());
+    push_constants.extend_from_slice(&self.render_height.to_le_bytes());
+    push_constants.extend_from_slice(&outline_offset.x.to_le_bytes());
+    push_constants.extend_from_slice(&outline_offset.y.to_le_bytes());
+    push_constants.extend_from_slice(&picked_object.draw_index.to_le_bytes());
+    selection_outline_program.push_constants(
+      index,
+      graphics_command_buffers,
+      0,
+      push_constants.as_slice(),
+    );
+
+    self.draw_screen_quad(
+      index,
+      graphics_command_buffers,
+      selection_outline_program,
+      Some(selection_outline_descriptor_set),
+    )?;
+
+    Ok(())
+  }
+
+  /// The final view pass, draws the lighting result or the selected debug view onto the final target.
+  /// param index: The index of the current image.
+  /// param graphics_command_buffers: The graphics command buffers.
+  /// return: The result.
+  pub(crate) fn final_view_pass(
+    &self,
+    index: usize,
+    graphics_command_buffers: &hala_gfx::HalaCommandBufferSet,
+  ) -> Result<(), HalaRendererError> {
+    let scene = self.scene_in_gpu.as_ref().ok_or(hala_gfx::HalaGfxError::new("The scene in GPU is none!", None))?;
+
+    if self.debug_settings.show_triangle {
+      let triangle_visualization_program = self.graphics_programs.get("triangle_visualization")
+        .ok_or(HalaRendererError::new("Failed to find the triangle visualization program.", None))?;
+      let triangle_visualization_descriptor_set = self.graphics_descriptor_sets.get("triangle_visualization");
+      self.draw_scene(
+        index,
+        graphics_command_buffers,
+        true,
+        triangle_visualization_program,
+        triangle_visualization_descriptor_set,
+      )?;
+    } else if self.debug_settings.show_meshlet {
+      let meshlet_visualization_program = self.graphics_programs.get("meshlet_visualization")
+        .ok_or(HalaRendererError::new("Failed to find the meshlet visualization program.", None))?;
+      let meshlet_visualization_descriptor_set = self.graphics_descriptor_sets.get("meshlet_visualization");
+      self.draw_scene(
+        index,
+        graphics_command_buffers,
+        true,
+        meshlet_visualization_program,
+        meshlet_visualization_descriptor_set,
+      )?;
+    } else if self.debug_settings.show_hiz {
+      let hiz_visualization_program = self.graphics_programs.get("hiz_visualization")
+        .ok_or(HalaRendererError::new("Failed to find the Hi-Z visualization program.", None))?;
+      hiz_visualization_program.push_constants_f32(
+        index,
+        graphics_command_buffers,
+        0,
+        &[100f32]
+      );
+      self.draw_screen_quad(
+        index,
+        graphics_command_buffers,
+        hiz_visualization_program,
+        self.hiz_descriptor_sets.get((self.debug_settings.hiz_level as usize).min(self.hiz_descriptor_sets.len() - 1)),
+      )?;
+    } else if self.debug_settings.show_visibility {
+      let id_buffer_visualization_program = self.graphics_programs.get("id_buffer_visualization")
+        .ok_or(HalaRendererError::new("Failed to find the id buffer visualization program.", None))?;
+      let id_buffer_visualization_descriptor_set = self.graphics_descriptor_sets.get("id_buffer_visualization");
+      self.draw_screen_quad(
+        index,
+        graphics_command_buffers,
+        id_buffer_visualization_program,
+        id_buffer_visualization_descriptor_set,
+      )?;
+    } else if self.debug_settings.show_normal {
+      let normal_visualization_program = self.graphics_programs.get("normal_visualization")
+        .ok_or(HalaRendererError::new("Failed to find the normal visualization program.", None))?;
+      let normal_visualization_descriptor_set = self.graphics_descriptor_sets.get("normal_visualization");
+      self.draw_screen_quad(
+        index,
+        graphics_command_buffers,
+        normal_visualization_program,
+        normal_visualization_descriptor_set,
+      )?;
+    } else if self.debug_settings.show_material_depth || self.debug_settings.show_albedo {
+      let attachment_to_screen_program = self.graphics_programs.get("attachment_to_screen")
+        .ok_or(HalaRendererError::new("Failed to find the attachment to screen program.", None))?;
+      let attachment_to_screen_descriptor_set = self.graphics_descriptor_sets.get("attachment_to_screen");
+      // Scale the material depth value to visualize.
+      let scale = GlobalConstants::CLASSIFY_DEPTH_RANGE as f32 / scene.materials.len() as f32;
+      let scales = if self.debug_settings.show_material_depth {
+        [scale, scale, scale, 1.0]
+      } else {
+        [1.0, 1.0, 1.0, 1.0]
+      };
+      attachment_to_screen_program.push_constants_f32(
+        index,
+        graphics_command_buffers,
+        0,
+        &scales,
+      );
+      self.draw_screen_quad(
+        index,
+        graphics_command_buffers,
+        attachment_to_screen_program,
+        attachment_to_screen_descriptor_set,
+      )?;
+    } else {
+      self.lighting_pass(index, graphics_command_buffers)?;
+      self.skybox_pass(index, graphics_command_buffers)?;
+      self.transparent_pass(index, graphics_command_buffers)?;
+    }
+
+    Ok(())
+  }
 }
\ No newline at end of file
@@ -1,6 +1,14 @@
 use hala_renderer::error::HalaRendererError;
 
-use crate::renderer::VisRenderer;
+use crate::renderer::{
+  VisRenderer,
+  material_variant::{
+    NUM_OF_MATERIAL_VARIANTS,
+    TRANSPARENT_PROGRAMS,
+    DRAW_FLAG_DOUBLE_SIDED,
+    get_variant_program_name,
+  },
+};
 
 /// The debug implementation of the visibility renderer.
 impl VisRenderer {
@@ -16,12 +24,6 @@ impl VisRenderer {
         0,
         &[self.albedo_image.as_ref()],
       );
-    } else if self.debug_settings.show_normal {
-      attachment_to_screen_descriptor_set.update_input_attachments(
-        0,
-        0,
-        &[self.normal_image.as_ref()],
-      );
     } else {
       attachment_to_screen_descriptor_set.update_input_attachments(
         0,
@@ -30,6 +32,14 @@ impl VisRenderer {
       );
     }
 
+    let normal_visualization_descriptor_set = self.graphics_descriptor_sets.get("normal_visualization")
+      .ok_or(HalaRendererError::new("Failed to find the normal visualization descriptor set.", None))?;
+    normal_visualization_descriptor_set.update_input_attachments(
+      0,
+      0,
+      &[self.normal_image.as_ref()],
+    );
+
     let id_buffer_visualization_descriptor_set = self.graphics_descriptor_sets.get("id_buffer_visualization")
       .ok_or(HalaRendererError::new("Failed to find the id buffer visualization descriptor set.", None))?;
     id_buffer_visualization_descriptor_set.update_input_attachments(
@@ -58,39 +68,42 @@ impl VisRenderer {
       &[self.depth_image.as_ref()],
     );
 
-    let one_pass_culling_descriptor_set = self.graphics_descriptor_sets.get("one_pass_culling")
-      .ok_or(HalaRendererError::new("Failed to find the one pass culling descriptor set.", None))?;
-    one_pass_culling_descriptor_set.update_sampled_images(
-      0,
-      0,
-      &[self.hiz_image.as_ref()],
-    );
+    // Each material variant of the culling programs has its own descriptor set.
+    for material_variant in 0..NUM_OF_MATERIAL_VARIANTS {
+      let one_pass_culling_descriptor_set = self.graphics_descriptor_sets.get(&get_variant_program_name("one_pass_culling", material_variant))
+        .ok_or(HalaRendererError::new("Failed to find the one pass culling descriptor set.", None))?;
+      one_pass_culling_descriptor_set.update_sampled_images(
+        0,
+        0,
+        &[self.hiz_image.as_ref()],
+      );
 
-    let pre_culling_descriptor_set = self.graphics_descriptor_sets.get("pre_culling")
-      .ok_or(HalaRendererError::new("Failed to find the pre culling descriptor set.", None))?;
-    pre_culling_descriptor_set.update_sampled_images(
-      0,
-      0,
-      &[self.hiz_image.as_ref()],
-    );
-    pre_culling_descriptor_set.update_storage_buffers(
-      0,
-      1,
-      &[self.pre_culling_flags.as_ref().unwrap()],
-    );
+      let pre_culling_descriptor_set = self.graphics_descriptor_sets.get(&get_variant_program_name("pre_culling", material_variant))
+        .ok_or(HalaRendererError::new("Failed to find the pre culling descriptor set.", None))?;
+      pre_culling_descriptor_set.update_sampled_images(
+        0,
+        0,
+        &[self.hiz_image.as_ref()],
+      );
+      pre_culling_descriptor_set.update_storage_buffers(
+        0,
+        1,
+        &[self.pre_culling_flags.as_ref().unwrap()],
+      );
 
-    let visibility_buffer_descriptor_set = self.graphics_descriptor_sets.get("visibility_buffer")
-      .ok_or(HalaRendererError::new("Failed to find the visibility buffer descriptor set.", None))?;
-    visibility_buffer_descriptor_set.update_sampled_images(
-      0,
-      0,
-      &[self.hiz_image.as_ref()],
-    );
-    visibility_buffer_descriptor_set.update_storage_buffers(
-      0,
-      1,
-      &[self.pre_culling_flags.as_ref().unwrap()],
-    );
+      let visibility_buffer_descriptor_set = self.graphics_descriptor_sets.get(&get_variant_program_name("visibility_buffer", material_variant))
+        .ok_or(HalaRendererError::new("Failed to find the visibility buffer descriptor set.", None))?;
+      visibility_buffer_descriptor_set.update_sampled_images(
+        0,
+        0,
+        &[self.hiz_image.as_ref()],
+      );
+      visibility_buffer_descriptor_set.update_storage_buffers(
+        0,
+        1,
+        &[self.pre_culling_flags.as_ref().unwrap()],
+      );
+    }
 
     let material_depth_descriptor_set = self.graphics_descriptor_sets.get("material_depth")
       .ok_or(HalaRendererError::new("Failed to find the material depth descriptor set.", None))?;
@@ -118,21 +131,16 @@ impl VisRenderer {
     material_classification_descriptor_set.update_combined_image_samplers(
       0,
       0,
-      &[(self.visibility_image.as_ref(), self.point_sampler.as_ref())],
-    );
-    material_classification_descriptor_set.update_combined_image_samplers(
-      0,
-      1,
-      &[(self.depth_image.as_ref(), self.point_sampler.as_ref())],
+      &[(self.material_depth_image.as_ref(), self.point_sampler.as_ref())],
     );
     material_classification_descriptor_set.update_storage_buffers(
       0,
-      2,
+      1,
       &[self.indirect_draw_buffer.as_ref()],
     );
     material_classification_descriptor_set.update_storage_buffers(
       0,
-      3,
+      2,
       &[self.tile_index_buffer.as_ref()],
     );
 
@@ -166,6 +174,299 @@ impl VisRenderer {
       2,
       &[self.depth_image.as_ref()],
     );
+    lighting_descriptor_set.update_input_attachments(
+      0,
+      3,
+      &[self.material_image.as_ref()],
+    );
+    lighting_descriptor_set.update_input_attachments(
+      0,
+      4,
+      &[self.emissive_image.as_ref()],
+    );
+    lighting_descriptor_set.update_storage_buffers(
+      0,
+      5,
+      &[self.light_cluster_buffer.as_ref()],
+    );
+    lighting_descriptor_set.update_combined_image_samplers(
+      0,
+      6,
+      &[(self.shadow_map_image.as_ref(), self.point_sampler.as_ref())],
+    );
+    lighting_descriptor_set.update_uniform_buffers(
+      0,
+      7,
+      &[self.shadow_uniform_buffer.as_ref()],
+    );
+    lighting_descriptor_set.update_combined_image_samplers(
+      0,
+      8,
+      &[(&self.environment.specular_image, self.environment.sampler.as_ref())],
+    );
+    lighting_descriptor_set.update_combined_image_samplers(
+      0,
+      9,
+      &[(&self.environment.irradiance_image, self.environment.sampler.as_ref())],
+    );
+
+    // Without the blur, the SSAO pass writes the ambient occlusion read by the lighting directly.
+    let ssao_raw_image = self.ssao_raw_image.as_ref()
+      .ok_or(HalaRendererError::new("Failed to find the raw SSAO image.", None))?;
+    let ssao_image = self.ssao_image.as_ref()
+      .ok_or(HalaRendererError::new("Failed to find the SSAO image.", None))?;
+    lighting_descriptor_set.update_combined_image_samplers(
+      0,
+      10,
+      &[(ssao_image, self.linear_sampler.as_ref())],
+    );
+
+    let ssao_descriptor_set = self.compute_descriptor_sets.get("ssao")
+      .ok_or(HalaRendererError::new("Failed to find the SSAO descriptor set.", None))?;
+    ssao_descriptor_set.update_combined_image_samplers(
+      0,
+      0,
+      &[(self.depth_image.as_ref(), self.point_sampler.as_ref())],
+    );
+    ssao_descriptor_set.update_combined_image_samplers(
+      0,
+      1,
+      &[(self.normal_image.as_ref(), self.point_sampler.as_ref())],
+    );
+    ssao_descriptor_set.update_storage_images(
+      0,
+      2,
+      &[if self.ssao_settings.blur { ssao_raw_image } else { ssao_image }],
+    );
+
+    let ssao_blur_descriptor_set = self.compute_descriptor_sets.get("ssao_blur")
+      .ok_or(HalaRendererError::new("Failed to find the SSAO blur descriptor set.", None))?;
+    ssao_blur_descriptor_set.update_combined_image_samplers(
+      0,
+      0,
+      &[(ssao_raw_image, self.point_sampler.as_ref())],
+    );
+    ssao_blur_descriptor_set.update_combined_image_samplers(
+      0,
+      1,
+      &[(self.depth_image.as_ref(), self.point_sampler.as_ref())],
+    );
+    ssao_blur_descriptor_set.update_storage_images(
+      0,
+      2,
+      &[ssao_image],
+    );
+
+    // The pick and the selection outline read the visibility IDs and the depth texel by texel.
+    let pick_descriptor_set = self.compute_descriptor_sets.get("pick")
+      .ok_or(HalaRendererError::new("Failed to find the pick descriptor set.", None))?;
+    pick_descriptor_set.update_combined_image_samplers(
+      0,
+      0,
+      &[(self.visibility_image.as_ref(), self.point_sampler.as_ref())],
+    );
+    pick_descriptor_set.update_combined_image_samplers(
+      0,
+      1,
+      &[(self.depth_image.as_ref(), self.point_sampler.as_ref())],
+    );
+    pick_descriptor_set.update_storage_buffers(
+      0,
+      2,
+      &[self.pick_buffer.as_ref()],
+    );
+
+    let selection_outline_descriptor_set = self.graphics_descriptor_sets.get("selection_outline")
+      .ok_or(HalaRendererError::new("Failed to find the selection outline descriptor set.", None))?;
+    selection_outline_descriptor_set.update_combined_image_samplers(
+      0,
+      0,
+      &[(self.visibility_image.as_ref(), self.point_sampler.as_ref())],
+    );
+    selection_outline_descriptor_set.update_combined_image_samplers(
+      0,
+      1,
+      &[(self.depth_image.as_ref(), self.point_sampler.as_ref())],
+    );
+
+    let skybox_descriptor_set = self.graphics_descriptor_sets.get("skybox")
+      .ok_or(HalaRendererError::new("Failed to find the skybox descriptor set.", None))?;
+    skybox_descriptor_set.update_combined_image_samplers(
+      0,
+      0,
+      &[(&self.environment.equirect_image, self.environment.equirect_sampler.as_ref())],
+    );
+
+    // The single-sided and the double-sided transparent programs light the fragments as the lighting pass.
+    for name in TRANSPARENT_PROGRAMS.iter() {
+      for material_variant in [0, DRAW_FLAG_DOUBLE_SIDED] {
+        let transparent_descriptor_set = self.graphics_descriptor_sets.get(&get_variant_program_name(name, material_variant))
+          .ok_or(HalaRendererError::new("Failed to find the transparent descriptor set.", None))?;
+        transparent_descriptor_set.update_storage_buffers(
+          0,
+          0,
+          &[self.light_cluster_buffer.as_ref()],
+        );
+        transparent_descriptor_set.update_combined_image_samplers(
+          0,
+          1,
+          &[(self.shadow_map_image.as_ref(), self.point_sampler.as_ref())],
+        );
+        transparent_descriptor_set.update_uniform_buffers(
+          0,
+          2,
+          &[self.shadow_uniform_buffer.as_ref()],
+        );
+        transparent_descriptor_set.update_combined_image_samplers(
+          0,
+          3,
+          &[(&self.environment.specular_image, self.environment.sampler.as_ref())],
+        );
+        transparent_descriptor_set.update_combined_image_samplers(
+          0,
+          4,
+          &[(&self.environment.irradiance_image, self.environment.sampler.as_ref())],
+        );
+      }
+    }
+
+    if let (Some(oit_accumulation_image), Some(oit_weight_image)) = (self.oit_accumulation_image.as_ref(), self.oit_weight_image.as_ref()) {
+      let transparent_composite_descriptor_set = self.graphics_descriptor_sets.get("transparent_composite")
+        .ok_or(HalaRendererError::new("Failed to find the transparent composite descriptor set.", None))?;
+      transparent_composite_descriptor_set.update_combined_image_samplers(
+        0,
+        0,
+        &[(oit_accumulation_image, self.point_sampler.as_ref())],
+      );
+      transparent_composite_descriptor_set.update_combined_image_samplers(
+        0,
+        1,
+        &[(oit_weight_image, self.point_sampler.as_ref())],
+      );
+    }
+
+    let shadow_map_descriptor_set = self.graphics_descriptor_sets.get("shadow_map")
+      .ok_or(HalaRendererError::new("Failed to find the shadow map descriptor set.", None))?;
+    shadow_map_descriptor_set.update_uniform_buffers(
+      0,
+      0,
+      &[self.shadow_uniform_buffer.as_ref()],
+    );
+
+    let light_culling_descriptor_set = self.compute_descriptor_sets.get("light_culling")
+      .ok_or(HalaRendererError::new("Failed to find the light culling descriptor set.", None))?;
+    light_culling_descriptor_set.update_combined_image_samplers(
+      0,
+      0,
+      &[(self.hiz_image.as_ref(), self.point_sampler.as_ref())],
+    );
+    light_culling_descriptor_set.update_storage_buffers(
+      0,
+      1,
+      &[self.light_cluster_buffer.as_ref()],
+    );
+
+    // The histogram reads the scene color texel by texel.
+    let scene_color_image = self.scene_color_image.as_ref()
+      .ok_or(HalaRendererError::new("Failed to find the scene color image.", None))?;
+    let luminance_histogram_descriptor_set = self.compute_descriptor_sets.get("luminance_histogram")
+      .ok_or(HalaRendererError::new("Failed to find the luminance histogram descriptor set.", None))?;
+    luminance_histogram_descriptor_set.update_combined_image_samplers(
+      0,
+      0,
+      &[(scene_color_image, self.point_sampler.as_ref())],
+    );
+    luminance_histogram_descriptor_set.update_storage_buffers(
+      0,
+      1,
+      &[self.luminance_histogram_buffer.as_ref()],
+    );
+
+    let exposure_descriptor_set = self.compute_descriptor_sets.get("exposure")
+      .ok_or(HalaRendererError::new("Failed to find the exposure descriptor set.", None))?;
+    exposure_descriptor_set.update_storage_buffers(
+      0,
+      0,
+      &[self.luminance_histogram_buffer.as_ref()],
+    );
+    exposure_descriptor_set.update_storage_buffers(
+      0,
+      1,
+      &[self.exposure_buffer.as_ref()],
+    );
+
+    // Each TAA descriptor set reads one history image, the other one is written in the same frame.
+    let motion_image = self.motion_image.as_ref()
+      .ok_or(HalaRendererError::new("Failed to find the motion image.", None))?;
+    for (taa_descriptor_set, history_image) in self.taa_descriptor_sets.iter().zip(self.taa_history_images.iter()) {
+      taa_descriptor_set.update_combined_image_samplers(
+        0,
+        0,
+        &[(scene_color_image, self.point_sampler.as_ref())],
+      );
+      taa_descriptor_set.update_combined_image_samplers(
+        0,
+        1,
+        &[(history_image, self.linear_sampler.as_ref())],
+      );
+      taa_descriptor_set.update_combined_image_samplers(
+        0,
+        2,
+        &[(motion_image, self.point_sampler.as_ref())],
+      );
+      taa_descriptor_set.update_combined_image_samplers(
+        0,
+        3,
+        &[(self.depth_image.as_ref(), self.point_sampler.as_ref())],
+      );
+    }
+    // The bloom is blurred from the color to be tonemapped, and added back when tonemapping.
+    let bloom_image = self.bloom_image.as_ref()
+      .ok_or(HalaRendererError::new("Failed to find the bloom image.", None))?;
+    for (bloom_source_descriptor_set, source_image) in self.bloom_source_descriptor_sets.iter().zip(std::iter::once(scene_color_image).chain(self.taa_history_images.iter())) {
+      bloom_source_descriptor_set.update_sampled_images(
+        0,
+        0,
+        &[source_image],
+      );
+    }
+
+    for (taa_tonemap_descriptor_set, history_image) in self.taa_tonemap_descriptor_sets.iter().zip(self.taa_history_images.iter()) {
+      taa_tonemap_descriptor_set.update_combined_image_samplers(
+        0,
+        0,
+        &[(history_image, self.linear_sampler.as_ref())],
+      );
+      taa_tonemap_descriptor_set.update_storage_buffers(
+        0,
+        1,
+        &[self.exposure_buffer.as_ref()],
+      );
+      taa_tonemap_descriptor_set.update_combined_image_samplers(
+        0,
+        2,
+        &[(bloom_image, self.linear_sampler.as_ref())],
+      );
+    }
+
+    // The scene color image is upscaled to the screen with the bilinear filter when tonemapping.
+    let tonemap_descriptor_set = self.graphics_descriptor_sets.get("tonemap")
+      .ok_or(HalaRendererError::new("Failed to find the tonemap descriptor set.", None))?;
+    tonemap_descriptor_set.update_combined_image_samplers(
+      0,
+      0,
+      &[(scene_color_image, self.linear_sampler.as_ref())],
+    );
+    tonemap_descriptor_set.update_storage_buffers(
+      0,
+      1,
+      &[self.exposure_buffer.as_ref()],
+    );
+    tonemap_descriptor_set.update_combined_image_samplers(
+      0,
+      2,
+      &[(bloom_image, self.linear_sampler.as_ref())],
+    );
 
     Ok(())
   }
@@ -13,8 +13,8 @@ use hala_renderer::renderer::{
 use super::{
   GlobalUniform,
   ObjectUniform,
-  GlobalConstants,
   VisRenderer,
+  shadow::ShadowUniform,
 };
 
 /// The RendererTrait implementation for the visibility renderer.
@@ -92,6 +92,9 @@ impl HalaRendererTrait for VisRenderer {
     let scene = self.scene_in_gpu.as_ref().ok_or(HalaRendererError::new("The scene in GPU is none!", None))?;
     let meshlets = scene.meshlets.as_ref().ok_or(HalaRendererError::new("The global meshlet buffer is none!", None))?;
     let meshlet_draw_data = scene.meshlet_draw_data.as_ref().ok_or(HalaRendererError::new("The draw data buffer is none!", None))?;
+    let draw_material_flags = self.draw_material_flags_buffer.as_ref().ok_or(HalaRendererError::new("The draw material flags buffer is none!", None))?;
+    let tangent_signs = self.tangent_signs_buffer.as_ref().ok_or(HalaRendererError::new("The tangent signs buffer is none!", None))?;
+    let light_ranges = self.light_ranges_buffer.as_ref().ok_or(HalaRendererError::new("The light ranges buffer is none!", None))?;
 
     // Assert camera count.
     if scene.camera_view_matrices.is_empty() || scene.camera_proj_matrices.is_empty() {
@@ -104,6 +107,10 @@ impl HalaRendererTrait for VisRenderer {
     self.static_descriptor_set.update_uniform_buffers(0, 2, &[scene.lights.as_ref()]);
     self.static_descriptor_set.update_storage_buffers(0, 3, &[meshlet_draw_data]);
     self.static_descriptor_set.update_storage_buffers(0, 4, &[meshlets]);
+    self.static_descriptor_set.update_storage_buffers(0, 5, &[&self.render_statistics_buffer]);
+    self.static_descriptor_set.update_storage_buffers(0, 6, &[draw_material_flags]);
+    self.static_descriptor_set.update_storage_buffers(0, 7, &[tangent_signs]);
+    self.static_descriptor_set.update_storage_buffers(0, 8, &[light_ranges]);
 
     // Collect vertex and index buffers.
     let mut vertex_buffers = Vec::new();
@@ -173,15 +180,15 @@ impl HalaRendererTrait for VisRenderer {
         ],
         "main_dynamic.descriptor_set_layout",
       )?,
-      context.swapchain.num_of_images,
+      self.num_of_frames,
       0,
       "main_dynamic.descriptor_set",
     )?;
 
     for (mesh_index, _mesh) in scene.meshes.iter().enumerate() {
       // Create object uniform buffer.
-      let mut buffers = Vec::with_capacity(context.swapchain.num_of_images);
-      for index in 0..context.swapchain.num_of_images {
+      let mut buffers = Vec::with_capacity(self.num_of_frames);
+      for index in 0..self.num_of_frames {
         let buffer = hala_gfx::HalaBuffer::new(
           Rc::clone(&context.logical_device),
           std::mem::size_of::<ObjectUniform>() as u64,
@@ -196,7 +203,7 @@ impl HalaRendererTrait for VisRenderer {
       self.object_uniform_buffers.push(buffers);
     }
 
-    for index in 0..context.swapchain.num_of_images {
+    for index in 0..self.num_of_frames {
       dynamic_descriptor_set.update_uniform_buffers(
         index,
         0,
@@ -296,49 +303,15 @@ impl HalaRendererTrait for VisRenderer {
   /// return: The result.
   fn check_and_restore_device(&mut self, width: u32, height: u32) -> Result<(), HalaRendererError> {
     if self.data().is_device_lost {
-      unsafe {
-        std::mem::ManuallyDrop::drop(&mut self.hiz_image);
-        std::mem::ManuallyDrop::drop(&mut self.normal_image);
-        std::mem::ManuallyDrop::drop(&mut self.albedo_image);
-        std::mem::ManuallyDrop::drop(&mut self.material_depth_image);
-        std::mem::ManuallyDrop::drop(&mut self.visibility_image);
-        std::mem::ManuallyDrop::drop(&mut self.depth_image);
-      }
-      let (
-        visibility_image,
-        depth_image,
-        material_depth_image,
-        albedo_image,
-        normal_image,
-        hiz_image,
-      ) = Self::create_offscreen_images(self.resources(), width, height, false)?;
-      self.visibility_image = std::mem::ManuallyDrop::new(visibility_image);
-      self.depth_image = std::mem::ManuallyDrop::new(depth_image);
-      self.material_depth_image = std::mem::ManuallyDrop::new(material_depth_image);
-      self.albedo_image = std::mem::ManuallyDrop::new(albedo_image);
-      self.normal_image = std::mem::ManuallyDrop::new(normal_image);
-      self.hiz_image = std::mem::ManuallyDrop::new(hiz_image);
-
-      unsafe {
-        std::mem::ManuallyDrop::drop(&mut self.tile_index_buffer);
-      }
-      let tile_index_buffer = {
-        let x = (width + GlobalConstants::CLASSIFY_TILE_WIDTH - 1) / GlobalConstants::CLASSIFY_TILE_WIDTH;
-        let y = (height + GlobalConstants::CLASSIFY_TILE_WIDTH - 1) / GlobalConstants::CLASSIFY_TILE_WIDTH;
-        let num_of_tiles = x * y;
-        hala_gfx::HalaBuffer::new(
-          Rc::clone(&self.resources().context.borrow().logical_device),
-          std::mem::size_of::<u32>() as u64 * num_of_tiles as u64 * GlobalConstants::CLASSIFY_DEPTH_RANGE as u64,
-          hala_gfx::HalaBufferUsageFlags::STORAGE_BUFFER | hala_gfx::HalaBufferUsageFlags::TRANSFER_SRC,
-          hala_gfx::HalaMemoryLocation::GpuOnly,
-          "tile_index.buffer",
-        )?
-      };
-      self.tile_index_buffer = std::mem::ManuallyDrop::new(tile_index_buffer);
-
-      self.setup_visibility()?;
+      self.recreate_size_dependent_resources(self.target_width, self.target_height)?;
+    }
+    // There is no swapchain without a window.
+    if !self.is_headless {
+      self.check_and_restore_swapchain(width, height)?;
     }
-    self.check_and_restore_swapchain(width, height)?;
+
+    // A plain resize only recreates the swapchain, the offscreen resources follow the new window size.
+    self.update_render_size(width, height)?;
 
     Ok(())
   }
@@ -349,42 +322,95 @@ impl HalaRendererTrait for VisRenderer {
   /// param height: The height of the window.
   /// param ui_fn: The draw UI function.
   /// return: The result.
-  fn update<F>(&mut self, _delta_time: f64, width: u32, height: u32, ui_fn: F) -> Result<(), HalaRendererError>
+  fn update<F>(&mut self, delta_time: f64, width: u32, height: u32, ui_fn: F) -> Result<(), HalaRendererError>
     where F: FnOnce(usize, &hala_gfx::HalaCommandBufferSet) -> Result<(), hala_gfx::HalaGfxError>
   {
     self.pre_update(width, height)?;
 
+    // The last frame of this image is finished, read back its pass timings and the picked object.
+    let gpu_time = self.gpu_profiler.resolve(self.data.image_index)?;
+    self.resolve_pick(self.data.image_index)?;
+
+    // Choose the render scale by the GPU time from the frame begin to the frame end of the frame resolved just now.
+    // No frame is resolved before the image is recorded, the last timing is not fed again.
+    if let Some(gpu_time) = gpu_time {
+      if self.dynamic_resolution.update(gpu_time) {
+        self.update_render_size(width, height)?;
+      }
+    }
+
+    // The sub-pixel jitter of the TAA only moves the rasterization, the motion vectors and the shadows use the unjittered matrices.
+    let jitter = self.get_taa_jitter();
+    let is_taa_history_valid = self.is_taa_history_valid;
+
     let scene = self.scene_in_gpu.as_ref().ok_or(HalaRendererError::new("The scene in GPU is none!", None))?;
 
-    // Update global uniform buffer(Only use No.1 camera).
-    let vp_mtx = scene.camera_proj_matrices[0] * scene.camera_view_matrices[0];
+    // Update global uniform buffer.
+    let v_mtx = self.camera_v_mtx;
+    let unjittered_p_mtx = self.camera_p_mtx;
+    let p_mtx = glam::Mat4::from_translation(glam::vec3(jitter.x, jitter.y, 0.0)) * unjittered_p_mtx;
+    let vp_mtx = p_mtx * v_mtx;
+    let unjittered_vp_mtx = unjittered_p_mtx * v_mtx;
     let global_uniform = GlobalUniform {
-      v_mtx: scene.camera_view_matrices[0],
-      p_mtx: scene.camera_proj_matrices[0],
+      v_mtx,
+      p_mtx,
       vp_mtx,
       i_vp_mtx: vp_mtx.inverse(),
       frustum_planes: Self::calc_frustum_planes(&vp_mtx, true, true),
+      camera_position: v_mtx.inverse().w_axis,
+      // The NDC y is up and the UV v is down.
+      jitter: glam::vec4(jitter.x, jitter.y, jitter.x * 0.5, -jitter.y * 0.5),
+      prev_vp_mtx: if is_taa_history_valid { self.prev_vp_mtx } else { unjittered_vp_mtx },
+      render_size: glam::vec4(
+        self.render_width as f32,
+        self.render_height as f32,
+        self.prev_render_size.0 as f32,
+        self.prev_render_size.1 as f32,
+      ),
+      inv_target_size: glam::vec4(1.0 / self.target_width as f32, 1.0 / self.target_height as f32, 0.0, 0.0),
     };
     self.global_uniform_buffer.update_memory(0, &[global_uniform])?;
 
+    // Update shadow uniform buffer.
+    let shadow_uniform = match self.shadow_light {
+      Some((light_index, light_direction)) => ShadowUniform::new(&self.shadow_settings, &v_mtx, &unjittered_p_mtx, light_index, light_direction),
+      None => ShadowUniform::default(),
+    };
+    self.shadow_uniform_buffer.update_memory(0, &[shadow_uniform])?;
+
     // Update object uniform buffers.
+    let mut mvp_mtxs = Vec::with_capacity(scene.meshes.len());
     for (mesh_index, mesh) in scene.meshes.iter().enumerate() {
       // Prepare object data.
-      let mv_mtx = scene.camera_view_matrices[0] * mesh.transform;
+      let mv_mtx = v_mtx * mesh.transform;
+      let unjittered_mvp_mtx = unjittered_p_mtx * mv_mtx;
+      let prev_mvp_mtx = match self.prev_mvp_mtxs.get(mesh_index) {
+        Some(prev_mvp_mtx) if is_taa_history_valid => *prev_mvp_mtx,
+        _ => unjittered_mvp_mtx,
+      };
       let object_uniform = ObjectUniform {
         m_mtx: mesh.transform,
         i_m_mtx: mesh.transform.inverse(),
         mv_mtx,
         t_mv_mtx: mv_mtx.transpose(),
         it_mv_mtx: mv_mtx.inverse().transpose(),
-        mvp_mtx: scene.camera_proj_matrices[0] * mv_mtx,
+        mvp_mtx: p_mtx * mv_mtx,
+        prev_mvp_mtx,
       };
+      mvp_mtxs.push(unjittered_mvp_mtx);
 
-      for index in 0..self.resources.context.borrow().swapchain.num_of_images {
+      for index in 0..self.num_of_frames {
         let buffer = self.object_uniform_buffers[mesh_index][index].as_ref();
         buffer.update_memory(0, &[object_uniform])?;
       }
     }
+    self.prev_vp_mtx = unjittered_vp_mtx;
+    self.prev_mvp_mtxs = mvp_mtxs;
+
+    // The eye adaptation of the automatic exposure follows the frame time.
+    self.exposure_adaptation = self.get_exposure_adaptation(delta_time as f32);
+
+    let is_picking = self.get_pick_texel().is_some();
 
     self.record_command_buffer(
       self.data.image_index,
@@ -392,6 +418,31 @@ impl HalaRendererTrait for VisRenderer {
       &self.resources.compute_command_buffers,
       ui_fn,
     )?;
+    self.is_hiz_valid = true;
+    self.prev_render_size = (self.render_width, self.render_height);
+    self.last_final_image_index = self.data.image_index;
+    if is_picking {
+      self.pick_request = None;
+      self.pick_image_index = Some(self.data.image_index);
+    }
+    if self.tonemap_settings.auto_exposure && self.is_tonemapped_view() {
+      self.is_exposure_valid = true;
+    }
+    if self.is_taa_active() {
+      self.is_taa_history_valid = true;
+      self.taa_frame_index = self.taa_frame_index.wrapping_add(1);
+    } else {
+      self.is_taa_history_valid = false;
+    }
+
+    // The async compute parts of the frame are submitted before the main graphics command buffers.
+    if self.use_async_compute {
+      self.async_compute.submit(
+        &self.resources.context.borrow(),
+        self.data.image_index,
+        &self.resources.compute_command_buffers,
+      )?;
+    }
 
     Ok(())
   }
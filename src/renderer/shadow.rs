@@ -0,0 +1,162 @@
+use super::VisRenderer;
+
+/// The maximum number of the shadow cascades.
+pub const MAX_SHADOW_CASCADES: usize = 4;
+
+/// The cascaded shadow map settings of the directional light.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShadowSettings {
+  // The number of the cascades, at most MAX_SHADOW_CASCADES.
+  pub num_of_cascades: u32,
+  // The width and height of each cascade in texels.
+  pub resolution: u32,
+  // The blend between the uniform(0.0) and the logarithmic(1.0) split of the cascades.
+  pub split_lambda: f32,
+  // The view distance covered by the last cascade.
+  pub max_distance: f32,
+}
+
+/// The default implementation of the shadow settings.
+impl Default for ShadowSettings {
+
+  fn default() -> Self {
+    Self {
+      num_of_cascades: 4,
+      resolution: 2048,
+      split_lambda: 0.75,
+      max_distance: 100.0,
+    }
+  }
+
+}
+
+/// The shadow uniform.
+#[repr(C, align(4))]
+#[derive(Debug, Clone, Copy)]
+pub struct ShadowUniform {
+  // The view-projection matrices of the cascades.
+  pub vp_mtx: [glam::Mat4; MAX_SHADOW_CASCADES],
+  // The frustum planes of the cascades.
+  pub frustum_planes: [glam::Vec4; MAX_SHADOW_CASCADES * 6],
+  // The farthest view distance of each cascade.
+  pub split_distances: glam::Vec4,
+  // The world size of a shadow map texel of each cascade.
+  pub texel_sizes: glam::Vec4,
+  // The depth of a texel size of each cascade.
+  pub depth_biases: glam::Vec4,
+  // The number of the cascades.
+  pub num_of_cascades: u32,
+  // The index of the directional light casting the shadow, -1 for no shadow.
+  pub light_index: i32,
+  // The width and height of each cascade.
+  pub resolution: u32,
+  pub _padding: u32,
+}
+
+/// The default implementation of the shadow uniform.
+impl Default for ShadowUniform {
+
+  fn default() -> Self {
+    Self {
+      vp_mtx: [glam::Mat4::IDENTITY; MAX_SHADOW_CASCADES],
+      frustum_planes: [glam::Vec4::ZERO; MAX_SHADOW_CASCADES * 6],
+      split_distances: glam::Vec4::ZERO,
+      texel_sizes: glam::Vec4::ZERO,
+      depth_biases: glam::Vec4::ZERO,
+      num_of_cascades: 0,
+      light_index: -1,
+      resolution: 0,
+      _padding: 0,
+    }
+  }
+
+}
+
+/// The implementation of the shadow uniform.
+impl ShadowUniform {
+
+  /// The casters in front of the cascade are kept until this multiple of the cascade radius.
+  const CASTER_DISTANCE_SCALE: f32 = 2.0;
+
+  /// Fit the cascades of the directional light to the slices of the camera frustum.
+  /// param settings: The shadow settings.
+  /// param v_mtx: The view matrix of the camera.
+  /// param p_mtx: The reversed-Z infinite projection matrix of the camera.
+  /// param light_index: The index of the directional light.
+  /// param light_direction: The direction of the light travelling.
+  /// return: The shadow uniform.
+  pub fn new(
+    settings: &ShadowSettings,
+    v_mtx: &glam::Mat4,
+    p_mtx: &glam::Mat4,
+    light_index: u32,
+    light_direction: glam::Vec3,
+  ) -> Self {
+    let num_of_cascades = (settings.num_of_cascades as usize).clamp(1, MAX_SHADOW_CASCADES);
+    let z_near = p_mtx.w_axis.z;
+    let z_far = settings.max_distance.max(z_near * 2.0);
+    let tan_half_fov_x = 1.0 / p_mtx.x_axis.x;
+    let tan_half_fov_y = 1.0 / p_mtx.y_axis.y;
+    let i_v_mtx = v_mtx.inverse();
+
+    let light_direction = light_direction.normalize_or(glam::Vec3::NEG_Y);
+    let up = if light_direction.y.abs() > 0.99 { glam::Vec3::Z } else { glam::Vec3::Y };
+    let texels = settings.resolution.max(1) as f32;
+
+    let mut uniform = Self {
+      num_of_cascades: num_of_cascades as u32,
+      light_index: light_index as i32,
+      resolution: settings.resolution,
+      ..Default::default()
+    };
+    let mut split_near = z_near;
+    for cascade in 0..num_of_cascades {
+      // The practical split scheme, blends the logarithmic and the uniform splits.
+      let p = (cascade + 1) as f32 / num_of_cascades as f32;
+      let log_split = z_near * (z_far / z_near).powf(p);
+      let uniform_split = z_near + (z_far - z_near) * p;
+      let split_far = settings.split_lambda * log_split + (1.0 - settings.split_lambda) * uniform_split;
+
+      // Bound the frustum slice by a sphere, so the size of the cascade does not change with the camera rotation.
+      let corners = [split_near, split_far].iter().flat_map(|&distance| {
+        let x = distance * tan_half_fov_x;
+        let y = distance * tan_half_fov_y;
+        [
+          glam::Vec3::new(-x, -y, -distance),
+          glam::Vec3::new( x, -y, -distance),
+          glam::Vec3::new(-x,  y, -distance),
+          glam::Vec3::new( x,  y, -distance),
+        ]
+      }).map(|corner| i_v_mtx.transform_point3(corner)).collect::<Vec<_>>();
+      let center = corners.iter().sum::<glam::Vec3>() / corners.len() as f32;
+      let radius = corners.iter().map(|corner| corner.distance(center)).fold(0.0f32, f32::max);
+      let radius = (radius * 16.0).ceil() / 16.0;
+
+      let caster_distance = radius * Self::CASTER_DISTANCE_SCALE;
+      let depth_range = caster_distance + radius * 2.0;
+      let view_mtx = glam::Mat4::look_at_rh(center - light_direction * (caster_distance + radius), center, up);
+      // The reversed-Z orthographic projection.
+      let mut proj_mtx = glam::Mat4::orthographic_rh(-radius, radius, -radius, radius, depth_range, 0.0);
+
+      // Snap the cascade to the texels to avoid the shimmering edges when the camera moves.
+      let origin = (proj_mtx * view_mtx).transform_point3(glam::Vec3::ZERO).truncate() * texels * 0.5;
+      let offset = (origin.round() - origin) * 2.0 / texels;
+      proj_mtx.w_axis.x += offset.x;
+      proj_mtx.w_axis.y += offset.y;
+
+      let vp_mtx = proj_mtx * view_mtx;
+      let texel_size = radius * 2.0 / texels;
+      uniform.vp_mtx[cascade] = vp_mtx;
+      // Skip the far plane, the meshlets beyond it are clipped by the rasterizer.
+      uniform.frustum_planes[cascade * 6..cascade * 6 + 6].copy_from_slice(&VisRenderer::calc_frustum_planes(&vp_mtx, true, true));
+      uniform.split_distances[cascade] = split_far;
+      uniform.texel_sizes[cascade] = texel_size;
+      uniform.depth_biases[cascade] = texel_size / depth_range;
+
+      split_near = split_far;
+    }
+
+    uniform
+  }
+
+}
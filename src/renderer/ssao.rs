@@ -0,0 +1,125 @@
+use std::rc::Rc;
+
+use hala_renderer::{
+  error::HalaRendererError,
+  renderer::HalaRendererResources,
+};
+
+use super::VisRenderer;
+
+/// The width and height of the thread groups of the SSAO and the blur passes.
+/// NOTICE: Keep it same as SSAO_THREAD_WIDTH in ssao.cs_6_8.hlsl and ssao_blur.cs_6_8.hlsl.
+pub(crate) const SSAO_THREAD_WIDTH: u32 = 8;
+
+/// The screen space ambient occlusion settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SsaoSettings {
+  pub enabled: bool,
+  // Compute the ambient occlusion in the half resolution, the lighting upsamples it.
+  pub half_resolution: bool,
+  // Blur the noisy ambient occlusion by the depth aware bilateral filter.
+  pub blur: bool,
+  // The world radius of the occluders around the pixel.
+  pub radius: f32,
+  // The power of the visibility, larger is darker.
+  pub intensity: f32,
+}
+
+/// The default implementation of the SSAO settings.
+impl Default for SsaoSettings {
+
+  fn default() -> Self {
+    Self {
+      enabled: true,
+      half_resolution: true,
+      blur: true,
+      radius: 0.5,
+      intensity: 1.0,
+    }
+  }
+
+}
+
+/// The implementation of the visibility renderer.
+impl VisRenderer {
+
+  /// Create the images of the ambient occlusion.
+  /// param resources: The renderer resources.
+  /// param width: The render width.
+  /// param height: The render height.
+  /// param half_resolution: Whether the ambient occlusion is in the half resolution.
+  /// return: The raw ambient occlusion image and the blurred one read by the lighting.
+  pub(crate) fn create_ssao_images(
+    resources: &HalaRendererResources,
+    width: u32,
+    height: u32,
+    half_resolution: bool,
+  ) -> Result<(hala_gfx::HalaImage, hala_gfx::HalaImage), HalaRendererError> {
+    let context = resources.context.borrow();
+    let scale = if half_resolution { 2 } else { 1 };
+    let (ssao_width, ssao_height) = ((width + scale - 1) / scale, (height + scale - 1) / scale);
+
+    let mut images = Vec::with_capacity(2);
+    for name in ["ssao_raw.image", "ssao.image"] {
+      images.push(hala_gfx::HalaImage::new_2d(
+        Rc::clone(&context.logical_device),
+        hala_gfx::HalaImageUsageFlags::STORAGE | hala_gfx::HalaImageUsageFlags::SAMPLED,
+        hala_gfx::HalaFormat::R32_SFLOAT,
+        ssao_width,
+        ssao_height,
+        1,
+        1,
+        hala_gfx::HalaMemoryLocation::GpuOnly,
+        name,
+      )?);
+    }
+    let ssao_image = images.pop().unwrap();
+    let ssao_raw_image = images.pop().unwrap();
+
+    Ok((ssao_raw_image, ssao_image))
+  }
+
+  /// Set the screen space ambient occlusion settings.
+  /// param settings: The settings.
+  /// return: The result.
+  pub fn set_ssao_settings(&mut self, settings: SsaoSettings) -> Result<(), HalaRendererError> {
+    let is_resized = settings.half_resolution != self.ssao_settings.half_resolution;
+    let is_graph_changed = settings.enabled != self.ssao_settings.enabled || settings.blur != self.ssao_settings.blur;
+    self.ssao_settings = settings;
+    if !is_resized && !is_graph_changed {
+      return Ok(());
+    }
+
+    // The old images and the descriptor sets may be still used by the frames in flight.
+    self.wait_idle()?;
+    if is_resized {
+      self.ssao_image = None;
+      self.ssao_raw_image = None;
+      let (ssao_raw_image, ssao_image) = Self::create_ssao_images(&self.resources, self.target_width, self.target_height, settings.half_resolution)?;
+      self.ssao_raw_image = Some(ssao_raw_image);
+      self.ssao_image = Some(ssao_image);
+    }
+    if !self.graphics_descriptor_sets.is_empty() {
+      self.setup_visibility()?;
+    }
+    // The SSAO passes read the depth and the normal, which changes the lifetimes of the offscreen targets.
+    if is_graph_changed && self.programs_file.is_some() {
+      self.update_offscreen_aliasing()?;
+    }
+
+    Ok(())
+  }
+
+  /// Get the screen space ambient occlusion settings.
+  /// return: The settings.
+  pub fn get_ssao_settings(&self) -> SsaoSettings {
+    self.ssao_settings
+  }
+
+  /// Get the ratio of the render size to the size of the ambient occlusion images.
+  /// return: The ratio.
+  pub(crate) fn get_ssao_resolution_scale(&self) -> u32 {
+    if self.ssao_settings.half_resolution { 2 } else { 1 }
+  }
+
+}
@@ -0,0 +1,206 @@
+use std::rc::Rc;
+
+use hala_renderer::{
+  error::HalaRendererError,
+  renderer::HalaRendererResources,
+};
+
+use super::VisRenderer;
+
+/// The number of the samples of the sub-pixel jitter sequence.
+pub(crate) const TAA_JITTER_SAMPLES: u32 = 8;
+
+/// The temporal anti-aliasing settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TaaSettings {
+  pub enabled: bool,
+  // The blend weight of the reprojected history, larger is smoother but follows the changes slower.
+  pub history_weight: f32,
+}
+
+/// The default implementation of the temporal anti-aliasing settings.
+impl Default for TaaSettings {
+
+  fn default() -> Self {
+    Self {
+      enabled: true,
+      history_weight: 0.9,
+    }
+  }
+
+}
+
+/// Get the element of the Halton low discrepancy sequence.
+/// param index: The index of the element, starts from 1.
+/// param base: The base of the sequence.
+/// return: The element in [0, 1).
+fn halton(index: u32, base: u32) -> f32 {
+  let mut result = 0.0;
+  let mut fraction = 1.0;
+  let mut i = index;
+  while i > 0 {
+    fraction /= base as f32;
+    result += fraction * (i % base) as f32;
+    i /= base;
+  }
+  result
+}
+
+/// The implementation of the visibility renderer.
+impl VisRenderer {
+
+  /// Create the images of the temporal anti-aliasing at the render size.
+  /// NOTICE: Keep the formats same as the material tile and the taa programs in programs.toml.
+  /// param resources: The renderer resources.
+  /// param width: The render width.
+  /// param height: The render height.
+  /// return: The motion image and the history images, which are read and written by turns.
+  pub(crate) fn create_taa_images(
+    resources: &HalaRendererResources,
+    width: u32,
+    height: u32,
+  ) -> Result<(hala_gfx::HalaImage, Vec<hala_gfx::HalaImage>), HalaRendererError> {
+    let context = resources.context.borrow();
+
+    let motion_image = hala_gfx::HalaImage::new_2d(
+      Rc::clone(&context.logical_device),
+      hala_gfx::HalaImageUsageFlags::COLOR_ATTACHMENT | hala_gfx::HalaImageUsageFlags::SAMPLED,
+      hala_gfx::HalaFormat::R16G16_SFLOAT,
+      width,
+      height,
+      1,
+      1,
+      hala_gfx::HalaMemoryLocation::GpuOnly,
+      "motion.image",
+    )?;
+
+    let mut history_images = Vec::with_capacity(2);
+    for index in 0..2 {
+      let history_image = hala_gfx::HalaImage::new_2d(
+        Rc::clone(&context.logical_device),
+        hala_gfx::HalaImageUsageFlags::COLOR_ATTACHMENT | hala_gfx::HalaImageUsageFlags::SAMPLED,
+        hala_gfx::HalaFormat::R16G16B16A16_SFLOAT,
+        width,
+        height,
+        1,
+        1,
+        hala_gfx::HalaMemoryLocation::GpuOnly,
+        &format!("taa_history_{}.image", index),
+      )?;
+      history_images.push(history_image);
+    }
+
+    Ok((motion_image, history_images))
+  }
+
+  /// Create the descriptor sets of the taa and the tonemap programs for each history image.
+  /// The history images are swapped every frame, so each one has its own descriptor sets.
+  /// NOTICE: Keep the bindings same as the taa and the tonemap programs in programs.toml.
+  /// param resources: The renderer resources.
+  /// return: The taa descriptor sets and the tonemap descriptor sets.
+  pub(crate) fn create_taa_descriptor_sets(
+    resources: &HalaRendererResources,
+  ) -> Result<(Vec<hala_gfx::HalaDescriptorSet>, Vec<hala_gfx::HalaDescriptorSet>), HalaRendererError> {
+    let logical_device = Rc::clone(&resources.context.borrow().logical_device);
+    let create_descriptor_set = |descriptor_types: &[hala_gfx::HalaDescriptorType], name: &str| {
+      let bindings = descriptor_types.iter().enumerate().map(|(binding_index, descriptor_type)| {
+        hala_gfx::HalaDescriptorSetLayoutBinding {
+          binding_index: binding_index as u32,
+          descriptor_type: *descriptor_type,
+          descriptor_count: 1,
+          stage_flags: hala_gfx::HalaShaderStageFlags::FRAGMENT | hala_gfx::HalaShaderStageFlags::VERTEX | hala_gfx::HalaShaderStageFlags::TASK | hala_gfx::HalaShaderStageFlags::MESH,
+          binding_flags: hala_gfx::HalaDescriptorBindingFlags::PARTIALLY_BOUND
+        }
+      }).collect::<Vec<_>>();
+      hala_gfx::HalaDescriptorSet::new_static(
+        Rc::clone(&logical_device),
+        Rc::clone(&resources.descriptor_pool),
+        hala_gfx::HalaDescriptorSetLayout::new(
+          Rc::clone(&logical_device),
+          bindings.as_slice(),
+          &format!("{}.descriptor_set_layout", name),
+        )?,
+        0,
+        &format!("{}.descriptor_set", name),
+      )
+    };
+
+    let mut taa_descriptor_sets = Vec::with_capacity(2);
+    let mut tonemap_descriptor_sets = Vec::with_capacity(2);
+    for index in 0..2 {
+      taa_descriptor_sets.push(create_descriptor_set(
+        &[
+          hala_gfx::HalaDescriptorType::COMBINED_IMAGE_SAMPLER,
+          hala_gfx::HalaDescriptorType::COMBINED_IMAGE_SAMPLER,
+          hala_gfx::HalaDescriptorType::COMBINED_IMAGE_SAMPLER,
+          hala_gfx::HalaDescriptorType::COMBINED_IMAGE_SAMPLER,
+        ],
+        &format!("taa[{}]", index),
+      )?);
+      tonemap_descriptor_sets.push(create_descriptor_set(
+        &[
+          hala_gfx::HalaDescriptorType::COMBINED_IMAGE_SAMPLER,
+          hala_gfx::HalaDescriptorType::STORAGE_BUFFER,
+          hala_gfx::HalaDescriptorType::COMBINED_IMAGE_SAMPLER,
+        ],
+        &format!("taa_tonemap[{}]", index),
+      )?);
+    }
+
+    Ok((taa_descriptor_sets, tonemap_descriptor_sets))
+  }
+
+  /// Set the temporal anti-aliasing settings.
+  /// The history is dropped when the temporal anti-aliasing is turned on.
+  /// param settings: The settings.
+  /// return: The result.
+  pub fn set_taa_settings(&mut self, settings: TaaSettings) -> Result<(), HalaRendererError> {
+    let is_toggled = settings.enabled != self.taa_settings.enabled;
+    self.taa_settings = settings;
+    if !is_toggled {
+      return Ok(());
+    }
+
+    log::info!("The temporal anti-aliasing is {}.", if settings.enabled { "enabled" } else { "disabled" });
+    self.is_taa_history_valid = false;
+    // The TAA pass reads the depth after the lighting, which changes the lifetimes of the offscreen targets.
+    if self.programs_file.is_some() {
+      self.update_offscreen_aliasing()?;
+    }
+
+    Ok(())
+  }
+
+  /// Get the temporal anti-aliasing settings.
+  /// return: The settings.
+  pub fn get_taa_settings(&self) -> TaaSettings {
+    self.taa_settings
+  }
+
+  /// Whether the temporal anti-aliasing resolves this frame, the debug views are drawn without it.
+  /// return: The result.
+  pub(crate) fn is_taa_active(&self) -> bool {
+    self.taa_settings.enabled && self.is_tonemapped_view()
+  }
+
+  /// Get the sub-pixel jitter of the projection matrix in this frame.
+  /// return: The jitter in NDC, zero without the temporal anti-aliasing.
+  pub(crate) fn get_taa_jitter(&self) -> glam::Vec2 {
+    if !self.is_taa_active() {
+      return glam::Vec2::ZERO;
+    }
+
+    // The Halton(2, 3) sequence covers the pixel evenly, it starts from 1 as the element 0 is the corner.
+    let sample_index = self.taa_frame_index % TAA_JITTER_SAMPLES + 1;
+    let offset = glam::vec2(halton(sample_index, 2), halton(sample_index, 3)) - 0.5;
+    offset * 2.0 / glam::vec2(self.render_width as f32, self.render_height as f32)
+  }
+
+  /// Get the history images used by this frame.
+  /// return: The index of the history image to read and the index of the one to write.
+  pub(crate) fn get_taa_history_indices(&self) -> (usize, usize) {
+    let read_index = (self.taa_frame_index % 2) as usize;
+    (read_index, 1 - read_index)
+  }
+
+}
@@ -0,0 +1,100 @@
+use hala_renderer::scene::cpu;
+
+/// The squared length below which the tangent is treated as missing.
+const MIN_TANGENT_LENGTH_SQUARED: f32 = 1e-8;
+
+/// Accumulate the tangents and the bitangents of the vertices from the texture coordinates of the triangles.
+/// param positions: The positions of the vertices.
+/// param tex_coords: The texture coordinates of the vertices.
+/// param indices: The indices of the triangles.
+/// return: The accumulated tangents and bitangents, not normalized.
+fn accumulate_tangent_frames(
+  positions: &[glam::Vec3],
+  tex_coords: &[glam::Vec2],
+  indices: &[u32],
+) -> (Vec<glam::Vec3>, Vec<glam::Vec3>) {
+  let mut tangents = vec![glam::Vec3::ZERO; positions.len()];
+  let mut bitangents = vec![glam::Vec3::ZERO; positions.len()];
+  for triangle in indices.chunks_exact(3) {
+    let [i0, i1, i2] = [triangle[0] as usize, triangle[1] as usize, triangle[2] as usize];
+    let dp1 = positions[i1] - positions[i0];
+    let dp2 = positions[i2] - positions[i0];
+    let duv1 = tex_coords[i1] - tex_coords[i0];
+    let duv2 = tex_coords[i2] - tex_coords[i0];
+    let det = duv1.x * duv2.y - duv1.y * duv2.x;
+    if det.abs() < f32::EPSILON {
+      continue;
+    }
+
+    // The derivatives of the position along U and V, the mirrored texture coordinates flip the bitangent.
+    let inv_det = 1.0 / det;
+    let tangent = (dp1 * duv2.y - dp2 * duv1.y) * inv_det;
+    let bitangent = (dp2 * duv1.x - dp1 * duv2.x) * inv_det;
+    for i in [i0, i1, i2] {
+      tangents[i] += tangent;
+      bitangents[i] += bitangent;
+    }
+  }
+
+  (tangents, bitangents)
+}
+
+/// Generate the tangents of the primitives which do not supply them.
+/// The tangents are accumulated from the texture coordinates of the triangles and orthogonalized to the normals.
+/// param scene: The scene in the CPU.
+/// return: The number of the primitives whose tangents are generated.
+pub(crate) fn generate_missing_tangents(scene: &mut cpu::HalaScene) -> usize {
+  let mut num_of_generated = 0;
+  for mesh in scene.meshes.iter_mut() {
+    for primitive in mesh.primitives.iter_mut() {
+      let has_tangents = primitive.vertices.iter().any(|vertex| vertex.tangent.length_squared() > MIN_TANGENT_LENGTH_SQUARED);
+      if has_tangents || primitive.vertices.is_empty() {
+        continue;
+      }
+
+      let positions = primitive.vertices.iter().map(|vertex| vertex.position).collect::<Vec<_>>();
+      let tex_coords = primitive.vertices.iter().map(|vertex| vertex.tex_coord).collect::<Vec<_>>();
+      let (tangents, _) = accumulate_tangent_frames(&positions, &tex_coords, &primitive.indices);
+      for (vertex, tangent) in primitive.vertices.iter_mut().zip(tangents.iter()) {
+        // Gram-Schmidt orthogonalize, fall back to any direction perpendicular to the normal.
+        let normal = vertex.normal.normalize_or(glam::Vec3::Z);
+        let tangent = *tangent - normal * normal.dot(*tangent);
+        vertex.tangent = tangent.try_normalize().unwrap_or_else(|| normal.any_orthonormal_vector());
+      }
+      num_of_generated += 1;
+    }
+  }
+
+  num_of_generated
+}
+
+/// Collect the handedness of the tangent frames, which is the tangent W of glTF.
+/// NOTICE: The vertex of the scene has no tangent W, so it is computed per vertex as the MikkTSpace does,
+/// the sign of the accumulated bitangent against the cross product of the normal and the tangent.
+/// The buffer starts with the offset in words of each draw, followed by one bit per vertex, set for -1.
+/// param scene: The scene in the CPU, whose tangents are generated.
+/// return: The tangent signs buffer.
+pub(crate) fn collect_tangent_signs(scene: &cpu::HalaScene) -> Vec<u32> {
+  let num_of_draws = scene.meshes.iter().map(|mesh| mesh.primitives.len()).sum::<usize>();
+  let mut tangent_signs = vec![0u32; num_of_draws.max(1)];
+  let mut draw_index = 0;
+  for mesh in scene.meshes.iter() {
+    for primitive in mesh.primitives.iter() {
+      let offset = tangent_signs.len();
+      tangent_signs[draw_index] = offset as u32;
+      tangent_signs.resize(offset + primitive.vertices.len().div_ceil(32), 0);
+
+      let positions = primitive.vertices.iter().map(|vertex| vertex.position).collect::<Vec<_>>();
+      let tex_coords = primitive.vertices.iter().map(|vertex| vertex.tex_coord).collect::<Vec<_>>();
+      let (_, bitangents) = accumulate_tangent_frames(&positions, &tex_coords, &primitive.indices);
+      for (vertex_index, (vertex, bitangent)) in primitive.vertices.iter().zip(bitangents.iter()).enumerate() {
+        if vertex.normal.cross(vertex.tangent).dot(*bitangent) < 0.0 {
+          tangent_signs[offset + vertex_index / 32] |= 1 << (vertex_index % 32);
+        }
+      }
+      draw_index += 1;
+    }
+  }
+
+  tangent_signs
+}
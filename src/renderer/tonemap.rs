@@ -0,0 +1,176 @@
+use std::rc::Rc;
+
+use hala_renderer::{
+  error::HalaRendererError,
+  renderer::HalaRendererResources,
+};
+
+use super::VisRenderer;
+
+/// The number of the bins of the log luminance histogram.
+/// NOTICE: Keep it same as LUMINANCE_HISTOGRAM_BINS in tonemapping.hlsl.
+pub const LUMINANCE_HISTOGRAM_BINS: u32 = 256;
+/// The width and height of the thread groups of the luminance histogram pass.
+/// NOTICE: Keep it same as LUMINANCE_HISTOGRAM_THREAD_WIDTH in luminance_histogram.cs_6_8.hlsl.
+pub(crate) const LUMINANCE_HISTOGRAM_THREAD_WIDTH: u32 = 16;
+
+/// The operators mapping the HDR scene color to the display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TonemapOperator {
+  Aces,
+  AgX,
+  Reinhard,
+}
+
+/// The implementation of the tonemap operator.
+impl TonemapOperator {
+
+  /// All operators in the order of displaying.
+  pub const ALL: [TonemapOperator; 3] = [
+    TonemapOperator::Aces,
+    TonemapOperator::AgX,
+    TonemapOperator::Reinhard,
+  ];
+
+  /// Get the name of the operator.
+  /// return: The name.
+  pub fn name(&self) -> &'static str {
+    match self {
+      TonemapOperator::Aces => "ACES",
+      TonemapOperator::AgX => "AgX",
+      TonemapOperator::Reinhard => "Reinhard",
+    }
+  }
+
+  /// Find the operator by its name, case insensitive.
+  /// param name: The name.
+  /// return: The operator.
+  pub fn from_name(name: &str) -> Option<Self> {
+    Self::ALL.iter().copied().find(|operator| operator.name().eq_ignore_ascii_case(name))
+  }
+
+  /// Get the operator index in the tonemap shader, 0 is reserved for the debug views.
+  /// NOTICE: Keep them same as TONEMAP_OPERATOR_* in tonemapping.hlsl.
+  /// return: The index.
+  pub(crate) fn get_shader_index(&self) -> u32 {
+    match self {
+      TonemapOperator::Aces => 1,
+      TonemapOperator::AgX => 2,
+      TonemapOperator::Reinhard => 3,
+    }
+  }
+
+}
+
+/// The tonemapping and exposure settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TonemapSettings {
+  pub operator: TonemapOperator,
+  // Expose the average luminance of the luminance histogram to the middle gray, otherwise use the manual exposure.
+  pub auto_exposure: bool,
+  // The manual exposure in EV, the scene color is scaled by 2^exposure.
+  pub exposure: f32,
+  // The EV added to the automatic exposure.
+  pub exposure_compensation: f32,
+  // The log2 luminance range covered by the histogram, which also limits the automatic exposure.
+  pub min_log_luminance: f32,
+  pub max_log_luminance: f32,
+  // The speed of the eye adaptation per second, larger is faster.
+  pub adaptation_speed: f32,
+}
+
+/// The default implementation of the tonemap settings.
+impl Default for TonemapSettings {
+
+  fn default() -> Self {
+    Self {
+      operator: TonemapOperator::Aces,
+      auto_exposure: false,
+      exposure: 0.0,
+      exposure_compensation: 0.0,
+      min_log_luminance: -10.0,
+      max_log_luminance: 10.0,
+      adaptation_speed: 1.5,
+    }
+  }
+
+}
+
+/// The implementation of the visibility renderer.
+impl VisRenderer {
+
+  /// Create the buffers of the automatic exposure.
+  /// They are written once by the CPU, so the histogram starts cleared and the exposure pass clears it after reading.
+  /// param resources: The renderer resources.
+  /// return: The luminance histogram buffer and the exposure buffer.
+  pub(crate) fn create_exposure_buffers(
+    resources: &HalaRendererResources,
+  ) -> Result<(hala_gfx::HalaBuffer, hala_gfx::HalaBuffer), HalaRendererError> {
+    let context = resources.context.borrow();
+
+    let luminance_histogram_buffer = hala_gfx::HalaBuffer::new(
+      Rc::clone(&context.logical_device),
+      std::mem::size_of::<u32>() as u64 * LUMINANCE_HISTOGRAM_BINS as u64,
+      hala_gfx::HalaBufferUsageFlags::STORAGE_BUFFER,
+      hala_gfx::HalaMemoryLocation::CpuToGpu,
+      "luminance_histogram.buffer",
+    )?;
+    luminance_histogram_buffer.update_memory(0, &[0u32; LUMINANCE_HISTOGRAM_BINS as usize])?;
+
+    // The adapted average luminance.
+    let exposure_buffer = hala_gfx::HalaBuffer::new(
+      Rc::clone(&context.logical_device),
+      std::mem::size_of::<f32>() as u64,
+      hala_gfx::HalaBufferUsageFlags::STORAGE_BUFFER,
+      hala_gfx::HalaMemoryLocation::CpuToGpu,
+      "exposure.buffer",
+    )?;
+    exposure_buffer.update_memory(0, &[1f32])?;
+
+    Ok((luminance_histogram_buffer, exposure_buffer))
+  }
+
+  /// Set the tonemapping and exposure settings.
+  /// The automatic exposure jumps to the scene luminance when it is turned on.
+  /// param settings: The settings.
+  pub fn set_tonemap_settings(&mut self, settings: TonemapSettings) {
+    if settings.auto_exposure && !self.tonemap_settings.auto_exposure {
+      self.is_exposure_valid = false;
+    }
+    self.tonemap_settings = settings;
+  }
+
+  /// Get the tonemapping and exposure settings.
+  /// return: The settings.
+  pub fn get_tonemap_settings(&self) -> TonemapSettings {
+    self.tonemap_settings
+  }
+
+  /// Whether the final view is the lighting result, which is exposed and tonemapped.
+  /// The debug views are drawn to the final target as they are.
+  /// return: The result.
+  pub(crate) fn is_tonemapped_view(&self) -> bool {
+    let debug_settings = &self.debug_settings;
+    !(debug_settings.show_hiz
+      || debug_settings.show_triangle
+      || debug_settings.show_meshlet
+      || debug_settings.show_visibility
+      || debug_settings.show_material_depth
+      || debug_settings.show_albedo
+      || debug_settings.show_normal
+      || debug_settings.show_light_count
+      || debug_settings.show_ambient_occlusion)
+  }
+
+  /// Get the blend weight of the target luminance in the eye adaptation of this frame.
+  /// param delta_time: The delta time in seconds.
+  /// return: The weight, 1 jumps to the target luminance.
+  pub(crate) fn get_exposure_adaptation(&self, delta_time: f32) -> f32 {
+    if self.is_exposure_valid {
+      1.0 - (-delta_time.max(0.0) * self.tonemap_settings.adaptation_speed).exp()
+    } else {
+      1.0
+    }
+  }
+
+}
@@ -0,0 +1,229 @@
+/// The transient offscreen targets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OffscreenTarget {
+  Visibility,
+  Depth,
+  MaterialDepth,
+  Albedo,
+  Normal,
+  Material,
+  Emissive,
+}
+
+/// The implementation of the offscreen target.
+impl OffscreenTarget {
+
+  /// All targets, in the order they are imported into the frame graph.
+  pub const ALL: [OffscreenTarget; 7] = [
+    OffscreenTarget::Visibility,
+    OffscreenTarget::Depth,
+    OffscreenTarget::MaterialDepth,
+    OffscreenTarget::Albedo,
+    OffscreenTarget::Normal,
+    OffscreenTarget::Material,
+    OffscreenTarget::Emissive,
+  ];
+
+  /// Get the name of the target, which is also the name of its frame graph resource.
+  /// return: The name.
+  pub fn name(&self) -> &'static str {
+    match self {
+      OffscreenTarget::Visibility => "visibility",
+      OffscreenTarget::Depth => "depth",
+      OffscreenTarget::MaterialDepth => "material_depth",
+      OffscreenTarget::Albedo => "albedo",
+      OffscreenTarget::Normal => "normal",
+      OffscreenTarget::Material => "material",
+      OffscreenTarget::Emissive => "emissive",
+    }
+  }
+
+  /// Get the debug name of the image of the target.
+  /// return: The debug name.
+  pub fn debug_name(&self) -> &'static str {
+    match self {
+      OffscreenTarget::Visibility => "visibility.image",
+      OffscreenTarget::Depth => "offscreen_depth.image",
+      OffscreenTarget::MaterialDepth => "material_depth.image",
+      OffscreenTarget::Albedo => "albedo.image",
+      OffscreenTarget::Normal => "normal.image",
+      OffscreenTarget::Material => "material.image",
+      OffscreenTarget::Emissive => "emissive.image",
+    }
+  }
+
+}
+
+/// The transient offscreen target which may share its memory with the others.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TransientTarget {
+  pub target: OffscreenTarget,
+  // The memory requirements of the image.
+  pub size: u64,
+  pub alignment: u64,
+  // The range of the passes using the target, None if no pass uses it.
+  pub lifetime: Option<(usize, usize)>,
+}
+
+/// The implementation of the transient target.
+impl TransientTarget {
+
+  /// Whether the target is used by a pass using the other one.
+  /// param other: The other target.
+  /// return: The result.
+  fn is_alive_with(&self, other: &Self) -> bool {
+    match (self.lifetime, other.lifetime) {
+      (Some((first, last)), Some((other_first, other_last))) => first <= other_last && other_first <= last,
+      _ => false,
+    }
+  }
+
+}
+
+/// The aliasing plan of the transient targets.
+/// All targets are placed in one memory heap, whatever their formats are.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct AliasingPlan {
+  // The offset of each target in the heap.
+  pub offsets: Vec<u64>,
+  // The targets before each target whose memory overlaps it, the first access of the target waits for all of them.
+  pub aliases: Vec<Vec<usize>>,
+  // The size of all targets in bytes.
+  pub logical_size: u64,
+  // The size of the heap in bytes.
+  pub heap_size: u64,
+}
+
+/// The implementation of the aliasing plan.
+impl AliasingPlan {
+
+  /// Plan the placement of the targets in the heap.
+  /// A target is placed at the lowest offset not used by the targets alive at the same time, the bigger targets are placed first.
+  /// The targets without lifetime are not used in the frame, so they are placed at the beginning of the heap.
+  /// param targets: The targets.
+  /// return: The aliasing plan.
+  pub fn new(targets: &[TransientTarget]) -> Self {
+    let mut offsets = vec![0; targets.len()];
+    let mut placed: Vec<usize> = Vec::with_capacity(targets.len());
+
+    let mut order = (0..targets.len()).filter(|i| targets[*i].lifetime.is_some()).collect::<Vec<_>>();
+    order.sort_by_key(|i| (std::cmp::Reverse(targets[*i].size), targets[*i].lifetime.map(|(first, _)| first)));
+    for i in order {
+      let target = &targets[i];
+      let mut ranges = placed.iter()
+        .filter(|j| target.is_alive_with(&targets[**j]))
+        .map(|j| (offsets[*j], offsets[*j] + targets[*j].size))
+        .collect::<Vec<_>>();
+      ranges.sort();
+
+      let mut offset = 0;
+      for (begin, end) in ranges {
+        if offset + target.size <= begin {
+          break;
+        }
+        offset = offset.max(end.next_multiple_of(target.alignment.max(1)));
+      }
+      offsets[i] = offset;
+      placed.push(i);
+    }
+
+    let is_overlapped = |i: usize, j: usize| offsets[i] < offsets[j] + targets[j].size && offsets[j] < offsets[i] + targets[i].size;
+    let aliases = (0..targets.len())
+      .map(|i| (0..i).filter(|j| is_overlapped(i, *j)).collect::<Vec<_>>())
+      .collect::<Vec<_>>();
+
+    Self {
+      logical_size: targets.iter().map(|target| target.size).sum(),
+      heap_size: targets.iter().zip(offsets.iter()).map(|(target, offset)| offset + target.size).max().unwrap_or(0),
+      offsets,
+      aliases,
+    }
+  }
+
+  /// Log the report of the plan.
+  /// param targets: The targets.
+  pub fn report(&self, targets: &[TransientTarget]) {
+    const MB: f64 = 1024.0 * 1024.0;
+    log::info!(
+      "Offscreen targets: {:.2}MB of {} targets placed in a {:.2}MB heap, {:.2}MB saved by aliasing.",
+      self.logical_size as f64 / MB,
+      targets.len(),
+      self.heap_size as f64 / MB,
+      self.logical_size.saturating_sub(self.heap_size) as f64 / MB,
+    );
+    for (target, offset) in targets.iter().zip(self.offsets.iter()) {
+      let lifetime = target.lifetime.map_or("unused".to_string(), |(first, last)| format!("passes {}..={}", first, last));
+      log::debug!(
+        "  {}: {:.2}MB at {:.2}MB, {}.",
+        target.target.name(),
+        target.size as f64 / MB,
+        *offset as f64 / MB,
+        lifetime,
+      );
+    }
+  }
+
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Create a target of the size with the lifetime.
+  fn transient_target(target: OffscreenTarget, size: u64, lifetime: Option<(usize, usize)>) -> TransientTarget {
+    TransientTarget {
+      target,
+      size,
+      alignment: 256,
+      lifetime,
+    }
+  }
+
+  #[test]
+  fn disjoint_lifetimes_share_memory() {
+    let targets = [
+      transient_target(OffscreenTarget::Visibility, 1024, Some((0, 1))),
+      transient_target(OffscreenTarget::Albedo, 1024, Some((2, 3))),
+    ];
+
+    let plan = AliasingPlan::new(&targets);
+    assert_eq!(plan.offsets, vec![0, 0]);
+    assert_eq!(plan.aliases, vec![vec![], vec![0]]);
+    assert_eq!(plan.logical_size, 2048);
+    assert_eq!(plan.heap_size, 1024);
+  }
+
+  #[test]
+  fn overlapping_lifetimes_do_not_share_memory() {
+    let targets = [
+      transient_target(OffscreenTarget::Visibility, 1024, Some((0, 2))),
+      transient_target(OffscreenTarget::Albedo, 1000, Some((2, 3))),
+      transient_target(OffscreenTarget::Normal, 512, None),
+    ];
+
+    let plan = AliasingPlan::new(&targets);
+    assert_eq!(plan.offsets[0], 0);
+    assert_eq!(plan.offsets[1], 1024);
+    assert_eq!(plan.aliases[1], Vec::<usize>::new());
+    // The unused target is placed at the beginning of the heap.
+    assert_eq!(plan.offsets[2], 0);
+    assert_eq!(plan.aliases[2], vec![0]);
+    assert_eq!(plan.heap_size, 2024);
+  }
+
+  #[test]
+  fn target_over_two_targets_aliases_both() {
+    let targets = [
+      transient_target(OffscreenTarget::Visibility, 1024, Some((0, 1))),
+      transient_target(OffscreenTarget::Albedo, 1024, Some((0, 1))),
+      transient_target(OffscreenTarget::Emissive, 2048, Some((2, 3))),
+    ];
+
+    let plan = AliasingPlan::new(&targets);
+    // The two targets alive together are placed side by side, the later target covers both of them.
+    assert_eq!(plan.offsets, vec![0, 1024, 0]);
+    assert_eq!(plan.aliases[1], Vec::<usize>::new());
+    assert_eq!(plan.aliases[2], vec![0, 1]);
+    assert_eq!(plan.heap_size, 2048);
+  }
+}
@@ -0,0 +1,100 @@
+use hala_renderer::scene::cpu;
+
+use super::material_variant::{
+  DrawMaterialFlags,
+  DRAW_FLAG_TRANSPARENT,
+};
+
+/// The draw index of the transparent program to render all transparent draws.
+pub(crate) const TRANSPARENT_ALL_DRAWS: u32 = u32::MAX;
+
+/// The size of the meshlet in the global meshlet buffer in words, and the word of its draw index.
+/// NOTICE: Keep it same as Meshlet in types.hlsl.
+pub(crate) const MESHLET_SIZE_IN_WORDS: usize = 16;
+const MESHLET_DRAW_INDEX_WORD: usize = 15;
+
+/// A transparent draw, which is sorted by its center every frame.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TransparentDraw {
+  // The draw index of the meshlets.
+  pub(crate) draw_index: u32,
+  // The center of the vertices in the world space, the mesh transforms are static.
+  pub(crate) center: glam::Vec3,
+  // The DRAW_FLAG_* bits of the draw.
+  pub(crate) flags: u32,
+  // The range of the meshlets of the draw in the global meshlet buffer.
+  pub(crate) meshlet_offset: u32,
+  pub(crate) meshlet_count: u32,
+}
+
+/// Collect the transparent draws, in the order of the draw indices.
+/// param scene: The scene in the CPU.
+/// param draw_material_flags: The material flags of the draws.
+/// return: The transparent draws.
+pub(crate) fn collect_transparent_draws(scene: &cpu::HalaScene, draw_material_flags: &[DrawMaterialFlags]) -> Vec<TransparentDraw> {
+  let mut transparent_draws = Vec::new();
+  let mut draw_index = 0;
+  for mesh in scene.meshes.iter() {
+    for primitive in mesh.primitives.iter() {
+      let flags = draw_material_flags.get(draw_index).map_or(0, |flags| flags.flags);
+      if flags & DRAW_FLAG_TRANSPARENT != 0 {
+        let center = if primitive.vertices.is_empty() {
+          glam::Vec3::ZERO
+        } else {
+          primitive.vertices.iter().map(|vertex| vertex.position).sum::<glam::Vec3>() / primitive.vertices.len() as f32
+        };
+        transparent_draws.push(TransparentDraw {
+          draw_index: draw_index as u32,
+          center: mesh.transform.transform_point3(center),
+          flags,
+          meshlet_offset: 0,
+          meshlet_count: 0,
+        });
+      }
+      draw_index += 1;
+    }
+  }
+
+  transparent_draws
+}
+
+/// Set the meshlet ranges of the transparent draws.
+/// The meshlets of a draw are contiguous in the global meshlet buffer, the range covers them even if they were not.
+/// param transparent_draws: The transparent draws.
+/// param meshlets: The words of the global meshlet buffer.
+pub(crate) fn set_meshlet_ranges(transparent_draws: &mut [TransparentDraw], meshlets: &[u32]) {
+  // The first and the last meshlet of each draw.
+  let mut ranges = std::collections::HashMap::new();
+  for (meshlet_index, meshlet) in meshlets.chunks_exact(MESHLET_SIZE_IN_WORDS).enumerate() {
+    let meshlet_index = meshlet_index as u32;
+    ranges.entry(meshlet[MESHLET_DRAW_INDEX_WORD])
+      .and_modify(|(_, last)| *last = meshlet_index)
+      .or_insert((meshlet_index, meshlet_index));
+  }
+
+  for draw in transparent_draws.iter_mut() {
+    (draw.meshlet_offset, draw.meshlet_count) = match ranges.get(&draw.draw_index) {
+      Some((first, last)) => (*first, last - first + 1),
+      None => (0, 0),
+    };
+  }
+}
+
+/// Sort the transparent draws from back to front.
+/// The draws are ordered by their centers, the triangles in a draw are not sorted.
+/// param transparent_draws: The transparent draws.
+/// param v_mtx: The view matrix of the camera.
+/// return: The sorted draws.
+pub(crate) fn sort_transparent_draws(
+  transparent_draws: &[TransparentDraw],
+  v_mtx: &glam::Mat4,
+) -> Vec<TransparentDraw> {
+  let mut sorted_draws = transparent_draws.iter().map(|draw| {
+    // The camera looks at -Z, the farther draw has the smaller view Z.
+    let view_z = v_mtx.transform_point3(draw.center).z;
+    (view_z, *draw)
+  }).collect::<Vec<_>>();
+  sorted_draws.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+  sorted_draws.into_iter().map(|(_, draw)| draw).collect()
+}